@@ -1,13 +1,19 @@
 use crate::config;
 use crate::config::AppListConfig;
+use crate::config::ClickAction;
+use crate::config::TopLevelFilter;
 use crate::config::APP_ID;
+use crate::dbus::item_activations;
 use crate::fl;
+use crate::icon_cache::{icon_theme_subscription, IconCache, IconData};
+use crate::launcher_entry::{launcher_entries, LauncherEntry, LauncherEntryUpdate};
 use crate::toplevel_subscription::toplevel_subscription;
 use crate::toplevel_subscription::ToplevelRequest;
 use crate::toplevel_subscription::ToplevelUpdate;
 use cctk::sctk::reexports::calloop::channel::Sender;
 use cctk::toplevel_info::ToplevelInfo;
 use cctk::wayland_client::protocol::wl_data_device_manager::DndAction;
+use cctk::wayland_client::protocol::wl_output::WlOutput;
 use cctk::wayland_client::protocol::wl_seat::WlSeat;
 use cosmic::app::{
     applet::{cosmic_panel_config::PanelAnchor, CosmicAppletHelper},
@@ -22,9 +28,12 @@ use cosmic::iced::wayland::actions::data_device::DndIcon;
 use cosmic::iced::wayland::popup::destroy_popup;
 use cosmic::iced::wayland::popup::get_popup;
 use cosmic::iced::widget::dnd_listener;
+use cosmic::iced::widget::horizontal_space;
 use cosmic::iced::widget::vertical_rule;
 use cosmic::iced::widget::vertical_space;
-use cosmic::iced::widget::{column, dnd_source, mouse_area, row, Column, Row};
+use cosmic::iced::widget::{
+    column, dnd_source, image, mouse_area, progress_bar, row, svg, text, Column, Row,
+};
 use cosmic::iced::Color;
 use cosmic::iced::{window, Subscription};
 use cosmic::iced_runtime::core::alignment::Horizontal;
@@ -41,7 +50,9 @@ use cosmic::widget::rectangle_tracker::rectangle_tracker_subscription;
 use cosmic::widget::rectangle_tracker::RectangleTracker;
 use cosmic::widget::rectangle_tracker::RectangleUpdate;
 use cosmic::{Element, Theme};
-use cosmic_protocols::toplevel_info::v1::client::zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1;
+use cosmic_protocols::toplevel_info::v1::client::zcosmic_toplevel_handle_v1::{
+    self, ZcosmicToplevelHandleV1,
+};
 use freedesktop_desktop_entry::DesktopEntry;
 use futures::future::pending;
 use iced::widget::container;
@@ -52,14 +63,17 @@ use itertools::Itertools;
 use rand::{thread_rng, Rng};
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use url::Url;
 
 static MIME_TYPE: &str = "text/uri-list";
+/// Width of the window thumbnails in the popup
+const PREVIEW_WIDTH: f32 = 160.0;
+/// How long the favorites take to make room for a dragged icon
+const REFLOW_DURATION: Duration = Duration::from_millis(150);
 
 pub fn run() -> cosmic::iced::Result {
     cosmic::app::applet::run::<CosmicAppList>(false, ())
@@ -101,12 +115,14 @@ impl DockItem {
         }
     }
 
-    fn as_icon(
-        &self,
+    fn as_icon<'a>(
+        &'a self,
         applet_helper: &CosmicAppletHelper,
+        icon_cache: &IconCache,
         rectangle_tracker: Option<&RectangleTracker<u32>>,
         interaction_enabled: bool,
-    ) -> Element<'_, Message> {
+        launcher_entry: Option<&LauncherEntry>,
+    ) -> Element<'a, Message> {
         let DockItem {
             toplevels,
             desktop_info,
@@ -114,23 +130,39 @@ impl DockItem {
             ..
         } = self;
 
-        let cosmic_icon = cosmic::widget::icon(
-            Path::new(&desktop_info.icon),
+        let mut cosmic_icon = icon_view(
+            icon_cache,
+            &desktop_info.icon,
             applet_helper.suggested_size().0,
         );
+        let launcher_entry = launcher_entry.copied().unwrap_or_default();
+        if let Some(progress) = launcher_entry.progress {
+            cosmic_icon = column![
+                cosmic_icon,
+                progress_bar(0.0..=1.0, progress as f32)
+                    .width(Length::Fixed(applet_helper.suggested_size().0.into()))
+                    .height(Length::Fixed(3.0)),
+            ]
+            .spacing(2)
+            .into();
+        }
 
         let dot_radius = 2;
-        let dots = (0..toplevels.len())
+        let urgent = launcher_entry.urgent;
+        let mut dots = (0..toplevels.len())
             .into_iter()
             .map(|_| {
                 container(vertical_space(Length::Fixed(0.0)))
                     .padding(dot_radius)
                     .style(<Theme as container::StyleSheet>::Style::Custom(Box::new(
-                        |theme| container::Appearance {
+                        move |theme| container::Appearance {
                             text_color: Some(Color::TRANSPARENT),
-                            background: Some(Background::Color(
-                                theme.cosmic().on_bg_color().into(),
-                            )),
+                            // apps asking for attention get accent colored dots
+                            background: Some(Background::Color(if urgent {
+                                theme.cosmic().accent_color().into()
+                            } else {
+                                theme.cosmic().on_bg_color().into()
+                            })),
                             border_radius: 4.0.into(),
                             border_width: 0.0,
                             border_color: Color::TRANSPARENT,
@@ -139,20 +171,43 @@ impl DockItem {
                     .into()
             })
             .collect_vec();
+        if let Some(count) = launcher_entry.count {
+            let label = if count > 99 {
+                "99+".to_string()
+            } else {
+                count.to_string()
+            };
+            dots.push(
+                container(text(label).size(10))
+                    .padding([0, 4])
+                    .style(<Theme as container::StyleSheet>::Style::Custom(Box::new(
+                        |theme| container::Appearance {
+                            text_color: Some(theme.cosmic().accent.on.into()),
+                            background: Some(Background::Color(
+                                theme.cosmic().accent_color().into(),
+                            )),
+                            border_radius: 8.0.into(),
+                            border_width: 0.0,
+                            border_color: Color::TRANSPARENT,
+                        },
+                    )))
+                    .into(),
+            );
+        }
         let icon_wrapper = match applet_helper.anchor {
-            PanelAnchor::Left => row(vec![column(dots).spacing(4).into(), cosmic_icon.into()])
+            PanelAnchor::Left => row(vec![column(dots).spacing(4).into(), cosmic_icon])
                 .align_items(iced::Alignment::Center)
                 .spacing(4)
                 .into(),
-            PanelAnchor::Right => row(vec![cosmic_icon.into(), column(dots).spacing(4).into()])
+            PanelAnchor::Right => row(vec![cosmic_icon, column(dots).spacing(4).into()])
                 .align_items(iced::Alignment::Center)
                 .spacing(4)
                 .into(),
-            PanelAnchor::Top => column(vec![row(dots).spacing(4).into(), cosmic_icon.into()])
+            PanelAnchor::Top => column(vec![row(dots).spacing(4).into(), cosmic_icon])
                 .align_items(iced::Alignment::Center)
                 .spacing(4)
                 .into(),
-            PanelAnchor::Bottom => column(vec![cosmic_icon.into(), row(dots).spacing(4).into()])
+            PanelAnchor::Bottom => column(vec![cosmic_icon, row(dots).spacing(4).into()])
                 .align_items(iced::Alignment::Center)
                 .spacing(4)
                 .into(),
@@ -165,12 +220,11 @@ impl DockItem {
             dnd_source(
                 mouse_area(
                     icon_button
-                        .on_press(
-                            toplevels
-                                .first()
-                                .map(|t| Message::Activate(t.0.clone()))
-                                .unwrap_or_else(|| Message::Exec(desktop_info.exec.clone())),
-                        )
+                        .on_press(if toplevels.is_empty() {
+                            Message::Exec(desktop_info.exec.clone())
+                        } else {
+                            Message::Click(desktop_info.id.clone())
+                        })
                         .width(Length::Shrink)
                         .height(Length::Shrink),
                 )
@@ -191,10 +245,51 @@ impl DockItem {
     }
 }
 
+/// The cached icon, or a placeholder of the same size while it is loading
+fn icon_view<'a>(icon_cache: &IconCache, name: &str, size: u16) -> Element<'a, Message> {
+    let length = Length::Fixed(size.into());
+    match icon_cache.get(name, size) {
+        Some(IconData::Svg(handle)) => svg(handle.clone()).width(length).height(length).into(),
+        Some(IconData::Raster(handle)) => image(handle.clone()).width(length).height(length).into(),
+        None => container(vertical_space(Length::Fixed(0.0)))
+            .width(length)
+            .height(length)
+            .style(<Theme as container::StyleSheet>::Style::Custom(Box::new(
+                |theme| container::Appearance {
+                    text_color: None,
+                    background: Some(Background::Color(theme.cosmic().bg_divider().into())),
+                    border_radius: 8.0.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+            )))
+            .into(),
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 struct DndOffer {
     dock_item: Option<DockItem>,
     preview_index: usize,
+    /// When the preview moved to its index, to animate the gap opening there
+    moved: Option<Instant>,
+    /// Where the preview was before, the gap there closes meanwhile
+    previous_index: Option<usize>,
+}
+
+impl DndOffer {
+    /// Eased progress of the gap opening at the preview index, from 0 to 1
+    fn reflow_progress(&self) -> f32 {
+        let t = self.moved.map_or(1.0, |moved| {
+            (moved.elapsed().as_secs_f32() / REFLOW_DURATION.as_secs_f32()).min(1.0)
+        });
+        1.0 - (1.0 - t) * (1.0 - t)
+    }
+
+    fn is_reflowing(&self) -> bool {
+        self.moved
+            .map_or(false, |moved| moved.elapsed() < REFLOW_DURATION)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -214,6 +309,15 @@ struct CosmicAppList {
     rectangles: HashMap<u32, iced::Rectangle>,
     dnd_offer: Option<DndOffer>,
     is_listening_for_dnd: bool,
+    icon_cache: IconCache,
+    /// Thumbnails of the windows in the popup, captured only while it is open
+    previews: HashMap<ZcosmicToplevelHandleV1, image::Handle>,
+    /// Badges and progress by desktop id
+    launcher_entries: HashMap<String, LauncherEntry>,
+    /// All toplevels with an app id, also those hidden by the filter
+    toplevels: Vec<(ZcosmicToplevelHandleV1, ToplevelInfo)>,
+    /// The output of the panel the app list is on
+    panel_output: Option<WlOutput>,
 }
 
 // TODO DnD after sctk merges DnD
@@ -225,6 +329,12 @@ enum Message {
     Popup(String),
     ClosePopup,
     Activate(ZcosmicToplevelHandleV1),
+    /// An app with windows was clicked
+    Click(String),
+    /// Activate the item at the index, pinned apps first
+    ActivateIndex(usize),
+    CloseToplevel(ZcosmicToplevelHandleV1),
+    RefreshPreviews,
     Exec(String),
     Quit(String),
     Ignore,
@@ -242,15 +352,43 @@ enum Message {
     StopListeningForDnd,
     IncrementSubscriptionCtr,
     ConfigUpdated(AppListConfig),
+    IconLoaded(String, u16, Option<IconData>),
+    IconThemeChanged(String),
+    ReflowFrame,
+    LauncherEntry(LauncherEntryUpdate),
 }
 
 #[derive(Debug, Clone, Default)]
 struct DesktopInfo {
     id: String,
-    icon: PathBuf,
+    /// Icon name or path from the desktop entry, resolved by the [`IconCache`]
+    icon: String,
     exec: String,
     name: String,
     path: PathBuf,
+    actions: Vec<DesktopAction>,
+}
+
+/// An additional way to launch an app, like "New Private Window"
+#[derive(Debug, Clone, Default)]
+struct DesktopAction {
+    name: String,
+    exec: String,
+}
+
+/// The actions listed in the `Actions` key that have a name and an exec line
+fn desktop_actions(de: &DesktopEntry) -> Vec<DesktopAction> {
+    de.actions()
+        .unwrap_or_default()
+        .split(';')
+        .filter(|action| !action.is_empty())
+        .filter_map(|action| {
+            Some(DesktopAction {
+                name: de.action_name(action, None)?.to_string(),
+                exec: de.action_exec(action)?.to_string(),
+            })
+        })
+        .collect()
 }
 
 fn desktop_info_for_app_ids(mut app_ids: Vec<String>) -> Vec<DesktopInfo> {
@@ -263,17 +401,14 @@ fn desktop_info_for_app_ids(mut app_ids: Vec<String>) -> Vec<DesktopInfo> {
                         .iter()
                         .position(|s| s == de.appid || s.eq(&de.name(None).unwrap_or_default()))
                     {
-                        freedesktop_icons::lookup(de.icon().unwrap_or(de.appid))
-                            .with_size(128)
-                            .with_cache()
-                            .find()
-                            .map(|buf| DesktopInfo {
-                                id: app_ids.remove(i),
-                                icon: buf,
-                                exec: de.exec().unwrap_or_default().to_string(),
-                                name: de.name(None).unwrap_or_default().to_string(),
-                                path: path.clone(),
-                            })
+                        Some(DesktopInfo {
+                            id: app_ids.remove(i),
+                            icon: de.icon().unwrap_or(de.appid).to_string(),
+                            exec: de.exec().unwrap_or_default().to_string(),
+                            name: de.name(None).unwrap_or_default().to_string(),
+                            path: path.clone(),
+                            actions: desktop_actions(&de),
+                        })
                     } else {
                         None
                     }
@@ -346,6 +481,204 @@ fn index_in_list(
     }
 }
 
+/// Spawns the command line of a desktop entry, without its field codes
+fn exec(exec_str: &str) {
+    let mut exec = shlex::Shlex::new(exec_str);
+    let mut cmd = match exec.next() {
+        Some(cmd) if !cmd.contains('=') => tokio::process::Command::new(cmd),
+        _ => return,
+    };
+    for arg in exec {
+        // TODO handle "%" args here if necessary?
+        if !arg.starts_with('%') {
+            cmd.arg(arg);
+        }
+    }
+    let _ = cmd.spawn();
+}
+
+impl CosmicAppList {
+    /// Toggles the popup of the app with the desktop id
+    fn open_popup(&mut self, id: String) -> Command<Message> {
+        if let Some((popup_id, _toplevel)) = self.popup.take() {
+            return destroy_popup(popup_id);
+        }
+        if let Some(toplevel_group) = self
+            .active_list
+            .iter()
+            .chain(self.favorite_list.iter())
+            .find(|t| t.desktop_info.id == id)
+        {
+            let rectangle = match self.rectangles.get(&toplevel_group.id) {
+                Some(r) => r,
+                None => return Command::none(),
+            };
+
+            self.surface_id_ctr += 1;
+            let new_id = window::Id(self.surface_id_ctr);
+            self.popup = Some((new_id, toplevel_group.clone()));
+            self.previews.clear();
+            self.capture_previews();
+
+            let mut popup_settings =
+                self.core
+                    .applet_helper
+                    .get_popup_settings(window::Id(0), new_id, None, None, None);
+            let iced::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } = *rectangle;
+            popup_settings.positioner.anchor_rect = iced::Rectangle::<i32> {
+                x: x as i32,
+                y: y as i32,
+                width: width as i32,
+                height: height as i32,
+            };
+            return get_popup(popup_settings);
+        }
+        Command::none()
+    }
+
+    fn activate(&mut self, handle: ZcosmicToplevelHandleV1) -> Command<Message> {
+        if let Some(p) = self.popup.take() {
+            return destroy_popup(p.0);
+        }
+        if let (Some(tx), Some(seat)) = (self.toplevel_sender.as_ref(), self.seat.as_ref()) {
+            let _ = tx.send(ToplevelRequest::Activate(handle, seat.clone()));
+        }
+        Command::none()
+    }
+
+    /// Focuses, cycles or minimizes the windows of the app, as configured
+    fn click(&mut self, id: String) -> Command<Message> {
+        let Some(toplevels) = self
+            .active_list
+            .iter()
+            .chain(self.favorite_list.iter())
+            .find(|t| t.desktop_info.id == id)
+            .map(|t| &t.toplevels)
+        else {
+            return Command::none();
+        };
+        let focused = toplevels.iter().position(|(_, info)| {
+            info.state
+                .contains(&zcosmic_toplevel_handle_v1::State::Activated)
+        });
+        // cycling and the chooser give a focused app's other windows a turn instead
+        let focuses_one =
+            toplevels.len() < 2 || self.config.click_action == ClickAction::FocusFirst;
+        if let Some(i) = focused.filter(|_| self.config.minimize_on_click && focuses_one) {
+            if let Some(tx) = self.toplevel_sender.as_ref() {
+                let _ = tx.send(ToplevelRequest::Minimize(toplevels[i].0.clone()));
+            }
+            return Command::none();
+        }
+        let handle = match self.config.click_action {
+            _ if toplevels.len() < 2 => toplevels.first(),
+            ClickAction::FocusFirst => toplevels.first(),
+            ClickAction::Cycle => toplevels.get(focused.map_or(0, |i| (i + 1) % toplevels.len())),
+            ClickAction::Chooser => return self.open_popup(id),
+        };
+        if let Some((handle, _)) = handle {
+            return self.activate(handle.clone());
+        }
+        Command::none()
+    }
+
+    /// Whether the toplevel is listed with the configured filter
+    fn shows_toplevel(&self, info: &ToplevelInfo) -> bool {
+        match (&self.config.filter_top_levels, &self.panel_output) {
+            (Some(TopLevelFilter::ConfiguredOutput), Some(output)) => info.output.contains(output),
+            _ => true,
+        }
+    }
+
+    fn add_toplevel(&mut self, handle: ZcosmicToplevelHandleV1, info: ToplevelInfo) {
+        if let Some(t) = self
+            .active_list
+            .iter_mut()
+            .chain(self.favorite_list.iter_mut())
+            .find(|DockItem { desktop_info, .. }| {
+                desktop_info.id == info.app_id || desktop_info.name == info.app_id
+            })
+        {
+            t.toplevels.push((handle, info));
+        } else {
+            let desktop_info = desktop_info_for_app_ids(vec![info.app_id.clone()]).remove(0);
+            self.item_ctr += 1;
+            self.active_list.push(DockItem {
+                id: self.item_ctr,
+                toplevels: vec![(handle, info)],
+                desktop_info,
+            });
+        }
+    }
+
+    fn remove_toplevel(&mut self, handle: &ZcosmicToplevelHandleV1) {
+        for t in self
+            .active_list
+            .iter_mut()
+            .chain(self.favorite_list.iter_mut())
+        {
+            t.toplevels.retain(|(t_handle, _)| t_handle != handle);
+        }
+        self.active_list.retain(|t| !t.toplevels.is_empty());
+    }
+
+    /// Lists the toplevels again after the filter or the panel's output changed
+    fn refilter_toplevels(&mut self) {
+        for t in &mut self.favorite_list {
+            t.toplevels.clear();
+        }
+        self.active_list.clear();
+        for (handle, info) in self.toplevels.clone() {
+            if self.shows_toplevel(&info) {
+                self.add_toplevel(handle, info);
+            }
+        }
+    }
+
+    /// The entry published for the item's desktop id or desktop file
+    fn launcher_entry(&self, item: &DockItem) -> Option<&LauncherEntry> {
+        self.launcher_entries
+            .get(&item.desktop_info.id)
+            .or_else(|| {
+                let stem = item.desktop_info.path.file_stem()?.to_str()?;
+                self.launcher_entries.get(stem)
+            })
+    }
+
+    /// Ask for new thumbnails of the windows in the popup, if it shows them
+    fn capture_previews(&self) {
+        let (Some(tx), Some((_, item))) = (self.toplevel_sender.as_ref(), self.popup.as_ref())
+        else {
+            return;
+        };
+        if item.toplevels.len() > 1 {
+            for (handle, _) in &item.toplevels {
+                let _ = tx.send(ToplevelRequest::Capture(handle.clone()));
+            }
+        }
+    }
+
+    /// Load the icons of all items that are not in the cache yet
+    fn request_icons(&mut self) -> Command<Message> {
+        let size = self.core.applet_helper.suggested_size().0;
+        let names = self
+            .favorite_list
+            .iter()
+            .chain(self.active_list.iter())
+            .chain(self.dnd_offer.iter().filter_map(|o| o.dock_item.as_ref()))
+            .map(|item| item.desktop_info.icon.as_str());
+        let commands = self.icon_cache.request(names, size, |name, size, data| {
+            cosmic::app::message::app(Message::IconLoaded(name, size, data))
+        });
+        Command::batch(commands)
+    }
+}
+
 impl cosmic::Application for CosmicAppList {
     type Message = Message;
     type Executor = cosmic::SingleThreadExecutor;
@@ -369,8 +702,9 @@ impl cosmic::Application for CosmicAppList {
             ..Default::default()
         };
         self_.item_ctr = self_.favorite_list.len() as u32;
+        let icons = self_.request_icons();
 
-        (self_, Command::none())
+        (self_, icons)
     }
 
     fn core(&self) -> &cosmic::app::Core {
@@ -384,45 +718,7 @@ impl cosmic::Application for CosmicAppList {
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Popup(id) => {
-                if let Some((popup_id, _toplevel)) = self.popup.take() {
-                    return destroy_popup(popup_id);
-                }
-                if let Some(toplevel_group) = self
-                    .active_list
-                    .iter()
-                    .chain(self.favorite_list.iter())
-                    .find(|t| t.desktop_info.id == id)
-                {
-                    let rectangle = match self.rectangles.get(&toplevel_group.id) {
-                        Some(r) => r,
-                        None => return Command::none(),
-                    };
-
-                    self.surface_id_ctr += 1;
-                    let new_id = window::Id(self.surface_id_ctr);
-                    self.popup = Some((new_id, toplevel_group.clone()));
-
-                    let mut popup_settings = self.core.applet_helper.get_popup_settings(
-                        window::Id(0),
-                        new_id,
-                        None,
-                        None,
-                        None,
-                    );
-                    let iced::Rectangle {
-                        x,
-                        y,
-                        width,
-                        height,
-                    } = *rectangle;
-                    popup_settings.positioner.anchor_rect = iced::Rectangle::<i32> {
-                        x: x as i32,
-                        y: y as i32,
-                        width: width as i32,
-                        height: height as i32,
-                    };
-                    return get_popup(popup_settings);
-                }
+                return self.open_popup(id);
             }
             Message::Favorite(id) => {
                 if let Some(i) = self
@@ -460,13 +756,40 @@ impl cosmic::Application for CosmicAppList {
                 }
             }
             Message::Activate(handle) => {
-                if let Some(p) = self.popup.take() {
-                    return destroy_popup(p.0);
+                return self.activate(handle);
+            }
+            Message::Click(id) => return self.click(id),
+            Message::ActivateIndex(i) => {
+                let Some(item) = self
+                    .favorite_list
+                    .iter()
+                    .chain(self.active_list.iter())
+                    .nth(i)
+                else {
+                    return Command::none();
+                };
+                if item.toplevels.is_empty() {
+                    exec(&item.desktop_info.exec);
+                } else {
+                    return self.click(item.desktop_info.id.clone());
                 }
-                if let (Some(tx), Some(seat)) = (self.toplevel_sender.as_ref(), self.seat.as_ref())
-                {
-                    let _ = tx.send(ToplevelRequest::Activate(handle, seat.clone()));
+            }
+            Message::CloseToplevel(handle) => {
+                if let Some(tx) = self.toplevel_sender.as_ref() {
+                    let _ = tx.send(ToplevelRequest::Quit(handle.clone()));
                 }
+                self.previews.remove(&handle);
+                if let Some((popup_id, item)) = self.popup.as_mut() {
+                    item.toplevels.retain(|(t, _)| t != &handle);
+                    if item.toplevels.is_empty() {
+                        let popup_id = *popup_id;
+                        self.popup = None;
+                        return destroy_popup(popup_id);
+                    }
+                }
+            }
+            Message::RefreshPreviews => {
+                self.capture_previews();
             }
             Message::Quit(id) => {
                 if let Some(toplevel_group) = self
@@ -554,6 +877,7 @@ impl cosmic::Application for CosmicAppList {
                 let index = index_in_list(num_favs, item_size as f32, 4.0, None, pos_in_list);
                 self.dnd_offer = Some(DndOffer {
                     preview_index: index,
+                    moved: Some(Instant::now()),
                     ..DndOffer::default()
                 });
                 let mut cmds = vec![
@@ -575,7 +899,13 @@ impl cosmic::Application for CosmicAppList {
                 return Command::batch(cmds);
             }
             Message::DndMotion(x, y) => {
-                if let Some(DndOffer { preview_index, .. }) = self.dnd_offer.as_mut() {
+                if let Some(DndOffer {
+                    preview_index,
+                    moved,
+                    previous_index,
+                    ..
+                }) = self.dnd_offer.as_mut()
+                {
                     let item_size = self.core.applet_helper.suggested_size().0;
                     let pos_in_list = match self.core.applet_helper.anchor {
                         PanelAnchor::Top | PanelAnchor::Bottom => x,
@@ -589,6 +919,10 @@ impl cosmic::Application for CosmicAppList {
                         Some(*preview_index),
                         pos_in_list,
                     );
+                    if index != *preview_index {
+                        *moved = Some(Instant::now());
+                        *previous_index = Some(*preview_index);
+                    }
                     *preview_index = index;
                 }
             }
@@ -601,18 +935,13 @@ impl cosmic::Application for CosmicAppList {
                     if let Some(di) = std::fs::read_to_string(&file_path).ok().and_then(|input| {
                         DesktopEntry::decode(&file_path, &input)
                             .ok()
-                            .and_then(|de| {
-                                freedesktop_icons::lookup(de.icon().unwrap_or(de.appid))
-                                    .with_size(128)
-                                    .with_cache()
-                                    .find()
-                                    .map(|buf| DesktopInfo {
-                                        id: de.id().to_string(),
-                                        icon: buf,
-                                        exec: de.exec().unwrap_or_default().to_string(),
-                                        name: de.name(None).unwrap_or_default().to_string(),
-                                        path: file_path.clone(),
-                                    })
+                            .map(|de| DesktopInfo {
+                                id: de.id().to_string(),
+                                icon: de.icon().unwrap_or(de.appid).to_string(),
+                                exec: de.exec().unwrap_or_default().to_string(),
+                                name: de.name(None).unwrap_or_default().to_string(),
+                                path: file_path.clone(),
+                                actions: desktop_actions(&de),
                             })
                     }) {
                         self.item_ctr += 1;
@@ -673,30 +1002,22 @@ impl cosmic::Application for CosmicAppList {
                         if info.app_id.is_empty() {
                             return Command::none();
                         }
-                        if let Some(t) = self
-                            .active_list
-                            .iter_mut()
-                            .chain(self.favorite_list.iter_mut())
-                            .find(|DockItem { desktop_info, .. }| {
-                                desktop_info.id == info.app_id || desktop_info.name == info.app_id
-                            })
-                        {
-                            t.toplevels.push((handle, info));
-                        } else {
-                            let desktop_info =
-                                desktop_info_for_app_ids(vec![info.app_id.clone()]).remove(0);
-                            self.item_ctr += 1;
-                            self.active_list.push(DockItem {
-                                id: self.item_ctr,
-                                toplevels: vec![(handle, info)],
-                                desktop_info,
-                            });
+                        self.toplevels.push((handle.clone(), info.clone()));
+                        if self.shows_toplevel(&info) {
+                            self.add_toplevel(handle, info);
+                        }
+                    }
+                    ToplevelUpdate::Output(output) => {
+                        self.panel_output = Some(output);
+                        if self.config.filter_top_levels == Some(TopLevelFilter::ConfiguredOutput) {
+                            self.refilter_toplevels();
                         }
                     }
                     ToplevelUpdate::Init(tx) => {
                         self.toplevel_sender.replace(tx);
                     }
                     ToplevelUpdate::Finished => {
+                        self.toplevels.clear();
                         for t in &mut self.favorite_list {
                             t.toplevels.clear();
                         }
@@ -719,32 +1040,41 @@ impl cosmic::Application for CosmicAppList {
                         )
                         .map(cosmic::app::message::app);
                     }
-                    ToplevelUpdate::RemoveToplevel(handle) => {
-                        for t in self
-                            .active_list
-                            .iter_mut()
-                            .chain(self.favorite_list.iter_mut())
-                        {
-                            t.toplevels.retain(|(t_handle, _)| t_handle != &handle);
+                    ToplevelUpdate::Preview(handle, image) => {
+                        let shown = self.popup.as_ref().map_or(false, |(_, item)| {
+                            item.toplevels.iter().any(|(t, _)| t == &handle)
+                        });
+                        if shown {
+                            self.previews.insert(handle, image);
                         }
-                        self.active_list.retain(|t| !t.toplevels.is_empty());
+                    }
+                    ToplevelUpdate::RemoveToplevel(handle) => {
+                        self.previews.remove(&handle);
+                        self.toplevels.retain(|(t_handle, _)| t_handle != &handle);
+                        self.remove_toplevel(&handle);
                     }
                     ToplevelUpdate::UpdateToplevel(handle, info) => {
                         // TODO probably want to make sure it is removed
                         if info.app_id.is_empty() {
                             return Command::none();
                         }
-                        'toplevel_loop: for toplevel_list in self
+                        match self.toplevels.iter_mut().find(|(t, _)| t == &handle) {
+                            Some((_, t_info)) => *t_info = info.clone(),
+                            None => self.toplevels.push((handle.clone(), info.clone())),
+                        }
+                        // windows move between outputs
+                        if !self.shows_toplevel(&info) {
+                            self.remove_toplevel(&handle);
+                        } else if let Some((_, t_info)) = self
                             .active_list
                             .iter_mut()
                             .chain(self.favorite_list.iter_mut())
+                            .flat_map(|t| t.toplevels.iter_mut())
+                            .find(|(t_handle, _)| t_handle == &handle)
                         {
-                            for (t_handle, t_info) in &mut toplevel_list.toplevels {
-                                if &handle == t_handle {
-                                    *t_info = info;
-                                    break 'toplevel_loop;
-                                }
-                            }
+                            *t_info = info;
+                        } else {
+                            self.add_toplevel(handle, info);
                         }
                     }
                 }
@@ -755,20 +1085,7 @@ impl cosmic::Application for CosmicAppList {
             Message::RemovedSeat(_) => {
                 self.seat.take();
             }
-            Message::Exec(exec_str) => {
-                let mut exec = shlex::Shlex::new(&exec_str);
-                let mut cmd = match exec.next() {
-                    Some(cmd) if !cmd.contains('=') => tokio::process::Command::new(cmd),
-                    _ => return Command::none(),
-                };
-                for arg in exec {
-                    // TODO handle "%" args here if necessary?
-                    if !arg.starts_with('%') {
-                        cmd.arg(arg);
-                    }
-                }
-                let _ = cmd.spawn();
-            }
+            Message::Exec(exec_str) => exec(&exec_str),
             Message::Rectangle(u) => match u {
                 RectangleUpdate::Rectangle(r) => {
                     self.rectangles.insert(r.0, r.1);
@@ -793,6 +1110,7 @@ impl cosmic::Application for CosmicAppList {
                 self.subscription_ctr += 1;
             }
             Message::ConfigUpdated(config) => {
+                let refilter = self.config.filter_top_levels != config.filter_top_levels;
                 self.config = config;
                 // drain to active list
                 for item in self.favorite_list.drain(..) {
@@ -818,10 +1136,28 @@ impl cosmic::Application for CosmicAppList {
                         }
                     })
                     .collect();
+                if refilter {
+                    self.refilter_toplevels();
+                }
+            }
+            Message::IconLoaded(name, size, data) => {
+                self.icon_cache.insert(name, size, data);
+            }
+            Message::IconThemeChanged(theme) => {
+                self.icon_cache.set_theme(theme);
             }
+            Message::ReflowFrame => {}
+            Message::LauncherEntry(update) => match update {
+                LauncherEntryUpdate::Entry(id, entry) => {
+                    self.launcher_entries.insert(id, entry);
+                }
+                LauncherEntryUpdate::Removed(id) => {
+                    self.launcher_entries.remove(&id);
+                }
+            },
         }
 
-        Command::none()
+        self.request_icons()
     }
 
     fn view(&self) -> Element<Message> {
@@ -835,18 +1171,51 @@ impl cosmic::Application for CosmicAppList {
             .map(|dock_item| {
                 dock_item.as_icon(
                     &self.core.applet_helper,
+                    &self.icon_cache,
                     self.rectangle_tracker.as_ref(),
                     self.popup.is_none(),
+                    self.launcher_entry(dock_item),
                 )
             })
             .collect();
 
-        if let Some((item, index)) = self
+        if let Some((item, offer)) = self
             .dnd_offer
             .as_ref()
-            .and_then(|o| o.dock_item.as_ref().map(|item| (item, o.preview_index)))
+            .and_then(|o| o.dock_item.as_ref().map(|item| (item, o)))
         {
-            favorites.insert(index, item.as_icon(&self.core.applet_helper, None, false));
+            let progress = offer.reflow_progress();
+            let item_length = self.core.applet_helper.suggested_size().0 as f32 + 16.0;
+            let gap = |length: f32| -> Element<_> {
+                if is_horizontal {
+                    horizontal_space(Length::Fixed(length)).into()
+                } else {
+                    vertical_space(Length::Fixed(length)).into()
+                }
+            };
+            // the neighbours slide apart first, then the icon takes the gap
+            if progress < 1.0 {
+                let mut gaps = vec![(offer.preview_index, gap(progress * item_length))];
+                if let Some(previous) = offer.previous_index {
+                    gaps.push((previous, gap((1.0 - progress) * item_length)));
+                }
+                // insert the later gap first, so the earlier index stays valid
+                gaps.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+                for (index, gap) in gaps {
+                    favorites.insert(index.min(favorites.len()), gap);
+                }
+            } else {
+                favorites.insert(
+                    offer.preview_index,
+                    item.as_icon(
+                        &self.core.applet_helper,
+                        &self.icon_cache,
+                        None,
+                        false,
+                        self.launcher_entry(item),
+                    ),
+                );
+            }
         } else if self.is_listening_for_dnd && self.favorite_list.is_empty() {
             // show star indicating favorite_list is drag target
             favorites.push(
@@ -865,8 +1234,10 @@ impl cosmic::Application for CosmicAppList {
             .map(|dock_item| {
                 dock_item.as_icon(
                     &self.core.applet_helper,
+                    &self.icon_cache,
                     self.rectangle_tracker.as_ref(),
                     self.popup.is_none(),
+                    self.launcher_entry(dock_item),
                 )
             })
             .collect();
@@ -964,11 +1335,11 @@ impl cosmic::Application for CosmicAppList {
 
     fn view_window(&self, id: window::Id) -> Element<Message> {
         if let Some((_, item, _)) = self.dnd_source.as_ref().filter(|s| s.0 == id) {
-            cosmic::widget::icon(
-                Path::new(&item.desktop_info.icon),
+            icon_view(
+                &self.icon_cache,
+                &item.desktop_info.icon,
                 self.core.applet_helper.suggested_size().0,
             )
-            .into()
         } else if let Some((
             _popup_id,
             DockItem {
@@ -990,6 +1361,13 @@ impl cosmic::Application for CosmicAppList {
             .padding(8)
             .spacing(4)
             .align_items(Alignment::Center);
+            for action in &desktop_info.actions {
+                content = content.push(
+                    cosmic::widget::button(Button::Text)
+                        .custom(vec![iced::widget::text(&action.name).into()])
+                        .on_press(Message::Exec(action.exec.clone())),
+                );
+            }
             if !toplevels.is_empty() {
                 let mut list_col = column![];
                 for (handle, info) in toplevels {
@@ -998,10 +1376,44 @@ impl cosmic::Application for CosmicAppList {
                     } else {
                         info.title.clone()
                     };
+                    if toplevels.len() == 1 {
+                        list_col = list_col.push(
+                            cosmic::widget::button(Button::Text)
+                                .custom(vec![iced::widget::text(title).into()])
+                                .on_press(Message::Activate(handle.clone())),
+                        );
+                        continue;
+                    }
+                    // several windows get thumbnails, middle click closes one
+                    let thumbnail: Element<_> = match self.previews.get(handle) {
+                        Some(preview) => image(preview.clone())
+                            .width(Length::Fixed(PREVIEW_WIDTH))
+                            .into(),
+                        None => container(vertical_space(Length::Fixed(PREVIEW_WIDTH * 0.6)))
+                            .width(Length::Fixed(PREVIEW_WIDTH))
+                            .style(<Theme as container::StyleSheet>::Style::Custom(Box::new(
+                                |theme| container::Appearance {
+                                    text_color: None,
+                                    background: Some(Background::Color(
+                                        theme.cosmic().bg_divider().into(),
+                                    )),
+                                    border_radius: 8.0.into(),
+                                    border_width: 0.0,
+                                    border_color: Color::TRANSPARENT,
+                                },
+                            )))
+                            .into(),
+                    };
                     list_col = list_col.push(
-                        cosmic::widget::button(Button::Text)
-                            .custom(vec![iced::widget::text(title).into()])
-                            .on_press(Message::Activate(handle.clone())),
+                        mouse_area(
+                            cosmic::widget::button(Button::Text)
+                                .custom(vec![column![thumbnail, iced::widget::text(title)]
+                                    .spacing(4)
+                                    .align_items(Alignment::Center)
+                                    .into()])
+                                .on_press(Message::Activate(handle.clone())),
+                        )
+                        .on_middle_release(Message::CloseToplevel(handle.clone())),
                     );
                 }
                 content = content.push(divider::horizontal::light());
@@ -1038,7 +1450,7 @@ impl cosmic::Application for CosmicAppList {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             toplevel_subscription(self.subscription_ctr).map(Message::Toplevel),
             events_with(|e, _| match e {
                 cosmic::iced_runtime::core::Event::PlatformSpecific(
@@ -1075,6 +1487,9 @@ impl cosmic::Application for CosmicAppList {
                 _ => None,
             }),
             rectangle_tracker_subscription(0).map(|update| Message::Rectangle(update.1)),
+            icon_theme_subscription().map(Message::IconThemeChanged),
+            launcher_entries().map(Message::LauncherEntry),
+            item_activations().map(Message::ActivateIndex),
             cosmic_config::config_subscription(0, Cow::from(APP_ID), 1).map(|(_, config)| {
                 match config {
                     Ok(config) => Message::ConfigUpdated(config),
@@ -1086,7 +1501,25 @@ impl cosmic::Application for CosmicAppList {
                     }
                 }
             }),
-        ])
+        ];
+        // previews are live while the popup shows them
+        if self
+            .popup
+            .as_ref()
+            .map_or(false, |(_, item)| item.toplevels.len() > 1)
+        {
+            subscriptions
+                .push(iced::time::every(Duration::from_secs(1)).map(|_| Message::RefreshPreviews));
+        }
+        if self
+            .dnd_offer
+            .as_ref()
+            .map_or(false, DndOffer::is_reflowing)
+        {
+            subscriptions
+                .push(iced::time::every(Duration::from_millis(16)).map(|_| Message::ReflowFrame));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
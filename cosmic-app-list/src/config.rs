@@ -17,10 +17,27 @@ pub enum TopLevelFilter {
     ConfiguredOutput,
 }
 
+/// What clicking an app with several windows does
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub enum ClickAction {
+    /// Focus the app's first window
+    #[default]
+    FocusFirst,
+    /// Focus the window after the focused one, or the first one
+    Cycle,
+    /// Open the window list to pick one
+    Chooser,
+}
+
 #[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
 pub struct AppListConfig {
     pub filter_top_levels: Option<TopLevelFilter>,
     pub favorites: Vec<String>,
+    #[serde(default)]
+    pub click_action: ClickAction,
+    /// Clicking an app whose window is focused minimizes that window
+    #[serde(default)]
+    pub minimize_on_click: bool,
 }
 
 impl AppListConfig {
@@ -0,0 +1,41 @@
+//! D-Bus interface that lets keybindings activate app list items, e.g. Super+1 bound to
+//! `busctl --user call com.system76.CosmicAppList /com/system76/CosmicAppList com.system76.CosmicAppList ActivateItem u 1`
+
+use cosmic::iced::{
+    self,
+    futures::{channel::mpsc, SinkExt},
+};
+use cosmic_applet_common::dbus;
+use zbus::dbus_interface;
+
+const DBUS_NAME: &str = "com.system76.CosmicAppList";
+const DBUS_PATH: &str = "/com/system76/CosmicAppList";
+
+struct AppList {
+    tx: mpsc::Sender<usize>,
+}
+
+#[dbus_interface(name = "com.system76.CosmicAppList")]
+impl AppList {
+    /// Activate the nth item, counting from 1 over the pinned and then the running apps.
+    /// Apps without windows are launched.
+    async fn activate_item(&self, index: u32) {
+        if let Some(i) = (index as usize).checked_sub(1) {
+            let _ = self.tx.clone().send(i).await;
+        }
+    }
+}
+
+/// Emits the index of the item to activate whenever requested over D-Bus
+pub fn item_activations() -> iced::Subscription<usize> {
+    struct ItemActivations;
+
+    // with an app list on several panels, the keybindings activate items of the one that
+    // started first
+    dbus::requests(
+        std::any::TypeId::of::<ItemActivations>(),
+        DBUS_NAME,
+        DBUS_PATH,
+        |tx| AppList { tx },
+    )
+}
@@ -0,0 +1,133 @@
+//! Launcher icons are looked up and read off the UI thread, then kept per icon name and size.
+//! The cache is invalidated when the icon theme of the COSMIC toolkit changes.
+
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use cosmic::iced::{
+    self,
+    widget::{image, svg},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Config shared by the COSMIC toolkit, where the icon theme is chosen
+const TOOLKIT_CONFIG: &str = "com.system76.CosmicTk";
+/// Shown for apps whose icon isn't in the theme
+const FALLBACK_ICON: &str = "application-x-executable";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+struct ToolkitConfig {
+    icon_theme: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum IconData {
+    Svg(svg::Handle),
+    Raster(image::Handle),
+}
+
+#[derive(Debug, Clone)]
+enum IconState {
+    Loading,
+    Missing,
+    Loaded(IconData),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IconCache {
+    theme: Option<String>,
+    icons: HashMap<(String, u16), IconState>,
+}
+
+impl IconCache {
+    /// The loaded icon, `None` while it is loading or if neither it nor the fallback exist
+    pub fn get(&self, name: &str, size: u16) -> Option<&IconData> {
+        match self.icons.get(&(name.to_string(), size)) {
+            Some(IconState::Loaded(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Start loading the icons that are not cached yet
+    pub fn request<'a, M: 'static>(
+        &mut self,
+        names: impl Iterator<Item = &'a str>,
+        size: u16,
+        on_load: fn(String, u16, Option<IconData>) -> M,
+    ) -> Vec<iced::Command<M>> {
+        let mut commands = Vec::new();
+        for name in names {
+            if name.is_empty() || self.icons.contains_key(&(name.to_string(), size)) {
+                continue;
+            }
+            self.icons
+                .insert((name.to_string(), size), IconState::Loading);
+            let name = name.to_string();
+            let theme = self.theme.clone();
+            commands.push(iced::Command::perform(
+                load(name.clone(), size, theme),
+                move |data| on_load(name.clone(), size, data),
+            ));
+        }
+        commands
+    }
+
+    pub fn insert(&mut self, name: String, size: u16, data: Option<IconData>) {
+        self.icons.insert(
+            (name, size),
+            data.map_or(IconState::Missing, IconState::Loaded),
+        );
+    }
+
+    /// Switch to another theme, dropping the icons loaded from the previous one.
+    /// An empty name stands for the default theme.
+    pub fn set_theme(&mut self, theme: String) {
+        let theme = (!theme.is_empty()).then_some(theme);
+        if self.theme != theme {
+            self.theme = theme;
+            self.icons.clear();
+        }
+    }
+}
+
+fn lookup(name: &str, size: u16, theme: Option<&str>) -> Option<PathBuf> {
+    if Path::new(name).is_absolute() {
+        return Some(PathBuf::from(name));
+    }
+    let lookup = freedesktop_icons::lookup(name).with_size(size).with_cache();
+    match theme {
+        Some(theme) => lookup.with_theme(theme).find(),
+        None => lookup.find(),
+    }
+}
+
+async fn load(name: String, size: u16, theme: Option<String>) -> Option<IconData> {
+    tokio::task::spawn_blocking(move || {
+        let path = lookup(&name, size, theme.as_deref())
+            .or_else(|| lookup(FALLBACK_ICON, size, theme.as_deref()))?;
+        let bytes = std::fs::read(&path).ok()?;
+        Some(if path.extension().map_or(false, |e| e == "svg") {
+            IconData::Svg(svg::Handle::from_memory(bytes))
+        } else {
+            IconData::Raster(image::Handle::from_memory(bytes))
+        })
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Emits the icon theme of the COSMIC toolkit, and again whenever it changes
+pub fn icon_theme_subscription() -> iced::Subscription<String> {
+    cosmic_config::config_subscription::<_, ToolkitConfig>(1, Cow::from(TOOLKIT_CONFIG), 1).map(
+        |(_, config)| match config {
+            Ok(config) => config.icon_theme,
+            // an unset theme is not worth logging, it leaves the default one
+            Err((_, config)) => config.icon_theme,
+        },
+    )
+}
@@ -0,0 +1,177 @@
+//! Badges and progress of apps, as published with the `com.canonical.Unity.LauncherEntry`
+//! D-Bus protocol by mail clients, download managers and the like
+
+use cosmic::iced::{self, subscription};
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use zbus::{
+    fdo::DBusProxy,
+    names::{BusName, OwnedUniqueName},
+    zvariant::OwnedValue,
+    Connection, MatchRule, MessageStream, MessageType,
+};
+
+/// What an app shows on its icon
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LauncherEntry {
+    pub count: Option<i64>,
+    /// From 0 to 1
+    pub progress: Option<f64>,
+    pub urgent: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum LauncherEntryUpdate {
+    /// The entry of the app with the desktop id changed
+    Entry(String, LauncherEntry),
+    /// The app left the bus, so its entry is gone
+    Removed(String),
+}
+
+/// The properties as sent, updates only carry the ones that changed
+#[derive(Debug, Clone, Default)]
+struct Properties {
+    count: i64,
+    count_visible: bool,
+    progress: f64,
+    progress_visible: bool,
+    urgent: bool,
+}
+
+impl Properties {
+    fn update(&mut self, properties: &HashMap<String, OwnedValue>) {
+        for (key, value) in properties {
+            match key.as_str() {
+                "count" => {
+                    if let Ok(count) = i64::try_from(value.clone()) {
+                        self.count = count;
+                    }
+                }
+                "count-visible" => {
+                    if let Ok(visible) = bool::try_from(value.clone()) {
+                        self.count_visible = visible;
+                    }
+                }
+                "progress" => {
+                    if let Ok(progress) = f64::try_from(value.clone()) {
+                        self.progress = progress.clamp(0.0, 1.0);
+                    }
+                }
+                "progress-visible" => {
+                    if let Ok(visible) = bool::try_from(value.clone()) {
+                        self.progress_visible = visible;
+                    }
+                }
+                "urgent" => {
+                    if let Ok(urgent) = bool::try_from(value.clone()) {
+                        self.urgent = urgent;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn entry(&self) -> LauncherEntry {
+        LauncherEntry {
+            count: (self.count_visible && self.count > 0).then_some(self.count),
+            progress: self.progress_visible.then_some(self.progress),
+            urgent: self.urgent,
+        }
+    }
+}
+
+/// The desktop id of an app uri like `application://firefox.desktop`
+fn desktop_id(app_uri: &str) -> &str {
+    let id = app_uri.strip_prefix("application://").unwrap_or(app_uri);
+    id.strip_suffix(".desktop").unwrap_or(id)
+}
+
+async fn listen(
+    output: &mut futures::channel::mpsc::Sender<LauncherEntryUpdate>,
+) -> zbus::Result<()> {
+    let conn = Connection::session().await?;
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface("com.canonical.Unity.LauncherEntry")?
+        .member("Update")?
+        .build();
+    let mut updates = MessageStream::for_match_rule(rule, &conn, None)
+        .await?
+        .fuse();
+    let dbus_proxy = DBusProxy::new(&conn).await?;
+    let mut name_owner_changed = dbus_proxy.receive_name_owner_changed().await?.fuse();
+    let mut apps: HashMap<String, (OwnedUniqueName, Properties)> = HashMap::new();
+
+    loop {
+        futures::select! {
+            msg = updates.next() => {
+                let Some(msg) = msg else {
+                    break;
+                };
+                let Ok(msg) = msg else {
+                    continue;
+                };
+                let Some(sender) = msg
+                    .header()
+                    .ok()
+                    .and_then(|h| h.sender().ok().flatten().map(|s| s.to_owned()))
+                else {
+                    continue;
+                };
+                let Ok((app_uri, properties)) =
+                    msg.body::<(String, HashMap<String, OwnedValue>)>()
+                else {
+                    continue;
+                };
+                let id = desktop_id(&app_uri).to_string();
+                let (owner, props) = apps
+                    .entry(id.clone())
+                    .or_insert_with(|| (sender.clone().into(), Properties::default()));
+                *owner = sender.into();
+                props.update(&properties);
+                let entry = props.entry();
+                _ = output.send(LauncherEntryUpdate::Entry(id, entry)).await;
+            }
+            changed = name_owner_changed.next() => {
+                let Some(changed) = changed else {
+                    break;
+                };
+                let Ok(args) = changed.args() else {
+                    continue;
+                };
+                let BusName::Unique(name) = &args.name else {
+                    continue;
+                };
+                if args.new_owner.is_some() {
+                    continue;
+                }
+                let gone: Vec<_> = apps
+                    .iter()
+                    .filter(|(_, (owner, _))| **owner == *name)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in gone {
+                    apps.remove(&id);
+                    _ = output.send(LauncherEntryUpdate::Removed(id)).await;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn launcher_entries() -> iced::Subscription<LauncherEntryUpdate> {
+    struct LauncherEntryWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<LauncherEntryWorker>(),
+        50,
+        |mut output| async move {
+            if let Err(err) = listen(&mut output).await {
+                log::error!("Failed to listen for launcher entries: {}", err);
+            }
+            futures::future::pending().await
+        },
+    )
+}
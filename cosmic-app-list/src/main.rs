@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0-only
 mod app;
 mod config;
+mod dbus;
+mod icon_cache;
+mod launcher_entry;
 mod localize;
 mod toplevel_handler;
 mod toplevel_subscription;
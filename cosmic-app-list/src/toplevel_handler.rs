@@ -1,32 +1,47 @@
 use crate::toplevel_subscription::{ToplevelRequest, ToplevelUpdate};
 use cctk::{
+    screencopy::{BufferInfo, ScreencopyHandler, ScreencopyState},
     sctk::{
         self,
+        output::{OutputHandler, OutputState},
         reexports::{
             calloop,
-            client::{protocol::wl_seat::WlSeat, WaylandSource},
+            client::{
+                protocol::{wl_buffer, wl_output::WlOutput, wl_seat::WlSeat},
+                WaylandSource,
+            },
         },
         seat::{SeatHandler, SeatState},
+        shm::{Shm, ShmHandler},
     },
     toplevel_info::{ToplevelInfoHandler, ToplevelInfoState},
     toplevel_management::{ToplevelManagerHandler, ToplevelManagerState},
     wayland_client::{self, WEnum},
 };
+use cosmic_applet_common::screencopy::Captures;
 use cosmic_protocols::{
+    screencopy::v1::client::zcosmic_screencopy_session_v1,
     toplevel_info::v1::client::zcosmic_toplevel_handle_v1,
     toplevel_management::v1::client::zcosmic_toplevel_manager_v1,
 };
 use futures::channel::mpsc::UnboundedSender;
 use sctk::registry::{ProvidesRegistryState, RegistryState};
-use wayland_client::{globals::registry_queue_init, Connection, QueueHandle};
+use wayland_client::{globals::registry_queue_init, Connection, Dispatch, QueueHandle};
 
 struct AppData {
     exit: bool,
     tx: UnboundedSender<ToplevelUpdate>,
     registry_state: RegistryState,
+    output_state: OutputState,
+    /// The name of the output of the panel the app list is on
+    configured_output: String,
     toplevel_info_state: ToplevelInfoState,
     toplevel_manager_state: ToplevelManagerState,
     seat_state: SeatState,
+    screencopy_state: ScreencopyState,
+    shm: Shm,
+    captures: Captures,
+    queue_handle: QueueHandle<Self>,
 }
 
 impl ProvidesRegistryState for AppData {
@@ -34,7 +49,26 @@ impl ProvidesRegistryState for AppData {
         &mut self.registry_state
     }
 
-    sctk::registry_handlers!();
+    sctk::registry_handlers![OutputState];
+}
+
+impl OutputHandler for AppData {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        let Some(info) = self.output_state.info(&output) else {
+            return;
+        };
+        if info.name.as_deref() == Some(&self.configured_output) {
+            let _ = self.tx.unbounded_send(ToplevelUpdate::Output(output));
+        }
+    }
+
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
+
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
 }
 
 impl SeatHandler for AppData {
@@ -80,6 +114,64 @@ impl ToplevelManagerHandler for AppData {
     }
 }
 
+impl ShmHandler for AppData {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for AppData {
+    fn event(
+        _: &mut Self,
+        _: &wl_buffer::WlBuffer,
+        _: wl_buffer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl ScreencopyHandler for AppData {
+    fn screencopy_state(&mut self) -> &mut ScreencopyState {
+        &mut self.screencopy_state
+    }
+
+    fn init_done(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        session: &zcosmic_screencopy_session_v1::ZcosmicScreencopySessionV1,
+        buffer_infos: &[BufferInfo],
+    ) {
+        self.captures
+            .init_done(&self.shm, qh, session, buffer_infos);
+    }
+
+    fn ready(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        session: &zcosmic_screencopy_session_v1::ZcosmicScreencopySessionV1,
+    ) {
+        if let Some((toplevel, image)) = self.captures.ready(session) {
+            let _ = self
+                .tx
+                .unbounded_send(ToplevelUpdate::Preview(toplevel, image));
+        }
+    }
+
+    fn failed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        session: &zcosmic_screencopy_session_v1::ZcosmicScreencopySessionV1,
+        _reason: WEnum<zcosmic_screencopy_session_v1::FailureReason>,
+    ) {
+        self.captures.failed(session);
+    }
+}
+
 impl ToplevelInfoHandler for AppData {
     fn toplevel_info_state(&mut self) -> &mut ToplevelInfoState {
         &mut self.toplevel_info_state
@@ -149,6 +241,15 @@ pub(crate) fn toplevel_handler(
                     let manager = &state.toplevel_manager_state.manager;
                     manager.close(&handle);
                 }
+                ToplevelRequest::Minimize(handle) => {
+                    let manager = &state.toplevel_manager_state.manager;
+                    manager.set_minimized(&handle);
+                }
+                ToplevelRequest::Capture(handle) => {
+                    state
+                        .captures
+                        .capture(&state.screencopy_state, &state.queue_handle, handle);
+                }
                 ToplevelRequest::Exit => {
                     state.exit = true;
                 }
@@ -165,9 +266,16 @@ pub(crate) fn toplevel_handler(
     let mut app_data = AppData {
         exit: false,
         tx,
+        // created before the toplevel info, so toplevels know their outputs
+        output_state: OutputState::new(&globals, &qh),
+        configured_output: std::env::var("COSMIC_PANEL_OUTPUT").unwrap_or_default(),
         seat_state: SeatState::new(&globals, &qh),
         toplevel_info_state: ToplevelInfoState::new(&registry_state, &qh),
         toplevel_manager_state: ToplevelManagerState::new(&registry_state, &qh),
+        screencopy_state: ScreencopyState::new(&globals, &qh),
+        shm: Shm::bind(&globals, &qh).unwrap(),
+        captures: Captures::default(),
+        queue_handle: qh.clone(),
         registry_state,
     };
 
@@ -180,6 +288,9 @@ pub(crate) fn toplevel_handler(
 }
 
 sctk::delegate_seat!(AppData);
+sctk::delegate_output!(AppData);
 sctk::delegate_registry!(AppData);
 cctk::delegate_toplevel_info!(AppData);
 cctk::delegate_toplevel_manager!(AppData);
+cctk::delegate_screencopy!(AppData);
+sctk::delegate_shm!(AppData);
@@ -2,10 +2,14 @@
 //!
 //! This code was generated by `zbus-xmlgen` `2.0.1` from DBus introspection data.
 //! Source: `Interface '/org/freedesktop/UPower/KbdBacklight' from service 'org.freedesktop.UPower' on system bus`.
-use cctk::sctk::reexports::{calloop, client::protocol::wl_seat::WlSeat};
+use cctk::sctk::reexports::{
+    calloop,
+    client::protocol::{wl_output::WlOutput, wl_seat::WlSeat},
+};
 use cctk::toplevel_info::ToplevelInfo;
 use cosmic::iced;
 use cosmic::iced::subscription;
+use cosmic::iced::widget::image;
 use cosmic_protocols::toplevel_info::v1::client::zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1;
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver},
@@ -78,6 +82,10 @@ pub enum ToplevelUpdate {
     AddToplevel(ZcosmicToplevelHandleV1, ToplevelInfo),
     UpdateToplevel(ZcosmicToplevelHandleV1, ToplevelInfo),
     RemoveToplevel(ZcosmicToplevelHandleV1),
+    /// A fresh thumbnail of the toplevel, captured on request
+    Preview(ZcosmicToplevelHandleV1, image::Handle),
+    /// The output of the panel, to filter toplevels by
+    Output(WlOutput),
     Init(calloop::channel::Sender<ToplevelRequest>),
 }
 
@@ -85,5 +93,8 @@ pub enum ToplevelUpdate {
 pub enum ToplevelRequest {
     Activate(ZcosmicToplevelHandleV1, WlSeat),
     Quit(ZcosmicToplevelHandleV1),
+    Minimize(ZcosmicToplevelHandleV1),
+    /// Capture a thumbnail of the toplevel, answered with a `ToplevelUpdate::Preview`
+    Capture(ZcosmicToplevelHandleV1),
     Exit,
 }
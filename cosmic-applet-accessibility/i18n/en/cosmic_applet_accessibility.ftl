@@ -0,0 +1,4 @@
+high-contrast = High Contrast
+screen-reader = Screen Reader
+mono-audio = Mono Audio
+unavailable = The accessibility settings could not be read.
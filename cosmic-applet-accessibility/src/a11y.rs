@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The accessibility settings the desktop acts on: high contrast, a setting of the COSMIC themes
+//! that libcosmic applications draw with, the screen reader, which is announced on the
+//! accessibility bus so that toolkits expose the contents of their windows to Orca, and mono
+//! audio, a sink of the sound server that the default output is routed through
+
+use cosmic::cosmic_config::{Config, ConfigGet, ConfigSet};
+use tokio::process::Command;
+
+const THEME_MODE: &str = "com.system76.CosmicTheme.Mode";
+/// High contrast is set in both themes, so it stays on when switching between dark and light
+const THEMES: [&str; 2] = [
+    "com.system76.CosmicTheme.Dark",
+    "com.system76.CosmicTheme.Light",
+];
+const THEME_VERSION: u64 = 1;
+const HIGH_CONTRAST: &str = "is_high_contrast";
+/// Name of the sink that mixes the output down to mono. It's made the default sink, so the audio
+/// applet shows it as the output in use, and choosing another output there turns mono off.
+const MONO_SINK: &str = "cosmic_mono";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    HighContrast,
+    ScreenReader,
+    MonoAudio,
+}
+
+pub const SETTINGS: [Setting; 3] = [
+    Setting::HighContrast,
+    Setting::ScreenReader,
+    Setting::MonoAudio,
+];
+
+#[zbus::dbus_proxy(
+    interface = "org.a11y.Status",
+    default_service = "org.a11y.Bus",
+    default_path = "/org/a11y/bus"
+)]
+trait Status {
+    #[dbus_proxy(property)]
+    fn screen_reader_enabled(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn set_screen_reader_enabled(&self, value: bool) -> zbus::Result<()>;
+}
+
+async fn status() -> zbus::Result<StatusProxy<'static>> {
+    let conn = zbus::Connection::session().await?;
+    StatusProxy::new(&conn).await
+}
+
+/// Whether the theme in use is high contrast
+fn high_contrast() -> Result<bool, String> {
+    let is_dark = Config::new(THEME_MODE, THEME_VERSION)
+        .ok()
+        .and_then(|config| config.get::<bool>("is_dark").ok())
+        .unwrap_or(true);
+    let theme = if is_dark { THEMES[0] } else { THEMES[1] };
+    let config = Config::new(theme, THEME_VERSION).map_err(|err| format!("{:?}", err))?;
+    Ok(config.get::<bool>(HIGH_CONTRAST).unwrap_or(false))
+}
+
+async fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .output()
+        .await
+        .map_err(|err| format!("pactl: {}", err))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The ids and output sinks of the loaded mono remapping modules
+async fn mono_modules() -> Result<Vec<(String, String)>, String> {
+    let sink_name = format!("sink_name={MONO_SINK}");
+    let modules = pactl(&["list", "short", "modules"]).await?;
+    Ok(modules
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let id = fields.next()?;
+            if fields.next()? != "module-remap-sink" {
+                return None;
+            }
+            let mut args = fields.next()?.split_whitespace();
+            if !args.clone().any(|arg| arg == sink_name) {
+                return None;
+            }
+            let master = args.find_map(|arg| arg.strip_prefix("master="))?;
+            Some((id.to_string(), master.to_string()))
+        })
+        .collect())
+}
+
+/// Routes the default output through a sink that mixes it down to mono, or back to the output
+async fn mono_audio(enabled: bool) -> Result<(), String> {
+    let modules = mono_modules().await?;
+    let default = pactl(&["get-default-sink"]).await?;
+    // the output the mono sink was made for, unless another one was chosen since
+    let output = match modules.first() {
+        Some((_, master)) if default == MONO_SINK => master.clone(),
+        _ => default,
+    };
+    for (id, _) in &modules {
+        pactl(&["unload-module", id]).await?;
+    }
+    if enabled {
+        pactl(&[
+            "load-module",
+            "module-remap-sink",
+            &format!("sink_name={MONO_SINK}"),
+            &format!("master={output}"),
+            "channels=1",
+            "channel_map=mono",
+            "sink_properties=device.description=Mono",
+        ])
+        .await?;
+        pactl(&["set-default-sink", MONO_SINK]).await?;
+    } else {
+        pactl(&["set-default-sink", &output]).await?;
+    }
+    Ok(())
+}
+
+async fn get(setting: Setting) -> Result<bool, String> {
+    match setting {
+        Setting::HighContrast => high_contrast(),
+        Setting::ScreenReader => {
+            let status = status().await.map_err(|err| err.to_string())?;
+            status
+                .screen_reader_enabled()
+                .await
+                .map_err(|err| err.to_string())
+        }
+        Setting::MonoAudio => Ok(pactl(&["get-default-sink"]).await? == MONO_SINK),
+    }
+}
+
+/// Whether each setting is enabled, leaving out those that can't be read, such as the screen
+/// reader in sessions without an accessibility bus
+pub async fn states() -> Vec<(Setting, bool)> {
+    let mut states = Vec::new();
+    for setting in SETTINGS {
+        match get(setting).await {
+            Ok(enabled) => states.push((setting, enabled)),
+            Err(err) => log::error!("Failed to read {:?}: {}", setting, err),
+        }
+    }
+    states
+}
+
+/// Announces the screen reader on the accessibility bus, so that applications expose what's in
+/// their windows, and starts or stops Orca
+async fn screen_reader(enabled: bool) -> Result<(), String> {
+    let status = status().await.map_err(|err| err.to_string())?;
+    status
+        .set_screen_reader_enabled(enabled)
+        .await
+        .map_err(|err| err.to_string())?;
+    let arg = if enabled { "--replace" } else { "--quit" };
+    Command::new("orca")
+        .arg(arg)
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("orca: {}", err))
+}
+
+pub async fn set(setting: Setting, enabled: bool) -> Result<(), String> {
+    match setting {
+        Setting::HighContrast => {
+            for theme in THEMES {
+                Config::new(theme, THEME_VERSION)
+                    .and_then(|config| config.set(HIGH_CONTRAST, enabled))
+                    .map_err(|err| format!("{}: {:?}", theme, err))?;
+            }
+            Ok(())
+        }
+        Setting::ScreenReader => screen_reader(enabled).await,
+        Setting::MonoAudio => mono_audio(enabled).await,
+    }
+}
@@ -0,0 +1,156 @@
+use crate::a11y::{self, Setting};
+use crate::fl;
+use cosmic::app::{self, Command};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{text, Column},
+    window, Length,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::widget::toggler;
+use cosmic::{Element, Theme};
+
+const ICON: &str = "preferences-desktop-accessibility-symbolic";
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Accessibility>(true, ())
+}
+
+#[derive(Default)]
+struct Accessibility {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    /// Whether each setting that could be read is enabled
+    states: Vec<(Setting, bool)>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    States(Vec<(Setting, bool)>),
+    Toggle(Setting, bool),
+    Toggled(Result<(), String>),
+}
+
+fn setting_name(setting: Setting) -> String {
+    match setting {
+        Setting::HighContrast => fl!("high-contrast"),
+        Setting::ScreenReader => fl!("screen-reader"),
+        Setting::MonoAudio => fl!("mono-audio"),
+    }
+}
+
+fn read_states() -> Command<Message> {
+    cosmic::iced::Command::perform(a11y::states(), |states| {
+        app::message::app(Message::States(states))
+    })
+}
+
+impl cosmic::Application for Accessibility {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = "com.system76.CosmicAppletAccessibility";
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Accessibility {
+                core,
+                ..Default::default()
+            },
+            read_states(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.error = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                // the settings may have been changed elsewhere since they were read
+                return Command::batch(vec![get_popup(popup_settings), read_states()]);
+            }
+            Message::States(states) => {
+                self.states = states;
+            }
+            Message::Toggle(setting, enabled) => {
+                // shown at once, and read again once it's been written
+                if let Some(state) = self.states.iter_mut().find(|(s, _)| *s == setting) {
+                    state.1 = enabled;
+                }
+                return cosmic::iced::Command::perform(a11y::set(setting, enabled), |res| {
+                    app::message::app(Message::Toggled(res))
+                });
+            }
+            Message::Toggled(res) => {
+                if let Err(err) = &res {
+                    log::error!("Failed to change an accessibility setting: {}", err);
+                }
+                self.error = res.err();
+                return read_states();
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        self.core
+            .applet_helper
+            .icon_button(ICON)
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        for &(setting, enabled) in &self.states {
+            content = content.push(
+                toggler(setting_name(setting), enabled, move |enabled| {
+                    Message::Toggle(setting, enabled)
+                })
+                .text_size(14)
+                .width(Length::Fill),
+            );
+        }
+        if self.states.is_empty() {
+            content = content.push(text(fl!("unavailable")).size(14));
+        }
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(12));
+        }
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+}
@@ -0,0 +1,9 @@
+mod a11y;
+mod app;
+mod localize;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
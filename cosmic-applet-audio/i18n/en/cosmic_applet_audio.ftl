@@ -4,3 +4,5 @@ show-media-controls = Show Media Controls on Top Panel
 sound-settings = Sound Settings...
 disconnected = PulseAudio Disconnected
 no-device = No device selected
+mute-on-lock = Mute when the screen is locked
+mute-on-suspend = Mute when suspending
@@ -0,0 +1,31 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletAudio";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct AudioAppletConfig {
+    /// Mute outputs while the session is locked
+    pub mute_on_lock: bool,
+    /// Mute outputs while the system is suspended
+    pub mute_on_suspend: bool,
+}
+
+impl AudioAppletConfig {
+    pub fn load() -> AudioAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| AudioAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_mute_on_lock(&mut self, mute_on_lock: bool, config: &Config) {
+        self.mute_on_lock = mute_on_lock;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_mute_on_suspend(&mut self, mute_on_suspend: bool, config: &Config) {
+        self.mute_on_suspend = mute_on_suspend;
+        let _ = self.write_entry(config);
+    }
+}
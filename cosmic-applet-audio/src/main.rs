@@ -1,13 +1,16 @@
+mod config;
 mod localize;
+mod session;
 
 use cosmic::app::Command;
+use cosmic::cosmic_config::{self, Config};
 use cosmic::iced::widget;
 use cosmic::iced::Limits;
 use cosmic::iced_runtime::core::alignment::Horizontal;
 use cosmic::theme::Svg;
 
 use cosmic::app::applet::applet_button_theme;
-use cosmic::widget::{button, divider, icon};
+use cosmic::widget::{button, divider, icon, toggler};
 use cosmic::Renderer;
 
 use cosmic::iced::{
@@ -21,8 +24,10 @@ use cosmic_time::{anim, chain, id, once_cell::sync::Lazy, Instant, Timeline};
 
 use iced::wayland::popup::{destroy_popup, get_popup};
 use iced::widget::container;
+use std::borrow::Cow;
 
 mod pulse;
+use crate::config::AudioAppletConfig;
 use crate::localize::localize;
 use crate::pulse::DeviceInfo;
 use libpulse_binding::volume::VolumeLinear;
@@ -53,6 +58,11 @@ struct Audio {
     show_media_controls_in_top_panel: bool,
     id_ctr: u128,
     timeline: Timeline,
+    config: AudioAppletConfig,
+    muted_for_lock: bool,
+    muted_for_suspend: bool,
+    /// Mute state of each output before it was muted automatically
+    restore_mute: Vec<(String, bool)>,
 }
 
 impl Audio {
@@ -82,6 +92,30 @@ impl Audio {
         }
     }
 
+    fn set_auto_mute(&mut self, mute: bool) {
+        let Some(conn) = self.pulse_state.connection() else {
+            return;
+        };
+        if mute {
+            let outputs: Vec<&DeviceInfo> = if self.outputs.is_empty() {
+                self.current_output.iter().collect()
+            } else {
+                self.outputs.iter().collect()
+            };
+            self.restore_mute = outputs
+                .into_iter()
+                .filter_map(|o| Some((o.name.clone()?, o.mute)))
+                .collect();
+            for (name, _) in &self.restore_mute {
+                conn.send(pulse::Message::SetSinkMuteByName(name.clone(), true));
+            }
+        } else {
+            for (name, mute) in self.restore_mute.drain(..) {
+                conn.send(pulse::Message::SetSinkMuteByName(name, mute));
+            }
+        }
+    }
+
     fn update_input(&mut self, input: Option<DeviceInfo>) {
         self.current_input = input;
         self.apply_input_volume();
@@ -125,6 +159,10 @@ enum Message {
     Pulse(pulse::Event),
     TogglePopup,
     ToggleMediaControlsInTopPanel(chain::Toggler, bool),
+    ToggleMuteOnLock(bool),
+    ToggleMuteOnSuspend(bool),
+    Session(session::Event),
+    ConfigUpdated(AudioAppletConfig),
     Frame(Instant),
 }
 
@@ -132,7 +170,7 @@ impl cosmic::Application for Audio {
     type Message = Message;
     type Executor = cosmic::SingleThreadExecutor;
     type Flags = ();
-    const APP_ID: &'static str = "com.system76.CosmicAppletAudio";
+    const APP_ID: &'static str = config::APP_ID;
 
     fn init(core: cosmic::app::Core, _flags: ()) -> (Audio, Command<Message>) {
         (
@@ -145,6 +183,7 @@ impl cosmic::Application for Audio {
                 inputs: vec![],
                 icon_name: "audio-volume-high-symbolic".to_string(),
                 input_icon_name: "audio-input-microphone-symbolic".to_string(),
+                config: AudioAppletConfig::load(),
                 ..Default::default()
             },
             Command::none(),
@@ -317,6 +356,33 @@ impl cosmic::Application for Audio {
                 self.timeline.set_chain(chain).start();
                 self.show_media_controls_in_top_panel = enabled;
             }
+            Message::ToggleMuteOnLock(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_mute_on_lock(enabled, &config);
+                }
+            }
+            Message::ToggleMuteOnSuspend(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_mute_on_suspend(enabled, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+            Message::Session(event) => {
+                let was_muted = self.muted_for_lock || self.muted_for_suspend;
+                match event {
+                    session::Event::Lock => self.muted_for_lock = self.config.mute_on_lock,
+                    session::Event::Unlock => self.muted_for_lock = false,
+                    session::Event::Suspend => self.muted_for_suspend = self.config.mute_on_suspend,
+                    session::Event::Resume => self.muted_for_suspend = false,
+                }
+                // stay muted until both the lock and the suspend that caused it are over
+                let muted = self.muted_for_lock || self.muted_for_suspend;
+                if muted != was_muted {
+                    self.set_auto_mute(muted);
+                }
+            }
         };
 
         Command::none()
@@ -325,9 +391,21 @@ impl cosmic::Application for Audio {
     fn subscription(&self) -> Subscription<Message> {
         Subscription::batch(vec![
             pulse::connect().map(Message::Pulse),
+            session::session_events().map(Message::Session),
             self.timeline
                 .as_subscription()
                 .map(|(_, now)| Message::Frame(now)),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
         ])
     }
 
@@ -446,6 +524,24 @@ impl cosmic::Application for Audio {
                 .text_size(14)
             )
             .padding([0, 24]),
+            container(
+                column![
+                    toggler(fl!("mute-on-lock"), self.config.mute_on_lock, |enabled| {
+                        Message::ToggleMuteOnLock(enabled)
+                    })
+                    .text_size(14)
+                    .width(Length::Fill),
+                    toggler(
+                        fl!("mute-on-suspend"),
+                        self.config.mute_on_suspend,
+                        |enabled| Message::ToggleMuteOnSuspend(enabled)
+                    )
+                    .text_size(14)
+                    .width(Length::Fill),
+                ]
+                .spacing(8)
+            )
+            .padding([8, 24]),
             container(divider::horizontal::light())
                 .padding([12, 24])
                 .width(Length::Fill),
@@ -149,6 +149,7 @@ pub enum Message {
     SetDefaultSource(DeviceInfo),
     SetSinkVolumeByName(String, ChannelVolumes),
     SetSourceVolumeByName(String, ChannelVolumes),
+    SetSinkMuteByName(String, bool),
 }
 
 struct PulseHandle {
@@ -261,6 +262,13 @@ impl PulseHandle {
                                 };
                                 server.set_source_volume_by_name(&name, &channel_volumes)
                             }
+                            Message::SetSinkMuteByName(name, mute) => {
+                                let server = match server.as_mut() {
+                                    Some(s) => s,
+                                    None => continue,
+                                };
+                                server.set_sink_mute_by_name(&name, mute)
+                            }
                             Message::UpdateConnection => {
                                 log::info!(
                                     "Updating Connection, server exists: {:?}",
@@ -608,6 +616,11 @@ impl PulseServer {
         self.wait_for_result(op).ok();
     }
 
+    fn set_sink_mute_by_name(&mut self, name: &str, mute: bool) {
+        let op = self.introspector.set_sink_mute_by_name(name, mute, None);
+        self.wait_for_result(op).ok();
+    }
+
     fn get_source_outputs(&mut self, source: u32) -> Vec<u32> {
         let result = Rc::new(RefCell::new(Vec::new()));
         let result_ref = Rc::new(RefCell::new(Vec::new()));
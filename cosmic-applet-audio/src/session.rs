@@ -0,0 +1,62 @@
+use cosmic::iced::{self, subscription};
+use cosmic::iced_futures::futures::{self, stream, SinkExt, StreamExt};
+use logind_zbus::{manager::ManagerProxy, session::SessionProxy};
+use zbus::Connection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Lock,
+    Unlock,
+    Suspend,
+    Resume,
+}
+
+/// Listen for logind lock/unlock requests on our session and system sleep
+pub fn session_events() -> iced::Subscription<Event> {
+    struct SessionWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<SessionWorker>(),
+        10,
+        |mut output| async move {
+            if let Err(err) = listen(&mut output).await {
+                log::error!("Failed to listen for session events: {}", err);
+            }
+            futures::future::pending().await
+        },
+    )
+}
+
+async fn listen(output: &mut futures::channel::mpsc::Sender<Event>) -> zbus::Result<()> {
+    let conn = Connection::system().await?;
+    let manager = ManagerProxy::new(&conn).await?;
+    let session = SessionProxy::builder(&conn)
+        .path("/org/freedesktop/login1/session/auto")?
+        .build()
+        .await?;
+
+    let sleep = manager
+        .receive_prepare_for_sleep()
+        .await?
+        .filter_map(|signal| async move {
+            let args = signal.args().ok()?;
+            Some(if *args.start() {
+                Event::Suspend
+            } else {
+                Event::Resume
+            })
+        })
+        .boxed();
+    let lock = session.receive_lock().await?.map(|_| Event::Lock).boxed();
+    let unlock = session
+        .receive_unlock()
+        .await?
+        .map(|_| Event::Unlock)
+        .boxed();
+
+    let mut events = stream::select_all([sleep, lock, unlock]);
+    while let Some(event) = events.next().await {
+        _ = output.send(event).await;
+    }
+    Ok(())
+}
@@ -11,3 +11,4 @@ minutes = m
 hours = h
 until-empty = until empty
 power-settings = Power and Battery Settings...
+plug-feedback = Sound on plug and unplug
@@ -1,7 +1,7 @@
 use crate::backlight::{
     screen_backlight_subscription, ScreenBacklightRequest, ScreenBacklightUpdate,
 };
-use crate::config;
+use crate::config::{self, BatteryAppletConfig};
 use crate::fl;
 use crate::power_daemon::{
     power_profile_subscription, Power, PowerProfileRequest, PowerProfileUpdate,
@@ -11,6 +11,7 @@ use crate::upower_kbdbacklight::{
     kbd_backlight_subscription, KeyboardBacklightRequest, KeyboardBacklightUpdate,
 };
 use cosmic::app::{applet::applet_button_theme, Command};
+use cosmic::cosmic_config::{self, Config};
 use cosmic::iced::alignment::Horizontal;
 use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
 use cosmic::iced::{
@@ -20,11 +21,13 @@ use cosmic::iced::{
 use cosmic::iced_runtime::core::layout::Limits;
 use cosmic::iced_style::application;
 use cosmic::theme::Svg;
-use cosmic::widget::{button, divider, icon};
+use cosmic::widget::{button, divider, icon, toggler};
 use cosmic::{Element, Theme};
 use cosmic_time::{anim, chain, id, once_cell::sync::Lazy, Instant, Timeline};
 
-use log::error;
+use log::{error, info};
+use std::borrow::Cow;
+use std::process;
 use std::time::Duration;
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -68,6 +71,9 @@ struct CosmicBatteryApplet {
     power_profile: Power,
     power_profile_sender: Option<UnboundedSender<PowerProfileRequest>>,
     timeline: Timeline,
+    config: BatteryAppletConfig,
+    // whether `on_battery` has been received at least once
+    power_source_known: bool,
 }
 
 impl CosmicBatteryApplet {
@@ -118,6 +124,28 @@ impl CosmicBatteryApplet {
             format!("cosmic-applet-battery-display-brightness-{screen_brightness}-symbolic",);
     }
 
+    fn power_source_changed(&self, on_battery: bool) {
+        if on_battery {
+            info!("AC power disconnected");
+        } else {
+            info!("AC power connected");
+        }
+        if self.config.plug_feedback {
+            // sound names from the freedesktop sound theme
+            let sound = if on_battery {
+                "power-unplug"
+            } else {
+                "power-plug"
+            };
+            if let Err(err) = process::Command::new("canberra-gtk-play")
+                .args(["-i", sound])
+                .spawn()
+            {
+                error!("Failed to play {} sound: {}", sound, err);
+            }
+        }
+    }
+
     fn set_charging_limit(&mut self, limit: bool) {
         self.charging_limit = limit;
         self.update_battery(self.battery_percent, self.on_battery);
@@ -145,6 +173,8 @@ enum Message {
     Profile(Power),
     SelectProfile(Power),
     Frame(Instant),
+    SetPlugFeedback(bool),
+    ConfigUpdated(BatteryAppletConfig),
 }
 
 impl cosmic::Application for CosmicBatteryApplet {
@@ -159,6 +189,7 @@ impl cosmic::Application for CosmicBatteryApplet {
                 core,
                 icon_name: "battery-symbolic".to_string(),
                 display_icon_name: "display-brightness-symbolic".to_string(),
+                config: BatteryAppletConfig::load(),
                 ..Default::default()
             },
             Command::none(),
@@ -192,6 +223,14 @@ impl cosmic::Application for CosmicBatteryApplet {
                 self.timeline.set_chain(chain).start();
                 self.set_charging_limit(enable);
             }
+            Message::SetPlugFeedback(enable) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_plug_feedback(enable, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
             Message::OpenBatterySettings => {
                 // TODO Ashley
             }
@@ -236,6 +275,10 @@ impl cosmic::Application for CosmicBatteryApplet {
                 percent,
                 time_to_empty,
             } => {
+                if self.power_source_known && on_battery != self.on_battery {
+                    self.power_source_changed(on_battery);
+                }
+                self.power_source_known = true;
                 self.update_battery(percent, on_battery);
                 self.time_remaining = Duration::from_secs(time_to_empty as u64);
             }
@@ -386,6 +429,15 @@ impl cosmic::Application for CosmicBatteryApplet {
                     )
                     .padding([0, 24])
                     .width(Length::Fill),
+                    container(
+                        toggler(fl!("plug-feedback"), self.config.plug_feedback, |enable| {
+                            Message::SetPlugFeedback(enable)
+                        })
+                        .text_size(14)
+                        .width(Length::Fill)
+                    )
+                    .padding([0, 24])
+                    .width(Length::Fill),
                     container(divider::horizontal::light())
                         .width(Length::Fill)
                         .padding([0, 12]),
@@ -464,6 +516,17 @@ impl cosmic::Application for CosmicBatteryApplet {
             self.timeline
                 .as_subscription()
                 .map(|(_, now)| Message::Frame(now)),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
         ])
     }
 
@@ -1,3 +1,26 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
 pub const APP_ID: &str = "com.system76.CosmicAppletButton";
 pub const PROFILE: &str = "";
 pub const VERSION: &str = "0.1.0";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct BatteryAppletConfig {
+    /// Play a sound when AC power is connected or disconnected
+    pub plug_feedback: bool,
+}
+
+impl BatteryAppletConfig {
+    pub fn load() -> BatteryAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| BatteryAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_plug_feedback(&mut self, plug_feedback: bool, config: &Config) {
+        self.plug_feedback = plug_feedback;
+        let _ = self.write_entry(config);
+    }
+}
@@ -17,7 +17,7 @@ use crate::config::{PROFILE, VERSION};
 
 fn main() -> cosmic::iced::Result {
     // Initialize logger
-    pretty_env_logger::init();
+    pretty_env_logger::init_timed();
     info!("Iced Workspaces Applet ({})", APP_ID);
     info!("Version: {} ({})", VERSION, PROFILE);
 
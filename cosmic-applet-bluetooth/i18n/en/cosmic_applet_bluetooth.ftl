@@ -8,5 +8,28 @@ cancel = Cancel
 unsuccessful = Pairing Unsuccessful
 check-device = Make sure {$deviceName} is turned on, in range, and is ready to pair.
 try-again = Try Again
-discoverable = Discoverable
-pairable = Pairable
\ No newline at end of file
+pairable = Pairable
+weak-signal = Weak signal, audio may drop out
+display-pin = Type the following PIN on {$deviceName}, then press Enter
+authorize-device = Allow {$deviceName} to pair with this computer?
+enter-pin = Enter the PIN for {$deviceName}. Type the suggested PIN on the device, or replace it with the one from the device's manual
+enter-passkey = Enter the passkey displayed on {$deviceName}
+send-file = Send file…
+sending-file = Sending {$fileName} to {$deviceName}
+file-sent = Sent {$fileName} to {$deviceName}
+file-not-sent = Unable to send {$fileName} to {$deviceName}
+dismiss = Dismiss
+profile-high-fidelity = High fidelity
+profile-headset = Headset
+trusted = Trusted
+blocked = Blocked
+remove-device = Remove device
+remove-device-confirm = Remove {$deviceName}? It will have to be paired again.
+remove = Remove
+auto-connect = Connect automatically
+auto-connect-default = Connect devices in range automatically
+visible-as = Visible as {$name} for {$minutes} minutes
+visible-as-remaining = Visible as {$name} ({$time} left)
+reconnect-device = Reconnect {$deviceName}
+device-name = Device name
+hardware-switch-off = Turned off by a hardware switch
@@ -1,11 +1,21 @@
-use crate::bluetooth::{BluerDeviceStatus, BluerRequest, BluerState};
+use crate::audio_profile::{self, AudioProfile};
+use crate::bluetooth::{
+    BluerAgentEvent, BluerDeviceStatus, BluerRequest, BluerState, DISCOVERABLE_TIMEOUT,
+};
+use crate::obex::{self, TransferEvent};
+use crate::rfkill::{self, RfkillState};
+use ashpd::desktop::file_chooser::SelectedFiles;
+use bluer::Address;
 use cosmic::app::{applet::applet_button_theme, Command};
+use cosmic::cosmic_config::{self, Config};
 use cosmic::iced_style;
 use cosmic::{
     iced::{
         self,
         wayland::popup::{destroy_popup, get_popup},
-        widget::{column, container, row, scrollable, text, Column},
+        widget::{
+            column, container, mouse_area, progress_bar, row, scrollable, text, text_input, Column,
+        },
         Alignment, Length, Subscription,
     },
     iced_runtime::core::{
@@ -16,14 +26,18 @@ use cosmic::{
     iced_style::{application, button::StyleSheet},
     theme::{Button, Svg},
     widget::{button, divider, icon, toggler},
-    Element, Theme,
+    Application, Element, Theme,
 };
-use std::collections::HashMap;
-use std::time::Duration;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::Sender;
+use zbus::zvariant::OwnedObjectPath;
 
 use crate::bluetooth::{bluetooth_subscription, BluerDevice, BluerEvent};
-use crate::{config, fl};
+use crate::config::{self, BluetoothAppletConfig};
+use crate::fl;
 
 pub fn run() -> cosmic::iced::Result {
     cosmic::app::applet::run::<CosmicBluetoothApplet>(false, ())
@@ -39,18 +53,153 @@ struct CosmicBluetoothApplet {
     bluer_sender: Option<Sender<BluerRequest>>,
     // UI state
     show_visible_devices: bool,
-    request_confirmation: Option<(BluerDevice, String, Sender<bool>)>,
+    agent_request: Option<AgentRequest>,
+    transfer: Option<FileTransfer>,
+    audio_profiles: HashMap<Address, AudioProfile>,
+    /// Device whose settings are shown below it
+    expanded_device: Option<Address>,
+    /// Device waiting for the user to confirm its removal
+    confirm_remove: Option<Address>,
+    /// Name being typed for the expanded device
+    alias_input: String,
+    config: BluetoothAppletConfig,
+    /// Devices that were connected automatically since they came into range
+    auto_connected: HashSet<Address>,
+    /// When the adapter stops being discoverable
+    discoverable_until: Option<Instant>,
+    rfkill: RfkillState,
+}
+
+/// A file being sent to a device
+struct FileTransfer {
+    address: Address,
+    device_name: String,
+    file: PathBuf,
+    /// Set once obexd accepted the file
+    path: Option<OwnedObjectPath>,
+    transferred: u64,
+    size: u64,
+    status: TransferStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TransferStatus {
+    Active,
+    Finished,
+    Failed(String),
+}
+
+/// A pairing prompt from the BlueZ agent that is waiting for the user
+enum AgentRequest {
+    /// A PIN or passkey that has to be typed on the device
+    Display(BluerDevice, String),
+    Confirm(BluerDevice, String, Sender<bool>),
+    Authorize(BluerDevice, Sender<bool>),
+    PinCode(BluerDevice, String, Sender<Option<String>>),
+    Passkey(BluerDevice, String, Sender<Option<u32>>),
+}
+
+impl AgentRequest {
+    fn device(&self) -> &BluerDevice {
+        match self {
+            AgentRequest::Display(d, _)
+            | AgentRequest::Confirm(d, _, _)
+            | AgentRequest::Authorize(d, _)
+            | AgentRequest::PinCode(d, _, _)
+            | AgentRequest::Passkey(d, _, _) => d,
+        }
+    }
+
+    /// Whether the user entered everything needed to answer the request
+    fn is_ready(&self) -> bool {
+        match self {
+            AgentRequest::PinCode(_, pin, _) => !pin.is_empty(),
+            AgentRequest::Passkey(_, passkey, _) => parse_passkey(passkey).is_some(),
+            _ => true,
+        }
+    }
+}
+
+fn parse_passkey(input: &str) -> Option<u32> {
+    input.parse().ok().filter(|p| *p <= 999999)
+}
+
+fn reply<T: Send + 'static>(tx: Sender<T>, reply: T) -> Command<Message> {
+    iced::Command::perform(
+        async move {
+            let _ = tx.send(reply).await;
+        },
+        |_| cosmic::app::message::app(Message::Ignore),
+    )
 }
 
 impl CosmicBluetoothApplet {
     fn update_icon(&mut self) {
-        self.icon_name = if self.bluer_state.bluetooth_enabled {
+        self.icon_name = if self.bluer_state.bluetooth_enabled && !self.rfkill.is_blocked() {
             "cosmic-applet-bluetooth-active-symbolic"
         } else {
             "cosmic-applet-bluetooth-disabled-symbolic"
         }
         .to_string();
     }
+
+    fn set_state(&mut self, state: BluerState) {
+        let newly_connected_audio = state.devices.iter().find(|d| {
+            d.status == BluerDeviceStatus::Connected
+                && d.is_audio()
+                && !self.bluer_state.devices.iter().any(|old| {
+                    old.address == d.address && old.status == BluerDeviceStatus::Connected
+                })
+        });
+        if let Some(device) = newly_connected_audio {
+            if self.config.last_audio_device() != Some(device.address) {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_last_audio_device(device.address, &config);
+                }
+            }
+        }
+        self.discoverable_until = match (state.discoverable, self.discoverable_until) {
+            (true, None) => Some(Instant::now() + DISCOVERABLE_TIMEOUT),
+            (true, until) => until,
+            (false, _) => None,
+        };
+        self.bluer_state = state;
+    }
+
+    /// The most recently used audio device, if it can be reconnected
+    fn last_audio_device(&self) -> Option<&BluerDevice> {
+        let address = self.config.last_audio_device()?;
+        self.bluer_state
+            .devices
+            .iter()
+            .find(|d| d.address == address && d.status == BluerDeviceStatus::Paired)
+    }
+
+    /// Connect the paired devices that came into range, going by the discovery results
+    fn auto_connect(&mut self) -> Command<Message> {
+        let devices = &self.bluer_state.devices;
+        // devices that left the range are connected again once they are back
+        self.auto_connected
+            .retain(|a| devices.iter().any(|d| d.address == *a && d.rssi.is_some()));
+        let in_range: Vec<_> = devices
+            .iter()
+            .filter(|d| {
+                d.status == BluerDeviceStatus::Paired
+                    && !d.blocked
+                    && d.rssi.is_some()
+                    && !self.auto_connected.contains(&d.address)
+                    && self.config.auto_connects(d.address)
+            })
+            .map(|d| d.address)
+            .collect();
+        self.auto_connected.extend(in_range.iter().copied());
+        Command::batch(
+            in_range
+                .into_iter()
+                .map(|address| self.update(Message::Request(BluerRequest::ConnectDevice(address))))
+                .collect::<Vec<_>>(),
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -62,9 +211,133 @@ enum Message {
     Request(BluerRequest),
     Cancel,
     Confirm,
+    AgentInput(String),
+    ChooseFile(Address),
+    SendFile(Address, Option<PathBuf>),
+    Transfer(TransferEvent),
+    CancelTransfer,
+    DismissTransfer,
+    AudioProfiles(HashMap<Address, AudioProfile>),
+    SetAudioProfile(Address, AudioProfile),
+    ToggleDeviceSettings(Address),
+    RemoveDevice(Address),
+    CancelRemoveDevice,
+    SetAutoConnectDefault(bool),
+    SetAutoConnect(Address, bool),
+    ConfigUpdated(BluetoothAppletConfig),
+    DiscoverableTick,
+    ReconnectLastAudioDevice,
+    AliasInput(String),
+    SetAlias(Address),
+    Rfkill(RfkillState),
 }
 
-impl cosmic::Application for CosmicBluetoothApplet {
+impl CosmicBluetoothApplet {
+    fn device_settings<'a>(&'a self, dev: &'a BluerDevice) -> Element<'a, Message> {
+        let address = dev.address;
+        let mut settings = column![
+            text_input(&fl!("device-name"), &self.alias_input)
+                .on_input(Message::AliasInput)
+                .on_submit(Message::SetAlias(address))
+                .size(14)
+                .padding([4, 8]),
+            toggler(fl!("trusted"), dev.trusted, move |t| {
+                Message::Request(BluerRequest::SetTrusted(address, t))
+            })
+            .text_size(14)
+            .width(Length::Fill),
+            toggler(fl!("blocked"), dev.blocked, move |b| {
+                Message::Request(BluerRequest::SetBlocked(address, b))
+            })
+            .text_size(14)
+            .width(Length::Fill),
+            toggler(
+                fl!("auto-connect"),
+                self.config.auto_connects(address),
+                move |a| Message::SetAutoConnect(address, a)
+            )
+            .text_size(14)
+            .width(Length::Fill),
+        ]
+        .spacing(8);
+        settings = if self.confirm_remove == Some(address) {
+            let args = HashMap::from_iter(vec![("deviceName", dev.name.clone())]);
+            settings.push(
+                row![
+                    text(fl!("remove-device-confirm", args))
+                        .size(14)
+                        .width(Length::Fill),
+                    button(Button::Secondary)
+                        .custom(vec![text(fl!("cancel")).size(14).into()])
+                        .padding([4, 12])
+                        .on_press(Message::CancelRemoveDevice),
+                    button(Button::Destructive)
+                        .custom(vec![text(fl!("remove")).size(14).into()])
+                        .padding([4, 12])
+                        .on_press(Message::RemoveDevice(address)),
+                ]
+                .align_items(Alignment::Center)
+                .spacing(8),
+            )
+        } else {
+            settings.push(
+                button(Button::Destructive)
+                    .custom(vec![text(fl!("remove-device")).size(14).into()])
+                    .padding([4, 12])
+                    .on_press(Message::RemoveDevice(address)),
+            )
+        };
+        container(settings).padding([0, 24]).into()
+    }
+}
+
+fn transfer_view(transfer: &FileTransfer) -> Element<'_, Message> {
+    let file_name = transfer
+        .file
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let args = HashMap::from_iter(vec![
+        ("fileName", file_name),
+        ("deviceName", transfer.device_name.clone()),
+    ]);
+    let mut col = column![].spacing(8);
+    let action = match &transfer.status {
+        TransferStatus::Active => {
+            col = col.push(text(fl!("sending-file", args)).size(14));
+            col = col.push(
+                progress_bar(
+                    0.0..=transfer.size.max(1) as f32,
+                    transfer.transferred as f32,
+                )
+                .height(Length::Fixed(4.0)),
+            );
+            button(Button::Secondary)
+                .custom(vec![text(fl!("cancel")).size(14).into()])
+                .padding([4, 12])
+                .on_press(Message::CancelTransfer)
+        }
+        TransferStatus::Finished => {
+            col = col.push(text(fl!("file-sent", args)).size(14));
+            button(Button::Secondary)
+                .custom(vec![text(fl!("dismiss")).size(14).into()])
+                .padding([4, 12])
+                .on_press(Message::DismissTransfer)
+        }
+        TransferStatus::Failed(err) => {
+            col = col
+                .push(text(fl!("file-not-sent", args)).size(14))
+                .push(text(err).size(12));
+            button(Button::Secondary)
+                .custom(vec![text(fl!("dismiss")).size(14).into()])
+                .padding([4, 12])
+                .on_press(Message::DismissTransfer)
+        }
+    };
+    container(col.push(action)).padding([0, 24]).into()
+}
+
+impl Application for CosmicBluetoothApplet {
     type Message = Message;
     type Executor = cosmic::SingleThreadExecutor;
     type Flags = ();
@@ -75,6 +348,7 @@ impl cosmic::Application for CosmicBluetoothApplet {
             CosmicBluetoothApplet {
                 core,
                 icon_name: "bluetooth-symbolic".to_string(),
+                config: BluetoothAppletConfig::load(),
                 ..Default::default()
             },
             Command::none(),
@@ -93,7 +367,18 @@ impl cosmic::Application for CosmicBluetoothApplet {
         match message {
             Message::TogglePopup => {
                 if let Some(p) = self.popup.take() {
-                    return destroy_popup(p);
+                    let tx = self.bluer_sender.as_ref().cloned();
+                    return Command::batch(vec![
+                        iced::Command::perform(
+                            async {
+                                if let Some(tx) = tx {
+                                    let _ = tx.send(BluerRequest::SetRssiUpdates(false)).await;
+                                }
+                            },
+                            |_| cosmic::app::message::app(Message::Ignore),
+                        ),
+                        destroy_popup(p),
+                    ]);
                 } else {
                     // TODO request update of state maybe
                     self.id_ctr += 1;
@@ -118,6 +403,7 @@ impl cosmic::Application for CosmicBluetoothApplet {
                         iced::Command::perform(
                             async {
                                 if let Some(tx) = tx {
+                                    let _ = tx.send(BluerRequest::SetRssiUpdates(true)).await;
                                     let _ = tx.send(BluerRequest::StateUpdate).await;
                                 }
                             },
@@ -140,9 +426,18 @@ impl cosmic::Application for CosmicBluetoothApplet {
                     if let Some(err_msg) = err_msg {
                         eprintln!("bluetooth request error: {}", err_msg);
                     }
-                    self.bluer_state = state;
+                    self.set_state(state);
                     // TODO special handling for some requests
                     match req {
+                        BluerRequest::PairDevice(address)
+                            if self
+                                .agent_request
+                                .as_ref()
+                                .map_or(false, |r| r.device().address == address) =>
+                        {
+                            // pairing finished or failed, the prompt is no longer needed
+                            self.agent_request = None;
+                        }
                         BluerRequest::StateUpdate
                             if self.popup.is_some() && self.bluer_sender.is_some() =>
                         {
@@ -161,55 +456,81 @@ impl cosmic::Application for CosmicBluetoothApplet {
                 }
                 BluerEvent::Init { sender, state } => {
                     self.bluer_sender.replace(sender);
-                    self.bluer_state = state;
+                    self.set_state(state);
+                    return self.auto_connect();
                 }
                 BluerEvent::DevicesChanged { state } => {
-                    self.bluer_state = state;
+                    self.set_state(state);
+                    // pairing started by the remote device only shows up as a state change
+                    if let Some(AgentRequest::Display(device, _)) = self.agent_request.as_ref() {
+                        if self.bluer_state.devices.iter().any(|d| {
+                            d.address == device.address
+                                && matches!(
+                                    d.status,
+                                    BluerDeviceStatus::Paired | BluerDeviceStatus::Connected
+                                )
+                        }) {
+                            self.agent_request = None;
+                        }
+                    }
+                    return self.auto_connect();
                 }
                 BluerEvent::Finished => {
                     // TODO should this exit with an error causing a restart?
                     eprintln!("bluetooth subscription finished. exiting...");
                     std::process::exit(0);
                 }
-                // TODO handle agent events
-                BluerEvent::AgentEvent(event) => match event {
-                    crate::bluetooth::BluerAgentEvent::DisplayPinCode(_d, _code) => {}
-                    crate::bluetooth::BluerAgentEvent::DisplayPasskey(_d, _code) => {}
-                    crate::bluetooth::BluerAgentEvent::RequestPinCode(_d) => {
-                        // TODO anything to be done here?
-                    }
-                    crate::bluetooth::BluerAgentEvent::RequestPasskey(_d) => {
-                        // TODO anything to be done here?
-                    }
-                    crate::bluetooth::BluerAgentEvent::RequestConfirmation(d, code, tx) => {
-                        self.request_confirmation.replace((d, code, tx));
-                    }
-                    crate::bluetooth::BluerAgentEvent::RequestDeviceAuthorization(_d, _tx) => {
-                        // TODO anything to be done here?
+                BluerEvent::AgentEvent(event) => {
+                    match event {
+                        BluerAgentEvent::DisplayPinCode(d, code)
+                        | BluerAgentEvent::DisplayPasskey(d, code) => {
+                            self.agent_request = Some(AgentRequest::Display(d, code));
+                        }
+                        BluerAgentEvent::RequestPinCode(d, suggested, tx) => {
+                            self.agent_request = Some(AgentRequest::PinCode(d, suggested, tx));
+                        }
+                        BluerAgentEvent::RequestPasskey(d, tx) => {
+                            self.agent_request = Some(AgentRequest::Passkey(d, String::new(), tx));
+                        }
+                        BluerAgentEvent::RequestConfirmation(d, code, tx) => {
+                            self.agent_request = Some(AgentRequest::Confirm(d, code, tx));
+                        }
+                        BluerAgentEvent::RequestDeviceAuthorization(d, tx) => {
+                            self.agent_request = Some(AgentRequest::Authorize(d, tx));
+                        }
+                        BluerAgentEvent::RequestServiceAuthorization(_d, _service, _tx) => {
+                            // my headphones seem to always request this
+                            // doesn't seem to be defined in the UX mockups
+                            // dbg!(
+                            //     "request service authorization",
+                            //     d.name,
+                            //     bluer::id::Service::try_from(service)
+                            //         .map(|s| s.to_string())
+                            //         .unwrap_or_else(|_| "unknown".to_string())
+                            // );
+                        }
+                        BluerAgentEvent::RequestTimedOut(address) => {
+                            if self
+                                .agent_request
+                                .as_ref()
+                                .map_or(false, |r| r.device().address == address)
+                            {
+                                self.agent_request = None;
+                            }
+                        }
                     }
-                    crate::bluetooth::BluerAgentEvent::RequestServiceAuthorization(
-                        _d,
-                        _service,
-                        _tx,
-                    ) => {
-                        // my headphones seem to always request this
-                        // doesn't seem to be defined in the UX mockups
-                        // dbg!(
-                        //     "request service authorization",
-                        //     d.name,
-                        //     bluer::id::Service::try_from(service)
-                        //         .map(|s| s.to_string())
-                        //         .unwrap_or_else(|_| "unknown".to_string())
-                        // );
+                    // pairing prompts are useless if nobody sees them
+                    if self.agent_request.is_some() && self.popup.is_none() {
+                        return self.update(Message::TogglePopup);
                     }
-                },
+                }
             },
             Message::Request(r) => {
                 match &r {
                     BluerRequest::SetBluetoothEnabled(enabled) => {
                         self.bluer_state.bluetooth_enabled = *enabled;
                         if !*enabled {
-                            self.bluer_state = BluerState::default();
+                            self.set_state(BluerState::default());
                         }
                     }
                     BluerRequest::ConnectDevice(add) => {
@@ -222,6 +543,8 @@ impl cosmic::Application for CosmicBluetoothApplet {
                             });
                     }
                     BluerRequest::DisconnectDevice(add) => {
+                        // don't connect it again right away
+                        self.auto_connected.insert(*add);
                         self.bluer_state
                             .devices
                             .iter_mut()
@@ -239,6 +562,26 @@ impl cosmic::Application for CosmicBluetoothApplet {
                                 d.status = BluerDeviceStatus::Pairing;
                             });
                     }
+                    BluerRequest::SetTrusted(add, trusted) => {
+                        if let Some(d) = self
+                            .bluer_state
+                            .devices
+                            .iter_mut()
+                            .find(|d| d.address == *add)
+                        {
+                            d.trusted = *trusted;
+                        }
+                    }
+                    BluerRequest::SetBlocked(add, blocked) => {
+                        if let Some(d) = self
+                            .bluer_state
+                            .devices
+                            .iter_mut()
+                            .find(|d| d.address == *add)
+                        {
+                            d.blocked = *blocked;
+                        }
+                    }
                     _ => {} // TODO
                 }
                 if let Some(tx) = self.bluer_sender.as_mut().cloned() {
@@ -250,37 +593,220 @@ impl cosmic::Application for CosmicBluetoothApplet {
                     );
                 }
             }
-            Message::Cancel => {
-                if let Some((_, _, tx)) = self.request_confirmation.take() {
-                    return iced::Command::perform(
-                        async move {
-                            let _ = tx.send(false).await;
-                        },
-                        |_| cosmic::app::message::app(Message::Ignore),
-                    );
+            Message::Cancel => match self.agent_request.take() {
+                Some(AgentRequest::Confirm(_, _, tx) | AgentRequest::Authorize(_, tx)) => {
+                    return reply(tx, false);
                 }
-            }
+                Some(AgentRequest::PinCode(_, _, tx)) => return reply(tx, None),
+                Some(AgentRequest::Passkey(_, _, tx)) => return reply(tx, None),
+                Some(AgentRequest::Display(..)) | None => {}
+            },
             Message::Confirm => {
-                if let Some((_, _, tx)) = self.request_confirmation.take() {
+                if !self.agent_request.as_ref().map_or(false, |r| r.is_ready()) {
+                    return Command::none();
+                }
+                match self.agent_request.take() {
+                    Some(AgentRequest::Confirm(_, _, tx) | AgentRequest::Authorize(_, tx)) => {
+                        return reply(tx, true);
+                    }
+                    Some(AgentRequest::PinCode(_, pin, tx)) => return reply(tx, Some(pin)),
+                    Some(AgentRequest::Passkey(_, passkey, tx)) => {
+                        return reply(tx, parse_passkey(&passkey));
+                    }
+                    Some(AgentRequest::Display(..)) | None => {}
+                }
+            }
+            Message::ChooseFile(address) => {
+                let title = fl!("send-file");
+                return iced::Command::perform(
+                    async move {
+                        let files = SelectedFiles::open_file()
+                            .title(title.as_str())
+                            .modal(false)
+                            .multiple(false)
+                            .send()
+                            .await
+                            .and_then(|r| r.response())
+                            .ok()?;
+                        files.uris().first()?.to_file_path().ok()
+                    },
+                    move |path| cosmic::app::message::app(Message::SendFile(address, path)),
+                );
+            }
+            Message::SendFile(address, Some(file)) => {
+                if self
+                    .transfer
+                    .as_ref()
+                    .map_or(false, |t| t.status == TransferStatus::Active)
+                {
+                    return Command::none();
+                }
+                let device_name = self
+                    .bluer_state
+                    .devices
+                    .iter()
+                    .find(|d| d.address == address)
+                    .map(|d| d.name.clone())
+                    .unwrap_or_else(|| address.to_string());
+                self.transfer = Some(FileTransfer {
+                    address,
+                    device_name,
+                    file,
+                    path: None,
+                    transferred: 0,
+                    size: 0,
+                    status: TransferStatus::Active,
+                });
+            }
+            Message::SendFile(_, None) => {}
+            Message::Transfer(event) => {
+                if let Some(transfer) = self.transfer.as_mut() {
+                    match event {
+                        TransferEvent::Started(path) => transfer.path = Some(path),
+                        TransferEvent::Progress { transferred, size } => {
+                            transfer.transferred = transferred;
+                            transfer.size = size;
+                        }
+                        TransferEvent::Finished => transfer.status = TransferStatus::Finished,
+                        TransferEvent::Failed(err) => {
+                            transfer.status = TransferStatus::Failed(err);
+                        }
+                    }
+                }
+            }
+            Message::CancelTransfer => {
+                if let Some(path) = self.transfer.take().and_then(|t| t.path) {
                     return iced::Command::perform(
                         async move {
-                            let _ = tx.send(true).await;
+                            if let Err(err) = obex::cancel(path).await {
+                                eprintln!("failed to cancel the file transfer: {}", err);
+                            }
                         },
                         |_| cosmic::app::message::app(Message::Ignore),
                     );
                 }
             }
+            Message::DismissTransfer => {
+                self.transfer = None;
+            }
+            Message::ToggleDeviceSettings(address) => {
+                self.confirm_remove = None;
+                self.expanded_device = if self.expanded_device == Some(address) {
+                    None
+                } else {
+                    Some(address)
+                };
+                self.alias_input = self
+                    .bluer_state
+                    .devices
+                    .iter()
+                    .find(|d| d.address == address)
+                    .map(|d| d.name.clone())
+                    .unwrap_or_default();
+            }
+            Message::Rfkill(rfkill) => {
+                self.rfkill = rfkill;
+                if rfkill.is_blocked() {
+                    self.set_state(BluerState::default());
+                }
+                // the adapter is powered on or off along with the radio
+                return self.update(Message::Request(BluerRequest::StateUpdate));
+            }
+            Message::AliasInput(alias) => {
+                self.alias_input = alias;
+            }
+            Message::SetAlias(address) => {
+                let alias = self.alias_input.trim().to_string();
+                if let Some(d) = self
+                    .bluer_state
+                    .devices
+                    .iter_mut()
+                    .find(|d| d.address == address)
+                {
+                    if !alias.is_empty() {
+                        d.name = alias.clone();
+                    }
+                }
+                return self.update(Message::Request(BluerRequest::SetAlias(address, alias)));
+            }
+            Message::RemoveDevice(address) => {
+                if self.confirm_remove != Some(address) {
+                    self.confirm_remove = Some(address);
+                    return Command::none();
+                }
+                self.confirm_remove = None;
+                self.expanded_device = None;
+                return self.update(Message::Request(BluerRequest::RemoveDevice(address)));
+            }
+            Message::CancelRemoveDevice => {
+                self.confirm_remove = None;
+            }
+            Message::SetAutoConnectDefault(auto_connect) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_auto_connect_default(auto_connect, &config);
+                }
+            }
+            Message::SetAutoConnect(address, auto_connect) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_auto_connect(address, auto_connect, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+            Message::ReconnectLastAudioDevice => {
+                if let Some(device) = self.last_audio_device() {
+                    let address = device.address;
+                    return self.update(Message::Request(BluerRequest::ConnectDevice(address)));
+                }
+            }
+            Message::DiscoverableTick => {
+                // BlueZ turns discoverability off by itself, make sure the popup notices
+                if self
+                    .discoverable_until
+                    .map_or(false, |until| until <= Instant::now())
+                {
+                    self.discoverable_until = None;
+                    return self.update(Message::Request(BluerRequest::StateUpdate));
+                }
+            }
+            Message::AudioProfiles(profiles) => {
+                self.audio_profiles = profiles;
+            }
+            Message::SetAudioProfile(address, profile) => {
+                return iced::Command::perform(
+                    async move {
+                        if let Err(err) = audio_profile::set_profile(address, profile).await {
+                            eprintln!("failed to switch the audio profile: {}", err);
+                        }
+                    },
+                    |_| cosmic::app::message::app(Message::Ignore),
+                );
+            }
+            Message::AgentInput(input) => match self.agent_request.as_mut() {
+                // legacy PIN codes are 1 to 16 characters
+                Some(AgentRequest::PinCode(_, pin, _)) => {
+                    *pin = input.chars().take(16).collect();
+                }
+                Some(AgentRequest::Passkey(_, passkey, _)) => {
+                    *passkey = input.chars().filter(char::is_ascii_digit).take(6).collect();
+                }
+                _ => {}
+            },
         }
         self.update_icon();
         Command::none()
     }
 
     fn view(&self) -> Element<Message> {
-        self.core
-            .applet_helper
-            .icon_button(&self.icon_name)
-            .on_press(Message::TogglePopup)
-            .into()
+        mouse_area(
+            self.core
+                .applet_helper
+                .icon_button(&self.icon_name)
+                .on_press(Message::TogglePopup),
+        )
+        .on_middle_press(Message::ReconnectLastAudioDevice)
+        .into()
     }
 
     fn view_window(&self, _id: window::Id) -> Element<Message> {
@@ -295,25 +821,64 @@ impl cosmic::Application for CosmicBluetoothApplet {
             }),
         };
         let mut known_bluetooth = column![];
+        if let Some(device) = self.last_audio_device() {
+            let args = HashMap::from_iter(vec![("deviceName", device.name.clone())]);
+            known_bluetooth = known_bluetooth.push(
+                button(applet_button_theme())
+                    .custom(vec![row![
+                        icon(device.icon.as_str(), 16).style(Svg::Symbolic),
+                        text(fl!("reconnect-device", args))
+                            .size(14)
+                            .width(Length::Fill),
+                        icon("view-refresh-symbolic", 16).style(Svg::Symbolic),
+                    ]
+                    .align_items(Alignment::Center)
+                    .spacing(12)
+                    .into()])
+                    .on_press(Message::ReconnectLastAudioDevice)
+                    .width(Length::Fill),
+            );
+        }
         for dev in self.bluer_state.devices.iter().filter(|d| {
             !self
-                .request_confirmation
+                .agent_request
                 .as_ref()
-                .map_or(false, |(dev, _, _)| d.address == dev.address)
+                .map_or(false, |r| d.address == r.device().address)
         }) {
-            let mut row = row![
-                icon(dev.icon.as_str(), 16).style(Svg::Symbolic),
-                text(dev.name.clone())
-                    .size(14)
-                    .horizontal_alignment(Horizontal::Left)
-                    .vertical_alignment(Vertical::Center)
-                    .width(Length::Fill)
-            ]
-            .align_items(Alignment::Center)
-            .spacing(12);
+            let mut name = column![text(dev.name.clone())
+                .size(14)
+                .horizontal_alignment(Horizontal::Left)
+                .vertical_alignment(Vertical::Center)]
+            .width(Length::Fill);
+            if dev.status == BluerDeviceStatus::Connected && dev.has_weak_signal() {
+                name = name.push(
+                    row![
+                        icon("dialog-warning-symbolic", 12).style(Svg::Symbolic),
+                        text(fl!("weak-signal")).size(10),
+                    ]
+                    .align_items(Alignment::Center)
+                    .spacing(4),
+                );
+            }
+            let mut row = row![icon(dev.icon.as_str(), 16).style(Svg::Symbolic), name]
+                .align_items(Alignment::Center)
+                .spacing(12);
 
             match &dev.status {
                 BluerDeviceStatus::Connected => {
+                    if let Some(signal_icon) = dev.signal_icon() {
+                        row = row.push(icon(signal_icon, 16).style(Svg::Symbolic));
+                    }
+                    if let (Some(battery), Some(battery_icon)) = (dev.battery, dev.battery_icon()) {
+                        row = row.push(
+                            row![
+                                icon(battery_icon.as_str(), 16).style(Svg::Symbolic),
+                                text(format!("{}%", battery)).size(12),
+                            ]
+                            .align_items(Alignment::Center)
+                            .spacing(4),
+                        );
+                    }
                     row = row.push(
                         text(fl!("connected"))
                             .size(14)
@@ -328,39 +893,118 @@ impl cosmic::Application for CosmicBluetoothApplet {
                 BluerDeviceStatus::Disconnected | BluerDeviceStatus::Pairing => continue,
             };
 
-            known_bluetooth = known_bluetooth.push(
-                button(applet_button_theme())
-                    .custom(vec![row.into()])
-                    .style(applet_button_theme())
-                    .on_press(match dev.status {
-                        BluerDeviceStatus::Connected => {
-                            Message::Request(BluerRequest::DisconnectDevice(dev.address))
-                        }
-                        BluerDeviceStatus::Disconnected => {
-                            Message::Request(BluerRequest::PairDevice(dev.address))
-                        }
-                        BluerDeviceStatus::Paired => {
-                            Message::Request(BluerRequest::ConnectDevice(dev.address))
-                        }
-                        BluerDeviceStatus::Connecting => {
-                            Message::Request(BluerRequest::CancelConnect(dev.address))
-                        }
-                        BluerDeviceStatus::Disconnecting => Message::Ignore, // Start connecting?
-                        BluerDeviceStatus::Pairing => Message::Ignore,       // Cancel pairing?
-                    })
-                    .width(Length::Fill),
-            );
+            let device_button = button(applet_button_theme())
+                .custom(vec![row.into()])
+                .style(applet_button_theme())
+                .on_press(match dev.status {
+                    BluerDeviceStatus::Connected => {
+                        Message::Request(BluerRequest::DisconnectDevice(dev.address))
+                    }
+                    BluerDeviceStatus::Disconnected => {
+                        Message::Request(BluerRequest::PairDevice(dev.address))
+                    }
+                    BluerDeviceStatus::Paired => {
+                        Message::Request(BluerRequest::ConnectDevice(dev.address))
+                    }
+                    BluerDeviceStatus::Connecting => {
+                        Message::Request(BluerRequest::CancelConnect(dev.address))
+                    }
+                    BluerDeviceStatus::Disconnecting => Message::Ignore, // Start connecting?
+                    BluerDeviceStatus::Pairing => Message::Ignore,       // Cancel pairing?
+                })
+                .width(Length::Fill);
+            if matches!(
+                dev.status,
+                BluerDeviceStatus::Connected | BluerDeviceStatus::Paired
+            ) {
+                let mut actions = row![device_button]
+                    .align_items(Alignment::Center)
+                    .padding([0, 12, 0, 0]);
+                if let Some(profile) = self
+                    .audio_profiles
+                    .get(&dev.address)
+                    .filter(|_| dev.status == BluerDeviceStatus::Connected)
+                {
+                    let label = match profile {
+                        AudioProfile::A2dp => fl!("profile-high-fidelity"),
+                        AudioProfile::Headset => fl!("profile-headset"),
+                    };
+                    actions = actions.push(
+                        button(Button::Text)
+                            .custom(vec![text(label).size(12).into()])
+                            .padding([4, 8])
+                            .on_press(Message::SetAudioProfile(dev.address, profile.other())),
+                    );
+                }
+                known_bluetooth = known_bluetooth.push(
+                    actions
+                        .push(
+                            button(Button::Text)
+                                .custom(vec![icon("document-send-symbolic", 16)
+                                    .style(Svg::Symbolic)
+                                    .into()])
+                                .padding(8)
+                                .on_press(Message::ChooseFile(dev.address)),
+                        )
+                        .push(
+                            button(Button::Text)
+                                .custom(vec![icon("emblem-system-symbolic", 16)
+                                    .style(Svg::Symbolic)
+                                    .into()])
+                                .padding(8)
+                                .on_press(Message::ToggleDeviceSettings(dev.address)),
+                        ),
+                );
+                if self.expanded_device == Some(dev.address) {
+                    known_bluetooth = known_bluetooth.push(self.device_settings(dev));
+                }
+            } else {
+                known_bluetooth = known_bluetooth.push(device_button);
+            }
+        }
+        if let Some(transfer) = self.transfer.as_ref() {
+            known_bluetooth = known_bluetooth.push(transfer_view(transfer));
         }
 
+        let hard_blocked = self.rfkill.hard_blocked;
+        let discoverable_label = match self.discoverable_until {
+            Some(until) => {
+                let left = until.saturating_duration_since(Instant::now()).as_secs();
+                fl!(
+                    "visible-as-remaining",
+                    HashMap::from_iter(vec![
+                        ("name", self.bluer_state.alias.clone()),
+                        ("time", format!("{}:{:02}", left / 60, left % 60)),
+                    ])
+                )
+            }
+            None => fl!(
+                "visible-as",
+                HashMap::from_iter(vec![
+                    ("name", self.bluer_state.alias.clone()),
+                    ("minutes", (DISCOVERABLE_TIMEOUT.as_secs() / 60).to_string()),
+                ])
+            ),
+        };
+        let mut power = column![toggler(
+            fl!("bluetooth"),
+            self.bluer_state.bluetooth_enabled && !self.rfkill.is_blocked(),
+            |m| if hard_blocked {
+                Message::Ignore
+            } else {
+                Message::Request(BluerRequest::SetBluetoothEnabled(m))
+            },
+        )
+        .text_size(14)
+        .width(Length::Fill)];
+        if hard_blocked {
+            power = power.push(text(fl!("hardware-switch-off")).size(12));
+        }
         let mut content = column![
             column![
-                toggler(fl!("bluetooth"), self.bluer_state.bluetooth_enabled, |m| {
-                    Message::Request(BluerRequest::SetBluetoothEnabled(m))
-                },)
-                .text_size(14)
-                .width(Length::Fill),
+                power,
                 // these are not in the UX mockup, but they are useful imo
-                toggler(fl!("discoverable"), self.bluer_state.discoverable, |m| {
+                toggler(discoverable_label, self.bluer_state.discoverable, |m| {
                     Message::Request(BluerRequest::SetDiscoverable(m))
                 },)
                 .text_size(14)
@@ -369,6 +1013,13 @@ impl cosmic::Application for CosmicBluetoothApplet {
                     Message::Request(BluerRequest::SetPairable(m))
                 },)
                 .text_size(14)
+                .width(Length::Fill),
+                toggler(
+                    fl!("auto-connect-default"),
+                    self.config.auto_connect_default,
+                    Message::SetAutoConnectDefault
+                )
+                .text_size(14)
                 .width(Length::Fill)
             ]
             .spacing(8)
@@ -409,61 +1060,76 @@ impl cosmic::Application for CosmicBluetoothApplet {
         let mut list_column: Vec<Element<'_, Message>> =
             Vec::with_capacity(self.bluer_state.devices.len());
 
-        if let Some((device, pin, _)) = self.request_confirmation.as_ref() {
-            let row = column![
+        if let Some(request) = self.agent_request.as_ref() {
+            let device = request.device();
+            let device_name = || HashMap::from_iter(vec![("deviceName", device.name.clone())]);
+            let prompt = match request {
+                AgentRequest::Display(..) => fl!("display-pin", device_name()),
+                AgentRequest::Confirm(..) => fl!("confirm-pin", device_name()),
+                AgentRequest::Authorize(..) => fl!("authorize-device", device_name()),
+                AgentRequest::PinCode(..) => fl!("enter-pin", device_name()),
+                AgentRequest::Passkey(..) => fl!("enter-passkey", device_name()),
+            };
+            let mut col = column![
                 icon(device.icon.as_str(), 16).style(Svg::Symbolic),
                 text(&device.name)
                     .size(14)
                     .horizontal_alignment(Horizontal::Left)
                     .vertical_alignment(Vertical::Center)
                     .width(Length::Fill),
-                text(fl!(
-                    "confirm-pin",
-                    HashMap::from_iter(vec![("deviceName", device.name.clone())])
-                ))
-                .horizontal_alignment(Horizontal::Left)
-                .vertical_alignment(Vertical::Center)
-                .width(Length::Fill)
-                .size(14),
-                text(pin)
-                    .horizontal_alignment(Horizontal::Center)
+                text(prompt)
+                    .horizontal_alignment(Horizontal::Left)
                     .vertical_alignment(Vertical::Center)
                     .width(Length::Fill)
-                    .size(22),
-                row![
-                    button(Button::Secondary)
-                        .custom(
-                            vec![text(fl!("cancel"))
-                                .size(14)
-                                .width(Length::Fill)
-                                .height(Length::Fixed(24.0))
-                                .vertical_alignment(Vertical::Center)
-                                .into(),]
-                            .into(),
-                        )
-                        .padding([8, 24])
-                        .style(button_style())
-                        .on_press(Message::Cancel)
-                        .width(Length::Fill),
-                    button(Button::Secondary)
-                        .custom(
-                            vec![text(fl!("confirm"))
-                                .size(14)
-                                .width(Length::Fill)
-                                .height(Length::Fixed(24.0))
-                                .vertical_alignment(Vertical::Center)
-                                .into(),]
-                            .into(),
-                        )
-                        .padding([8, 24])
-                        .style(button_style())
-                        .on_press(Message::Confirm)
-                        .width(Length::Fill),
-                ]
+                    .size(14),
             ]
             .padding([0, 24])
             .spacing(12);
-            list_column.push(row.into());
+            match request {
+                AgentRequest::Display(_, pin) | AgentRequest::Confirm(_, pin, _) => {
+                    col = col.push(
+                        text(pin)
+                            .horizontal_alignment(Horizontal::Center)
+                            .vertical_alignment(Vertical::Center)
+                            .width(Length::Fill)
+                            .size(22),
+                    );
+                }
+                AgentRequest::PinCode(_, input, _) | AgentRequest::Passkey(_, input, _) => {
+                    col = col.push(
+                        text_input("", input)
+                            .on_input(Message::AgentInput)
+                            .on_submit(Message::Confirm)
+                            .size(22),
+                    );
+                }
+                AgentRequest::Authorize(..) => {}
+            }
+            let dialog_button = |label: String| {
+                button(Button::Secondary)
+                    .custom(
+                        vec![text(label)
+                            .size(14)
+                            .width(Length::Fill)
+                            .height(Length::Fixed(24.0))
+                            .vertical_alignment(Vertical::Center)
+                            .into()]
+                        .into(),
+                    )
+                    .padding([8, 24])
+                    .style(button_style())
+                    .width(Length::Fill)
+            };
+            let mut buttons = row![dialog_button(fl!("cancel")).on_press(Message::Cancel)];
+            if !matches!(request, AgentRequest::Display(..)) {
+                let mut confirm = dialog_button(fl!("confirm"));
+                if request.is_ready() {
+                    confirm = confirm.on_press(Message::Confirm);
+                }
+                buttons = buttons.push(confirm);
+            }
+            col = col.push(buttons);
+            list_column.push(col.into());
         }
         let mut visible_devices_count = 0;
         if self.show_visible_devices {
@@ -474,19 +1140,30 @@ impl cosmic::Application for CosmicBluetoothApplet {
                         d.status,
                         BluerDeviceStatus::Disconnected | BluerDeviceStatus::Pairing
                     ) && !self
-                        .request_confirmation
+                        .agent_request
                         .as_ref()
-                        .map_or(false, |(dev, _, _)| d.address == dev.address)
+                        .map_or(false, |r| d.address == r.device().address)
                 }) {
-                    let row = row![
+                    let mut row = row![
                         icon(dev.icon.as_str(), 16).style(Svg::Symbolic),
                         text(dev.name.clone())
                             .horizontal_alignment(Horizontal::Left)
+                            .width(Length::Fill)
                             .size(14),
                     ]
                     .width(Length::Fill)
                     .align_items(Alignment::Center)
                     .spacing(12);
+                    if let (Some(rssi), Some(signal_icon)) = (dev.rssi, dev.signal_icon()) {
+                        row = row.push(
+                            row![
+                                text(format!("{} dBm", rssi)).size(10),
+                                icon(signal_icon, 16).style(Svg::Symbolic),
+                            ]
+                            .align_items(Alignment::Center)
+                            .spacing(4),
+                        );
+                    }
                     visible_devices = visible_devices.push(
                         button(applet_button_theme())
                             .custom(vec![row.width(Length::Fill).into()])
@@ -502,7 +1179,7 @@ impl cosmic::Application for CosmicBluetoothApplet {
         }
         let item_counter = visible_devices_count
                 // request confirmation is pretty big
-                + if self.request_confirmation.is_some() {
+                + if self.agent_request.is_some() {
                     5
                 } else {
                     0
@@ -518,7 +1195,40 @@ impl cosmic::Application for CosmicBluetoothApplet {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        bluetooth_subscription(0).map(Message::BluetoothEvent)
+        let config_sub = cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+            |(_, config)| match config {
+                Ok(config) => Message::ConfigUpdated(config),
+                Err((errors, config)) => {
+                    for error in errors {
+                        eprintln!("{:?}", error);
+                    }
+                    Message::ConfigUpdated(config)
+                }
+            },
+        );
+        let mut subscriptions = vec![
+            bluetooth_subscription(0).map(Message::BluetoothEvent),
+            rfkill::rfkill_subscription().map(Message::Rfkill),
+            config_sub,
+        ];
+        if let Some(transfer) = self
+            .transfer
+            .as_ref()
+            .filter(|t| t.status == TransferStatus::Active)
+        {
+            subscriptions.push(
+                obex::send_file(transfer.address, transfer.file.clone()).map(Message::Transfer),
+            );
+        }
+        if self.popup.is_some() && self.discoverable_until.is_some() {
+            subscriptions
+                .push(iced::time::every(Duration::from_secs(1)).map(|_| Message::DiscoverableTick));
+        }
+        if self.popup.is_some() {
+            subscriptions
+                .push(audio_profile::audio_profile_subscription().map(Message::AudioProfiles));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
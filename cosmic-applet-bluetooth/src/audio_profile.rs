@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Audio profiles of Bluetooth cards, read from and switched through the sound server.
+//! The sound server owns the state, so the audio applet sees the same profile as this one.
+
+use std::{collections::HashMap, process::Stdio};
+
+use anyhow::{anyhow, bail};
+use bluer::Address;
+use cosmic::iced::{
+    self,
+    futures::{self, SinkExt},
+    subscription,
+};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioProfile {
+    /// High quality playback, no microphone
+    A2dp,
+    /// Headset / hands-free with microphone
+    Headset,
+}
+
+impl AudioProfile {
+    fn from_name(name: &str) -> Option<Self> {
+        if name.contains("a2dp") {
+            Some(Self::A2dp)
+        } else if name.contains("headset") || name.contains("handsfree") {
+            Some(Self::Headset)
+        } else {
+            None
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Self::A2dp => Self::Headset,
+            Self::Headset => Self::A2dp,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Card {
+    name: String,
+    active_profile: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, CardProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CardProfile {
+    #[serde(default)]
+    priority: u32,
+    #[serde(default = "available_default")]
+    available: bool,
+}
+
+fn available_default() -> bool {
+    true
+}
+
+/// Sound servers name Bluetooth cards `bluez_card.AA_BB_CC_DD_EE_FF`
+fn card_name(address: Address) -> String {
+    format!("bluez_card.{}", address.to_string().replace(':', "_"))
+}
+
+fn card_address(name: &str) -> Option<Address> {
+    name.strip_prefix("bluez_card.")?
+        .replace('_', ":")
+        .parse()
+        .ok()
+}
+
+async fn cards() -> anyhow::Result<Vec<Card>> {
+    let output = Command::new("pactl")
+        .args(["--format=json", "list", "cards"])
+        .output()
+        .await?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Active profile of every connected Bluetooth audio device
+pub async fn active_profiles() -> HashMap<Address, AudioProfile> {
+    match cards().await {
+        Ok(cards) => cards
+            .into_iter()
+            .filter_map(|card| {
+                let profile = AudioProfile::from_name(card.active_profile.as_deref()?)?;
+                Some((card_address(&card.name)?, profile))
+            })
+            .collect(),
+        Err(err) => {
+            log::warn!("Failed to read the audio cards: {}", err);
+            HashMap::new()
+        }
+    }
+}
+
+/// Switch the device to the best available profile of the given kind
+pub async fn set_profile(address: Address, profile: AudioProfile) -> anyhow::Result<()> {
+    let name = card_name(address);
+    let card = cards()
+        .await?
+        .into_iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| anyhow!("{} is not an audio device", address))?;
+    let (target, _) = card
+        .profiles
+        .iter()
+        .filter(|(n, p)| p.available && AudioProfile::from_name(n) == Some(profile))
+        .max_by_key(|(_, p)| p.priority)
+        .ok_or_else(|| anyhow!("{} does not support the profile", address))?;
+    let output = Command::new("pactl")
+        .args(["set-card-profile", &name, target])
+        .output()
+        .await?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Emits the active profiles, and again whenever a card changes
+pub fn audio_profile_subscription() -> iced::Subscription<HashMap<Address, AudioProfile>> {
+    struct AudioProfileWatcher;
+
+    subscription::channel(
+        std::any::TypeId::of::<AudioProfileWatcher>(),
+        10,
+        |mut output| async move {
+            _ = output.send(active_profiles().await).await;
+
+            match Command::new("pactl")
+                .arg("subscribe")
+                .stdout(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+            {
+                Ok(mut child) => {
+                    if let Some(stdout) = child.stdout.take() {
+                        let mut lines = BufReader::new(stdout).lines();
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            // e.g. `Event 'change' on card #42`
+                            if line.contains(" on card ") {
+                                _ = output.send(active_profiles().await).await;
+                            }
+                        }
+                    }
+                }
+                Err(err) => log::warn!("Failed to watch the audio cards: {}", err),
+            }
+            futures::future::pending().await
+        },
+    )
+}
@@ -1,8 +1,14 @@
-use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    sync::Arc,
+    time::Duration,
+};
 
 use bluer::{
-    agent::{Agent, AgentHandle},
-    Adapter, Address, DeviceProperty, Session, Uuid,
+    agent::{Agent, AgentHandle, ReqError, ReqResult},
+    Adapter, Address, DeviceEvent, DeviceProperty, DiscoveryFilter, Session, Uuid,
 };
 use cosmic::iced::{
     self,
@@ -11,6 +17,8 @@ use cosmic::iced::{
 };
 
 use rand::Rng;
+
+use crate::rfkill;
 use tokio::{
     spawn,
     sync::{
@@ -21,6 +29,14 @@ use tokio::{
     time::timeout,
 };
 
+const WEAK_SIGNAL_RSSI: i16 = -80;
+/// How long a pairing prompt waits for the user before the request is canceled
+const AGENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often newly added devices are picked up for battery level updates
+const BATTERY_RESCAN: Duration = Duration::from_secs(10);
+/// How long the adapter stays discoverable before BlueZ turns it off again
+pub const DISCOVERABLE_TIMEOUT: Duration = Duration::from_secs(180);
+
 pub fn bluetooth_subscription<I: 'static + Hash + Copy + Send + Sync + Debug>(
     id: I,
 ) -> iced::Subscription<BluerEvent> {
@@ -124,6 +140,14 @@ pub enum BluerRequest {
     ConnectDevice(Address),
     DisconnectDevice(Address),
     CancelConnect(Address),
+    SetTrusted(Address, bool),
+    SetBlocked(Address, bool),
+    /// Rename the device, an empty alias restores the name reported by the device
+    SetAlias(Address, String),
+    /// Unpair the device and forget it
+    RemoveDevice(Address),
+    /// Report every advertisement while discovering, so the signal strength stays current
+    SetRssiUpdates(bool),
     StateUpdate,
 }
 
@@ -151,6 +175,8 @@ pub struct BluerState {
     pub bluetooth_enabled: bool,
     pub discoverable: bool,
     pub pairable: bool,
+    /// Name other devices see while the adapter is discoverable
+    pub alias: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -172,6 +198,14 @@ pub struct BluerDevice {
     pub status: BluerDeviceStatus,
     pub properties: Vec<DeviceProperty>,
     pub icon: String,
+    /// Received signal strength in dBm, only reported while the adapter is discovering
+    pub rssi: Option<i16>,
+    /// Battery percentage from the Battery1 interface
+    pub battery: Option<u8>,
+    /// Connections from the device are accepted without asking
+    pub trusted: bool,
+    /// Connections from the device are rejected
+    pub blocked: bool,
 }
 
 impl Eq for BluerDevice {}
@@ -204,11 +238,11 @@ impl PartialEq for BluerDevice {
 
 impl BluerDevice {
     pub async fn from_device(device: &bluer::Device) -> Self {
+        // the alias is the name set by the user, it falls back to the name of the device
         let mut name = device
-            .name()
+            .alias()
             .await
-            .unwrap_or_default()
-            .unwrap_or_else(|| device.address().to_string());
+            .unwrap_or_else(|_| device.address().to_string());
         if name == "" {
             name = device.address().to_string();
         };
@@ -232,6 +266,28 @@ impl BluerDevice {
                 }
             })
             .unwrap_or_else(|| "bluetooth-symbolic".into());
+        let rssi = properties.iter().find_map(|p| {
+            if let DeviceProperty::Rssi(rssi) = p {
+                Some(*rssi)
+            } else {
+                None
+            }
+        });
+
+        let battery = properties.iter().find_map(|p| {
+            if let DeviceProperty::BatteryPercentage(battery) = p {
+                Some(*battery)
+            } else {
+                None
+            }
+        });
+
+        let trusted = properties
+            .iter()
+            .any(|p| matches!(p, DeviceProperty::Trusted(true)));
+        let blocked = properties
+            .iter()
+            .any(|p| matches!(p, DeviceProperty::Blocked(true)));
 
         Self {
             name,
@@ -239,8 +295,44 @@ impl BluerDevice {
             status,
             properties,
             icon,
+            rssi,
+            battery,
+            trusted,
+            blocked,
         }
     }
+
+    /// Signal bars icon for the device's RSSI
+    pub fn signal_icon(&self) -> Option<&'static str> {
+        let rssi = self.rssi?;
+        Some(if rssi >= -55 {
+            "network-cellular-signal-excellent-symbolic"
+        } else if rssi >= -67 {
+            "network-cellular-signal-good-symbolic"
+        } else if rssi >= -75 {
+            "network-cellular-signal-ok-symbolic"
+        } else if rssi >= -85 {
+            "network-cellular-signal-weak-symbolic"
+        } else {
+            "network-cellular-signal-none-symbolic"
+        })
+    }
+
+    /// Battery icon for the reported battery percentage
+    pub fn battery_icon(&self) -> Option<String> {
+        let battery = self.battery?.min(100);
+        Some(format!("battery-level-{}-symbolic", battery / 10 * 10))
+    }
+
+    /// Headsets, headphones and speakers
+    pub fn is_audio(&self) -> bool {
+        self.icon.starts_with("audio-")
+    }
+
+    /// Whether the device is close to the edge of its range, where audio starts to drop out
+    pub fn has_weak_signal(&self) -> bool {
+        self.rssi.map_or(false, |rssi| rssi < WEAK_SIGNAL_RSSI)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -259,11 +351,34 @@ pub enum BluerSessionEvent {
 pub enum BluerAgentEvent {
     DisplayPinCode(BluerDevice, String),
     DisplayPasskey(BluerDevice, String),
-    RequestPinCode(BluerDevice),
-    RequestPasskey(BluerDevice),
+    /// The suggested PIN code can be edited by the user, `None` rejects the request
+    RequestPinCode(BluerDevice, String, Sender<Option<String>>),
+    RequestPasskey(BluerDevice, Sender<Option<u32>>),
     RequestConfirmation(BluerDevice, String, Sender<bool>), // Note mpsc channel is used bc the sender must be cloned in the iced Message machinery
     RequestDeviceAuthorization(BluerDevice, Sender<bool>),
     RequestServiceAuthorization(BluerDevice, Uuid, Sender<bool>),
+    /// The user did not answer the request for the device in time
+    RequestTimedOut(Address),
+}
+
+/// Wait for the user to answer an agent request, giving up after [`AGENT_TIMEOUT`]
+async fn agent_reply<T>(
+    tx: &Sender<BluerSessionEvent>,
+    address: Address,
+    rx: &mut Receiver<T>,
+) -> ReqResult<T> {
+    match timeout(AGENT_TIMEOUT, rx.recv()).await {
+        Ok(Some(reply)) => Ok(reply),
+        Ok(None) => Err(ReqError::Rejected),
+        Err(_) => {
+            let _ = tx
+                .send(BluerSessionEvent::AgentEvent(
+                    BluerAgentEvent::RequestTimedOut(address),
+                ))
+                .await;
+            Err(ReqError::Canceled)
+        }
+    }
 }
 
 pub struct BluerSessionState {
@@ -308,18 +423,23 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
+                    // suggest a random PIN, which is typed on the device, e.g. a keyboard
+                    let pin_code = format!("{:06}", rand::thread_rng().gen_range(0..999999));
+                    let (tx, mut rx) = channel(1);
                     let _ = tx_clone
                         .send(BluerSessionEvent::AgentEvent(
                             BluerAgentEvent::RequestPinCode(
                                 BluerDevice::from_device(&device).await,
+                                pin_code,
+                                tx,
                             ),
                         ))
                         .await;
-                    let mut rng = rand::thread_rng();
-                    let pin_code = rng.gen_range(0..999999);
-                    Ok(format!("{:06}", pin_code))
+                    agent_reply(&tx_clone, req.device, &mut rx)
+                        .await?
+                        .ok_or(ReqError::Rejected)
                 })
             })),
             display_pin_code: Some(Box::new(move |req| {
@@ -328,7 +448,7 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
                     let _ = tx_clone
                         .send(BluerSessionEvent::AgentEvent(
@@ -348,18 +468,20 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
+                    let (tx, mut rx) = channel(1);
                     let _ = tx_clone
                         .send(BluerSessionEvent::AgentEvent(
                             BluerAgentEvent::RequestPasskey(
                                 BluerDevice::from_device(&device).await,
+                                tx,
                             ),
                         ))
                         .await;
-                    let mut rng = rand::thread_rng();
-                    let pin_code = rng.gen_range(0..999999);
-                    Ok(pin_code)
+                    agent_reply(&tx_clone, req.device, &mut rx)
+                        .await?
+                        .ok_or(ReqError::Rejected)
                 })
             })),
             display_passkey: Some(Box::new(move |req| {
@@ -368,7 +490,7 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
                     let _ = tx_clone
                         .send(BluerSessionEvent::AgentEvent(
@@ -387,7 +509,7 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
                     let (tx, mut rx) = channel(1);
                     let _ = tx_clone
@@ -399,10 +521,10 @@ impl BluerSessionState {
                             ),
                         ))
                         .await;
-                    let res = rx.recv().await;
-                    match res {
-                        Some(res) if res => Ok(()),
-                        _ => Err(bluer::agent::ReqError::Rejected),
+                    if agent_reply(&tx_clone, req.device, &mut rx).await? {
+                        Ok(())
+                    } else {
+                        Err(ReqError::Rejected)
                     }
                 })
             })),
@@ -412,7 +534,7 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
                     let (tx, mut rx) = channel(1);
                     let _ = tx_clone
@@ -423,10 +545,10 @@ impl BluerSessionState {
                             ),
                         ))
                         .await;
-                    let res = rx.recv().await;
-                    match res {
-                        Some(res) if res => Ok(()),
-                        _ => Err(bluer::agent::ReqError::Rejected),
+                    if agent_reply(&tx_clone, req.device, &mut rx).await? {
+                        Ok(())
+                    } else {
+                        Err(ReqError::Rejected)
                     }
                 })
             })),
@@ -436,7 +558,7 @@ impl BluerSessionState {
                 Box::pin(async move {
                     let device = match agent_clone.device(req.device) {
                         Ok(d) => d,
-                        Err(_) => return Err(bluer::agent::ReqError::Rejected),
+                        Err(_) => return Err(ReqError::Rejected),
                     };
                     let (tx, mut rx) = channel(1);
                     // TODO better describe the service to the user
@@ -449,10 +571,10 @@ impl BluerSessionState {
                             ),
                         ))
                         .await;
-                    let res = rx.recv().await;
-                    match res {
-                        Some(res) if res => Ok(()),
-                        _ => Err(bluer::agent::ReqError::Rejected),
+                    if agent_reply(&tx_clone, req.device, &mut rx).await? {
+                        Ok(())
+                    } else {
+                        Err(ReqError::Rejected)
                     }
                 })
             })),
@@ -471,6 +593,7 @@ impl BluerSessionState {
         };
         self_.process_requests(request_rx);
         self_.process_changes();
+        self_.process_battery_changes();
 
         Ok(self_)
     }
@@ -497,18 +620,9 @@ impl BluerSessionState {
                     if devices_changed {
                         devices_changed = false;
                         let _ = tx
-                            .send(BluerSessionEvent::ChangesProcessed(BluerState {
-                                devices: build_device_list(&adapter_clone).await,
-                                bluetooth_enabled: adapter_clone
-                                    .is_powered()
-                                    .await
-                                    .unwrap_or_default(),
-                                discoverable: adapter_clone
-                                    .is_discoverable()
-                                    .await
-                                    .unwrap_or_default(),
-                                pairable: adapter_clone.is_pairable().await.unwrap_or_default(),
-                            }))
+                            .send(BluerSessionEvent::ChangesProcessed(
+                                build_state(&adapter_clone).await,
+                            ))
                             .await;
                         // reset timeout
                         milli_timeout = 10;
@@ -522,6 +636,44 @@ impl BluerSessionState {
             });
     }
 
+    /// Battery levels are not part of the discovery changes, so watch each device's properties
+    pub(crate) fn process_battery_changes(&self) {
+        let tx = self.tx.clone();
+        let adapter = self.adapter.clone();
+        let _handle: JoinHandle<()> = spawn(async move {
+            let mut watched = HashSet::new();
+            loop {
+                for address in adapter.device_addresses().await.unwrap_or_default() {
+                    if watched.contains(&address) {
+                        continue;
+                    }
+                    let Ok(device) = adapter.device(address) else {
+                        continue;
+                    };
+                    let Ok(mut events) = device.events().await else {
+                        continue;
+                    };
+                    watched.insert(address);
+                    let tx = tx.clone();
+                    let adapter = adapter.clone();
+                    spawn(async move {
+                        while let Some(DeviceEvent::PropertyChanged(property)) = events.next().await
+                        {
+                            if matches!(property, DeviceProperty::BatteryPercentage(_)) {
+                                let _ = tx
+                                    .send(BluerSessionEvent::ChangesProcessed(
+                                        build_state(&adapter).await,
+                                    ))
+                                    .await;
+                            }
+                        }
+                    });
+                }
+                tokio::time::sleep(BATTERY_RESCAN).await;
+            }
+        });
+    }
+
     pub(crate) fn process_requests(&self, request_rx: Receiver<BluerRequest>) {
         let active_requests = self.active_requests.clone();
         let adapter = self.adapter.clone();
@@ -540,12 +692,24 @@ impl BluerSessionState {
                     let mut err_msg = None;
                     match &req_clone {
                         BluerRequest::SetBluetoothEnabled(enabled) => {
+                            // the radio has to be unblocked before the adapter can be powered,
+                            // and blocked after it was powered off so devices disconnect cleanly
+                            if *enabled {
+                                if let Err(e) = rfkill::set_blocked(false).await {
+                                    err_msg = Some(e.to_string());
+                                }
+                            }
                             let res = adapter_clone.set_powered(*enabled).await;
                             if let Err(e) = res {
                                 err_msg = Some(e.to_string());
                             }
+                            if !*enabled {
+                                if let Err(e) = rfkill::set_blocked(true).await {
+                                    err_msg = Some(e.to_string());
+                                }
+                            }
                             if *enabled {
-                                let res = adapter_clone.set_discoverable(*enabled).await;
+                                let res = set_discoverable(&adapter_clone).await;
                                 if let Err(e) = res {
                                     err_msg = Some(e.to_string());
                                 }
@@ -592,6 +756,56 @@ impl BluerSessionState {
                                 err_msg = Some("No active connection request found".to_string());
                             }
                         }
+                        BluerRequest::SetTrusted(address, trusted) => {
+                            let res = adapter_clone.device(address.clone());
+                            if let Err(err) = res {
+                                err_msg = Some(err.to_string());
+                            } else if let Ok(device) = res {
+                                let res = device.set_trusted(*trusted).await;
+                                if let Err(err) = res {
+                                    err_msg = Some(err.to_string());
+                                }
+                            }
+                        }
+                        BluerRequest::SetBlocked(address, blocked) => {
+                            let res = adapter_clone.device(address.clone());
+                            if let Err(err) = res {
+                                err_msg = Some(err.to_string());
+                            } else if let Ok(device) = res {
+                                let res = device.set_blocked(*blocked).await;
+                                if let Err(err) = res {
+                                    err_msg = Some(err.to_string());
+                                }
+                            }
+                        }
+                        BluerRequest::SetAlias(address, alias) => {
+                            let res = adapter_clone.device(address.clone());
+                            if let Err(err) = res {
+                                err_msg = Some(err.to_string());
+                            } else if let Ok(device) = res {
+                                let res = device.set_alias(alias.clone()).await;
+                                if let Err(err) = res {
+                                    err_msg = Some(err.to_string());
+                                }
+                            }
+                        }
+                        BluerRequest::RemoveDevice(address) => {
+                            let res = adapter_clone.remove_device(*address).await;
+                            if let Err(err) = res {
+                                err_msg = Some(err.to_string());
+                            }
+                        }
+                        BluerRequest::SetRssiUpdates(enabled) => {
+                            let res = adapter_clone
+                                .set_discovery_filter(DiscoveryFilter {
+                                    duplicate_data: *enabled,
+                                    ..Default::default()
+                                })
+                                .await;
+                            if let Err(err) = res {
+                                err_msg = Some(err.to_string());
+                            }
+                        }
                         BluerRequest::StateUpdate => {}
                         BluerRequest::SetPairable(enabled) => {
                             let res = adapter_clone.set_pairable(*enabled).await;
@@ -600,19 +814,18 @@ impl BluerSessionState {
                             }
                         }
                         BluerRequest::SetDiscoverable(enabled) => {
-                            let res = adapter_clone.set_discoverable(*enabled).await;
+                            let res = if *enabled {
+                                set_discoverable(&adapter_clone).await
+                            } else {
+                                adapter_clone.set_discoverable(false).await
+                            };
                             if let Err(e) = res {
                                 err_msg = Some(e.to_string());
                             }
                         }
                     };
 
-                    let state = BluerState {
-                        devices: build_device_list(&adapter_clone).await,
-                        bluetooth_enabled: adapter_clone.is_powered().await.unwrap_or_default(),
-                        discoverable: adapter_clone.is_discoverable().await.unwrap_or_default(),
-                        pairable: adapter_clone.is_pairable().await.unwrap_or_default(),
-                    };
+                    let state = build_state(&adapter_clone).await;
 
                     let _ = tx_clone
                         .send(BluerSessionEvent::RequestResponse {
@@ -635,13 +848,26 @@ impl BluerSessionState {
     }
 
     pub(crate) async fn bluer_state(&self) -> BluerState {
-        BluerState {
-            devices: build_device_list(&self.adapter).await,
-            // TODO is this a proper way of checking if bluetooth is enabled?
-            bluetooth_enabled: self.adapter.is_powered().await.unwrap_or_default(),
-            discoverable: self.adapter.is_discoverable().await.unwrap_or_default(),
-            pairable: self.adapter.is_pairable().await.unwrap_or_default(),
-        }
+        build_state(&self.adapter).await
+    }
+}
+
+/// Make the adapter discoverable, BlueZ turns it off again after `DISCOVERABLE_TIMEOUT`
+async fn set_discoverable(adapter: &Adapter) -> bluer::Result<()> {
+    adapter
+        .set_discoverable_timeout(DISCOVERABLE_TIMEOUT.as_secs() as u32)
+        .await?;
+    adapter.set_discoverable(true).await
+}
+
+async fn build_state(adapter: &Adapter) -> BluerState {
+    BluerState {
+        devices: build_device_list(adapter).await,
+        // TODO is this a proper way of checking if bluetooth is enabled?
+        bluetooth_enabled: adapter.is_powered().await.unwrap_or_default(),
+        discoverable: adapter.is_discoverable().await.unwrap_or_default(),
+        pairable: adapter.is_pairable().await.unwrap_or_default(),
+        alias: adapter.alias().await.unwrap_or_default(),
     }
 }
 
@@ -1,3 +1,54 @@
+use std::collections::HashMap;
+
+use bluer::Address;
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
 pub const APP_ID: &str = "com.system76.CosmicAppletBluetooth";
 pub const PROFILE: &str = "";
 pub const VERSION: &str = "0.1.0";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct BluetoothAppletConfig {
+    /// Reconnect paired devices when they come into range, unless overridden per device
+    pub auto_connect_default: bool,
+    /// Per device auto-connect setting, keyed by device address
+    pub auto_connect: HashMap<String, bool>,
+    /// Address of the audio device that connected most recently
+    pub last_audio_device: Option<String>,
+}
+
+impl BluetoothAppletConfig {
+    pub fn load() -> BluetoothAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| BluetoothAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn auto_connects(&self, address: Address) -> bool {
+        self.auto_connect
+            .get(&address.to_string())
+            .copied()
+            .unwrap_or(self.auto_connect_default)
+    }
+
+    pub fn set_auto_connect_default(&mut self, auto_connect: bool, config: &Config) {
+        self.auto_connect_default = auto_connect;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_auto_connect(&mut self, address: Address, auto_connect: bool, config: &Config) {
+        self.auto_connect.insert(address.to_string(), auto_connect);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn last_audio_device(&self) -> Option<Address> {
+        self.last_audio_device.as_ref()?.parse().ok()
+    }
+
+    pub fn set_last_audio_device(&mut self, address: Address, config: &Config) {
+        self.last_audio_device = Some(address.to_string());
+        let _ = self.write_entry(config);
+    }
+}
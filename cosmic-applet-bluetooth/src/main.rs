@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 mod app;
+mod audio_profile;
 mod bluetooth;
 mod config;
 mod localize;
+mod obex;
+mod rfkill;
 
 use log::info;
 
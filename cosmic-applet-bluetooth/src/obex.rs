@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sending files to devices through obexd's Object Push profile
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::bail;
+use bluer::Address;
+use cosmic::iced::{
+    self,
+    futures::{SinkExt, StreamExt},
+    subscription,
+};
+use zbus::{
+    dbus_proxy,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[dbus_proxy(
+    interface = "org.bluez.obex.Client1",
+    default_service = "org.bluez.obex",
+    default_path = "/org/bluez/obex"
+)]
+trait Client {
+    fn create_session(
+        &self,
+        destination: &str,
+        args: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn remove_session(&self, session: &OwnedObjectPath) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.bluez.obex.ObjectPush1",
+    default_service = "org.bluez.obex"
+)]
+trait ObjectPush {
+    fn send_file(
+        &self,
+        sourcefile: &str,
+    ) -> zbus::Result<(OwnedObjectPath, HashMap<String, OwnedValue>)>;
+}
+
+#[dbus_proxy(
+    interface = "org.bluez.obex.Transfer1",
+    default_service = "org.bluez.obex"
+)]
+trait Transfer {
+    fn cancel(&self) -> zbus::Result<()>;
+
+    /// `queued`, `active`, `suspended`, `complete` or `error`
+    #[dbus_proxy(property)]
+    fn status(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn size(&self) -> zbus::Result<u64>;
+
+    #[dbus_proxy(property)]
+    fn transferred(&self) -> zbus::Result<u64>;
+}
+
+#[derive(Debug, Clone)]
+pub enum TransferEvent {
+    /// The transfer object, needed to cancel it
+    Started(OwnedObjectPath),
+    Progress {
+        transferred: u64,
+        size: u64,
+    },
+    Finished,
+    Failed(String),
+}
+
+/// Push the file to the device, reporting the progress until the transfer ends
+pub fn send_file(address: Address, file: PathBuf) -> iced::Subscription<TransferEvent> {
+    subscription::channel((address, file.clone()), 10, move |mut output| async move {
+        match push(address, file, &mut output).await {
+            Ok(()) => _ = output.send(TransferEvent::Finished).await,
+            Err(err) => _ = output.send(TransferEvent::Failed(err.to_string())).await,
+        }
+        iced::futures::future::pending().await
+    })
+}
+
+async fn push(
+    address: Address,
+    file: PathBuf,
+    output: &mut futures::channel::mpsc::Sender<TransferEvent>,
+) -> anyhow::Result<()> {
+    let conn = Connection::session().await?;
+    let client = ClientProxy::new(&conn).await?;
+    let session = client
+        .create_session(
+            &address.to_string(),
+            HashMap::from([("Target", Value::from("opp"))]),
+        )
+        .await?;
+    let res = transfer(&conn, &session, file, output).await;
+    let _ = client.remove_session(&session).await;
+    res
+}
+
+async fn transfer(
+    conn: &Connection,
+    session: &OwnedObjectPath,
+    file: PathBuf,
+    output: &mut futures::channel::mpsc::Sender<TransferEvent>,
+) -> anyhow::Result<()> {
+    let object_push = ObjectPushProxy::builder(conn)
+        .path(session.clone())?
+        .build()
+        .await?;
+    let (path, _) = object_push.send_file(&file.to_string_lossy()).await?;
+    _ = output.send(TransferEvent::Started(path.clone())).await;
+
+    let transfer = TransferProxy::builder(conn).path(path)?.build().await?;
+    let size = transfer.size().await.unwrap_or_default();
+    let mut statuses = transfer.receive_status_changed().await;
+    let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+    loop {
+        tokio::select! {
+            biased;
+            Some(status) = statuses.next() => match status.get().await?.as_str() {
+                "complete" => return Ok(()),
+                "error" => bail!("The device did not accept the file"),
+                _ => {}
+            },
+            _ = progress.tick() => {
+                // obexd removes the transfer once it ends, and always reports the final status first
+                let Ok(transferred) = transfer.transferred().await else {
+                    bail!("The transfer was interrupted");
+                };
+                _ = output.send(TransferEvent::Progress { transferred, size }).await;
+            }
+        }
+    }
+}
+
+pub async fn cancel(transfer: OwnedObjectPath) -> anyhow::Result<()> {
+    let conn = Connection::session().await?;
+    TransferProxy::builder(&conn)
+        .path(transfer)?
+        .build()
+        .await?
+        .cancel()
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Bluetooth radio state as seen by rfkill, which also covers hardware switches and airplane mode.
+//! Events are read from and written to the rfkill device, as the `rfkill` tool does.
+
+use std::collections::HashMap;
+
+use cosmic::iced::{
+    self,
+    futures::{self, SinkExt},
+    subscription,
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+
+const RFKILL_DEVICE: &str = "/dev/rfkill";
+/// Size of the original `struct rfkill_event`, which the kernel sends unless asked for more
+const EVENT_SIZE: usize = 8;
+/// `RFKILL_TYPE_BLUETOOTH` from linux/rfkill.h
+const TYPE_BLUETOOTH: u8 = 2;
+/// `RFKILL_OP_DEL`
+const OP_DEL: u8 = 1;
+/// `RFKILL_OP_CHANGE_ALL`
+const OP_CHANGE_ALL: u8 = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RfkillState {
+    /// Blocked in software, e.g. by airplane mode
+    pub soft_blocked: bool,
+    /// Blocked by a hardware switch, it can't be undone from here
+    pub hard_blocked: bool,
+}
+
+impl RfkillState {
+    pub fn is_blocked(&self) -> bool {
+        self.soft_blocked || self.hard_blocked
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Event {
+    idx: u32,
+    type_: u8,
+    op: u8,
+    soft: bool,
+    hard: bool,
+}
+
+impl Event {
+    /// `struct rfkill_event { __u32 idx; __u8 type; __u8 op; __u8 soft; __u8 hard; }`
+    fn from_bytes(bytes: [u8; EVENT_SIZE]) -> Self {
+        Event {
+            idx: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            type_: bytes[4],
+            op: bytes[5],
+            soft: bytes[6] != 0,
+            hard: bytes[7] != 0,
+        }
+    }
+}
+
+/// Block or unblock all Bluetooth radios
+pub async fn set_blocked(blocked: bool) -> anyhow::Result<()> {
+    let mut event = [0; EVENT_SIZE];
+    event[4] = TYPE_BLUETOOTH;
+    event[5] = OP_CHANGE_ALL;
+    event[6] = blocked.into();
+    let mut device = OpenOptions::new().write(true).open(RFKILL_DEVICE).await?;
+    device.write_all(&event).await?;
+    device.flush().await?;
+    Ok(())
+}
+
+/// Emits the combined state of the Bluetooth radios, starting with the current one
+pub fn rfkill_subscription() -> iced::Subscription<RfkillState> {
+    struct RfkillWatcher;
+
+    subscription::channel(
+        std::any::TypeId::of::<RfkillWatcher>(),
+        10,
+        |mut output| async move {
+            match File::open(RFKILL_DEVICE).await {
+                Ok(mut device) => {
+                    // the existing radios are reported as added first
+                    let mut radios = HashMap::new();
+                    let mut event = [0; EVENT_SIZE];
+                    while device.read_exact(&mut event).await.is_ok() {
+                        let event = Event::from_bytes(event);
+                        if event.type_ != TYPE_BLUETOOTH {
+                            continue;
+                        }
+                        if event.op == OP_DEL {
+                            radios.remove(&event.idx);
+                        } else {
+                            radios.insert(event.idx, (event.soft, event.hard));
+                        }
+                        let state = RfkillState {
+                            soft_blocked: radios.values().any(|(soft, _)| *soft),
+                            hard_blocked: radios.values().any(|(_, hard)| *hard),
+                        };
+                        _ = output.send(state).await;
+                    }
+                }
+                Err(err) => log::warn!("Failed to watch rfkill: {}", err),
+            }
+            futures::future::pending().await
+        },
+    )
+}
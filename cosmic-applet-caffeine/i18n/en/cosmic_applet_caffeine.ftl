@@ -0,0 +1,11 @@
+keep-awake = Keep Awake
+inhibit-reason = Keeping the screen awake on request
+duration = Duration
+minutes = { $minutes } min
+hours = { $hours ->
+    [one] { $hours } hour
+   *[other] { $hours } hours
+}
+until-off = Until off
+remaining = { $time } remaining
+unsupported = The compositor doesn't allow keeping the screen awake
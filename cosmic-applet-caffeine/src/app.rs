@@ -0,0 +1,274 @@
+use crate::config::{self, CaffeineAppletConfig, DURATIONS};
+use crate::fl;
+use crate::idle::{idle_inhibitor, IdleUpdate};
+use crate::logind;
+use cctk::sctk::reexports::calloop::channel;
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    time,
+    widget::{button, column, mouse_area, text, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::toggler;
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zbus::zvariant::OwnedFd;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Caffeine>(true, ())
+}
+
+#[derive(Default)]
+struct Caffeine {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: CaffeineAppletConfig,
+    idle_tx: Option<channel::Sender<bool>>,
+    unsupported: bool,
+    active: bool,
+    /// When a timed inhibition ends
+    until: Option<Instant>,
+    /// Keeps the logind inhibitor until dropped
+    logind_inhibitor: Option<Arc<OwnedFd>>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Toggle,
+    SetActive(bool),
+    SetDuration(u32),
+    Idle(IdleUpdate),
+    LogindInhibited(Option<Arc<OwnedFd>>),
+    Tick,
+    ConfigUpdated(CaffeineAppletConfig),
+}
+
+fn duration_label(minutes: u32) -> String {
+    match minutes {
+        0 => fl!("until-off"),
+        m if m % 60 == 0 => fl!("hours", hours = m / 60),
+        m => fl!("minutes", minutes = m),
+    }
+}
+
+fn format_remaining(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+impl Caffeine {
+    fn activate(&mut self) -> Command<Message> {
+        self.until = match self.config.duration_minutes {
+            0 => None,
+            minutes => Some(Instant::now() + Duration::from_secs(u64::from(minutes) * 60)),
+        };
+        if self.active {
+            return Command::none();
+        }
+        self.active = true;
+        if let Some(tx) = &self.idle_tx {
+            let _ = tx.send(true);
+        }
+        cosmic::iced::Command::perform(logind::inhibit(fl!("inhibit-reason")), |res| {
+            app::message::app(Message::LogindInhibited(match res {
+                Ok(fd) => Some(Arc::new(fd)),
+                Err(err) => {
+                    log::error!("Failed to inhibit sleep: {}", err);
+                    None
+                }
+            }))
+        })
+    }
+
+    fn deactivate(&mut self) {
+        self.active = false;
+        self.until = None;
+        self.logind_inhibitor = None;
+        if let Some(tx) = &self.idle_tx {
+            let _ = tx.send(false);
+        }
+    }
+}
+
+impl cosmic::Application for Caffeine {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Caffeine {
+                core,
+                config: CaffeineAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(250.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Toggle => {
+                if self.active {
+                    self.deactivate();
+                } else {
+                    return self.activate();
+                }
+            }
+            Message::SetActive(true) => return self.activate(),
+            Message::SetActive(false) => self.deactivate(),
+            Message::SetDuration(duration_minutes) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_duration_minutes(duration_minutes, &config);
+                }
+                // the new duration counts from now
+                if self.active {
+                    return self.activate();
+                }
+            }
+            Message::Idle(IdleUpdate::Init(tx)) => {
+                if self.active {
+                    let _ = tx.send(true);
+                }
+                self.idle_tx = Some(tx);
+            }
+            Message::Idle(IdleUpdate::Failed) => {
+                self.unsupported = true;
+            }
+            Message::LogindInhibited(inhibitor) => {
+                // dropped right away if turned off in the meantime
+                if self.active {
+                    self.logind_inhibitor = inhibitor;
+                }
+            }
+            Message::Tick => {
+                if self.until.map_or(false, |until| until <= Instant::now()) {
+                    self.deactivate();
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let icon = if self.active {
+            "cosmic-applet-caffeine-on-symbolic"
+        } else {
+            "cosmic-applet-caffeine-off-symbolic"
+        };
+        mouse_area(
+            self.core
+                .applet_helper
+                .icon_button(icon)
+                .on_press(Message::Toggle),
+        )
+        .on_right_press(Message::TogglePopup)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut durations = Row::new().spacing(8).align_items(Alignment::Center);
+        durations = durations.push(text(fl!("duration")).size(14).width(Length::Fill));
+        for minutes in DURATIONS {
+            let style = if minutes == self.config.duration_minutes {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            durations = durations.push(
+                button(text(duration_label(minutes)).size(14))
+                    .padding([4, 8])
+                    .style(style)
+                    .on_press(Message::SetDuration(minutes)),
+            );
+        }
+
+        let mut content = column![toggler(fl!("keep-awake"), self.active, Message::SetActive)
+            .text_size(14)
+            .width(Length::Fill),]
+        .spacing(12)
+        .padding([8, 24]);
+        if let Some(until) = self.until {
+            let remaining = until.saturating_duration_since(Instant::now());
+            content =
+                content.push(text(fl!("remaining", time = format_remaining(remaining))).size(12));
+        }
+        content = content.push(durations);
+        if self.unsupported {
+            content = content.push(text(fl!("unsupported")).size(12));
+        }
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            idle_inhibitor().map(Message::Idle),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ];
+        if self.until.is_some() {
+            subscriptions.push(time::every(Duration::from_secs(1)).map(|_| Message::Tick));
+        }
+        Subscription::batch(subscriptions)
+    }
+}
@@ -0,0 +1,27 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletCaffeine";
+
+/// Durations offered in the popup, in minutes; `0` lasts until turned off
+pub const DURATIONS: [u32; 3] = [30, 60, 0];
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct CaffeineAppletConfig {
+    /// How long a click on the panel icon keeps the screen awake, as in [`DURATIONS`]
+    pub duration_minutes: u32,
+}
+
+impl CaffeineAppletConfig {
+    pub fn load() -> CaffeineAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| CaffeineAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_duration_minutes(&mut self, duration_minutes: u32, config: &Config) {
+        self.duration_minutes = duration_minutes;
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,258 @@
+//! Inhibits idling with the idle-inhibit protocol. An inhibitor only applies while its surface
+//! is visible, so it's attached to a transparent pixel on the overlay layer, which passes
+//! through all input.
+
+use cctk::sctk::reexports::{
+    calloop::{self, channel},
+    client::{
+        globals::{registry_queue_init, GlobalListContents},
+        protocol::{
+            wl_buffer::WlBuffer,
+            wl_compositor::WlCompositor,
+            wl_region::WlRegion,
+            wl_registry::WlRegistry,
+            wl_shm::{self, WlShm},
+            wl_shm_pool::WlShmPool,
+            wl_surface::WlSurface,
+        },
+        Connection, Dispatch, Proxy, QueueHandle, WaylandSource,
+    },
+};
+use cosmic::iced::{
+    self,
+    futures::{
+        channel::mpsc::{unbounded, UnboundedSender},
+        future, SinkExt, StreamExt,
+    },
+    subscription,
+};
+use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+use std::ffi::CStr;
+use std::thread;
+use wayland_protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+};
+use wayland_protocols_wlr::layer_shell::v1::client::{
+    zwlr_layer_shell_v1::{Layer, ZwlrLayerShellV1},
+    zwlr_layer_surface_v1::{self, Anchor, KeyboardInteractivity, ZwlrLayerSurfaceV1},
+};
+
+#[derive(Debug, Clone)]
+pub enum IdleUpdate {
+    Init(channel::Sender<bool>),
+    /// The compositor lacks idle-inhibit or layer-shell
+    Failed,
+}
+
+/// The inhibiting surface and its objects
+struct Inhibitor {
+    surface: WlSurface,
+    layer_surface: ZwlrLayerSurfaceV1,
+    inhibitor: ZwpIdleInhibitorV1,
+    buffer: Option<WlBuffer>,
+}
+
+struct State {
+    compositor: WlCompositor,
+    shm: WlShm,
+    layer_shell: ZwlrLayerShellV1,
+    inhibit_manager: ZwpIdleInhibitManagerV1,
+    inhibitor: Option<Inhibitor>,
+    queue_handle: QueueHandle<Self>,
+    exit: bool,
+}
+
+impl State {
+    fn inhibit(&mut self) {
+        if self.inhibitor.is_some() {
+            return;
+        }
+        let qh = &self.queue_handle;
+        let surface = self.compositor.create_surface(qh, ());
+        let region = self.compositor.create_region(qh, ());
+        surface.set_input_region(Some(&region));
+        region.destroy();
+        let layer_surface = self.layer_shell.get_layer_surface(
+            &surface,
+            None,
+            Layer::Overlay,
+            String::from("cosmic-applet-caffeine"),
+            qh,
+            (),
+        );
+        layer_surface.set_size(1, 1);
+        layer_surface.set_anchor(Anchor::Top | Anchor::Left);
+        layer_surface.set_keyboard_interactivity(KeyboardInteractivity::None);
+        surface.commit();
+        let inhibitor = self.inhibit_manager.create_inhibitor(&surface, qh, ());
+        self.inhibitor = Some(Inhibitor {
+            surface,
+            layer_surface,
+            inhibitor,
+            buffer: None,
+        });
+    }
+
+    fn uninhibit(&mut self) {
+        if let Some(inhibitor) = self.inhibitor.take() {
+            inhibitor.inhibitor.destroy();
+            inhibitor.layer_surface.destroy();
+            inhibitor.surface.destroy();
+            if let Some(buffer) = inhibitor.buffer {
+                buffer.destroy();
+            }
+        }
+    }
+
+    /// A single transparent pixel
+    fn buffer(&self) -> nix::Result<WlBuffer> {
+        let name = CStr::from_bytes_with_nul(b"cosmic-applet-caffeine\0").unwrap();
+        let fd = memfd_create(name, MemFdCreateFlag::MFD_CLOEXEC)?;
+        let res = nix::unistd::ftruncate(fd, 4).map(|_| {
+            let pool = self.shm.create_pool(fd, 4, &self.queue_handle, ());
+            let buffer =
+                pool.create_buffer(0, 1, 1, 4, wl_shm::Format::Argb8888, &self.queue_handle, ());
+            pool.destroy();
+            buffer
+        });
+        let _ = nix::unistd::close(fd);
+        res
+    }
+}
+
+fn run(rx: channel::Channel<bool>) -> anyhow::Result<()> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, event_queue) = registry_queue_init::<State>(&conn)?;
+    let qh = event_queue.handle();
+    let mut event_loop = calloop::EventLoop::<State>::try_new()?;
+    WaylandSource::new(event_queue)?
+        .insert(event_loop.handle())
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
+
+    let mut state = State {
+        compositor: globals.bind(&qh, 1..=4, ())?,
+        shm: globals.bind(&qh, 1..=1, ())?,
+        layer_shell: globals.bind(&qh, 1..=4, ())?,
+        inhibit_manager: globals.bind(&qh, 1..=1, ())?,
+        inhibitor: None,
+        queue_handle: qh,
+        exit: false,
+    };
+
+    event_loop
+        .handle()
+        .insert_source(rx, |event, _, state: &mut State| match event {
+            channel::Event::Msg(true) => state.inhibit(),
+            channel::Event::Msg(false) => state.uninhibit(),
+            channel::Event::Closed => state.exit = true,
+        })
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
+
+    while !state.exit {
+        event_loop.dispatch(None, &mut state)?;
+    }
+    Ok(())
+}
+
+pub fn idle_inhibitor() -> iced::Subscription<IdleUpdate> {
+    struct IdleInhibitor;
+
+    subscription::channel(
+        std::any::TypeId::of::<IdleInhibitor>(),
+        5,
+        |mut output| async move {
+            let (tx, mut rx) = unbounded();
+            let (request_tx, request_rx) = channel::channel();
+            thread::spawn(move || {
+                if let Err(err) = run(request_rx) {
+                    log::error!("Idle inhibition is unavailable: {}", err);
+                    let _ = tx.unbounded_send(IdleUpdate::Failed);
+                }
+            });
+            _ = output.send(IdleUpdate::Init(request_tx)).await;
+            while let Some(update) = rx.next().await {
+                _ = output.send(update).await;
+            }
+            future::pending().await
+        },
+    )
+}
+
+impl Dispatch<ZwlrLayerSurfaceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        layer_surface: &ZwlrLayerSurfaceV1,
+        event: zwlr_layer_surface_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_layer_surface_v1::Event::Configure { serial, .. } => {
+                layer_surface.ack_configure(serial);
+                let buffer = match state.inhibitor.as_ref().map(|i| i.buffer.is_none()) {
+                    Some(true) => match state.buffer() {
+                        Ok(buffer) => Some(buffer),
+                        Err(err) => {
+                            log::error!("Failed to create the inhibiting surface: {}", err);
+                            return;
+                        }
+                    },
+                    _ => None,
+                };
+                if let Some(inhibitor) = state.inhibitor.as_mut() {
+                    if let Some(buffer) = buffer {
+                        inhibitor.surface.attach(Some(&buffer), 0, 0);
+                        inhibitor.buffer = Some(buffer);
+                    }
+                    inhibitor.surface.commit();
+                }
+            }
+            // the surface was closed, such as when its output went away
+            zwlr_layer_surface_v1::Event::Closed => state.uninhibit(),
+            _ => {}
+        }
+    }
+}
+
+macro_rules! ignore_events {
+    ($($ty:ty),*) => {
+        $(
+            impl Dispatch<$ty, ()> for State {
+                fn event(
+                    _: &mut Self,
+                    _: &$ty,
+                    _: <$ty as Proxy>::Event,
+                    _: &(),
+                    _: &Connection,
+                    _: &QueueHandle<Self>,
+                ) {
+                }
+            }
+        )*
+    };
+}
+
+ignore_events!(
+    WlCompositor,
+    WlSurface,
+    WlRegion,
+    WlShm,
+    WlShmPool,
+    WlBuffer,
+    ZwlrLayerShellV1,
+    ZwpIdleInhibitManagerV1,
+    ZwpIdleInhibitorV1
+);
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
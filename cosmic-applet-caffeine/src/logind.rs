@@ -0,0 +1,21 @@
+//! Blocks idle actions and suspend through logind, for as long as the returned fd is open
+
+use zbus::{dbus_proxy, zvariant::OwnedFd, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    /// Inhibit method
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+}
+
+pub async fn inhibit(why: String) -> zbus::Result<OwnedFd> {
+    let conn = Connection::system().await?;
+    let proxy = ManagerProxy::new(&conn).await?;
+    proxy
+        .inhibit("idle:sleep", "COSMIC Caffeine", &why, "block")
+        .await
+}
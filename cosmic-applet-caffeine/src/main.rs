@@ -0,0 +1,11 @@
+mod app;
+mod config;
+mod idle;
+mod localize;
+mod logind;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
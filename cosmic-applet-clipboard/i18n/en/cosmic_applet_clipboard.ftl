@@ -0,0 +1,19 @@
+search = Search...
+image = Image
+empty = Nothing copied yet
+no-matches = No matching entries
+unsupported = The compositor doesn't allow a clipboard history
+pause = Pause Recording
+ignore-secrets = Ignore Passwords
+forget-after = Forget Entries After
+never = Never
+minutes = { $minutes } min
+hours = { $hours ->
+    [one] 1 hour
+    *[other] { $hours } hours
+}
+days = { $days ->
+    [one] 1 day
+    *[other] { $days } days
+}
+clear = Clear History
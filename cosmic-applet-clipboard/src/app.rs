@@ -0,0 +1,425 @@
+use crate::config::{self, ClipboardAppletConfig, EXPIRY_CHOICES};
+use crate::fl;
+use crate::wayland::{clipboard, ClipboardRequest, ClipboardUpdate, Content};
+use cctk::sctk::reexports::calloop::channel::Sender;
+use cosmic::app::{applet::applet_button_theme, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    time,
+    widget::{self, column, container, image, row, scrollable, text, text_input, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{button, divider, icon, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+/// Characters of text entries shown in the list
+const PREVIEW_CHARS: usize = 80;
+/// The list scrolls with more entries than this
+const SCROLL_AFTER: usize = 6;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<ClipboardApplet>(false, ())
+}
+
+struct Entry {
+    id: u64,
+    content: Content,
+    /// Decoded once, for the thumbnail of images
+    image: Option<image::Handle>,
+    pinned: bool,
+    copied_at: Instant,
+}
+
+impl Entry {
+    fn matches(&self, search: &str) -> bool {
+        match &self.content {
+            Content::Text(copied) => copied.to_lowercase().contains(search),
+            Content::Image { .. } => search.is_empty(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ClipboardApplet {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: ClipboardAppletConfig,
+    /// Newest first
+    entries: Vec<Entry>,
+    entry_ctr: u64,
+    search: String,
+    /// Nothing is recorded while paused, e.g. while handling sensitive data
+    paused: bool,
+    failed: bool,
+    sender: Option<Sender<ClipboardRequest>>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Clipboard(ClipboardUpdate),
+    Search(String),
+    Copy(u64),
+    TogglePin(u64),
+    Remove(u64),
+    Clear,
+    SetPaused(bool),
+    SetIgnoreSecrets(bool),
+    SetExpiry(Option<u32>),
+    Expire,
+    ConfigUpdated(ClipboardAppletConfig),
+}
+
+impl ClipboardApplet {
+    /// Records the content as the newest entry, an entry with the same content moves up
+    fn add(&mut self, content: Content, pinned: bool) {
+        let entry = match self.entries.iter().position(|e| e.content == content) {
+            Some(i) => self.entries.remove(i),
+            None => {
+                self.entry_ctr += 1;
+                let image = match &content {
+                    Content::Image { data, .. } => Some(image::Handle::from_memory(data.clone())),
+                    Content::Text(_) => None,
+                };
+                Entry {
+                    id: self.entry_ctr,
+                    content,
+                    image,
+                    pinned,
+                    copied_at: Instant::now(),
+                }
+            }
+        };
+        self.entries.insert(
+            0,
+            Entry {
+                copied_at: Instant::now(),
+                ..entry
+            },
+        );
+        let mut unpinned = 0;
+        let max_entries = self.config.max_entries;
+        self.entries.retain(|e| {
+            unpinned += usize::from(!e.pinned);
+            e.pinned || unpinned <= max_entries
+        });
+    }
+
+    fn save_pins(&mut self) {
+        let pinned = self
+            .entries
+            .iter()
+            .filter(|e| e.pinned)
+            .filter_map(|e| match &e.content {
+                Content::Text(copied) => Some(copied.clone()),
+                Content::Image { .. } => None,
+            })
+            .collect();
+        if let Ok(config) = Config::new(config::APP_ID, 1) {
+            self.config.set_pinned(pinned, &config);
+        }
+    }
+
+    fn entry_row(&self, entry: &Entry) -> Element<Message> {
+        let preview: Element<_> = match (&entry.content, &entry.image) {
+            (_, Some(handle)) => image(handle.clone()).height(Length::Fixed(48.0)).into(),
+            (Content::Text(copied), _) => {
+                let mut lines = copied.trim().lines();
+                let line = lines.next().unwrap_or_default();
+                let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+                if line.chars().count() > PREVIEW_CHARS || lines.next().is_some() {
+                    preview.push('…');
+                }
+                text(preview).size(14).into()
+            }
+            (Content::Image { .. }, None) => text(fl!("image")).size(14).into(),
+        };
+        row![
+            button(applet_button_theme())
+                .custom(vec![preview])
+                .padding([8, 24])
+                .width(Length::Fill)
+                .on_press(Message::Copy(entry.id)),
+            widget::button(icon("view-pin-symbolic", 16).style(if entry.pinned {
+                Svg::SymbolicActive
+            } else {
+                Svg::Symbolic
+            }))
+            .style(theme::Button::Text)
+            .on_press(Message::TogglePin(entry.id)),
+            widget::button(icon("edit-delete-symbolic", 16).style(Svg::Symbolic))
+                .style(theme::Button::Text)
+                .on_press(Message::Remove(entry.id)),
+        ]
+        .padding([0, 12, 0, 0])
+        .align_items(Alignment::Center)
+        .into()
+    }
+}
+
+fn expiry_label(expire_minutes: Option<u32>) -> String {
+    match expire_minutes {
+        None => fl!("never"),
+        Some(m) if m % (24 * 60) == 0 => fl!("days", days = m / (24 * 60)),
+        Some(m) if m % 60 == 0 => fl!("hours", hours = m / 60),
+        Some(m) => fl!("minutes", minutes = m),
+    }
+}
+
+impl cosmic::Application for ClipboardApplet {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        let mut applet = ClipboardApplet {
+            core,
+            config: ClipboardAppletConfig::load(),
+            ..Default::default()
+        };
+        // added from the last, as each entry goes on top
+        for text in applet.config.pinned.clone().into_iter().rev() {
+            applet.add(Content::Text(text), true);
+        }
+        (applet, Command::none())
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.search.clear();
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(200.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Clipboard(update) => match update {
+                ClipboardUpdate::Init(sender) => {
+                    self.sender = Some(sender);
+                }
+                ClipboardUpdate::Selection { content, secret } => {
+                    if !self.paused && !(secret && self.config.ignore_secrets) {
+                        self.add(content, false);
+                    }
+                }
+                ClipboardUpdate::Failed => {
+                    self.failed = true;
+                }
+            },
+            Message::Search(search) => {
+                self.search = search;
+            }
+            Message::Copy(id) => {
+                let Some(entry) = self.entries.iter().find(|e| e.id == id) else {
+                    return Command::none();
+                };
+                let content = entry.content.clone();
+                if let Some(sender) = self.sender.as_ref() {
+                    let _ = sender.send(ClipboardRequest::Copy(content.clone()));
+                }
+                self.add(content, false);
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+            }
+            Message::TogglePin(id) => {
+                if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+                    entry.pinned = !entry.pinned;
+                    self.save_pins();
+                }
+            }
+            Message::Remove(id) => {
+                let pinned = self.entries.iter().any(|e| e.id == id && e.pinned);
+                self.entries.retain(|e| e.id != id);
+                if pinned {
+                    self.save_pins();
+                }
+            }
+            Message::Clear => {
+                self.entries.retain(|e| e.pinned);
+            }
+            Message::SetPaused(paused) => {
+                self.paused = paused;
+            }
+            Message::SetIgnoreSecrets(ignore_secrets) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_ignore_secrets(ignore_secrets, &config);
+                }
+            }
+            Message::SetExpiry(expire_minutes) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_expire_minutes(expire_minutes, &config);
+                }
+            }
+            Message::Expire => {
+                if let Some(minutes) = self.config.expire_minutes {
+                    let max_age = Duration::from_secs(u64::from(minutes) * 60);
+                    self.entries
+                        .retain(|e| e.pinned || e.copied_at.elapsed() < max_age);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        self.core
+            .applet_helper
+            .icon_button("edit-paste-symbolic")
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let search = self.search.to_lowercase();
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|e| e.pinned)
+            .chain(self.entries.iter().filter(|e| !e.pinned))
+            .filter(|e| e.matches(&search))
+            .map(|e| self.entry_row(e))
+            .collect();
+
+        let mut content = Column::new()
+            .push(
+                container(
+                    text_input(&fl!("search"), &self.search)
+                        .on_input(Message::Search)
+                        .size(14)
+                        .width(Length::Fill),
+                )
+                .padding([0, 24]),
+            )
+            .spacing(8)
+            .padding([8, 0]);
+        if self.failed {
+            content = content.push(container(text(fl!("unsupported")).size(14)).padding([0, 24]));
+        } else if entries.is_empty() {
+            let empty = if self.search.is_empty() {
+                fl!("empty")
+            } else {
+                fl!("no-matches")
+            };
+            content = content.push(container(text(empty).size(14)).padding([0, 24]));
+        } else if entries.len() > SCROLL_AFTER {
+            content = content
+                .push(scrollable(Column::with_children(entries)).height(Length::Fixed(300.0)));
+        } else {
+            content = content.push(Column::with_children(entries));
+        }
+
+        let mut expiry = Row::new().spacing(8).align_items(Alignment::Center);
+        for choice in EXPIRY_CHOICES {
+            let style = if choice == self.config.expire_minutes {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            expiry = expiry.push(
+                button(style)
+                    .custom(vec![text(expiry_label(choice)).size(14).into()])
+                    .padding([4, 8])
+                    .on_press(Message::SetExpiry(choice)),
+            );
+        }
+
+        content = content
+            .push(
+                container(divider::horizontal::light())
+                    .width(Length::Fill)
+                    .padding([0, 12]),
+            )
+            .push(
+                column![
+                    toggler(fl!("pause"), self.paused, Message::SetPaused)
+                        .text_size(14)
+                        .width(Length::Fill),
+                    toggler(
+                        fl!("ignore-secrets"),
+                        self.config.ignore_secrets,
+                        Message::SetIgnoreSecrets
+                    )
+                    .text_size(14)
+                    .width(Length::Fill),
+                    text(fl!("forget-after")).size(14),
+                    expiry,
+                ]
+                .spacing(8)
+                .padding([0, 24]),
+            )
+            .push(
+                container(divider::horizontal::light())
+                    .width(Length::Fill)
+                    .padding([0, 12]),
+            )
+            .push(
+                button(applet_button_theme())
+                    .custom(vec![text(fl!("clear")).size(14).into()])
+                    .padding([8, 24])
+                    .width(Length::Fill)
+                    .on_press(Message::Clear),
+            );
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            clipboard().map(Message::Clipboard),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ];
+        if self.config.expire_minutes.is_some() {
+            subscriptions.push(time::every(Duration::from_secs(60)).map(|_| Message::Expire));
+        }
+        Subscription::batch(subscriptions)
+    }
+}
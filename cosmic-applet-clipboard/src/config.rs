@@ -0,0 +1,55 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletClipboard";
+
+/// Expiry choices offered in the popup, in minutes, `None` keeps entries until they are
+/// pushed out
+pub const EXPIRY_CHOICES: [Option<u32>; 4] = [None, Some(10), Some(60), Some(24 * 60)];
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct ClipboardAppletConfig {
+    /// Entries kept besides the pinned ones
+    pub max_entries: usize,
+    /// Skip what password managers mark as secret
+    pub ignore_secrets: bool,
+    /// Unpinned entries are forgotten after this many minutes
+    pub expire_minutes: Option<u32>,
+    /// Pinned text, kept across sessions
+    pub pinned: Vec<String>,
+}
+
+impl Default for ClipboardAppletConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 50,
+            ignore_secrets: true,
+            expire_minutes: None,
+            pinned: Vec::new(),
+        }
+    }
+}
+
+impl ClipboardAppletConfig {
+    pub fn load() -> ClipboardAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| ClipboardAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_ignore_secrets(&mut self, ignore_secrets: bool, config: &Config) {
+        self.ignore_secrets = ignore_secrets;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_expire_minutes(&mut self, expire_minutes: Option<u32>, config: &Config) {
+        self.expire_minutes = expire_minutes;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_pinned(&mut self, pinned: Vec<String>, config: &Config) {
+        self.pinned = pinned;
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,322 @@
+//! Watches and sets the clipboard with the wlr-data-control protocol, which lets a client
+//! without focus see every selection
+
+use cctk::sctk::reexports::{
+    calloop::{self, channel},
+    client::{
+        event_created_child,
+        globals::{registry_queue_init, GlobalListContents},
+        protocol::{wl_registry::WlRegistry, wl_seat::WlSeat},
+        Connection, Dispatch, Proxy, QueueHandle, WaylandSource,
+    },
+};
+use cosmic::iced::{
+    self,
+    futures::{
+        channel::mpsc::{unbounded, UnboundedSender},
+        future, SinkExt, StreamExt,
+    },
+    subscription,
+};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::sync::Mutex;
+use std::thread;
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+    zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+};
+
+/// Preferred first
+const TEXT_MIME_TYPES: [&str; 5] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+const IMAGE_MIME_TYPES: [&str; 2] = ["image/png", "image/jpeg"];
+/// Offered along our own selections, so they aren't recorded again
+const OWN_MIME_TYPE: &str = "application/x-cosmic-clipboard-history";
+/// Set by KeePassXC and others on copied passwords
+const SECRET_MIME_TYPE: &str = "x-kde-passwordManagerHint";
+/// Bigger selections are not recorded
+const MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Content {
+    Text(String),
+    Image { mime_type: String, data: Vec<u8> },
+}
+
+impl Content {
+    fn mime_types(&self) -> Vec<String> {
+        match self {
+            Content::Text(_) => TEXT_MIME_TYPES.iter().map(|m| m.to_string()).collect(),
+            Content::Image { mime_type, .. } => vec![mime_type.clone()],
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Content::Text(text) => text.as_bytes(),
+            Content::Image { data, .. } => data,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ClipboardUpdate {
+    Init(channel::Sender<ClipboardRequest>),
+    /// Something new was copied, `secret` if a password manager marked it so
+    Selection {
+        content: Content,
+        secret: bool,
+    },
+    /// The compositor doesn't support data control
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClipboardRequest {
+    Copy(Content),
+}
+
+/// The mime types of an offer, as they are announced
+#[derive(Debug, Default)]
+struct OfferData(Mutex<Vec<String>>);
+
+struct State {
+    tx: UnboundedSender<ClipboardUpdate>,
+    manager: ZwlrDataControlManagerV1,
+    device: ZwlrDataControlDeviceV1,
+    queue_handle: QueueHandle<Self>,
+    exit: bool,
+}
+
+impl State {
+    fn receive(&self, conn: &Connection, offer: &ZwlrDataControlOfferV1) {
+        let mime_types = offer
+            .data::<OfferData>()
+            .map(|d| d.0.lock().unwrap().clone())
+            .unwrap_or_default();
+        let offers = |m: &&str| mime_types.iter().any(|o| o == m);
+        if offers(&OWN_MIME_TYPE) {
+            return;
+        }
+        let secret = offers(&SECRET_MIME_TYPE);
+        let text = TEXT_MIME_TYPES.iter().find(offers);
+        let Some(mime_type) = text.or_else(|| IMAGE_MIME_TYPES.iter().find(offers)) else {
+            return;
+        };
+        let Ok((read, write)) = nix::unistd::pipe() else {
+            return;
+        };
+        offer.receive(mime_type.to_string(), write);
+        let _ = nix::unistd::close(write);
+        let _ = conn.flush();
+
+        let (tx, is_text, mime_type) = (self.tx.clone(), text.is_some(), mime_type.to_string());
+        // the source writes at its own pace, which mustn't hold up the event loop
+        thread::spawn(move || {
+            let file = unsafe { File::from_raw_fd(read) };
+            let mut data = Vec::new();
+            if file.take(MAX_SIZE + 1).read_to_end(&mut data).is_err()
+                || data.is_empty()
+                || data.len() as u64 > MAX_SIZE
+            {
+                return;
+            }
+            let content = if is_text {
+                Content::Text(String::from_utf8_lossy(&data).into_owned())
+            } else {
+                Content::Image { mime_type, data }
+            };
+            let _ = tx.unbounded_send(ClipboardUpdate::Selection { content, secret });
+        });
+    }
+}
+
+fn run(
+    tx: UnboundedSender<ClipboardUpdate>,
+    rx: channel::Channel<ClipboardRequest>,
+) -> anyhow::Result<()> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, event_queue) = registry_queue_init::<State>(&conn)?;
+    let qh = event_queue.handle();
+    let mut event_loop = calloop::EventLoop::<State>::try_new()?;
+    WaylandSource::new(event_queue)?
+        .insert(event_loop.handle())
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
+
+    let manager: ZwlrDataControlManagerV1 = globals.bind(&qh, 1..=2, ())?;
+    let seat: WlSeat = globals.bind(&qh, 1..=7, ())?;
+    let device = manager.get_data_device(&seat, &qh, ());
+    let mut state = State {
+        tx,
+        manager,
+        device,
+        queue_handle: qh,
+        exit: false,
+    };
+
+    event_loop
+        .handle()
+        .insert_source(rx, |event, _, state: &mut State| match event {
+            channel::Event::Msg(ClipboardRequest::Copy(content)) => {
+                let source = state
+                    .manager
+                    .create_data_source(&state.queue_handle, content.clone());
+                for mime_type in content.mime_types() {
+                    source.offer(mime_type);
+                }
+                source.offer(OWN_MIME_TYPE.to_string());
+                state.device.set_selection(Some(&source));
+            }
+            channel::Event::Closed => state.exit = true,
+        })
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
+
+    while !state.exit {
+        event_loop.dispatch(None, &mut state)?;
+    }
+    Ok(())
+}
+
+pub fn clipboard() -> iced::Subscription<ClipboardUpdate> {
+    struct Clipboard;
+
+    subscription::channel(
+        std::any::TypeId::of::<Clipboard>(),
+        50,
+        |mut output| async move {
+            let (tx, mut rx) = unbounded();
+            let (request_tx, request_rx) = channel::channel();
+            thread::spawn(move || {
+                if let Err(err) = run(tx.clone(), request_rx) {
+                    log::error!("Clipboard history is unavailable: {}", err);
+                    let _ = tx.unbounded_send(ClipboardUpdate::Failed);
+                }
+            });
+            _ = output.send(ClipboardUpdate::Init(request_tx)).await;
+            while let Some(update) = rx.next().await {
+                _ = output.send(update).await;
+            }
+            future::pending().await
+        },
+    )
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        conn: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            // the mime types follow as events of the offer
+            zwlr_data_control_device_v1::Event::DataOffer { .. } => {}
+            zwlr_data_control_device_v1::Event::Selection { id: Some(offer) } => {
+                state.receive(conn, &offer);
+                offer.destroy();
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => {
+                offer.destroy();
+            }
+            zwlr_data_control_device_v1::Event::Finished => {
+                state.exit = true;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE
+            => (ZwlrDataControlOfferV1, OfferData::default()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, OfferData> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        data: &OfferData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            data.0.lock().unwrap().push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, Content> for State {
+    fn event(
+        _: &mut Self,
+        source: &ZwlrDataControlSourceV1,
+        event: zwlr_data_control_source_v1::Event,
+        content: &Content,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_source_v1::Event::Send { fd, .. } => {
+                let content = content.clone();
+                thread::spawn(move || write_content(fd, &content));
+            }
+            zwlr_data_control_source_v1::Event::Cancelled => source.destroy(),
+            _ => {}
+        }
+    }
+}
+
+/// Writes the content to a pasting client, which may read slowly
+fn write_content(fd: RawFd, content: &Content) {
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    if let Err(err) = file.write_all(content.bytes()) {
+        log::warn!("Failed to paste clipboard entry: {}", err);
+    }
+}
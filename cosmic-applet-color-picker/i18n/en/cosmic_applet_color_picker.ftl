@@ -0,0 +1,6 @@
+pick = Pick a Color
+no-colors = Pick a color anywhere on the screen to see its values.
+copy = Copy
+history = History
+clear = Clear
+copy-after-picking = Copy after picking
@@ -0,0 +1,327 @@
+use crate::color::Rgb;
+use crate::config::{self, ColorPickerAppletConfig, Format};
+use crate::fl;
+use crate::portal;
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, container, horizontal_space, row, text, Column, Row},
+    window, Alignment, Color, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::divider;
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::process;
+use std::time::Duration;
+
+const ICON: &str = "color-select-symbolic";
+/// Time for the popup to be gone before the screen is picked from
+const POPUP_CLOSE_DELAY: Duration = Duration::from_millis(200);
+/// Colors in a row of the palette
+const COLUMNS: usize = 8;
+const FORMATS: [Format; 3] = [Format::Hex, Format::Rgb, Format::Hsl];
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<ColorPicker>(true, ())
+}
+
+#[derive(Default)]
+struct ColorPicker {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: ColorPickerAppletConfig,
+    /// The color whose values are shown, or the latest one if none was chosen
+    selected: Option<Rgb>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Pick,
+    Picked(Result<Option<Rgb>, String>),
+    Select(Rgb),
+    Copy(String),
+    SetCopyFormat(Format),
+    ClearHistory,
+    ConfigUpdated(ColorPickerAppletConfig),
+}
+
+fn format_name(format: Format) -> &'static str {
+    match format {
+        Format::Hex => "HEX",
+        Format::Rgb => "RGB",
+        Format::Hsl => "HSL",
+    }
+}
+
+fn copy(value: &str) -> Result<(), String> {
+    process::Command::new("wl-copy")
+        .arg(value)
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("wl-copy: {}", err))
+}
+
+/// A square of the color, outlined so that it stands out from a background of the same color
+fn swatch<'a>(color: Rgb, size: f32) -> Element<'a, Message> {
+    container(horizontal_space(Length::Fixed(size)))
+        .height(Length::Fixed(size))
+        .style(theme::Container::custom(move |theme| {
+            cosmic::iced_style::container::Appearance {
+                text_color: None,
+                background: Some(Color::from(color).into()),
+                border_radius: 4.0.into(),
+                border_width: 1.0,
+                border_color: theme.cosmic().background.on.into(),
+            }
+        }))
+        .into()
+}
+
+impl ColorPicker {
+    fn close_popup(&mut self) -> Command<Message> {
+        match self.popup.take() {
+            Some(p) => destroy_popup(p),
+            None => Command::none(),
+        }
+    }
+
+    fn selected(&self) -> Option<Rgb> {
+        self.selected
+            .or_else(|| self.config.history.first().copied())
+    }
+
+    fn values_view(&self, color: Rgb) -> Element<Message> {
+        let mut values = Column::new().spacing(4).width(Length::Fill);
+        for format in FORMATS {
+            let value = color.format(format);
+            values = values.push(
+                row![
+                    text(format_name(format))
+                        .size(12)
+                        .width(Length::Fixed(36.0)),
+                    text(value.clone()).size(14).width(Length::Fill),
+                    button(text(fl!("copy")).size(12))
+                        .padding([4, 8])
+                        .style(theme::Button::Secondary)
+                        .on_press(Message::Copy(value)),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        row![swatch(color, 64.0), values]
+            .spacing(12)
+            .align_items(Alignment::Center)
+            .into()
+    }
+
+    fn palette_view(&self) -> Element<Message> {
+        let selected = self.selected();
+        let mut palette = Column::new().spacing(4);
+        for chunk in self.config.history.chunks(COLUMNS) {
+            let mut palette_row = Row::new().spacing(4);
+            for color in chunk {
+                let style = if Some(*color) == selected {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                };
+                palette_row = palette_row.push(
+                    button(swatch(*color, 24.0))
+                        .padding(2)
+                        .style(style)
+                        .on_press(Message::Select(*color)),
+                );
+            }
+            palette = palette.push(palette_row);
+        }
+        palette.into()
+    }
+}
+
+impl cosmic::Application for ColorPicker {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            ColorPicker {
+                core,
+                config: ColorPickerAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.error = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Pick => {
+                let pick = cosmic::iced::Command::perform(
+                    async {
+                        tokio::time::sleep(POPUP_CLOSE_DELAY).await;
+                        portal::pick().await
+                    },
+                    |res| app::message::app(Message::Picked(res)),
+                );
+                return Command::batch(vec![self.close_popup(), pick]);
+            }
+            Message::Picked(Ok(color)) => {
+                if let Some(color) = color {
+                    if let Ok(config) = Config::new(config::APP_ID, 1) {
+                        self.config.add_color(color, &config);
+                    }
+                    self.selected = Some(color);
+                    self.error = copy(&color.format(self.config.copy_format)).err();
+                }
+            }
+            Message::Picked(Err(err)) => {
+                log::error!("Failed to pick a color: {}", err);
+                self.error = Some(err);
+            }
+            Message::Select(color) => {
+                self.selected = Some(color);
+            }
+            Message::Copy(value) => {
+                self.error = copy(&value).err();
+            }
+            Message::SetCopyFormat(copy_format) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_copy_format(copy_format, &config);
+                }
+            }
+            Message::ClearHistory => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.clear_history(&config);
+                }
+                self.selected = None;
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        self.core
+            .applet_helper
+            .icon_button(ICON)
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        content = content.push(
+            button(text(fl!("pick")).size(14))
+                .padding([8, 16])
+                .style(theme::Button::Primary)
+                .width(Length::Fill)
+                .on_press(Message::Pick),
+        );
+        match self.selected() {
+            Some(color) => content = content.push(self.values_view(color)),
+            None => content = content.push(text(fl!("no-colors")).size(14)),
+        }
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(12));
+        }
+
+        if !self.config.history.is_empty() {
+            content = content
+                .push(container(divider::horizontal::light()).width(Length::Fill))
+                .push(
+                    row![
+                        text(fl!("history")).size(12).width(Length::Fill),
+                        button(text(fl!("clear")).size(12))
+                            .padding([4, 8])
+                            .style(theme::Button::Secondary)
+                            .on_press(Message::ClearHistory),
+                    ]
+                    .align_items(Alignment::Center),
+                )
+                .push(self.palette_view());
+        }
+
+        let mut formats = Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(text(fl!("copy-after-picking")).size(14).width(Length::Fill));
+        for format in FORMATS {
+            let style = if self.config.copy_format == format {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            formats = formats.push(
+                button(text(format_name(format)).size(12))
+                    .padding([4, 8])
+                    .style(style)
+                    .on_press(Message::SetCopyFormat(format)),
+            );
+        }
+        content = content
+            .push(container(divider::horizontal::light()).width(Length::Fill))
+            .push(formats);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(|(_, config)| {
+            match config {
+                Ok(config) => Message::ConfigUpdated(config),
+                Err((errors, config)) => {
+                    for error in errors {
+                        log::error!("{:?}", error);
+                    }
+                    Message::ConfigUpdated(config)
+                }
+            }
+        })
+    }
+}
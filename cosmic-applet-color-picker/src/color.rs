@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Colors and the notations they're copied in
+
+use crate::config::Format;
+use cosmic::iced::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    /// From components between 0 and 1, as the portal gives them
+    pub fn from_f64(red: f64, green: f64, blue: f64) -> Rgb {
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Rgb {
+            red: channel(red),
+            green: channel(green),
+            blue: channel(blue),
+        }
+    }
+
+    /// Hue in degrees, and saturation and lightness in percent
+    fn hsl(self) -> (u16, u8, u8) {
+        let [r, g, b] = [self.red, self.green, self.blue].map(|c| f64::from(c) / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let lightness = (max + min) / 2.0;
+        if delta == 0.0 {
+            return (0, 0, (lightness * 100.0).round() as u8);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        (
+            (hue * 60.0).round() as u16 % 360,
+            (saturation * 100.0).round() as u8,
+            (lightness * 100.0).round() as u8,
+        )
+    }
+
+    pub fn format(self, format: Format) -> String {
+        match format {
+            Format::Hex => format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue),
+            Format::Rgb => format!("rgb({}, {}, {})", self.red, self.green, self.blue),
+            Format::Hsl => {
+                let (hue, saturation, lightness) = self.hsl();
+                format!("hsl({hue}, {saturation}%, {lightness}%)")
+            }
+        }
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Color {
+        Color::from_rgb8(rgb.red, rgb.green, rgb.blue)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::color::Rgb;
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletColorPicker";
+
+/// Colors kept in the palette
+const MAX_HISTORY: usize = 24;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Format {
+    /// Such as `#3584e4`
+    #[default]
+    Hex,
+    /// Such as `rgb(53, 132, 228)`
+    Rgb,
+    /// Such as `hsl(213, 76%, 55%)`
+    Hsl,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct ColorPickerAppletConfig {
+    /// Picked colors, the latest first
+    pub history: Vec<Rgb>,
+    /// The format a picked color is copied in
+    pub copy_format: Format,
+}
+
+impl ColorPickerAppletConfig {
+    pub fn load() -> ColorPickerAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| ColorPickerAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn add_color(&mut self, color: Rgb, config: &Config) {
+        self.history.retain(|c| *c != color);
+        self.history.insert(0, color);
+        self.history.truncate(MAX_HISTORY);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn clear_history(&mut self, config: &Config) {
+        self.history.clear();
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_copy_format(&mut self, copy_format: Format, config: &Config) {
+        self.copy_format = copy_format;
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,11 @@
+mod app;
+mod color;
+mod config;
+mod localize;
+mod portal;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Colors picked from the screen through the screenshot portal
+
+use crate::color::Rgb;
+use ashpd::desktop::screenshot::Color;
+use ashpd::desktop::ResponseError;
+
+/// Lets the user pick a pixel anywhere on the screen. Returns `None` if the user cancels.
+pub async fn pick() -> Result<Option<Rgb>, String> {
+    match Color::pick().send().await.and_then(|r| r.response()) {
+        Ok(color) => Ok(Some(Rgb::from_f64(
+            color.red(),
+            color.green(),
+            color.blue(),
+        ))),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
@@ -0,0 +1,45 @@
+//! Serving an applet's own interface on the session bus under a well-known name
+
+use cosmic::iced::{
+    futures::{channel::mpsc, future},
+    subscription, Subscription,
+};
+use std::hash::Hash;
+use zbus::{Connection, ConnectionBuilder, Interface};
+
+/// Takes `name` and serves `iface` at `path`. Fails if the name is already owned, e.g. by
+/// another instance of the applet.
+pub async fn serve<I: Interface>(name: &str, path: &str, iface: I) -> zbus::Result<Connection> {
+    ConnectionBuilder::session()?
+        .name(name)?
+        .serve_at(path, iface)?
+        .build()
+        .await
+}
+
+/// Serves the interface built by `iface` for as long as the subscription runs, emitting what
+/// the interface sends to the channel it's given. If the name can't be taken the failure is
+/// logged and nothing is ever emitted.
+pub fn requests<T, I>(
+    id: impl Hash + 'static,
+    name: &'static str,
+    path: &'static str,
+    iface: impl FnOnce(mpsc::Sender<T>) -> I + Send + 'static,
+) -> Subscription<T>
+where
+    T: Send + 'static,
+    I: Interface,
+{
+    subscription::channel(id, 10, move |output| async move {
+        let _conn = match serve(name, path, iface(output)).await {
+            Ok(conn) => Some(conn),
+            Err(err) => {
+                log::warn!("Failed to serve {} on D-Bus: {}", name, err);
+                None
+            }
+        };
+        loop {
+            future::pending::<()>().await;
+        }
+    })
+}
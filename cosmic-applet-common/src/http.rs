@@ -0,0 +1,56 @@
+//! HTTP/1.1 requests to local services, such as the APIs of daemons on their domain sockets
+
+use hyper::client::conn;
+use std::{io, path::Path, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::UnixStream,
+};
+
+pub use hyper::{header, Body, Method, Request, StatusCode};
+
+pub struct Response {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+fn other(err: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+/// Sends the request on a new connection and reads the whole response, failing if that
+/// takes longer than `timeout`
+pub async fn send<S>(stream: S, request: Request<Body>, timeout: Duration) -> io::Result<Response>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let exchange = async {
+        let (mut sender, connection) = conn::handshake(stream).await.map_err(other)?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                log::debug!("HTTP connection failed: {}", err);
+            }
+        });
+        let response = sender.send_request(request).await.map_err(other)?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(other)?;
+        Ok(Response {
+            status,
+            body: body.to_vec(),
+        })
+    };
+    tokio::time::timeout(timeout, exchange)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "HTTP request timed out"))?
+}
+
+/// Sends the request to the service listening on the domain socket
+pub async fn send_unix(
+    socket: &Path,
+    request: Request<Body>,
+    timeout: Duration,
+) -> io::Result<Response> {
+    send(UnixStream::connect(socket).await?, request, timeout).await
+}
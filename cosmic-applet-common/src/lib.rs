@@ -0,0 +1,10 @@
+//! Code shared between the applets, the parts that need more dependencies are behind the
+//! feature of the same name
+
+#[cfg(feature = "dbus")]
+pub mod dbus;
+pub mod format;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "screencopy")]
+pub mod screencopy;
@@ -0,0 +1,160 @@
+//! Thumbnails of toplevels, captured through the cosmic screencopy protocol
+//!
+//! The applet's wayland state keeps a [`Captures`] next to its `ScreencopyState` and `Shm`,
+//! and forwards its `ScreencopyHandler` events to it.
+
+use cctk::{
+    screencopy::{BufferInfo, ScreencopySessionData, ScreencopyState},
+    sctk::shm::{raw::RawPool, Shm},
+    wayland_client::{
+        protocol::{wl_buffer::WlBuffer, wl_shm},
+        Dispatch, QueueHandle, WEnum,
+    },
+};
+use cosmic::iced::widget::image;
+use cosmic_protocols::{
+    screencopy::v1::client::{
+        zcosmic_screencopy_manager_v1::CursorMode,
+        zcosmic_screencopy_session_v1::{self, ZcosmicScreencopySessionV1},
+    },
+    toplevel_info::v1::client::zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1,
+};
+
+/// Thumbnails are scaled down to this width
+const THUMBNAIL_WIDTH: u32 = 240;
+
+/// A screencopy of a toplevel in flight
+struct Capture {
+    toplevel: ZcosmicToplevelHandleV1,
+    session: ZcosmicScreencopySessionV1,
+    buffer: Option<(RawPool, WlBuffer, BufferInfo)>,
+}
+
+impl Capture {
+    fn destroy(self) {
+        self.session.destroy();
+        if let Some((_, buffer, _)) = self.buffer {
+            buffer.destroy();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Captures {
+    captures: Vec<Capture>,
+}
+
+impl Captures {
+    /// Starts capturing the toplevel, unless a capture of it is already in flight
+    pub fn capture<D>(
+        &mut self,
+        screencopy_state: &ScreencopyState,
+        qh: &QueueHandle<D>,
+        toplevel: ZcosmicToplevelHandleV1,
+    ) where
+        D: Dispatch<ZcosmicScreencopySessionV1, ScreencopySessionData> + 'static,
+    {
+        if self.captures.iter().any(|c| c.toplevel == toplevel) {
+            return;
+        }
+        let session = screencopy_state.screencopy_manager.capture_toplevel(
+            &toplevel,
+            CursorMode::Hidden,
+            qh,
+            ScreencopySessionData::default(),
+        );
+        self.captures.push(Capture {
+            toplevel,
+            session,
+            buffer: None,
+        });
+    }
+
+    fn take(&mut self, session: &ZcosmicScreencopySessionV1) -> Option<Capture> {
+        let i = self.captures.iter().position(|c| &c.session == session)?;
+        Some(self.captures.remove(i))
+    }
+
+    /// Attaches a shm buffer once the compositor announced the formats it offers,
+    /// or gives up on the capture if none of them can be read
+    pub fn init_done<D>(
+        &mut self,
+        shm: &Shm,
+        qh: &QueueHandle<D>,
+        session: &ZcosmicScreencopySessionV1,
+        buffer_infos: &[BufferInfo],
+    ) where
+        D: Dispatch<WlBuffer, ()> + 'static,
+    {
+        let Some(capture) = self.captures.iter_mut().find(|c| &c.session == session) else {
+            return;
+        };
+        let info = buffer_infos.iter().find(|info| {
+            info.type_ == WEnum::Value(zcosmic_screencopy_session_v1::BufferType::WlShm)
+                && (info.format == wl_shm::Format::Argb8888 as u32
+                    || info.format == wl_shm::Format::Xrgb8888 as u32)
+        });
+        let buffer = info.and_then(|info| {
+            let format = wl_shm::Format::try_from(info.format).ok()?;
+            let mut pool = RawPool::new((info.stride * info.height) as usize, shm).ok()?;
+            let buffer = pool.create_buffer(
+                0,
+                info.width as i32,
+                info.height as i32,
+                info.stride as i32,
+                format,
+                (),
+                qh,
+            );
+            Some((pool, buffer, info.clone()))
+        });
+        match buffer {
+            Some((pool, buffer, info)) => {
+                session.attach_buffer(&buffer, None, 0);
+                session.commit(zcosmic_screencopy_session_v1::Options::empty());
+                capture.buffer = Some((pool, buffer, info));
+            }
+            None => {
+                if let Some(capture) = self.take(session) {
+                    capture.destroy();
+                }
+            }
+        }
+    }
+
+    /// The thumbnail of a finished capture, with the toplevel it shows
+    pub fn ready(
+        &mut self,
+        session: &ZcosmicScreencopySessionV1,
+    ) -> Option<(ZcosmicToplevelHandleV1, image::Handle)> {
+        let mut capture = self.take(session)?;
+        let image = capture.buffer.as_mut().map(|(pool, _, info)| {
+            let opaque = info.format == wl_shm::Format::Xrgb8888 as u32;
+            thumbnail(pool.mmap(), info, opaque)
+        });
+        let toplevel = capture.toplevel.clone();
+        capture.destroy();
+        Some((toplevel, image?))
+    }
+
+    pub fn failed(&mut self, session: &ZcosmicScreencopySessionV1) {
+        if let Some(capture) = self.take(session) {
+            capture.destroy();
+        }
+    }
+}
+
+/// Scales the shm buffer down and converts it from little endian (A|X)RGB to RGBA
+fn thumbnail(data: &[u8], info: &BufferInfo, opaque: bool) -> image::Handle {
+    let step = (info.width / THUMBNAIL_WIDTH).max(1);
+    let (width, height) = (info.width / step, info.height / step);
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * step * info.stride + x * step * 4) as usize;
+            let alpha = if opaque { 255 } else { data[i + 3] };
+            pixels.extend_from_slice(&[data[i + 2], data[i + 1], data[i], alpha]);
+        }
+    }
+    image::Handle::from_pixels(width, height, pixels)
+}
@@ -0,0 +1,6 @@
+start = Start
+stop = Stop
+stats = CPU { $cpu } · Memory { $memory }
+no-containers = No running containers
+no-engines = Neither Podman nor Docker is running. For Podman, enable its socket with systemctl --user enable --now podman.socket.
+show-stopped = Show Stopped Containers
@@ -0,0 +1,268 @@
+use crate::config::{self, ContainersAppletConfig};
+use crate::engine::{self, overview_subscription, Action, Container, Overview};
+use crate::fl;
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, text, Column},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon, toggler};
+use cosmic::{Element, Theme};
+use cosmic_applet_common::format::format_bytes;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+const ICON: &str = "package-x-generic-symbolic";
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Containers>(true, ())
+}
+
+#[derive(Default)]
+struct Containers {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: ContainersAppletConfig,
+    overview: Overview,
+    /// Ids of containers being started or stopped
+    busy: HashSet<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Overview(Overview),
+    Act(Container, Action),
+    Done(String, Result<(), String>),
+    SetShowStopped(bool),
+    ConfigUpdated(ContainersAppletConfig),
+}
+
+impl Containers {
+    fn running(&self) -> usize {
+        self.overview
+            .containers
+            .iter()
+            .filter(|c| c.running())
+            .count()
+    }
+
+    fn container_view<'a>(&self, container: &'a Container) -> Element<'a, Message> {
+        let mut details = column![
+            text(&container.name).size(14),
+            text(format!("{} · {}", container.image, container.status)).size(12),
+        ]
+        .spacing(2)
+        .width(Length::Fill);
+        if let Some(stats) = container.stats {
+            details = details.push(
+                text(fl!(
+                    "stats",
+                    cpu = format!("{:.0}%", stats.cpu * 100.0),
+                    memory = format_bytes(stats.memory)
+                ))
+                .size(12),
+            );
+        }
+
+        let (label, action) = if container.running() {
+            (fl!("stop"), Action::Stop)
+        } else {
+            (fl!("start"), Action::Start)
+        };
+        let mut action_button = button(text(label).size(12))
+            .padding([4, 8])
+            .style(theme::Button::Secondary);
+        if !self.busy.contains(&container.id) {
+            action_button = action_button.on_press(Message::Act(container.clone(), action));
+        }
+
+        let status_icon = if container.running() {
+            "media-playback-start-symbolic"
+        } else {
+            "media-playback-stop-symbolic"
+        };
+        row![
+            icon(status_icon, 16).style(Svg::Symbolic),
+            details,
+            action_button
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .into()
+    }
+}
+
+impl cosmic::Application for Containers {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Containers {
+                core,
+                config: ContainersAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.error = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(450.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Overview(overview) => {
+                self.overview = overview;
+            }
+            Message::Act(container, action) => {
+                self.busy.insert(container.id.clone());
+                self.error = None;
+                let id = container.id.clone();
+                return cosmic::iced::Command::perform(
+                    engine::act(container.socket, container.id, action),
+                    move |res| app::message::app(Message::Done(id, res)),
+                );
+            }
+            Message::Done(id, res) => {
+                self.busy.remove(&id);
+                if let Err(err) = res {
+                    self.error = Some(err);
+                }
+            }
+            Message::SetShowStopped(show_stopped) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_stopped(show_stopped, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let running = self.running();
+        if running == 0 {
+            return self
+                .core
+                .applet_helper
+                .icon_button(ICON)
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+        button(
+            row![
+                icon(ICON, 16).style(Svg::Symbolic),
+                text(running.to_string()).size(14)
+            ]
+            .spacing(4)
+            .align_items(Alignment::Center),
+        )
+        .on_press(Message::TogglePopup)
+        .style(theme::Button::Text)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        for engine in &self.overview.engines {
+            content = content.push(text(engine.kind.name()).size(12));
+            if let Some(error) = &engine.error {
+                content = content.push(text(error).size(12));
+                continue;
+            }
+            let listed: Vec<&Container> = self
+                .overview
+                .containers
+                .iter()
+                .filter(|c| c.socket == engine.socket)
+                .filter(|c| c.running() || self.config.show_stopped)
+                .collect();
+            for item in &listed {
+                content = content.push(self.container_view(item));
+            }
+            if listed.is_empty() {
+                content = content.push(text(fl!("no-containers")).size(14));
+            }
+        }
+        if self.overview.engines.is_empty() {
+            content = content.push(text(fl!("no-engines")).size(14));
+        }
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(12));
+        }
+        content = content
+            .push(container(divider::horizontal::light()).width(Length::Fill))
+            .push(
+                toggler(
+                    fl!("show-stopped"),
+                    self.config.show_stopped,
+                    Message::SetShowStopped,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            );
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            overview_subscription().map(Message::Overview),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
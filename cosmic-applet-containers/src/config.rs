@@ -0,0 +1,24 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletContainers";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct ContainersAppletConfig {
+    /// Whether containers that aren't running are listed, to be started
+    pub show_stopped: bool,
+}
+
+impl ContainersAppletConfig {
+    pub fn load() -> ContainersAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| ContainersAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_show_stopped(&mut self, show_stopped: bool, config: &Config) {
+        self.show_stopped = show_stopped;
+        let _ = self.write_entry(config);
+    }
+}
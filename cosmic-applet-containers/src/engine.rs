@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Containers of the Podman and Docker engines on the system, through the Docker API that both
+//! serve on their sockets
+
+use cosmic::iced::{
+    futures::{future::join_all, SinkExt},
+    subscription, Subscription,
+};
+use cosmic_applet_common::http::{self, header, Body, Method, Request, StatusCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Long enough for a container to be stopped, which is killed after ten seconds
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Podman,
+    Docker,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Podman => "Podman",
+            Kind::Docker => "Docker",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Engine {
+    pub kind: Kind,
+    pub socket: PathBuf,
+    /// Why its containers couldn't be listed, such as missing permission to use the socket
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Share of one core, like `top` shows it
+    pub cpu: f32,
+    /// In bytes, without the page cache
+    pub memory: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    /// Socket of the container's engine
+    pub socket: PathBuf,
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// Such as `running`, `exited` or `paused`
+    pub state: String,
+    /// Readable state, such as `Up 5 minutes`
+    pub status: String,
+    /// Only read for running containers
+    pub stats: Option<Stats>,
+}
+
+impl Container {
+    pub fn running(&self) -> bool {
+        self.state == "running"
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overview {
+    pub engines: Vec<Engine>,
+    pub containers: Vec<Container>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Summary {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Deserialize, Default)]
+struct CpuUsage {
+    #[serde(default)]
+    total_usage: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct CpuStats {
+    #[serde(default)]
+    cpu_usage: CpuUsage,
+    #[serde(default)]
+    system_cpu_usage: u64,
+    #[serde(default)]
+    online_cpus: u32,
+}
+
+#[derive(Deserialize, Default)]
+struct MemoryStats {
+    #[serde(default)]
+    usage: u64,
+    #[serde(default)]
+    stats: HashMap<String, u64>,
+}
+
+#[derive(Deserialize)]
+struct StatsResponse {
+    #[serde(default)]
+    cpu_stats: CpuStats,
+    #[serde(default)]
+    precpu_stats: CpuStats,
+    #[serde(default)]
+    memory_stats: MemoryStats,
+}
+
+/// Sockets of the engines that are running, without duplicates such as the `docker.sock` of
+/// podman-docker
+fn sockets() -> Vec<(Kind, PathBuf)> {
+    let mut candidates = Vec::new();
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        candidates.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+    }
+    candidates.push(PathBuf::from("/run/podman/podman.sock"));
+    let docker_host = std::env::var("DOCKER_HOST")
+        .ok()
+        .and_then(|host| Some(PathBuf::from(host.strip_prefix("unix://")?)));
+    candidates.push(docker_host.unwrap_or_else(|| PathBuf::from("/var/run/docker.sock")));
+
+    let mut sockets: Vec<(Kind, PathBuf)> = Vec::new();
+    for socket in candidates {
+        let Ok(canonical) = fs::canonicalize(&socket) else {
+            continue;
+        };
+        if sockets.iter().any(|(_, s)| *s == canonical) {
+            continue;
+        }
+        let kind = if canonical.to_string_lossy().contains("podman") {
+            Kind::Podman
+        } else {
+            Kind::Docker
+        };
+        sockets.push((kind, canonical));
+    }
+    sockets
+}
+
+/// Sends a request without a body and returns the body of the response
+async fn request(socket: &Path, method: Method, path: &str) -> io::Result<Vec<u8>> {
+    let request = Request::builder()
+        .method(method)
+        .uri(path)
+        .header(header::HOST, "localhost")
+        .body(Body::empty())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let response = http::send_unix(socket, request, TIMEOUT).await?;
+    // "not modified" is returned when starting a running container, or stopping a stopped one
+    if response.status.is_success() || response.status == StatusCode::NOT_MODIFIED {
+        Ok(response.body)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            error_message(response.status, &response.body),
+        ))
+    }
+}
+
+/// The message of an error response, which the engines give as JSON
+fn error_message(status: StatusCode, body: &[u8]) -> String {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| Some(value.get("message")?.as_str()?.to_string()))
+        .unwrap_or_else(|| format!("HTTP status {}", status))
+}
+
+fn parse<'a, T: Deserialize<'a>>(body: &'a [u8]) -> io::Result<T> {
+    serde_json::from_slice(body).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+async fn list(socket: &Path) -> io::Result<Vec<Container>> {
+    let body = request(socket, Method::GET, "/containers/json?all=true").await?;
+    let summaries: Vec<Summary> = parse(&body)?;
+    Ok(summaries
+        .into_iter()
+        .map(|summary| Container {
+            socket: socket.to_path_buf(),
+            name: summary
+                .names
+                .first()
+                .map(|name| name.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| summary.id.chars().take(12).collect()),
+            id: summary.id,
+            image: summary.image,
+            state: summary.state,
+            status: summary.status,
+            stats: None,
+        })
+        .collect())
+}
+
+/// Takes about a second, which the engine samples the CPU usage over
+async fn stats(socket: &Path, id: &str) -> io::Result<Stats> {
+    let body = request(
+        socket,
+        Method::GET,
+        &format!("/containers/{id}/stats?stream=false"),
+    )
+    .await?;
+    let stats: StatsResponse = parse(&body)?;
+    let cpu = stats
+        .cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+    let system = stats
+        .cpu_stats
+        .system_cpu_usage
+        .saturating_sub(stats.precpu_stats.system_cpu_usage);
+    // cgroup v2 and v1 respectively
+    let inactive = ["inactive_file", "total_inactive_file"]
+        .iter()
+        .find_map(|key| stats.memory_stats.stats.get(*key))
+        .copied()
+        .unwrap_or_default();
+    Ok(Stats {
+        cpu: if system == 0 {
+            0.0
+        } else {
+            cpu as f32 / system as f32 * stats.cpu_stats.online_cpus.max(1) as f32
+        },
+        memory: stats.memory_stats.usage.saturating_sub(inactive),
+    })
+}
+
+async fn overview() -> Overview {
+    let mut overview = Overview::default();
+    for (kind, socket) in sockets() {
+        let error = match list(&socket).await {
+            Ok(containers) => {
+                overview.containers.extend(containers);
+                None
+            }
+            Err(err) => Some(err.to_string()),
+        };
+        overview.engines.push(Engine {
+            kind,
+            socket,
+            error,
+        });
+    }
+    let stats = join_all(overview.containers.iter().map(|container| async move {
+        if container.running() {
+            stats(&container.socket, &container.id).await.ok()
+        } else {
+            None
+        }
+    }))
+    .await;
+    for (container, stats) in overview.containers.iter_mut().zip(stats) {
+        container.stats = stats;
+    }
+    overview
+}
+
+pub async fn act(socket: PathBuf, id: String, action: Action) -> Result<(), String> {
+    let verb = match action {
+        Action::Start => "start",
+        Action::Stop => "stop",
+    };
+    request(&socket, Method::POST, &format!("/containers/{id}/{verb}"))
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+pub fn overview_subscription() -> Subscription<Overview> {
+    struct OverviewWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<OverviewWorker>(),
+        1,
+        |mut output| async move {
+            loop {
+                _ = output.send(overview().await).await;
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,13 @@
+drives = Removable Drives
+no-drives = No removable drives
+volume = { $size } Volume
+free-of = { $free } free of { $total }
+mount = Mount
+unmount = Unmount
+open = Open
+eject = Eject
+power-off = Power Off
+safely-remove = Safely Remove
+safe-to-remove = Safe to remove
+safe-to-remove-body = { $drive } can now be unplugged.
+notify-safe = Notify when safe to remove
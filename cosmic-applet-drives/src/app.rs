@@ -0,0 +1,358 @@
+use crate::config::{self, DrivesAppletConfig};
+use crate::fl;
+use crate::notification;
+use crate::udisks::{self, drives_subscription, RemovableDrive, Removal, Volume};
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, progress_bar, row, text, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::{divider, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process;
+use zbus::zvariant::OwnedObjectPath;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Drives>(true, ())
+}
+
+#[derive(Default)]
+struct Drives {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: DrivesAppletConfig,
+    drives: Vec<RemovableDrive>,
+    /// Volumes and drives with an operation in progress
+    busy: HashSet<OwnedObjectPath>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Drives(Vec<RemovableDrive>),
+    Mount(OwnedObjectPath),
+    Unmount(OwnedObjectPath),
+    Open(PathBuf),
+    Remove(OwnedObjectPath, Removal),
+    Done(OwnedObjectPath, Result<(), String>),
+    Removed(OwnedObjectPath, String, Result<(), String>),
+    SetNotifySafe(bool),
+    ConfigUpdated(DrivesAppletConfig),
+    Ignore,
+}
+
+/// Sizes in decimal units, as printed on drives
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn volume_name(volume: &Volume) -> String {
+    if volume.label.is_empty() {
+        fl!("volume", size = format_size(volume.size))
+    } else {
+        volume.label.clone()
+    }
+}
+
+fn action_button<'a>(label: String, message: Option<Message>) -> Element<'a, Message> {
+    let action = button(text(label).size(14))
+        .padding([4, 8])
+        .style(theme::Button::Secondary);
+    match message {
+        Some(message) => action.on_press(message),
+        None => action,
+    }
+    .into()
+}
+
+impl Drives {
+    fn perform<T: Send + 'static>(
+        &mut self,
+        path: OwnedObjectPath,
+        future: impl std::future::Future<Output = zbus::Result<T>> + Send + 'static,
+    ) -> Command<Message> {
+        self.busy.insert(path.clone());
+        self.error = None;
+        cosmic::iced::Command::perform(future, move |res| {
+            app::message::app(Message::Done(
+                path,
+                res.map(|_| ()).map_err(|err| err.to_string()),
+            ))
+        })
+    }
+
+    fn volume_view(&self, volume: &Volume) -> Element<Message> {
+        let busy = self.busy.contains(&volume.path);
+        let mut content = Column::new().spacing(4).push(row![
+            text(volume_name(volume)).size(14).width(Length::Fill),
+            text(&volume.device).size(12)
+        ]);
+        if let Some(usage) = volume.usage {
+            content = content
+                .push(progress_bar(0.0..=1.0, usage.used()).height(Length::Fixed(6.0)))
+                .push(
+                    text(fl!(
+                        "free-of",
+                        free = format_size(usage.free),
+                        total = format_size(usage.total)
+                    ))
+                    .size(12),
+                );
+        }
+        let mut actions = Row::new().spacing(8);
+        match &volume.mount_point {
+            Some(mount_point) => {
+                actions = actions
+                    .push(action_button(
+                        fl!("open"),
+                        Some(Message::Open(mount_point.clone())),
+                    ))
+                    .push(action_button(
+                        fl!("unmount"),
+                        (!busy).then(|| Message::Unmount(volume.path.clone())),
+                    ));
+            }
+            None => {
+                actions = actions.push(action_button(
+                    fl!("mount"),
+                    (!busy).then(|| Message::Mount(volume.path.clone())),
+                ));
+            }
+        }
+        content.push(actions).into()
+    }
+
+    fn drive_view(&self, drive: &RemovableDrive) -> Element<Message> {
+        let busy = self.busy.contains(&drive.path);
+        let removal = |removal| (!busy).then(|| Message::Remove(drive.path.clone(), removal));
+        let mut heading = Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(text(&drive.name).size(14).width(Length::Fill));
+        if drive.ejectable {
+            heading = heading.push(action_button(fl!("eject"), removal(Removal::Eject)));
+        }
+        if drive.can_power_off {
+            heading = heading.push(action_button(fl!("power-off"), removal(Removal::PowerOff)));
+        }
+        if !drive.ejectable && !drive.can_power_off && drive.is_mounted() {
+            heading = heading.push(action_button(
+                fl!("safely-remove"),
+                removal(Removal::Unmount),
+            ));
+        }
+
+        let mut content = Column::new().spacing(12).push(heading);
+        for volume in &drive.volumes {
+            content = content.push(self.volume_view(volume));
+        }
+        content.into()
+    }
+}
+
+impl cosmic::Application for Drives {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Drives {
+                core,
+                config: DrivesAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                self.error = None;
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                // free space isn't signaled, so read it again
+                let refresh = cosmic::iced::Command::perform(udisks::drives(), |res| {
+                    app::message::app(match res {
+                        Ok(drives) => Message::Drives(drives),
+                        Err(err) => {
+                            log::error!("Failed to list removable drives: {}", err);
+                            Message::Ignore
+                        }
+                    })
+                });
+                return Command::batch(vec![get_popup(popup_settings), refresh]);
+            }
+            Message::Drives(drives) => {
+                self.drives = drives;
+            }
+            Message::Mount(path) => {
+                return self.perform(path.clone(), udisks::mount(path));
+            }
+            Message::Unmount(path) => {
+                return self.perform(path.clone(), udisks::unmount(path));
+            }
+            Message::Open(mount_point) => {
+                let _ = process::Command::new("xdg-open").arg(mount_point).spawn();
+            }
+            Message::Remove(path, removal) => {
+                let Some(drive) = self.drives.iter().find(|d| d.path == path).cloned() else {
+                    return Command::none();
+                };
+                self.busy.insert(path.clone());
+                self.error = None;
+                let name = drive.name.clone();
+                return cosmic::iced::Command::perform(
+                    udisks::remove(drive, removal),
+                    move |res| {
+                        app::message::app(Message::Removed(
+                            path,
+                            name,
+                            res.map_err(|err| err.to_string()),
+                        ))
+                    },
+                );
+            }
+            Message::Done(path, res) => {
+                self.busy.remove(&path);
+                self.error = res.err();
+            }
+            Message::Removed(path, name, res) => {
+                self.busy.remove(&path);
+                match res {
+                    Ok(()) if self.config.notify_safe => {
+                        return cosmic::iced::Command::perform(
+                            notification::notify(
+                                fl!("safe-to-remove"),
+                                fl!("safe-to-remove-body", drive = name),
+                            ),
+                            |_| app::message::app(Message::Ignore),
+                        );
+                    }
+                    Ok(()) => {}
+                    Err(err) => self.error = Some(err),
+                }
+            }
+            Message::SetNotifySafe(notify_safe) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_notify_safe(notify_safe, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+            Message::Ignore => {}
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        self.core
+            .applet_helper
+            .icon_button("drive-removable-media-symbolic")
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut drives = Column::new().spacing(16);
+        for drive in &self.drives {
+            drives = drives.push(self.drive_view(drive));
+        }
+        if self.drives.is_empty() {
+            drives = drives.push(text(fl!("no-drives")).size(14));
+        }
+        if let Some(error) = &self.error {
+            drives = drives.push(text(error).size(12));
+        }
+
+        let content = column![
+            container(drives).padding([0, 24]),
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            container(
+                toggler(
+                    fl!("notify-safe"),
+                    self.config.notify_safe,
+                    Message::SetNotifySafe
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 24]),
+        ]
+        .spacing(12)
+        .padding([8, 0]);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            drives_subscription().map(Message::Drives),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
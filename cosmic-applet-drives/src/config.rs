@@ -0,0 +1,30 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletDrives";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct DrivesAppletConfig {
+    /// Send a notification once a drive can be unplugged
+    pub notify_safe: bool,
+}
+
+impl Default for DrivesAppletConfig {
+    fn default() -> Self {
+        Self { notify_safe: true }
+    }
+}
+
+impl DrivesAppletConfig {
+    pub fn load() -> DrivesAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| DrivesAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_notify_safe(&mut self, notify_safe: bool, config: &Config) {
+        self.notify_safe = notify_safe;
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,11 @@
+mod app;
+mod config;
+mod localize;
+mod notification;
+mod udisks;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Desktop notifications sent by the applet
+
+use std::collections::HashMap;
+use zbus::{dbus_proxy, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    /// Notify method
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, zbus::zvariant::Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+pub async fn notify(summary: String, body: String) -> zbus::Result<u32> {
+    let conn = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&conn).await?;
+    proxy
+        .notify(
+            &crate::fl!("drives"),
+            0,
+            "drive-removable-media-symbolic",
+            &summary,
+            &body,
+            &[],
+            HashMap::new(),
+            -1,
+        )
+        .await
+}
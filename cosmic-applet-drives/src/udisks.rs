@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Removable drives and their filesystems, as reported by UDisks2
+
+use cosmic::iced::{
+    futures::{self, FutureExt, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use zbus::{
+    dbus_proxy,
+    fdo::ObjectManagerProxy,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+    Connection, MatchRule, MessageStream, MessageType,
+};
+
+const SERVICE: &str = "org.freedesktop.UDisks2";
+const PATH: &str = "/org/freedesktop/UDisks2";
+const BLOCK: &str = "org.freedesktop.UDisks2.Block";
+const DRIVE: &str = "org.freedesktop.UDisks2.Drive";
+const FILESYSTEM: &str = "org.freedesktop.UDisks2.Filesystem";
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.UDisks2",
+    interface = "org.freedesktop.UDisks2.Filesystem"
+)]
+trait Filesystem {
+    /// Mount method
+    fn mount(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<String>;
+
+    /// Unmount method
+    fn unmount(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.UDisks2",
+    interface = "org.freedesktop.UDisks2.Drive"
+)]
+trait Drive {
+    /// Eject method
+    fn eject(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    /// PowerOff method
+    fn power_off(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub free: u64,
+    pub total: u64,
+}
+
+impl Usage {
+    /// The used fraction of the filesystem
+    pub fn used(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        1.0 - self.free as f32 / self.total as f32
+    }
+}
+
+/// A filesystem on a removable drive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    pub path: OwnedObjectPath,
+    pub label: String,
+    /// Device file, such as `/dev/sdb1`
+    pub device: String,
+    /// Size of the block device in bytes
+    pub size: u64,
+    pub mount_point: Option<PathBuf>,
+    /// Free space, while mounted
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovableDrive {
+    pub path: OwnedObjectPath,
+    pub name: String,
+    pub ejectable: bool,
+    pub can_power_off: bool,
+    pub volumes: Vec<Volume>,
+}
+
+impl RemovableDrive {
+    pub fn is_mounted(&self) -> bool {
+        self.volumes.iter().any(|v| v.mount_point.is_some())
+    }
+}
+
+/// How a drive is prepared for unplugging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    /// Only unmount its filesystems
+    Unmount,
+    Eject,
+    PowerOff,
+}
+
+type Properties = HashMap<String, OwnedValue>;
+
+fn interface<'a>(
+    interfaces: &'a HashMap<zbus::names::OwnedInterfaceName, Properties>,
+    name: &str,
+) -> Option<&'a Properties> {
+    interfaces
+        .iter()
+        .find(|(interface, _)| interface.as_str() == name)
+        .map(|(_, properties)| properties)
+}
+
+fn get<T: TryFrom<Value<'static>>>(properties: &Properties, key: &str) -> Option<T> {
+    T::try_from(Value::from(properties.get(key)?.clone())).ok()
+}
+
+/// Paths are passed as NUL terminated byte strings
+fn bytes_path(mut bytes: Vec<u8>) -> PathBuf {
+    if bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+fn usage(mount_point: &Path) -> Option<Usage> {
+    let stat = nix::sys::statvfs::statvfs(mount_point).ok()?;
+    let fragment_size = stat.fragment_size() as u64;
+    Some(Usage {
+        free: stat.blocks_available() as u64 * fragment_size,
+        total: stat.blocks() as u64 * fragment_size,
+    })
+}
+
+async fn list(conn: &Connection) -> zbus::Result<Vec<RemovableDrive>> {
+    let manager = ObjectManagerProxy::builder(conn)
+        .destination(SERVICE)?
+        .path(PATH)?
+        .build()
+        .await?;
+    let objects = manager.get_managed_objects().await?;
+
+    let mut drives = Vec::new();
+    for (path, interfaces) in &objects {
+        let Some(drive) = interface(interfaces, DRIVE) else {
+            continue;
+        };
+        // USB disks often report fixed media, but are just as easily unplugged
+        let removable = get(drive, "Removable").unwrap_or(false)
+            || get(drive, "MediaRemovable").unwrap_or(false)
+            || get::<String>(drive, "ConnectionBus").as_deref() == Some("usb");
+        if !removable {
+            continue;
+        }
+        let name = [
+            get::<String>(drive, "Vendor").unwrap_or_default(),
+            get::<String>(drive, "Model").unwrap_or_default(),
+        ]
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+        drives.push(RemovableDrive {
+            path: path.clone(),
+            name,
+            ejectable: get(drive, "Ejectable").unwrap_or(false),
+            can_power_off: get(drive, "CanPowerOff").unwrap_or(false),
+            volumes: Vec::new(),
+        });
+    }
+
+    for (path, interfaces) in &objects {
+        let (Some(block), Some(filesystem)) =
+            (interface(interfaces, BLOCK), interface(interfaces, FILESYSTEM))
+        else {
+            continue;
+        };
+        if get(block, "HintIgnore").unwrap_or(false) {
+            continue;
+        }
+        let Some(drive_path) = get::<OwnedObjectPath>(block, "Drive") else {
+            continue;
+        };
+        let Some(drive) = drives.iter_mut().find(|d| d.path == drive_path) else {
+            continue;
+        };
+        let mount_point = get::<Vec<Vec<u8>>>(filesystem, "MountPoints")
+            .and_then(|points| points.into_iter().next())
+            .map(bytes_path);
+        drive.volumes.push(Volume {
+            path: path.clone(),
+            label: get(block, "IdLabel").unwrap_or_default(),
+            device: get::<Vec<u8>>(block, "PreferredDevice")
+                .map(|device| bytes_path(device).to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            size: get(block, "Size").unwrap_or(0),
+            usage: mount_point.as_deref().and_then(usage),
+            mount_point,
+        });
+    }
+
+    // card readers without a card have nothing to show
+    drives.retain(|d| !d.volumes.is_empty());
+    drives.sort_by(|a, b| a.name.cmp(&b.name));
+    for drive in &mut drives {
+        drive.volumes.sort_by(|a, b| a.device.cmp(&b.device));
+    }
+    Ok(drives)
+}
+
+pub async fn drives() -> zbus::Result<Vec<RemovableDrive>> {
+    let conn = Connection::system().await?;
+    list(&conn).await
+}
+
+/// Mounts the volume, returning its mount point
+pub async fn mount(volume: OwnedObjectPath) -> zbus::Result<String> {
+    let conn = Connection::system().await?;
+    let proxy = FilesystemProxy::builder(&conn)
+        .path(volume)?
+        .build()
+        .await?;
+    proxy.mount(HashMap::new()).await
+}
+
+pub async fn unmount(volume: OwnedObjectPath) -> zbus::Result<()> {
+    let conn = Connection::system().await?;
+    let proxy = FilesystemProxy::builder(&conn)
+        .path(volume)?
+        .build()
+        .await?;
+    proxy.unmount(HashMap::new()).await
+}
+
+/// Unmounts all of the drive's filesystems, then ejects or powers it off
+pub async fn remove(drive: RemovableDrive, removal: Removal) -> zbus::Result<()> {
+    for volume in drive.volumes.iter().filter(|v| v.mount_point.is_some()) {
+        unmount(volume.path.clone()).await?;
+    }
+    if removal == Removal::Unmount {
+        return Ok(());
+    }
+    let conn = Connection::system().await?;
+    let proxy = DriveProxy::builder(&conn).path(drive.path)?.build().await?;
+    match removal {
+        Removal::PowerOff => proxy.power_off(HashMap::new()).await,
+        _ => proxy.eject(HashMap::new()).await,
+    }
+}
+
+/// The removable drives, listed again whenever UDisks2 reports a change
+pub fn drives_subscription() -> Subscription<Vec<RemovableDrive>> {
+    struct DrivesWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<DrivesWorker>(),
+        10,
+        |mut output| async move {
+            let res: zbus::Result<()> = async {
+                let conn = Connection::system().await?;
+                // added and removed objects, and property changes such as mount points
+                let rule = MatchRule::builder()
+                    .msg_type(MessageType::Signal)
+                    .path_namespace(PATH)?
+                    .build();
+                let mut signals = MessageStream::for_match_rule(rule, &conn, None).await?;
+                loop {
+                    _ = output.send(list(&conn).await?).await;
+                    if signals.next().await.is_none() {
+                        return Ok(());
+                    }
+                    // a device being plugged in sends a burst of signals while it is probed
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                    while let Some(Some(_)) = signals.next().now_or_never() {}
+                }
+            }
+            .await;
+            if let Err(err) = res {
+                log::error!("Failed to watch removable drives: {}", err);
+            }
+            futures::future::pending().await
+        },
+    )
+}
@@ -0,0 +1,15 @@
+search = Search emoji
+results = Search Results
+recent = Recently Used
+none-found = No emoji
+smileys-and-emotion = Smileys & Emotion
+people-and-body = People & Body
+animals-and-nature = Animals & Nature
+food-and-drink = Food & Drink
+travel-and-places = Travel & Places
+activities = Activities
+objects = Objects
+symbols = Symbols
+flags = Flags
+skin-tone = Skin Tone
+insert = Type into Focused Window
@@ -0,0 +1,383 @@
+use crate::config::{self, EmojiAppletConfig, SkinTone};
+use crate::fl;
+use crate::keyboard;
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, container, scrollable, text, text_input, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::{divider, toggler};
+use cosmic::{Element, Theme};
+use emojis::{Emoji, Group};
+use std::borrow::Cow;
+use std::process;
+use std::time::Duration;
+
+const ICON: &str = "face-smile-symbolic";
+/// Emoji in a row of the grid
+const COLUMNS: usize = 8;
+/// Emoji listed for a search
+const MAX_RESULTS: usize = 200;
+/// Time for the focus to return to the window that had it before the popup
+const FOCUS_DELAY: Duration = Duration::from_millis(150);
+
+/// The groups, with the emoji on their tabs
+const GROUPS: [(Group, &str); 9] = [
+    (Group::SmileysAndEmotion, "😀"),
+    (Group::PeopleAndBody, "👋"),
+    (Group::AnimalsAndNature, "🐻"),
+    (Group::FoodAndDrink, "🍔"),
+    (Group::TravelAndPlaces, "🚗"),
+    (Group::Activities, "⚽"),
+    (Group::Objects, "💡"),
+    (Group::Symbols, "🔣"),
+    (Group::Flags, "🏁"),
+];
+
+const SKIN_TONES: [SkinTone; 6] = [
+    SkinTone::Default,
+    SkinTone::Light,
+    SkinTone::MediumLight,
+    SkinTone::Medium,
+    SkinTone::MediumDark,
+    SkinTone::Dark,
+];
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<EmojiApplet>(true, ())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Recent,
+    Group(Group),
+}
+
+#[derive(Default)]
+struct EmojiApplet {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: EmojiAppletConfig,
+    search: String,
+    category: Option<Category>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Search(String),
+    Category(Category),
+    SetSkinTone(SkinTone),
+    Pick(String),
+    Inserted(String, Result<(), String>),
+    SetInsert(bool),
+    ConfigUpdated(EmojiAppletConfig),
+}
+
+fn skin_tone(tone: SkinTone) -> emojis::SkinTone {
+    match tone {
+        SkinTone::Default => emojis::SkinTone::Default,
+        SkinTone::Light => emojis::SkinTone::Light,
+        SkinTone::MediumLight => emojis::SkinTone::MediumLight,
+        SkinTone::Medium => emojis::SkinTone::Medium,
+        SkinTone::MediumDark => emojis::SkinTone::MediumDark,
+        SkinTone::Dark => emojis::SkinTone::Dark,
+    }
+}
+
+fn group_name(group: Group) -> String {
+    match group {
+        Group::SmileysAndEmotion => fl!("smileys-and-emotion"),
+        Group::PeopleAndBody => fl!("people-and-body"),
+        Group::AnimalsAndNature => fl!("animals-and-nature"),
+        Group::FoodAndDrink => fl!("food-and-drink"),
+        Group::TravelAndPlaces => fl!("travel-and-places"),
+        Group::Activities => fl!("activities"),
+        Group::Objects => fl!("objects"),
+        Group::Symbols => fl!("symbols"),
+        Group::Flags => fl!("flags"),
+    }
+}
+
+fn copy(emoji: &str) {
+    if let Err(err) = process::Command::new("wl-copy").arg(emoji).spawn() {
+        log::error!("Failed to copy {}: {}", emoji, err);
+    }
+}
+
+async fn insert(emoji: String) -> Result<(), String> {
+    tokio::time::sleep(FOCUS_DELAY).await;
+    match tokio::task::spawn_blocking(move || keyboard::type_text(&emoji)).await {
+        Ok(res) => res.map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+impl EmojiApplet {
+    /// The emoji in the skin tone that's chosen, if it has that variant
+    fn toned(&self, emoji: &'static Emoji) -> &'static str {
+        emoji
+            .with_skin_tone(skin_tone(self.config.skin_tone))
+            .unwrap_or(emoji)
+            .as_str()
+    }
+
+    /// The selected category, which is the recent emoji if there are any
+    fn category(&self) -> Category {
+        self.category.unwrap_or(if self.config.recent.is_empty() {
+            Category::Group(GROUPS[0].0)
+        } else {
+            Category::Recent
+        })
+    }
+
+    /// The emoji to show, those found by name or shortcode while searching
+    fn listed(&self) -> Vec<String> {
+        let search = self.search.trim().to_lowercase();
+        if !search.is_empty() {
+            return emojis::iter()
+                .filter(|emoji| {
+                    emoji.name().contains(&search)
+                        || emoji.shortcodes().any(|code| code.contains(&search))
+                })
+                .take(MAX_RESULTS)
+                .map(|emoji| self.toned(emoji).to_string())
+                .collect();
+        }
+        match self.category() {
+            Category::Recent => self.config.recent.clone(),
+            Category::Group(group) => emojis::iter()
+                .filter(|emoji| emoji.group() == group)
+                .map(|emoji| self.toned(emoji).to_string())
+                .collect(),
+        }
+    }
+
+    fn tab(&self, label: &'static str, category: Category) -> Element<Message> {
+        let style = if self.category() == category {
+            theme::Button::Primary
+        } else {
+            theme::Button::Text
+        };
+        button(text(label).size(16))
+            .padding(4)
+            .style(style)
+            .on_press(Message::Category(category))
+            .into()
+    }
+
+    fn grid(&self, emoji: Vec<String>) -> Element<Message> {
+        let mut grid = Column::new().spacing(4);
+        for chunk in emoji.chunks(COLUMNS) {
+            let mut row = Row::new().spacing(4);
+            for emoji in chunk {
+                row = row.push(
+                    button(text(emoji.clone()).size(20))
+                        .width(Length::Fixed(36.0))
+                        .padding(4)
+                        .style(theme::Button::Text)
+                        .on_press(Message::Pick(emoji.clone())),
+                );
+            }
+            grid = grid.push(row);
+        }
+        scrollable(grid).height(Length::Fixed(300.0)).into()
+    }
+}
+
+impl cosmic::Application for EmojiApplet {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            EmojiApplet {
+                core,
+                config: EmojiAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.search.clear();
+                self.category = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(360.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Search(search) => {
+                self.search = search;
+            }
+            Message::Category(category) => {
+                self.category = Some(category);
+                self.search.clear();
+            }
+            Message::SetSkinTone(skin_tone) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_skin_tone(skin_tone, &config);
+                }
+            }
+            Message::Pick(emoji) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_recent(&emoji, &config);
+                }
+                let close = match self.popup.take() {
+                    Some(p) => destroy_popup(p),
+                    None => Command::none(),
+                };
+                if !self.config.insert {
+                    copy(&emoji);
+                    return close;
+                }
+                // typed once the popup is gone and the previous window is focused again
+                return Command::batch([
+                    close,
+                    cosmic::iced::Command::perform(insert(emoji.clone()), move |res| {
+                        app::message::app(Message::Inserted(emoji, res))
+                    }),
+                ]);
+            }
+            Message::Inserted(emoji, res) => {
+                // copied instead, for compositors without virtual keyboards
+                if let Err(err) = res {
+                    log::error!("Failed to type {}: {}", emoji, err);
+                    copy(&emoji);
+                }
+            }
+            Message::SetInsert(insert) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_insert(insert, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        self.core
+            .applet_helper
+            .icon_button(ICON)
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        content = content.push(
+            text_input(&fl!("search"), &self.search)
+                .on_input(Message::Search)
+                .size(14)
+                .width(Length::Fill),
+        );
+
+        let mut tabs = Row::new().spacing(2);
+        tabs = tabs.push(self.tab("🕘", Category::Recent));
+        for (group, label) in GROUPS {
+            tabs = tabs.push(self.tab(label, Category::Group(group)));
+        }
+        content = content.push(tabs);
+
+        let heading = if !self.search.trim().is_empty() {
+            fl!("results")
+        } else {
+            match self.category() {
+                Category::Recent => fl!("recent"),
+                Category::Group(group) => group_name(group),
+            }
+        };
+        content = content.push(text(heading).size(12));
+        let listed = self.listed();
+        if listed.is_empty() {
+            content = content.push(text(fl!("none-found")).size(14));
+        } else {
+            content = content.push(self.grid(listed));
+        }
+
+        let mut tones = Row::new()
+            .spacing(4)
+            .align_items(Alignment::Center)
+            .push(text(fl!("skin-tone")).size(14).width(Length::Fill));
+        let hand = emojis::get("✋").expect("raised hand is an emoji");
+        for tone in SKIN_TONES {
+            let style = if self.config.skin_tone == tone {
+                theme::Button::Primary
+            } else {
+                theme::Button::Text
+            };
+            let variant = hand.with_skin_tone(skin_tone(tone)).unwrap_or(hand);
+            tones = tones.push(
+                button(text(variant.as_str()).size(16))
+                    .padding(4)
+                    .style(style)
+                    .on_press(Message::SetSkinTone(tone)),
+            );
+        }
+        content = content
+            .push(container(divider::horizontal::light()).width(Length::Fill))
+            .push(tones)
+            .push(
+                toggler(fl!("insert"), self.config.insert, Message::SetInsert)
+                    .text_size(14)
+                    .width(Length::Fill),
+            );
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(|(_, config)| {
+            match config {
+                Ok(config) => Message::ConfigUpdated(config),
+                Err((errors, config)) => {
+                    for error in errors {
+                        log::error!("{:?}", error);
+                    }
+                    Message::ConfigUpdated(config)
+                }
+            }
+        })
+    }
+}
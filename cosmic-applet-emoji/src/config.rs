@@ -0,0 +1,55 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletEmoji";
+
+/// Emoji remembered as recently used
+const MAX_RECENT: usize = 24;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SkinTone {
+    /// The yellow of emoji without a skin tone
+    #[default]
+    Default,
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct EmojiAppletConfig {
+    /// Used for the emoji that have skin tone variants
+    pub skin_tone: SkinTone,
+    /// Emoji picked lately, the most recent first
+    pub recent: Vec<String>,
+    /// Whether picked emoji are typed into the focused window rather than copied
+    pub insert: bool,
+}
+
+impl EmojiAppletConfig {
+    pub fn load() -> EmojiAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| EmojiAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_skin_tone(&mut self, skin_tone: SkinTone, config: &Config) {
+        self.skin_tone = skin_tone;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn add_recent(&mut self, emoji: &str, config: &Config) {
+        self.recent.retain(|e| e != emoji);
+        self.recent.insert(0, emoji.to_string());
+        self.recent.truncate(MAX_RECENT);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_insert(&mut self, insert: bool, config: &Config) {
+        self.insert = insert;
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,124 @@
+//! Types text into the focused window with the virtual keyboard protocol, through a keymap made
+//! for the text, with a key for each of its characters
+
+use cctk::sctk::reexports::client::{
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{
+        wl_keyboard::{KeyState, KeymapFormat},
+        wl_registry::WlRegistry,
+        wl_seat::WlSeat,
+    },
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
+    zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
+    zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+};
+
+/// Keycodes of XKB start at 8 more than those of evdev, which the protocol sends
+const KEYCODE_OFFSET: u32 = 8;
+/// Characters typed at once, which the keycodes of a keymap are enough for
+const MAX_CHARS: usize = 200;
+
+struct State;
+
+/// A keymap whose keys, from evdev keycode 1 on, produce the characters in order
+fn keymap(chars: &[char]) -> String {
+    let mut keycodes = String::new();
+    let mut symbols = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        let keycode = i as u32 + 1 + KEYCODE_OFFSET;
+        let _ = writeln!(keycodes, "<K{keycode}> = {keycode};");
+        let _ = writeln!(symbols, "key <K{keycode}> {{ [ U{:04X} ] }};", *c as u32);
+    }
+    format!(
+        "xkb_keymap {{\n\
+         xkb_keycodes \"(unnamed)\" {{\nminimum = 8;\nmaximum = 255;\n{keycodes}}};\n\
+         xkb_types \"(unnamed)\" {{ include \"complete\" }};\n\
+         xkb_compatibility \"(unnamed)\" {{ include \"complete\" }};\n\
+         xkb_symbols \"(unnamed)\" {{\n{symbols}}};\n\
+         }};\n"
+    )
+}
+
+pub fn type_text(text: &str) -> anyhow::Result<()> {
+    let chars: Vec<char> = text.chars().take(MAX_CHARS).collect();
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init::<State>(&conn)?;
+    let qh = event_queue.handle();
+    let manager: ZwpVirtualKeyboardManagerV1 = globals.bind(&qh, 1..=1, ())?;
+    let seat: WlSeat = globals.bind(&qh, 1..=7, ())?;
+    let keyboard = manager.create_virtual_keyboard(&seat, &qh, ());
+
+    // the keymap is read from the file by the compositor, as a null-terminated string
+    let mut file = tempfile::tempfile()?;
+    let mut keymap = keymap(&chars).into_bytes();
+    keymap.push(0);
+    file.write_all(&keymap)?;
+    keyboard.keymap(
+        KeymapFormat::XkbV1 as u32,
+        file.as_raw_fd(),
+        keymap.len() as u32,
+    );
+    event_queue.roundtrip(&mut State)?;
+
+    for i in 0..chars.len() as u32 {
+        keyboard.key(i, i + 1, KeyState::Pressed as u32);
+        keyboard.key(i, i + 1, KeyState::Released as u32);
+    }
+    event_queue.roundtrip(&mut State)?;
+    keyboard.destroy();
+    conn.flush()?;
+    Ok(())
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpVirtualKeyboardManagerV1,
+        _: <ZwpVirtualKeyboardManagerV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpVirtualKeyboardV1,
+        _: <ZwpVirtualKeyboardV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
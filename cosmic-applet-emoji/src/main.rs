@@ -0,0 +1,10 @@
+mod app;
+mod config;
+mod keyboard;
+mod localize;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
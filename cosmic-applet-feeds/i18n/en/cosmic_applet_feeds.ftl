@@ -0,0 +1,8 @@
+no-headlines = No unread headlines
+mark-all-read = Mark All as Read
+show-read = Show Read Headlines
+feeds = Feeds…
+feed-url = Feed URL
+add = Add
+interval = Check every
+minutes = { $minutes } min
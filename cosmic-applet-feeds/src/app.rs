@@ -0,0 +1,381 @@
+use crate::config::{self, FeedsAppletConfig, INTERVALS};
+use crate::feeds::{feeds_subscription, Feed, Item};
+use crate::fl;
+use chrono::Local;
+use cosmic::app::Command;
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, scrollable, text, text_input, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::process;
+use std::time::Duration;
+
+const ICON: &str = "application-rss+xml-symbolic";
+/// Headlines listed in the popup, the latest first
+const MAX_HEADLINES: usize = 50;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Feeds>(true, ())
+}
+
+#[derive(Default)]
+struct Feeds {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: FeedsAppletConfig,
+    feeds: Vec<Feed>,
+    /// Whether the list of feeds is shown, to add and remove feeds
+    show_feeds: bool,
+    url_input: String,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Feeds(Vec<Feed>),
+    Open(Item),
+    MarkRead(String),
+    MarkAllRead,
+    ToggleFeeds,
+    UrlInput(String),
+    AddFeed,
+    RemoveFeed(String),
+    SetInterval(u64),
+    SetShowRead(bool),
+    ConfigUpdated(FeedsAppletConfig),
+}
+
+impl Feeds {
+    fn unread(&self) -> impl Iterator<Item = &Item> {
+        self.feeds
+            .iter()
+            .flat_map(|feed| &feed.items)
+            .filter(|item| !self.config.read.contains(&item.id))
+    }
+
+    fn mark_read(&mut self, ids: Vec<String>) {
+        // read items are only forgotten once every feed has been read, so none are missing
+        let complete = self.feeds.len() == self.config.feeds.len()
+            && self.feeds.iter().all(|feed| feed.error.is_none());
+        let current = complete.then(|| {
+            self.feeds
+                .iter()
+                .flat_map(|feed| &feed.items)
+                .map(|item| item.id.as_str())
+                .collect::<HashSet<&str>>()
+        });
+        if let Ok(config) = Config::new(config::APP_ID, 1) {
+            self.config.mark_read(ids, current, &config);
+        }
+    }
+
+    fn headline_view<'a>(&self, feed: &'a Feed, item: &'a Item) -> Element<'a, Message> {
+        let mut details = column![text(&item.title).size(14)].spacing(2);
+        details = details.push(
+            text(match item.published {
+                Some(published) => format!(
+                    "{} · {}",
+                    feed.title,
+                    published.with_timezone(&Local).format("%b %-d, %H:%M")
+                ),
+                None => feed.title.clone(),
+            })
+            .size(12),
+        );
+        let mut headline = button(details)
+            .style(theme::Button::Text)
+            .width(Length::Fill);
+        if item.link.is_some() {
+            headline = headline.on_press(Message::Open(item.clone()));
+        }
+
+        let mut headline_row = Row::new()
+            .push(headline)
+            .spacing(8)
+            .align_items(Alignment::Center);
+        if !self.config.read.contains(&item.id) {
+            headline_row = headline_row.push(
+                button(icon("object-select-symbolic", 16).style(Svg::Symbolic))
+                    .on_press(Message::MarkRead(item.id.clone()))
+                    .style(theme::Button::Text),
+            );
+        }
+        headline_row.into()
+    }
+
+    fn feeds_view(&self) -> Element<Message> {
+        let mut content = Column::new().spacing(8);
+        for url in &self.config.feeds {
+            // the URL until the feed has been read
+            let feed = self.feeds.iter().find(|feed| feed.url == *url);
+            let mut details =
+                column![text(feed.map_or(url, |feed| &feed.title)).size(14)].spacing(2);
+            if let Some(error) = feed.and_then(|feed| feed.error.as_ref()) {
+                details = details.push(text(error).size(12));
+            }
+            content = content.push(
+                row![
+                    container(details).width(Length::Fill),
+                    button(icon("edit-delete-symbolic", 16).style(Svg::Symbolic))
+                        .on_press(Message::RemoveFeed(url.clone()))
+                        .style(theme::Button::Text),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        content = content.push(
+            row![
+                text_input(&fl!("feed-url"), &self.url_input)
+                    .on_input(Message::UrlInput)
+                    .on_submit(Message::AddFeed)
+                    .size(14)
+                    .width(Length::Fill),
+                button(text(fl!("add")).size(14))
+                    .on_press(Message::AddFeed)
+                    .style(theme::Button::Text),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+
+        let mut intervals = Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(text(fl!("interval")).size(14).width(Length::Fill));
+        for minutes in INTERVALS {
+            let style = if self.config.interval_minutes == minutes {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            intervals = intervals.push(
+                button(text(fl!("minutes", minutes = minutes)).size(12))
+                    .padding([4, 8])
+                    .style(style)
+                    .on_press(Message::SetInterval(minutes)),
+            );
+        }
+        content.push(intervals).into()
+    }
+}
+
+impl cosmic::Application for Feeds {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Feeds {
+                core,
+                config: FeedsAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                // the feeds are shown at first until there are any
+                self.show_feeds = self.config.feeds.is_empty();
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(450.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Feeds(feeds) => {
+                self.feeds = feeds;
+            }
+            Message::Open(item) => {
+                if let Some(link) = &item.link {
+                    if let Err(err) = process::Command::new("xdg-open").arg(link).spawn() {
+                        log::error!("Failed to open {}: {}", link, err);
+                        return Command::none();
+                    }
+                }
+                if !self.config.read.contains(&item.id) {
+                    self.mark_read(vec![item.id]);
+                }
+            }
+            Message::MarkRead(id) => {
+                self.mark_read(vec![id]);
+            }
+            Message::MarkAllRead => {
+                let ids = self.unread().map(|item| item.id.clone()).collect();
+                self.mark_read(ids);
+            }
+            Message::ToggleFeeds => {
+                self.show_feeds = !self.show_feeds;
+            }
+            Message::UrlInput(input) => {
+                self.url_input = input;
+            }
+            Message::AddFeed => {
+                let url = self.url_input.trim();
+                if url.is_empty() {
+                    return Command::none();
+                }
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_feed(url.to_string(), &config);
+                }
+                self.url_input.clear();
+            }
+            Message::RemoveFeed(url) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.remove_feed(&url, &config);
+                }
+                self.feeds.retain(|feed| feed.url != url);
+            }
+            Message::SetInterval(interval_minutes) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_interval_minutes(interval_minutes, &config);
+                }
+            }
+            Message::SetShowRead(show_read) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_read(show_read, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let unread = self.unread().count();
+        if unread == 0 {
+            return self
+                .core
+                .applet_helper
+                .icon_button(ICON)
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+        button(
+            row![
+                icon(ICON, 16).style(Svg::Symbolic),
+                text(unread.to_string()).size(14)
+            ]
+            .spacing(4)
+            .align_items(Alignment::Center),
+        )
+        .on_press(Message::TogglePopup)
+        .style(theme::Button::Text)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+
+        let mut headlines: Vec<(&Feed, &Item)> = self
+            .feeds
+            .iter()
+            .flat_map(|feed| feed.items.iter().map(move |item| (feed, item)))
+            .filter(|(_, item)| self.config.show_read || !self.config.read.contains(&item.id))
+            .collect();
+        // the latest first, and undated items last
+        headlines.sort_by(|(_, a), (_, b)| b.published.cmp(&a.published));
+        headlines.truncate(MAX_HEADLINES);
+        if headlines.is_empty() {
+            content = content.push(text(fl!("no-headlines")).size(14));
+        } else {
+            let rows: Vec<Element<Message>> = headlines
+                .into_iter()
+                .map(|(feed, item)| self.headline_view(feed, item))
+                .collect();
+            content = content.push(
+                scrollable(Column::with_children(rows).spacing(4)).height(Length::Fixed(400.0)),
+            );
+        }
+
+        let mut mark_all = button(text(fl!("mark-all-read")).size(12))
+            .padding([4, 8])
+            .style(theme::Button::Secondary);
+        if self.unread().next().is_some() {
+            mark_all = mark_all.on_press(Message::MarkAllRead);
+        }
+        content = content
+            .push(mark_all)
+            .push(container(divider::horizontal::light()).width(Length::Fill))
+            .push(
+                toggler(
+                    fl!("show-read"),
+                    self.config.show_read,
+                    Message::SetShowRead,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            )
+            .push(
+                button(text(fl!("feeds")).size(14))
+                    .on_press(Message::ToggleFeeds)
+                    .style(theme::Button::Text),
+            );
+        if self.show_feeds {
+            content = content.push(self.feeds_view());
+        }
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let interval = Duration::from_secs(self.config.interval_minutes.max(1) * 60);
+        Subscription::batch(vec![
+            feeds_subscription(self.config.feeds.clone(), interval).map(Message::Feeds),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
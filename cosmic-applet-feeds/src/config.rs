@@ -0,0 +1,75 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const APP_ID: &str = "com.system76.CosmicAppletFeeds";
+
+/// Choices of the time between polls, in minutes
+pub const INTERVALS: [u64; 3] = [15, 30, 60];
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct FeedsAppletConfig {
+    /// URLs of the RSS and Atom feeds, in the order they were added
+    pub feeds: Vec<String>,
+    /// Ids of the items that were read
+    pub read: Vec<String>,
+    pub interval_minutes: u64,
+    /// Whether read items are listed along with unread ones
+    pub show_read: bool,
+}
+
+impl Default for FeedsAppletConfig {
+    fn default() -> Self {
+        Self {
+            feeds: Vec::new(),
+            read: Vec::new(),
+            interval_minutes: 30,
+            show_read: false,
+        }
+    }
+}
+
+impl FeedsAppletConfig {
+    pub fn load() -> FeedsAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| FeedsAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn add_feed(&mut self, url: String, config: &Config) {
+        if !self.feeds.contains(&url) {
+            self.feeds.push(url);
+            let _ = self.write_entry(config);
+        }
+    }
+
+    pub fn remove_feed(&mut self, url: &str, config: &Config) {
+        self.feeds.retain(|u| u != url);
+        let _ = self.write_entry(config);
+    }
+
+    /// Marks the items read, forgetting the read items that are no longer in the feeds, if all
+    /// of the feeds' items are known
+    pub fn mark_read(&mut self, ids: Vec<String>, current: Option<HashSet<&str>>, config: &Config) {
+        if let Some(current) = current {
+            self.read.retain(|id| current.contains(id.as_str()));
+        }
+        for id in ids {
+            if !self.read.contains(&id) {
+                self.read.push(id);
+            }
+        }
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_interval_minutes(&mut self, interval_minutes: u64, config: &Config) {
+        self.interval_minutes = interval_minutes;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_show_read(&mut self, show_read: bool, config: &Config) {
+        self.show_read = show_read;
+        let _ = self.write_entry(config);
+    }
+}
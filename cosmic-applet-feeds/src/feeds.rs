@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! RSS and Atom feeds, polled with conditional requests so that feeds that haven't changed
+//! aren't downloaded again
+
+use chrono::{DateTime, Utc};
+use cosmic::iced::{futures::SinkExt, subscription, Subscription};
+use reqwest::{header, Client, StatusCode};
+use roxmltree::Node;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    /// The item's guid or id, or its link if it has none
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feed {
+    pub url: String,
+    /// The feed's own title, or its URL until it's been read
+    pub title: String,
+    pub items: Vec<Item>,
+    /// Why the feed couldn't be read the last time, in which case the items are those read
+    /// before
+    pub error: Option<String>,
+}
+
+/// A feed as last downloaded, with the validators to request it again with
+struct Cached {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    feed: Feed,
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().name() == name)
+}
+
+/// Text of the element, including that of any elements in it
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    child(node, name).map(text).filter(|text| !text.is_empty())
+}
+
+/// Link of an RSS item, or the alternate link of an Atom entry
+fn link(item: Node) -> Option<String> {
+    item.children()
+        .filter(|n| n.tag_name().name() == "link")
+        .find_map(|n| match n.attribute("href") {
+            Some(href) if matches!(n.attribute("rel"), None | Some("alternate")) => {
+                Some(href.to_string())
+            }
+            Some(_) => None,
+            None => Some(text(n)).filter(|text| !text.is_empty()),
+        })
+}
+
+fn published(item: Node) -> Option<DateTime<Utc>> {
+    if let Some(date) = child_text(item, "pubDate") {
+        return DateTime::parse_from_rfc2822(&date)
+            .ok()
+            .map(|date| date.with_timezone(&Utc));
+    }
+    // Atom dates, and the Dublin Core dates of RSS 1.0
+    ["published", "updated", "date"]
+        .iter()
+        .filter_map(|name| child_text(item, name))
+        .find_map(|date| DateTime::parse_from_rfc3339(&date).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn parse(url: &str, body: &str) -> Result<Feed, String> {
+    let doc = roxmltree::Document::parse(body).map_err(|err| err.to_string())?;
+    let root = doc.root_element();
+    // the channel of RSS, or the feed of Atom
+    let channel = match root.tag_name().name() {
+        "feed" => root,
+        "rss" | "RDF" => child(root, "channel").ok_or("not an RSS feed")?,
+        _ => return Err("not an RSS or Atom feed".to_string()),
+    };
+    let items = root
+        .descendants()
+        .filter(|n| matches!(n.tag_name().name(), "item" | "entry"))
+        .filter_map(|item| {
+            let title = child_text(item, "title");
+            let link = link(item);
+            let id = child_text(item, "guid")
+                .or_else(|| child_text(item, "id"))
+                .or_else(|| link.clone())
+                .or_else(|| title.clone())?;
+            Some(Item {
+                id,
+                title: title.unwrap_or_default(),
+                link,
+                published: published(item),
+            })
+        })
+        .collect();
+    Ok(Feed {
+        url: url.to_string(),
+        title: child_text(channel, "title").unwrap_or_else(|| url.to_string()),
+        items,
+        error: None,
+    })
+}
+
+/// The feed, or `None` if it hasn't changed since it was cached
+async fn fetch(
+    client: &Client,
+    url: &str,
+    cached: Option<&Cached>,
+) -> Result<Option<Cached>, String> {
+    let mut request = client.get(url);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().await.map_err(|err| err.to_string())?;
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("HTTP status {}", status));
+    }
+    let validator = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = validator(header::ETAG);
+    let last_modified = validator(header::LAST_MODIFIED);
+    let body = response.text().await.map_err(|err| err.to_string())?;
+    Ok(Some(Cached {
+        etag,
+        last_modified,
+        feed: parse(url, &body)?,
+    }))
+}
+
+pub fn feeds_subscription(urls: Vec<String>, interval: Duration) -> Subscription<Vec<Feed>> {
+    struct FeedsWorker;
+
+    subscription::channel(
+        (
+            std::any::TypeId::of::<FeedsWorker>(),
+            urls.clone(),
+            interval,
+        ),
+        1,
+        move |mut output| async move {
+            let client = Client::builder()
+                .timeout(TIMEOUT)
+                .user_agent(concat!("cosmic-applet-feeds/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default();
+            let mut cache: HashMap<String, Cached> = HashMap::new();
+            loop {
+                let mut feeds = Vec::new();
+                for url in &urls {
+                    let feed = match fetch(&client, url, cache.get(url)).await {
+                        Ok(Some(fetched)) => {
+                            let feed = fetched.feed.clone();
+                            cache.insert(url.clone(), fetched);
+                            feed
+                        }
+                        // not modified, which is only reported for cached feeds
+                        Ok(None) => cache[url].feed.clone(),
+                        Err(err) => {
+                            log::error!("Failed to read {}: {}", url, err);
+                            let mut feed = match cache.get(url) {
+                                Some(cached) => cached.feed.clone(),
+                                None => Feed {
+                                    url: url.clone(),
+                                    title: url.clone(),
+                                    items: Vec::new(),
+                                    error: None,
+                                },
+                            };
+                            feed.error = Some(err);
+                            feed
+                        }
+                    };
+                    feeds.push(feed);
+                }
+                _ = output.send(feeds).await;
+                tokio::time::sleep(interval).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,3 @@
+no-layouts = No keyboard layouts configured
+per-window = Remember the Layout per Window
+keyboard-settings = Keyboard Settings...
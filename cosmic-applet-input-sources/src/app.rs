@@ -0,0 +1,280 @@
+use crate::config::{self, InputSourcesAppletConfig};
+use crate::fl;
+use crate::focus::{focus_updates, FocusUpdate};
+use crate::xkb::{self, CompConfig, Layout};
+use cctk::wayland_client::backend::ObjectId;
+use cosmic::app::{applet::applet_button_theme, Command};
+use cosmic::cosmic_config::{self, Config, CosmicConfigEntry};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{self, column, container, row, text, Column},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{button, divider, icon, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::process;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<InputSources>(true, ())
+}
+
+#[derive(Default)]
+struct InputSources {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: InputSourcesAppletConfig,
+    comp_config: CompConfig,
+    names: HashMap<Layout, String>,
+    focused: Option<ObjectId>,
+    /// The layout last used in each window, with `per_window`
+    window_layouts: HashMap<ObjectId, Layout>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Activate(Layout),
+    Focus(FocusUpdate),
+    SetPerWindow(bool),
+    OpenSettings,
+    ConfigUpdated(InputSourcesAppletConfig),
+    CompConfigUpdated(CompConfig),
+}
+
+impl InputSources {
+    /// Writes the layout to the front of the compositor's list
+    fn activate(&mut self, layout: &Layout) {
+        if !self.comp_config.xkb_config.activate(layout) {
+            return;
+        }
+        match Config::new(xkb::COMP_ID, 1) {
+            Ok(config) => {
+                if let Err(err) = self.comp_config.write_entry(&config) {
+                    log::error!("Failed to switch the keyboard layout: {:?}", err);
+                }
+            }
+            Err(err) => log::error!("Failed to switch the keyboard layout: {:?}", err),
+        }
+    }
+
+    fn name(&self, layout: &Layout) -> String {
+        self.names
+            .get(layout)
+            .cloned()
+            .unwrap_or_else(|| match layout.variant.as_str() {
+                "" => layout.layout.clone(),
+                variant => format!("{} ({})", layout.layout, variant),
+            })
+    }
+}
+
+impl cosmic::Application for InputSources {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            InputSources {
+                core,
+                config: InputSourcesAppletConfig::load(),
+                comp_config: CompConfig::load(),
+                names: xkb::layout_names(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(250.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Activate(layout) => {
+                self.activate(&layout);
+                if let Some(focused) = self.focused.clone().filter(|_| self.config.per_window) {
+                    self.window_layouts.insert(focused, layout);
+                }
+            }
+            Message::Focus(FocusUpdate::Focused(id)) => {
+                if self.config.per_window {
+                    match self.window_layouts.get(&id).cloned() {
+                        Some(layout) => self.activate(&layout),
+                        // new windows keep the layout in use
+                        None => {
+                            if let Some(layout) = self.comp_config.xkb_config.active() {
+                                self.window_layouts.insert(id.clone(), layout);
+                            }
+                        }
+                    }
+                }
+                self.focused = Some(id);
+            }
+            Message::Focus(FocusUpdate::Closed(id)) => {
+                self.window_layouts.remove(&id);
+                if self.focused.as_ref() == Some(&id) {
+                    self.focused = None;
+                }
+            }
+            Message::SetPerWindow(per_window) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_per_window(per_window, &config);
+                }
+            }
+            Message::OpenSettings => {
+                let _ = process::Command::new("cosmic-settings").spawn();
+            }
+            Message::ConfigUpdated(config) => {
+                if !config.per_window {
+                    self.window_layouts.clear();
+                    self.focused = None;
+                }
+                self.config = config;
+            }
+            Message::CompConfigUpdated(comp_config) => {
+                self.comp_config = comp_config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let Some(active) = self.comp_config.xkb_config.active() else {
+            return self
+                .core
+                .applet_helper
+                .icon_button("input-keyboard-symbolic")
+                .on_press(Message::TogglePopup)
+                .into();
+        };
+        widget::button(text(active.code().to_string()).size(14))
+            .on_press(Message::TogglePopup)
+            .style(theme::Button::Text)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let active = self.comp_config.xkb_config.active();
+        let mut layouts = Column::new();
+        for layout in self.comp_config.xkb_config.layouts() {
+            let is_active = active.as_ref() == Some(&layout);
+            layouts = layouts.push(
+                button(applet_button_theme())
+                    .custom(vec![row![
+                        text(self.name(&layout)).size(14).width(Length::Fill),
+                        icon("emblem-ok-symbolic", 12).size(12).style(if is_active {
+                            Svg::SymbolicActive
+                        } else {
+                            Svg::Default
+                        }),
+                    ]
+                    .align_items(Alignment::Center)
+                    .into()])
+                    .padding([8, 24])
+                    .width(Length::Fill)
+                    .on_press(Message::Activate(layout)),
+            );
+        }
+        if active.is_none() {
+            layouts = layouts.push(container(text(fl!("no-layouts")).size(14)).padding([0, 24]));
+        }
+
+        let content = column![
+            layouts,
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            container(
+                toggler(
+                    fl!("per-window"),
+                    self.config.per_window,
+                    Message::SetPerWindow
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 24]),
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            button(applet_button_theme())
+                .custom(vec![text(fl!("keyboard-settings")).size(14).into()])
+                .padding([8, 24])
+                .width(Length::Fill)
+                .on_press(Message::OpenSettings),
+        ]
+        .spacing(8)
+        .padding([8, 0]);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+            cosmic_config::config_subscription(1, Cow::from(xkb::COMP_ID), 1).map(|(_, config)| {
+                match config {
+                    Ok(config) => Message::CompConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::CompConfigUpdated(config)
+                    }
+                }
+            }),
+        ];
+        if self.config.per_window {
+            subscriptions.push(focus_updates().map(Message::Focus));
+        }
+        Subscription::batch(subscriptions)
+    }
+}
@@ -0,0 +1,24 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletInputSources";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct InputSourcesAppletConfig {
+    /// Each window keeps the layout last used in it
+    pub per_window: bool,
+}
+
+impl InputSourcesAppletConfig {
+    pub fn load() -> InputSourcesAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| InputSourcesAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_per_window(&mut self, per_window: bool, config: &Config) {
+        self.per_window = per_window;
+        let _ = self.write_entry(config);
+    }
+}
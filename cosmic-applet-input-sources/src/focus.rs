@@ -0,0 +1,141 @@
+//! Follows which window has focus, to remember a layout per window
+
+use cctk::{
+    sctk::{
+        self,
+        reexports::{calloop, client::WaylandSource},
+        registry::{ProvidesRegistryState, RegistryState},
+    },
+    toplevel_info::{ToplevelInfoHandler, ToplevelInfoState},
+    wayland_client::{
+        backend::ObjectId, globals::registry_queue_init, Connection, Proxy, QueueHandle,
+    },
+};
+use cosmic::iced::{
+    self,
+    futures::{
+        channel::mpsc::{unbounded, UnboundedSender},
+        future, SinkExt, StreamExt,
+    },
+    subscription,
+};
+use cosmic_protocols::toplevel_info::v1::client::zcosmic_toplevel_handle_v1::{
+    State, ZcosmicToplevelHandleV1,
+};
+
+#[derive(Debug, Clone)]
+pub enum FocusUpdate {
+    /// The window got focus
+    Focused(ObjectId),
+    Closed(ObjectId),
+}
+
+struct AppData {
+    tx: UnboundedSender<FocusUpdate>,
+    registry_state: RegistryState,
+    toplevel_info_state: ToplevelInfoState,
+    focused: Option<ObjectId>,
+}
+
+impl AppData {
+    fn check_focus(&mut self, toplevel: &ZcosmicToplevelHandleV1) {
+        let Some(info) = self.toplevel_info_state.info(toplevel) else {
+            return;
+        };
+        let id = toplevel.id();
+        if info.state.contains(&State::Activated) && self.focused.as_ref() != Some(&id) {
+            self.focused = Some(id.clone());
+            let _ = self.tx.unbounded_send(FocusUpdate::Focused(id));
+        }
+    }
+}
+
+impl ProvidesRegistryState for AppData {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    sctk::registry_handlers!();
+}
+
+impl ToplevelInfoHandler for AppData {
+    fn toplevel_info_state(&mut self) -> &mut ToplevelInfoState {
+        &mut self.toplevel_info_state
+    }
+
+    fn new_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        self.check_focus(toplevel);
+    }
+
+    fn update_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        self.check_focus(toplevel);
+    }
+
+    fn toplevel_closed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        let id = toplevel.id();
+        if self.focused.as_ref() == Some(&id) {
+            self.focused = None;
+        }
+        let _ = self.tx.unbounded_send(FocusUpdate::Closed(id));
+    }
+}
+
+fn run(tx: UnboundedSender<FocusUpdate>) -> anyhow::Result<()> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, event_queue) = registry_queue_init(&conn)?;
+    let qh = event_queue.handle();
+    let mut event_loop = calloop::EventLoop::<AppData>::try_new()?;
+    WaylandSource::new(event_queue)?
+        .insert(event_loop.handle())
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
+
+    let registry_state = RegistryState::new(&globals);
+    let mut app_data = AppData {
+        tx,
+        toplevel_info_state: ToplevelInfoState::new(&registry_state, &qh),
+        registry_state,
+        focused: None,
+    };
+    loop {
+        event_loop.dispatch(None, &mut app_data)?;
+    }
+}
+
+pub fn focus_updates() -> iced::Subscription<FocusUpdate> {
+    struct FocusUpdates;
+
+    subscription::channel(
+        std::any::TypeId::of::<FocusUpdates>(),
+        50,
+        |mut output| async move {
+            let (tx, mut rx) = unbounded();
+            std::thread::spawn(move || {
+                if let Err(err) = run(tx) {
+                    log::error!("Failed to follow the focused window: {}", err);
+                }
+            });
+            while let Some(update) = rx.next().await {
+                _ = output.send(update).await;
+            }
+            future::pending().await
+        },
+    )
+}
+
+sctk::delegate_registry!(AppData);
+cctk::delegate_toplevel_info!(AppData);
@@ -0,0 +1,11 @@
+mod app;
+mod config;
+mod focus;
+mod localize;
+mod xkb;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
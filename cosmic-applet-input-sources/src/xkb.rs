@@ -0,0 +1,136 @@
+//! The keyboard layouts of the compositor's input config, and their names from the xkb rules
+
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+pub const COMP_ID: &str = "com.system76.CosmicComp";
+
+const RULES: &str = "/usr/share/X11/xkb/rules/evdev.lst";
+
+/// The keymap as cosmic-comp reads it, layouts and variants are comma separated lists
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct XkbConfig {
+    pub rules: String,
+    pub model: String,
+    pub layout: String,
+    pub variant: String,
+    pub options: Option<String>,
+}
+
+/// The part of the compositor config the applet reads and writes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, CosmicConfigEntry)]
+pub struct CompConfig {
+    pub xkb_config: XkbConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Layout {
+    pub layout: String,
+    pub variant: String,
+}
+
+impl Layout {
+    /// Shown in the panel, e.g. `us` or `de`
+    pub fn code(&self) -> &str {
+        &self.layout
+    }
+}
+
+impl XkbConfig {
+    pub fn layouts(&self) -> Vec<Layout> {
+        let mut variants = self.variant.split(',');
+        self.layout
+            .split(',')
+            .filter(|l| !l.trim().is_empty())
+            .map(|layout| Layout {
+                layout: layout.trim().to_string(),
+                variant: variants.next().unwrap_or_default().trim().to_string(),
+            })
+            .collect()
+    }
+
+    /// The first layout is the one in use, the compositor has no other way to pick one
+    pub fn active(&self) -> Option<Layout> {
+        self.layouts().into_iter().next()
+    }
+
+    /// Moves the layout to the front, keeping the order of the others
+    pub fn activate(&mut self, layout: &Layout) -> bool {
+        let mut layouts = self.layouts();
+        let Some(i) = layouts.iter().position(|l| l == layout) else {
+            return false;
+        };
+        if i == 0 {
+            return false;
+        }
+        let layout = layouts.remove(i);
+        layouts.insert(0, layout);
+        self.layout = layouts
+            .iter()
+            .map(|l| l.layout.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.variant = layouts
+            .iter()
+            .map(|l| l.variant.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        true
+    }
+}
+
+impl CompConfig {
+    pub fn load() -> CompConfig {
+        Config::new(COMP_ID, 1)
+            .map(|config| CompConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+}
+
+/// Human readable names of layouts and variants, e.g. `English (US, intl., with dead keys)`
+pub fn layout_names() -> HashMap<Layout, String> {
+    let Ok(rules) = fs::read_to_string(RULES) else {
+        return HashMap::new();
+    };
+    let mut names = HashMap::new();
+    let mut section = "";
+    for line in rules.lines() {
+        if let Some(name) = line.strip_prefix("! ") {
+            section = name.trim();
+            continue;
+        }
+        let Some((name, description)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let description = description.trim();
+        match section {
+            "layout" => {
+                names.insert(
+                    Layout {
+                        layout: name.to_string(),
+                        variant: String::new(),
+                    },
+                    description.to_string(),
+                );
+            }
+            // variants are listed as `intl  us: English (US, intl., with dead keys)`
+            "variant" => {
+                if let Some((layout, description)) = description.split_once(": ") {
+                    names.insert(
+                        Layout {
+                            layout: layout.to_string(),
+                            variant: name.to_string(),
+                        },
+                        description.to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
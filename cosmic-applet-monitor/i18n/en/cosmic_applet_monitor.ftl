@@ -0,0 +1,12 @@
+cpu = CPU
+memory = Memory
+network = Network
+disk = Disk
+memory-used = { $used } of { $total }
+network-rates = ↓ { $rx }  ↑ { $tx }
+disk-rates = Read { $read }, write { $write }
+top-processes = Top Processes
+no-processes = Nothing busy
+panel-graphs = Graphs in the Panel
+interval = Update Interval
+seconds = { $secs } s
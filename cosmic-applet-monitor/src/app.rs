@@ -0,0 +1,341 @@
+use crate::config::{self, MonitorAppletConfig, Resource, INTERVALS};
+use crate::fl;
+use crate::sample::{samples, Sample};
+use crate::sparkline::{sparkline, History};
+use cosmic::app::{applet::cosmic_panel_config::PanelAnchor, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, progress_bar, row, svg, text, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::{divider, toggler};
+use cosmic::{Element, Theme};
+use cosmic_applet_common::format::{format_bytes, format_rate};
+use std::borrow::Cow;
+
+/// Rates below this fill the network and disk graphs less than completely, so an idle
+/// machine doesn't show noise as full load
+const MIN_RATE_SCALE: f32 = 64.0 * 1024.0;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Monitor>(true, ())
+}
+
+#[derive(Default)]
+struct Monitor {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: MonitorAppletConfig,
+    sample: Sample,
+    cpu: History,
+    memory: History,
+    network: History,
+    disk: History,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Sample(Sample),
+    SetInterval(u64),
+    SetInPanel(Resource, bool),
+    ConfigUpdated(MonitorAppletConfig),
+}
+
+impl Monitor {
+    fn history(&self, resource: Resource) -> (&History, f32) {
+        match resource {
+            Resource::Cpu => (&self.cpu, 1.0),
+            Resource::Memory => (&self.memory, 1.0),
+            Resource::Network => (&self.network, self.network.max().max(MIN_RATE_SCALE)),
+            Resource::Disk => (&self.disk, self.disk.max().max(MIN_RATE_SCALE)),
+        }
+    }
+}
+
+fn resource_name(resource: Resource) -> String {
+    match resource {
+        Resource::Cpu => fl!("cpu"),
+        Resource::Memory => fl!("memory"),
+        Resource::Network => fl!("network"),
+        Resource::Disk => fl!("disk"),
+    }
+}
+
+fn percent(value: f32) -> String {
+    format!("{:.0}%", value * 100.0)
+}
+
+/// A resource's name with its current value on the right
+fn heading<'a>(name: String, value: String) -> Element<'a, Message> {
+    row![
+        text(name).size(14).width(Length::Fill),
+        text(value).size(14)
+    ]
+    .into()
+}
+
+fn bar<'a>(value: f32) -> Element<'a, Message> {
+    progress_bar(0.0..=1.0, value)
+        .height(Length::Fixed(6.0))
+        .into()
+}
+
+impl cosmic::Application for Monitor {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Monitor {
+                core,
+                config: MonitorAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(200.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Sample(sample) => {
+                self.cpu.push(sample.cpu);
+                self.memory.push(sample.memory());
+                self.network.push((sample.net_rx + sample.net_tx) as f32);
+                self.disk
+                    .push((sample.disk_read + sample.disk_write) as f32);
+                self.sample = sample;
+            }
+            Message::SetInterval(interval_ms) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_interval_ms(interval_ms, &config);
+                }
+            }
+            Message::SetInPanel(resource, in_panel) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_in_panel(resource, in_panel, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                // the graphs would mix samples of different intervals
+                if config.interval_ms != self.config.interval_ms {
+                    for history in [
+                        &mut self.cpu,
+                        &mut self.memory,
+                        &mut self.network,
+                        &mut self.disk,
+                    ] {
+                        history.clear();
+                    }
+                }
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        if self.config.panel_resources.is_empty() {
+            return self
+                .core
+                .applet_helper
+                .icon_button("utilities-system-monitor-symbolic")
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+        let graphs = self.config.panel_resources.iter().map(|resource| {
+            let (history, max) = self.history(*resource);
+            svg(sparkline(history, max))
+                .width(Length::Fixed(32.0))
+                .height(Length::Fixed(16.0))
+                .style(theme::Svg::Symbolic)
+                .into()
+        });
+        let content: Element<_> = if matches!(
+            self.core.applet_helper.anchor,
+            PanelAnchor::Top | PanelAnchor::Bottom
+        ) {
+            Row::with_children(graphs.collect())
+                .spacing(4)
+                .align_items(Alignment::Center)
+                .into()
+        } else {
+            Column::with_children(graphs.collect())
+                .spacing(4)
+                .align_items(Alignment::Center)
+                .into()
+        };
+        button(content)
+            .on_press(Message::TogglePopup)
+            .style(theme::Button::Text)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let sample = &self.sample;
+
+        let mut cores = Column::new().spacing(4);
+        for (i, pair) in sample.cores.chunks(2).enumerate() {
+            let mut core_row = Row::new().spacing(12).align_items(Alignment::Center);
+            for (j, usage) in pair.iter().enumerate() {
+                core_row = core_row.push(
+                    row![
+                        text(format!("{}", i * 2 + j + 1))
+                            .size(10)
+                            .width(Length::Fixed(16.0)),
+                        bar(*usage)
+                    ]
+                    .spacing(4)
+                    .align_items(Alignment::Center)
+                    .width(Length::Fill),
+                );
+            }
+            cores = cores.push(core_row);
+        }
+
+        let mut processes = Column::new().spacing(4);
+        for process in &sample.processes {
+            processes = processes.push(row![
+                text(&process.name).size(14).width(Length::Fill),
+                text(percent(process.cpu)).size(14)
+            ]);
+        }
+        if sample.processes.is_empty() {
+            processes = processes.push(text(fl!("no-processes")).size(14));
+        }
+
+        let mut panel_toggles = Column::new().spacing(8);
+        for resource in Resource::ALL {
+            panel_toggles = panel_toggles.push(
+                toggler(
+                    resource_name(resource),
+                    self.config.panel_resources.contains(&resource),
+                    move |in_panel| Message::SetInPanel(resource, in_panel),
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            );
+        }
+
+        let mut intervals = Row::new().spacing(8).align_items(Alignment::Center);
+        intervals = intervals.push(text(fl!("interval")).size(14).width(Length::Fill));
+        for interval_ms in INTERVALS {
+            let style = if interval_ms == self.config.interval_ms {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            intervals = intervals.push(
+                button(text(fl!("seconds", secs = interval_ms / 1000)).size(14))
+                    .padding([4, 8])
+                    .style(style)
+                    .on_press(Message::SetInterval(interval_ms)),
+            );
+        }
+
+        let content = column![
+            column![
+                heading(fl!("cpu"), percent(sample.cpu)),
+                bar(sample.cpu),
+                cores
+            ]
+            .spacing(8),
+            column![
+                heading(
+                    fl!("memory"),
+                    fl!(
+                        "memory-used",
+                        used = format_bytes(sample.memory_used),
+                        total = format_bytes(sample.memory_total)
+                    )
+                ),
+                bar(sample.memory())
+            ]
+            .spacing(8),
+            heading(
+                fl!("network"),
+                fl!(
+                    "network-rates",
+                    rx = format_rate(sample.net_rx as u64),
+                    tx = format_rate(sample.net_tx as u64)
+                )
+            ),
+            heading(
+                fl!("disk"),
+                fl!(
+                    "disk-rates",
+                    read = format_rate(sample.disk_read as u64),
+                    write = format_rate(sample.disk_write as u64)
+                )
+            ),
+            container(divider::horizontal::light()).width(Length::Fill),
+            text(fl!("top-processes")).size(14),
+            processes,
+            container(divider::horizontal::light()).width(Length::Fill),
+            text(fl!("panel-graphs")).size(14),
+            panel_toggles,
+            intervals,
+        ]
+        .spacing(12)
+        .padding([8, 24]);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            samples(self.config.interval_ms).map(Message::Sample),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
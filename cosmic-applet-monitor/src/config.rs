@@ -0,0 +1,66 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletMonitor";
+
+/// Sampling intervals offered in the popup, in milliseconds
+pub const INTERVALS: [u64; 3] = [1000, 2000, 5000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Network,
+    Disk,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 4] = [
+        Resource::Cpu,
+        Resource::Memory,
+        Resource::Network,
+        Resource::Disk,
+    ];
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct MonitorAppletConfig {
+    /// Time between samples in milliseconds
+    pub interval_ms: u64,
+    /// Resources with a sparkline in the panel, in this order
+    pub panel_resources: Vec<Resource>,
+}
+
+impl Default for MonitorAppletConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 2000,
+            panel_resources: vec![Resource::Cpu, Resource::Memory],
+        }
+    }
+}
+
+impl MonitorAppletConfig {
+    pub fn load() -> MonitorAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| MonitorAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_interval_ms(&mut self, interval_ms: u64, config: &Config) {
+        self.interval_ms = interval_ms;
+        let _ = self.write_entry(config);
+    }
+
+    /// Shows or hides the resource's sparkline, keeping the order of [`Resource::ALL`]
+    pub fn set_in_panel(&mut self, resource: Resource, in_panel: bool, config: &Config) {
+        self.panel_resources.retain(|r| *r != resource);
+        if in_panel {
+            self.panel_resources.push(resource);
+            self.panel_resources
+                .sort_by_key(|r| Resource::ALL.iter().position(|a| a == r));
+        }
+        let _ = self.write_entry(config);
+    }
+}
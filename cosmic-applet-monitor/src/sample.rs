@@ -0,0 +1,266 @@
+//! Samples of the system load, read from procfs
+
+use cosmic::iced::{self, futures::SinkExt, subscription};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Processes listed in the popup
+const TOP_PROCESSES: usize = 5;
+/// Sector size used by `/proc/diskstats`, whatever the device's real one
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Debug, Clone, Default)]
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+    /// Share of one core, like `top` shows it
+    pub cpu: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sample {
+    /// Usage of all cores together, from 0 to 1
+    pub cpu: f32,
+    /// Usage of each core, from 0 to 1
+    pub cores: Vec<f32>,
+    /// In bytes
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// In bytes per second
+    pub net_rx: f64,
+    pub net_tx: f64,
+    pub disk_read: f64,
+    pub disk_write: f64,
+    /// The busiest processes, busiest first
+    pub processes: Vec<Process>,
+}
+
+impl Sample {
+    pub fn memory(&self) -> f32 {
+        if self.memory_total == 0 {
+            0.0
+        } else {
+            self.memory_used as f32 / self.memory_total as f32
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    fn usage_since(&self, prev: &CpuTimes) -> f32 {
+        let total = self.total.saturating_sub(prev.total);
+        if total == 0 {
+            0.0
+        } else {
+            self.busy.saturating_sub(prev.busy) as f32 / total as f32
+        }
+    }
+}
+
+/// Keeps the counters of the previous sample, as procfs only has totals since boot
+#[derive(Debug, Default)]
+pub struct Sampler {
+    cpu: CpuTimes,
+    cores: Vec<CpuTimes>,
+    net: (u64, u64),
+    disk: (u64, u64),
+    processes: HashMap<u32, u64>,
+    at: Option<Instant>,
+}
+
+impl Sampler {
+    /// The first sample only has rates of zero, there is nothing to compare with yet
+    pub fn sample(&mut self) -> Sample {
+        let now = Instant::now();
+        let secs = self
+            .at
+            .map_or(0.0, |at| now.duration_since(at).as_secs_f64());
+        let first = self.at.is_none();
+        self.at = Some(now);
+
+        let mut sample = Sample::default();
+        let (cpu, cores) = read_cpu_times();
+        sample.cpu = cpu.usage_since(&self.cpu);
+        sample.cores = cores
+            .iter()
+            .enumerate()
+            .map(|(i, core)| core.usage_since(&self.cores.get(i).copied().unwrap_or_default()))
+            .collect();
+        let ticks = cpu.total.saturating_sub(self.cpu.total);
+        let core_count = cores.len().max(1);
+        self.cpu = cpu;
+        self.cores = cores;
+
+        (sample.memory_used, sample.memory_total) = read_memory();
+
+        let net = read_net();
+        let disk = read_disk();
+        if !first && secs > 0.0 {
+            sample.net_rx = net.0.saturating_sub(self.net.0) as f64 / secs;
+            sample.net_tx = net.1.saturating_sub(self.net.1) as f64 / secs;
+            sample.disk_read = disk.0.saturating_sub(self.disk.0) as f64 / secs;
+            sample.disk_write = disk.1.saturating_sub(self.disk.1) as f64 / secs;
+        }
+        self.net = net;
+        self.disk = disk;
+
+        let processes = read_processes();
+        if !first && ticks > 0 {
+            // ticks of all cores together, so a single busy core is 1 / core_count of them
+            let core_ticks = ticks as f32 / core_count as f32;
+            let mut top: Vec<_> = processes
+                .iter()
+                .filter_map(|(pid, (name, t))| {
+                    let prev = self.processes.get(pid)?;
+                    Some(Process {
+                        pid: *pid,
+                        name: name.clone(),
+                        cpu: t.saturating_sub(*prev) as f32 / core_ticks,
+                    })
+                })
+                .filter(|p| p.cpu > 0.0)
+                .collect();
+            top.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+            top.truncate(TOP_PROCESSES);
+            sample.processes = top;
+        }
+        self.processes = processes
+            .into_iter()
+            .map(|(pid, (_, t))| (pid, t))
+            .collect();
+
+        sample
+    }
+}
+
+/// The aggregate and the per-core times of `/proc/stat`
+fn read_cpu_times() -> (CpuTimes, Vec<CpuTimes>) {
+    let Ok(stat) = fs::read_to_string("/proc/stat") else {
+        return Default::default();
+    };
+    let mut total = CpuTimes::default();
+    let mut cores = Vec::new();
+    for line in stat.lines().filter(|l| l.starts_with("cpu")) {
+        let mut fields = line.split_whitespace();
+        let name = fields.next().unwrap_or_default();
+        let values: Vec<u64> = fields.filter_map(|f| f.parse().ok()).collect();
+        // user nice system idle iowait irq softirq steal, guest time is already in user
+        let all: u64 = values.iter().take(8).sum();
+        let idle =
+            values.get(3).copied().unwrap_or_default() + values.get(4).copied().unwrap_or_default();
+        let times = CpuTimes {
+            busy: all.saturating_sub(idle),
+            total: all,
+        };
+        if name == "cpu" {
+            total = times;
+        } else {
+            cores.push(times);
+        }
+    }
+    (total, cores)
+}
+
+/// Used and total memory in bytes, what is available to programs counts as free
+fn read_memory() -> (u64, u64) {
+    let Ok(meminfo) = fs::read_to_string("/proc/meminfo") else {
+        return (0, 0);
+    };
+    let field = |key: &str| {
+        meminfo
+            .lines()
+            .find_map(|l| l.strip_prefix(key))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .unwrap_or_default()
+            * 1024
+    };
+    let total = field("MemTotal:");
+    (total.saturating_sub(field("MemAvailable:")), total)
+}
+
+/// Received and sent bytes of the interfaces backed by a device, the traffic of tunnels and
+/// bridges is already counted on the physical link
+fn read_net() -> (u64, u64) {
+    let Ok(entries) = fs::read_dir("/sys/class/net") else {
+        return (0, 0);
+    };
+    let read = |path: &Path, counter: &str| {
+        fs::read_to_string(path.join("statistics").join(counter))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("device").exists())
+        .fold((0, 0), |(rx, tx), path| {
+            (rx + read(&path, "rx_bytes"), tx + read(&path, "tx_bytes"))
+        })
+}
+
+/// Read and written bytes of whole disks, partitions would count twice
+fn read_disk() -> (u64, u64) {
+    let Ok(stats) = fs::read_to_string("/proc/diskstats") else {
+        return (0, 0);
+    };
+    stats
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            let name = *fields.get(2)?;
+            if name.starts_with("loop")
+                || name.starts_with("ram")
+                || !Path::new("/sys/block").join(name).exists()
+            {
+                return None;
+            }
+            let read: u64 = fields.get(5)?.parse().ok()?;
+            let written: u64 = fields.get(9)?.parse().ok()?;
+            Some((read * SECTOR_SIZE, written * SECTOR_SIZE))
+        })
+        .fold((0, 0), |(r, w), (read, written)| (r + read, w + written))
+}
+
+/// Name and CPU ticks of every process
+fn read_processes() -> HashMap<u32, (String, u64)> {
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return HashMap::new();
+    };
+    dir.filter_map(|entry| {
+        let pid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+        let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        // the name may contain spaces and parentheses itself
+        let (start, end) = (stat.find('(')?, stat.rfind(')')?);
+        let name = stat.get(start + 1..end)?.to_string();
+        let fields: Vec<&str> = stat.get(end + 1..)?.split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        Some((pid, (name, utime + stime)))
+    })
+    .collect()
+}
+
+/// Samples in a background task, every interval
+pub fn samples(interval_ms: u64) -> iced::Subscription<Sample> {
+    struct Samples;
+
+    subscription::channel(
+        (std::any::TypeId::of::<Samples>(), interval_ms),
+        1,
+        move |mut output| async move {
+            let mut sampler = Sampler::default();
+            loop {
+                _ = output.send(sampler.sample()).await;
+                tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+            }
+        },
+    )
+}
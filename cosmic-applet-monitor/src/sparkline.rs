@@ -0,0 +1,52 @@
+use cosmic::iced::widget::svg;
+use std::collections::VecDeque;
+
+/// Samples kept for the sparklines
+pub const HISTORY_LEN: usize = 30;
+
+const WIDTH: f32 = 32.0;
+const HEIGHT: f32 = 16.0;
+
+/// The latest samples of one resource
+#[derive(Debug, Clone, Default)]
+pub struct History(VecDeque<f32>);
+
+impl History {
+    pub fn push(&mut self, value: f32) {
+        if self.0.len() == HISTORY_LEN {
+            self.0.pop_front();
+        }
+        self.0.push_back(value);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn max(&self) -> f32 {
+        self.0.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// A filled line graph of the history scaled to `max`, right-aligned so the latest sample is
+/// at the edge
+pub fn sparkline(history: &History, max: f32) -> svg::Handle {
+    let step = WIDTH / (HISTORY_LEN - 1) as f32;
+    let start = WIDTH - step * history.0.len().saturating_sub(1) as f32;
+    let mut points = String::new();
+    for (i, value) in history.0.iter().enumerate() {
+        let y = HEIGHT - 1.0 - (value / max).clamp(0.0, 1.0) * (HEIGHT - 2.0);
+        points.push_str(&format!("{:.1},{:.1} ", start + i as f32 * step, y));
+    }
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}"
+ viewBox="0 0 {WIDTH} {HEIGHT}">
+<rect x="0.5" y="0.5" width="{w}" height="{h}" rx="2" fill="none" stroke="#000" opacity="0.3"/>
+<polygon points="{start:.1},{HEIGHT} {points}{WIDTH},{HEIGHT}" fill="#000" opacity="0.3"/>
+<polyline points="{points}" fill="none" stroke="#000" stroke-width="1.5" stroke-linejoin="round"/>
+</svg>"#,
+        w = WIDTH - 1.0,
+        h = HEIGHT - 1.0,
+    );
+    svg::Handle::from_memory(svg.into_bytes())
+}
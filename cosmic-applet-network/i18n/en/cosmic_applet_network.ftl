@@ -16,3 +16,67 @@ enter-password = Enter the password or encryption key
 router-wps-button = You can also connect by pressing the "WPS" button on the router
 unable-to-connect = Unable to connect to network
 check-wifi-connection = Make sure Wi-Fi is connected to the internet and the password is correct
+
+data-usage-today = Today
+data-usage-month = This month
+details = Details
+ipv4-gateway = IPv4 Gateway
+ipv6-gateway = IPv6 Gateway
+dns-servers = DNS Servers
+link-speed = Link Speed
+uuid = UUID
+metered-connection = Metered connection
+metered-badge = Metered
+show-throughput = Show network speed in panel
+known-networks = Known Networks
+autoconnect = Connect automatically
+forget = Forget
+forget-network-question = Forget { $name }? Its password and settings will be removed.
+known-networks-order = Networks higher in the list are preferred
+full-duplex = full duplex
+half-duplex = half duplex
+cable-unplugged = Cable unplugged
+disconnected = Disconnected
+wired-interface = Wired ({ $interface })
+ipv6-configured = IPv6 is configured on this connection
+ipv6-not-configured = IPv6 is not configured on this connection
+advanced = Advanced
+dns-over-tls = DNS over TLS
+dns-resolver = Resolver
+import-profile = Import VPN or network profile…
+vpn-and-8021x-profiles = VPN and 802.1x profiles
+import-drop-hint = Or drop an .ovpn, WireGuard .conf, or .eap-config file here
+importing = Importing profile…
+import-success = Profile imported
+import-failure = Unable to import profile
+enter-credentials = Enter the identity and password for this network
+identity = Identity
+password = Password
+import = Import
+frequency-band = Band
+preferred-band = Preferred band
+band-any = Any
+band-2-4-ghz = 2.4 GHz
+band-5-ghz = 5 GHz
+band-6-ghz = 6 GHz
+stay-on-access-point = Stay on this access point
+proxy = Proxy
+proxy-scope = Used by applications that follow the GNOME proxy settings
+proxy-direct = Direct connection
+proxy-failed = Unable to update the proxy settings
+proxy-add = Add proxy profile…
+proxy-name = Profile name
+proxy-manual = Manual
+proxy-auto = Automatic (PAC)
+proxy-host = Host
+proxy-port = Port
+proxy-pac-url = Configuration URL
+save = Save
+tailscale = Tailscale
+tailscale-connected = Connected
+tailscale-disconnected = Disconnected
+tailscale-starting = Connecting…
+tailscale-needs-login = Sign in with the Tailscale client to connect
+tailscale-exit-node = Exit node: { $node }
+tailscale-no-exit-node = No exit node
+tailscale-toggle-failed = Unable to change the Tailscale state. Is your user set as the operator?
@@ -1,10 +1,17 @@
-use cosmic::app::Command;
+use ashpd::desktop::file_chooser::{FileFilter, SelectedFiles};
+use cctk::wayland_client::protocol::wl_data_device_manager::DndAction;
+use cosmic::app::{
+    applet::{applet_button_theme, cosmic_panel_config::PanelAnchor},
+    Command,
+};
+use cosmic::cosmic_config::{self, Config};
 use cosmic::iced_style;
 use cosmic::iced_widget::Row;
 use cosmic::{
     iced::{
+        self, clipboard, time,
         wayland::popup::{destroy_popup, get_popup},
-        widget::{column, container, row, scrollable, text, text_input, Column},
+        widget::{column, container, dnd_listener, row, scrollable, text, text_input, Column},
         Alignment, Length, Subscription,
     },
     iced_runtime::core::{
@@ -12,23 +19,41 @@ use cosmic::{
         layout::Limits,
         window,
     },
+    iced_sctk::commands::data_device::{
+        accept_mime_type, finish_dnd, request_dnd_data, set_actions,
+    },
     iced_style::{application, button::StyleSheet},
     theme::{Button, Svg},
-    widget::{button, divider, icon},
+    widget::{button, divider, icon, toggler},
     Element, Theme,
 };
+use cosmic_applet_common::format::{format_bytes, format_compact, format_rate};
 use cosmic_dbus_networkmanager::interface::enums::{ActiveConnectionState, DeviceState};
 use cosmic_time::{anim, chain, id, once_cell::sync::Lazy, Instant, Timeline};
 
 use futures::channel::mpsc::UnboundedSender;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
 use zbus::Connection;
 
 use crate::network_manager::active_conns::active_conns_subscription;
+use crate::network_manager::data_usage::{data_usage_subscription, DataUsage};
 use crate::network_manager::devices::devices_subscription;
+use crate::network_manager::import::ProfileKind;
+use crate::network_manager::resolved::DnsPreset;
+use crate::network_manager::saved_conns::WifiBand;
+use crate::network_manager::throughput::ThroughputSampler;
 use crate::network_manager::wireless_enabled::wireless_enabled_subscription;
 use crate::network_manager::NetworkManagerState;
+use crate::proxy::{self, ProxyMode, ProxyProfile};
+#[cfg(feature = "tailscale")]
+use crate::tailscale::{self, TailscaleStatus};
 use crate::{
-    config, fl,
+    config::{self, NetworkAppletConfig},
+    fl,
     network_manager::{
         available_wifi::AccessPoint, current_networks::ActiveConnectionInfo,
         network_manager_subscription, NetworkManagerEvent, NetworkManagerRequest,
@@ -76,6 +101,70 @@ impl Into<AccessPoint> for NewConnectionState {
     }
 }
 
+#[derive(Debug)]
+enum ImportState {
+    EnterCredentials {
+        path: PathBuf,
+        identity: String,
+        password: String,
+    },
+    Waiting,
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProxyField {
+    Name,
+    Host,
+    Port,
+    Url,
+}
+
+/// A proxy profile being created in the popup
+#[derive(Debug, Default)]
+struct ProxyForm {
+    name: String,
+    /// PAC URL instead of a manual host and port
+    auto: bool,
+    host: String,
+    port: String,
+    url: String,
+}
+
+impl ProxyForm {
+    fn profile(&self) -> Option<ProxyProfile> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let mode = if self.auto {
+            let url = self.url.trim();
+            if url.is_empty() {
+                return None;
+            }
+            ProxyMode::Auto {
+                url: url.to_string(),
+            }
+        } else {
+            let host = self.host.trim();
+            if host.is_empty() {
+                return None;
+            }
+            ProxyMode::Manual {
+                host: host.to_string(),
+                port: self.port.trim().parse().ok()?,
+            }
+        };
+        Some(ProxyProfile {
+            name: name.to_string(),
+            mode,
+        })
+    }
+}
+
+static DND_MIME_TYPE: &str = "text/uri-list";
+
 static WIFI: Lazy<id::Toggler> = Lazy::new(id::Toggler::unique);
 static AIRPLANE_MODE: Lazy<id::Toggler> = Lazy::new(id::Toggler::unique);
 
@@ -86,15 +175,52 @@ struct CosmicNetworkApplet {
     popup: Option<window::Id>,
     id_ctr: u128,
     nm_state: NetworkManagerState,
+    data_usage: DataUsage,
+    config: NetworkAppletConfig,
+    throughput: ThroughputSampler,
     // UI state
     nm_sender: Option<UnboundedSender<NetworkManagerRequest>>,
     show_visible_networks: bool,
     new_connection: Option<NewConnectionState>,
+    expanded_details: Option<String>,
+    show_saved_networks: bool,
+    forget_confirm: Option<String>,
+    show_advanced: bool,
+    dns_preset: DnsPreset,
+    import: Option<ImportState>,
+    import_dnd_path: Option<PathBuf>,
+    proxy_form: Option<ProxyForm>,
+    proxy_failed: bool,
+    #[cfg(feature = "tailscale")]
+    tailscale: Option<TailscaleStatus>,
+    #[cfg(feature = "tailscale")]
+    tailscale_failed: bool,
     conn: Option<Connection>,
     timeline: Timeline,
     toggle_wifi_ctr: u128,
 }
 
+fn button_style() -> Button {
+    Button::Custom {
+        active: Box::new(|t| iced_style::button::Appearance {
+            border_radius: 0.0.into(),
+            ..t.active(&Button::Text)
+        }),
+        hover: Box::new(|t| iced_style::button::Appearance {
+            border_radius: 0.0.into(),
+            ..t.hovered(&Button::Text)
+        }),
+    }
+}
+
+fn band_name(frequency: u32) -> String {
+    match frequency {
+        ..=2500 => fl!("band-2-4-ghz"),
+        5925.. => fl!("band-6-ghz"),
+        _ => fl!("band-5-ghz"),
+    }
+}
+
 fn wifi_icon(strength: u8) -> &'static str {
     if strength < 25 {
         "network-wireless-signal-weak-symbolic"
@@ -162,6 +288,393 @@ impl CosmicNetworkApplet {
             timeline.start();
         }
     }
+
+    fn data_usage_text(&self, name: &str) -> Option<Element<Message>> {
+        let usage = self.data_usage.get(name)?;
+        let today = usage.today();
+        let month = usage.this_month();
+        Some(
+            column![
+                text(format!(
+                    "{}: ↓ {} ↑ {}",
+                    fl!("data-usage-today"),
+                    format_bytes(today.rx),
+                    format_bytes(today.tx)
+                ))
+                .size(10),
+                text(format!(
+                    "{}: ↓ {} ↑ {}",
+                    fl!("data-usage-month"),
+                    format_bytes(month.rx),
+                    format_bytes(month.tx)
+                ))
+                .size(10),
+            ]
+            .into(),
+        )
+    }
+
+    fn link_speed_text(&self, speed: u32, hw_address: &str) -> String {
+        let speed = format!("{speed} {}", fl!("megabits-per-second"));
+        match self
+            .nm_state
+            .wired_devices
+            .iter()
+            .find(|d| d.hw_address == hw_address)
+            .and_then(|d| d.duplex.as_deref())
+        {
+            Some("full") => format!("{speed}, {}", fl!("full-duplex")),
+            Some("half") => format!("{speed}, {}", fl!("half-duplex")),
+            _ => speed,
+        }
+    }
+
+    fn wired_devices(&self) -> Element<Message> {
+        let mut col = column![].spacing(8);
+        for device in &self.nm_state.wired_devices {
+            let status = if !device.carrier {
+                fl!("cable-unplugged")
+            } else if device.is_enabled() {
+                self.link_speed_text(device.speed, &device.hw_address)
+            } else {
+                fl!("disconnected")
+            };
+            let interface = device.interface.clone();
+            col = col.push(
+                column![
+                    toggler(
+                        fl!("wired-interface", interface = device.interface.as_str()),
+                        device.is_enabled(),
+                        move |enable| Message::ToggleWired(interface.clone(), enable),
+                    )
+                    .text_size(14)
+                    .width(Length::Fill),
+                    text(status).size(12),
+                ]
+                .spacing(4),
+            );
+        }
+        container(col).padding([0, 12]).into()
+    }
+
+    #[cfg(feature = "tailscale")]
+    fn tailscale_view(&self) -> Option<Element<Message>> {
+        let status = self.tailscale.as_ref()?;
+        let state = match status.backend_state.as_str() {
+            "Running" => fl!("tailscale-connected"),
+            "NeedsLogin" | "NeedsMachineAuth" => fl!("tailscale-needs-login"),
+            "Starting" => fl!("tailscale-starting"),
+            _ => fl!("tailscale-disconnected"),
+        };
+        let mut col = column![
+            toggler(fl!("tailscale"), status.is_running(), |running| {
+                Message::ToggleTailscale(running)
+            })
+            .text_size(14)
+            .width(Length::Fill),
+            text(state).size(12),
+        ]
+        .spacing(4);
+        if status.is_running() {
+            if let Some(ip) = status.ips.first() {
+                col = col.push(text(format!("{} · {}", status.host_name, ip)).size(12));
+            }
+            col = col.push(
+                text(match status.exit_node.as_ref() {
+                    Some(node) => fl!("tailscale-exit-node", node = node.as_str()),
+                    None => fl!("tailscale-no-exit-node"),
+                })
+                .size(12),
+            );
+        }
+        if self.tailscale_failed {
+            col = col.push(text(fl!("tailscale-toggle-failed")).size(12));
+        }
+        Some(container(col).padding([0, 12]).into())
+    }
+
+    fn proxy_view(&self) -> Element<Message> {
+        let active = self.config.active_proxy_profile.as_ref();
+        let profile_button = |label: String, name: Option<String>| {
+            let selected = active == name.as_ref();
+            button(button_style())
+                .custom(vec![row![
+                    text(label).size(14).width(Length::Fill),
+                    icon("emblem-ok-symbolic", 12).size(12).style(if selected {
+                        Svg::SymbolicActive
+                    } else {
+                        Svg::Default
+                    }),
+                ]
+                .align_items(Alignment::Center)
+                .into()])
+                .padding([4, 12])
+                .width(Length::Fill)
+                .on_press(Message::SelectProxyProfile(name))
+        };
+        let mut col = column![
+            text(fl!("proxy")).size(12),
+            text(fl!("proxy-scope")).size(10),
+            profile_button(fl!("proxy-direct"), None)
+        ]
+        .spacing(4);
+        for profile in &self.config.proxy_profiles {
+            col = col.push(
+                row![
+                    profile_button(profile.name.clone(), Some(profile.name.clone())),
+                    button(Button::Text)
+                        .custom(vec![icon("user-trash-symbolic", 16)
+                            .style(Svg::Symbolic)
+                            .into()])
+                        .padding(4)
+                        .on_press(Message::RemoveProxyProfile(profile.name.clone())),
+                ]
+                .align_items(Alignment::Center),
+            );
+        }
+        if self.proxy_failed {
+            col = col.push(text(fl!("proxy-failed")).size(12));
+        }
+        if let Some(form) = self.proxy_form.as_ref() {
+            let kind = row![
+                button(if form.auto {
+                    Button::Secondary
+                } else {
+                    Button::Primary
+                })
+                .custom(vec![text(fl!("proxy-manual")).size(12).into()])
+                .padding([4, 12])
+                .on_press(Message::ProxyFormAuto(false)),
+                button(if form.auto {
+                    Button::Primary
+                } else {
+                    Button::Secondary
+                })
+                .custom(vec![text(fl!("proxy-auto")).size(12).into()])
+                .padding([4, 12])
+                .on_press(Message::ProxyFormAuto(true)),
+            ]
+            .spacing(8);
+            let mut form_col = column![
+                text_input(&fl!("proxy-name"), &form.name)
+                    .on_input(|v| Message::ProxyFormInput(ProxyField::Name, v)),
+                kind,
+            ]
+            .spacing(8);
+            form_col = if form.auto {
+                form_col.push(
+                    text_input(&fl!("proxy-pac-url"), &form.url)
+                        .on_input(|v| Message::ProxyFormInput(ProxyField::Url, v))
+                        .on_submit(Message::SaveProxyProfile),
+                )
+            } else {
+                form_col.push(
+                    row![
+                        text_input(&fl!("proxy-host"), &form.host)
+                            .on_input(|v| Message::ProxyFormInput(ProxyField::Host, v))
+                            .width(Length::FillPortion(3)),
+                        text_input(&fl!("proxy-port"), &form.port)
+                            .on_input(|v| Message::ProxyFormInput(ProxyField::Port, v))
+                            .on_submit(Message::SaveProxyProfile)
+                            .width(Length::FillPortion(1)),
+                    ]
+                    .spacing(8),
+                )
+            };
+            let mut save = button(Button::Secondary)
+                .custom(vec![container(text(fl!("save"))).padding([0, 24]).into()]);
+            if form.profile().is_some() {
+                save = save.on_press(Message::SaveProxyProfile);
+            }
+            form_col = form_col.push(
+                row![
+                    button(Button::Secondary)
+                        .custom(vec![container(text(fl!("cancel"))).padding([0, 24]).into()])
+                        .on_press(Message::CancelProxyProfile),
+                    save,
+                ]
+                .spacing(24),
+            );
+            col = col.push(form_col);
+        } else {
+            col = col.push(
+                button(Button::Text)
+                    .custom(vec![text(fl!("proxy-add")).size(14).into()])
+                    .padding([4, 12])
+                    .on_press(Message::NewProxyProfile),
+            );
+        }
+        container(col).width(Length::Fill).padding([0, 24]).into()
+    }
+
+    fn connection_details(&self, conn: &ActiveConnectionInfo) -> Element<Message> {
+        let name = conn.name();
+        let expanded = self.expanded_details.as_ref() == Some(&name);
+        let dropdown_icon = if expanded {
+            "go-down-symbolic"
+        } else {
+            "go-next-symbolic"
+        };
+        let toggle = button(Button::Text)
+            .custom(vec![row![
+                text(fl!("details"))
+                    .size(12)
+                    .width(Length::Fill)
+                    .vertical_alignment(Vertical::Center),
+                icon(dropdown_icon, 12).style(Svg::Symbolic),
+            ]
+            .align_items(Alignment::Center)
+            .into()])
+            .padding([4, 24])
+            .width(Length::Fill)
+            .on_press(Message::ToggleConnectionDetails(name));
+        if !expanded {
+            return toggle.into();
+        }
+
+        let details = conn.details();
+        let mut fields = Vec::new();
+        let (ip_addresses, hw_address, speed) = match conn {
+            ActiveConnectionInfo::Wired {
+                ip_addresses,
+                hw_address,
+                speed,
+                ..
+            } => (ip_addresses, Some(hw_address), Some(*speed)),
+            ActiveConnectionInfo::WiFi {
+                ip_addresses,
+                hw_address,
+                bitrate,
+                frequency,
+                ..
+            } => {
+                if *frequency > 0 {
+                    fields.push((fl!("frequency-band"), band_name(*frequency)));
+                }
+                (ip_addresses, Some(hw_address), Some(*bitrate / 1000))
+            }
+            ActiveConnectionInfo::Vpn { ip_addresses, .. } => (ip_addresses, None, None),
+        };
+        for addr in ip_addresses {
+            fields.push((fl!("ipv4"), addr.to_string()));
+        }
+        for addr in &details.ipv6_addresses {
+            fields.push((fl!("ipv6"), addr.to_string()));
+        }
+        if let Some(gateway) = details.ipv4_gateway.as_ref() {
+            fields.push((fl!("ipv4-gateway"), gateway.clone()));
+        }
+        if let Some(gateway) = details.ipv6_gateway.as_ref() {
+            fields.push((fl!("ipv6-gateway"), gateway.clone()));
+        }
+        if !details.dns_servers.is_empty() {
+            let dns = details
+                .dns_servers
+                .iter()
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            fields.push((fl!("dns-servers"), dns));
+        }
+        if let Some(hw_address) = hw_address {
+            fields.push((fl!("mac"), hw_address.clone()));
+        }
+        if let Some(speed) = speed {
+            fields.push((
+                fl!("link-speed"),
+                format!("{speed} {}", fl!("megabits-per-second")),
+            ));
+        }
+        if !details.uuid.is_empty() {
+            fields.push((fl!("uuid"), details.uuid.clone()));
+        }
+
+        let rows = fields
+            .into_iter()
+            .map(|(label, value)| {
+                row![
+                    column![text(label).size(10), text(value.clone()).size(12)].width(Length::Fill),
+                    button(Button::Text)
+                        .custom(vec![icon("edit-copy-symbolic", 16)
+                            .style(Svg::Symbolic)
+                            .into()])
+                        .padding(4)
+                        .on_press(Message::CopyToClipboard(value)),
+                ]
+                .align_items(Alignment::Center)
+                .spacing(8)
+                .into()
+            })
+            .collect();
+        let saved = self
+            .nm_state
+            .saved_conns
+            .iter()
+            .find(|c| c.id == conn.name());
+        let ipv6_status = if details.ipv6_addresses.is_empty() {
+            fl!("ipv6-not-configured")
+        } else {
+            fl!("ipv6-configured")
+        };
+        let ipv6_id = conn.name();
+        let ipv6 = toggler(
+            fl!("ipv6"),
+            saved.map_or(true, |c| c.ipv6_enabled()),
+            move |enabled| Message::SetIpv6(ipv6_id.clone(), enabled),
+        )
+        .text_size(12)
+        .width(Length::Fill);
+        let id = conn.name();
+        let metered = toggler(
+            fl!("metered-connection"),
+            self.nm_state.is_metered(&id),
+            move |m| Message::SetMetered(id.clone(), m),
+        )
+        .text_size(12)
+        .width(Length::Fill);
+        let mut content = column![
+            toggle,
+            Column::with_children(rows).spacing(4).padding([0, 24]),
+            container(column![ipv6, text(ipv6_status).size(10)].spacing(2)).padding([0, 24]),
+            container(metered).padding([0, 24]),
+        ]
+        .spacing(4);
+        if let ActiveConnectionInfo::WiFi { bssid, .. } = conn {
+            let current_band = saved.map(|c| c.band).unwrap_or_default();
+            let mut bands = row![].spacing(8);
+            for band in WifiBand::ALL {
+                let label = match band {
+                    WifiBand::Any => fl!("band-any"),
+                    WifiBand::Bg => fl!("band-2-4-ghz"),
+                    WifiBand::A => fl!("band-5-ghz"),
+                };
+                bands = bands.push(
+                    button(if band == current_band {
+                        Button::Primary
+                    } else {
+                        Button::Secondary
+                    })
+                    .custom(vec![text(label).size(12).into()])
+                    .padding([4, 12])
+                    .on_press(Message::SetWifiBand(conn.name(), band)),
+                );
+            }
+            let id = conn.name();
+            let bssid = bssid.clone();
+            let locked = saved.map_or(false, |c| c.bssid.is_some());
+            let lock = toggler(fl!("stay-on-access-point"), locked, move |lock| {
+                Message::SetBssidLock(id.clone(), lock.then(|| bssid.clone()))
+            })
+            .text_size(12)
+            .width(Length::Fill);
+            content = content.push(
+                column![text(fl!("preferred-band")).size(12), bands, lock]
+                    .spacing(4)
+                    .padding([0, 24]),
+            );
+        }
+        content.into()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -172,6 +685,50 @@ pub(crate) enum Message {
     ToggleAirplaneMode(bool),
     ToggleWiFi(bool),
     ToggleVisibleNetworks,
+    ToggleConnectionDetails(String),
+    CopyToClipboard(String),
+    SetMetered(String, bool),
+    ToggleSavedNetworks,
+    SetAutoconnect(String, bool),
+    ConfirmForget(String),
+    CancelForget,
+    Forget(String),
+    MoveSavedConnection(String, bool),
+    ToggleWired(String, bool),
+    SetIpv6(String, bool),
+    SetWifiBand(String, WifiBand),
+    SetBssidLock(String, Option<String>),
+    ToggleAdvanced,
+    ToggleDnsOverTls(bool),
+    SelectDnsPreset(DnsPreset),
+    SelectProxyProfile(Option<String>),
+    ProxyApplied(bool),
+    NewProxyProfile,
+    ProxyFormInput(ProxyField, String),
+    ProxyFormAuto(bool),
+    SaveProxyProfile,
+    CancelProxyProfile,
+    RemoveProxyProfile(String),
+    #[cfg(feature = "tailscale")]
+    TailscaleStatus(Option<TailscaleStatus>),
+    #[cfg(feature = "tailscale")]
+    ToggleTailscale(bool),
+    #[cfg(feature = "tailscale")]
+    TailscaleToggled(bool),
+    ChooseImportFile,
+    ImportFile(Option<PathBuf>),
+    ImportIdentity(String),
+    ImportPassword(String),
+    SubmitImportCredentials,
+    CancelImport,
+    ImportDndEnter,
+    ImportDndExit,
+    ImportDndData(PathBuf),
+    ImportDndDrop,
+    Ignore,
+    ToggleThroughput(bool),
+    SampleThroughput,
+    ConfigUpdated(NetworkAppletConfig),
     NetworkManagerEvent(NetworkManagerEvent),
     SelectWirelessAccessPoint(AccessPoint),
     CancelNewConnection,
@@ -192,6 +749,7 @@ impl cosmic::Application for CosmicNetworkApplet {
             CosmicNetworkApplet {
                 core,
                 icon_name: "network-offline-symbolic".to_string(),
+                config: NetworkAppletConfig::load(),
                 ..Default::default()
             },
             Command::none(),
@@ -212,6 +770,11 @@ impl cosmic::Application for CosmicNetworkApplet {
             Message::TogglePopup => {
                 if let Some(p) = self.popup.take() {
                     self.show_visible_networks = false;
+                    self.show_saved_networks = false;
+                    self.forget_confirm = None;
+                    if !matches!(self.import, Some(ImportState::Waiting)) {
+                        self.import = None;
+                    }
                     return destroy_popup(p);
                 } else {
                     // TODO request update of state maybe
@@ -264,6 +827,9 @@ impl cosmic::Application for CosmicNetworkApplet {
                 | NetworkManagerEvent::ActiveConns(state) => {
                     self.update_nm_state(state);
                 }
+                NetworkManagerEvent::DataUsage(usage) => {
+                    self.data_usage = usage;
+                }
                 NetworkManagerEvent::RequestResponse {
                     state,
                     success,
@@ -282,6 +848,13 @@ impl cosmic::Application for CosmicNetworkApplet {
                             self.new_connection = None;
                         }
                     }
+                    if let NetworkManagerRequest::ImportProfile(..) = &req {
+                        self.import = Some(if success {
+                            ImportState::Success
+                        } else {
+                            ImportState::Failure
+                        });
+                    }
                     if !success {
                         match req {
                             NetworkManagerRequest::Password(_, _) => {
@@ -321,6 +894,321 @@ impl cosmic::Application for CosmicNetworkApplet {
                 self.new_connection.take();
                 self.show_visible_networks = !self.show_visible_networks;
             }
+            Message::ToggleConnectionDetails(name) => {
+                if self.expanded_details.as_ref() == Some(&name) {
+                    self.expanded_details = None;
+                } else {
+                    self.expanded_details = Some(name);
+                }
+            }
+            Message::CopyToClipboard(value) => {
+                return clipboard::write(value);
+            }
+            Message::SetMetered(id, metered) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::SetMetered(id, metered));
+                }
+            }
+            Message::ToggleSavedNetworks => {
+                self.forget_confirm = None;
+                self.show_saved_networks = !self.show_saved_networks;
+            }
+            Message::SetAutoconnect(uuid, autoconnect) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ =
+                        tx.unbounded_send(NetworkManagerRequest::SetAutoconnect(uuid, autoconnect));
+                }
+            }
+            Message::ConfirmForget(uuid) => {
+                self.forget_confirm = Some(uuid);
+            }
+            Message::CancelForget => {
+                self.forget_confirm = None;
+            }
+            Message::Forget(uuid) => {
+                self.forget_confirm = None;
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::Forget(uuid));
+                }
+            }
+            Message::MoveSavedConnection(uuid, up) => {
+                let saved = &mut self.nm_state.saved_conns;
+                let Some(i) = saved.iter().position(|c| c.uuid == uuid) else {
+                    return Command::none();
+                };
+                let j = if up {
+                    i.checked_sub(1)
+                } else {
+                    Some(i + 1).filter(|j| *j < saved.len())
+                };
+                let Some(j) = j else {
+                    return Command::none();
+                };
+                saved.swap(i, j);
+                // renumber so that the list order is the priority order
+                let len = saved.len() as i32;
+                let mut priorities = Vec::new();
+                for (index, c) in saved.iter_mut().enumerate() {
+                    let priority = len - index as i32;
+                    if c.priority != priority {
+                        c.priority = priority;
+                        priorities.push((c.uuid.clone(), priority));
+                    }
+                }
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::SetPriorities(priorities));
+                }
+            }
+            Message::ToggleWired(interface, enabled) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx
+                        .unbounded_send(NetworkManagerRequest::SetWiredEnabled(interface, enabled));
+                }
+            }
+            Message::SetIpv6(id, enabled) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::SetIpv6(id, enabled));
+                }
+            }
+            Message::SetWifiBand(id, band) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::SetWifiBand(id, band));
+                }
+            }
+            Message::SetBssidLock(id, bssid) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::SetBssidLock(id, bssid));
+                }
+            }
+            Message::SelectProxyProfile(name) => {
+                let mode = match name.as_ref() {
+                    Some(name) => match self.config.proxy_profiles.iter().find(|p| &p.name == name)
+                    {
+                        Some(profile) => profile.mode.clone(),
+                        None => return Command::none(),
+                    },
+                    None => ProxyMode::Direct,
+                };
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_active_proxy_profile(name, &config);
+                }
+                return iced::Command::perform(
+                    async move {
+                        match proxy::apply(mode).await {
+                            Ok(()) => true,
+                            Err(err) => {
+                                log::error!("Failed to apply proxy settings: {}", err);
+                                false
+                            }
+                        }
+                    },
+                    |success| cosmic::app::message::app(Message::ProxyApplied(success)),
+                );
+            }
+            Message::ProxyApplied(success) => {
+                self.proxy_failed = !success;
+            }
+            #[cfg(feature = "tailscale")]
+            Message::TailscaleStatus(status) => {
+                self.tailscale = status;
+            }
+            #[cfg(feature = "tailscale")]
+            Message::ToggleTailscale(running) => {
+                return iced::Command::perform(
+                    async move {
+                        match tailscale::set_running(running).await {
+                            Ok(()) => true,
+                            Err(err) => {
+                                log::error!("Failed to toggle Tailscale: {}", err);
+                                false
+                            }
+                        }
+                    },
+                    |success| cosmic::app::message::app(Message::TailscaleToggled(success)),
+                );
+            }
+            #[cfg(feature = "tailscale")]
+            Message::TailscaleToggled(success) => {
+                self.tailscale_failed = !success;
+            }
+            Message::NewProxyProfile => {
+                self.proxy_form = Some(ProxyForm::default());
+            }
+            Message::ProxyFormInput(field, value) => {
+                if let Some(form) = self.proxy_form.as_mut() {
+                    match field {
+                        ProxyField::Name => form.name = value,
+                        ProxyField::Host => form.host = value,
+                        ProxyField::Port => form.port = value,
+                        ProxyField::Url => form.url = value,
+                    }
+                }
+            }
+            Message::ProxyFormAuto(auto) => {
+                if let Some(form) = self.proxy_form.as_mut() {
+                    form.auto = auto;
+                }
+            }
+            Message::SaveProxyProfile => {
+                let Some(profile) = self.proxy_form.as_ref().and_then(ProxyForm::profile) else {
+                    return Command::none();
+                };
+                self.proxy_form = None;
+                let mut profiles = self.config.proxy_profiles.clone();
+                // saving a profile with an existing name replaces it
+                profiles.retain(|p| p.name != profile.name);
+                profiles.push(profile);
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_proxy_profiles(profiles, &config);
+                }
+            }
+            Message::CancelProxyProfile => {
+                self.proxy_form = None;
+            }
+            Message::RemoveProxyProfile(name) => {
+                let mut profiles = self.config.proxy_profiles.clone();
+                profiles.retain(|p| p.name != name);
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_proxy_profiles(profiles, &config);
+                }
+                if self.config.active_proxy_profile.as_ref() == Some(&name) {
+                    return self.update(Message::SelectProxyProfile(None));
+                }
+            }
+            Message::ChooseImportFile => {
+                let title = fl!("import-profile");
+                let filter_name = fl!("vpn-and-8021x-profiles");
+                return iced::Command::perform(
+                    async move {
+                        let files = SelectedFiles::open_file()
+                            .title(title.as_str())
+                            .modal(false)
+                            .multiple(false)
+                            .filter(
+                                FileFilter::new(&filter_name)
+                                    .glob("*.ovpn")
+                                    .glob("*.conf")
+                                    .glob("*.eap-config"),
+                            )
+                            .send()
+                            .await
+                            .and_then(|r| r.response())
+                            .ok()?;
+                        files.uris().first()?.to_file_path().ok()
+                    },
+                    |path| cosmic::app::message::app(Message::ImportFile(path)),
+                );
+            }
+            Message::ImportFile(path) => {
+                let Some(path) = path else {
+                    return Command::none();
+                };
+                match ProfileKind::from_path(&path) {
+                    Some(kind) if kind.needs_credentials() => {
+                        self.import = Some(ImportState::EnterCredentials {
+                            path,
+                            identity: String::new(),
+                            password: String::new(),
+                        });
+                    }
+                    Some(_) => {
+                        if let Some(tx) = self.nm_sender.as_ref() {
+                            let _ =
+                                tx.unbounded_send(NetworkManagerRequest::ImportProfile(path, None));
+                            self.import = Some(ImportState::Waiting);
+                        }
+                    }
+                    None => {
+                        self.import = Some(ImportState::Failure);
+                    }
+                }
+            }
+            Message::ImportIdentity(value) => {
+                if let Some(ImportState::EnterCredentials { identity, .. }) = self.import.as_mut() {
+                    *identity = value;
+                }
+            }
+            Message::ImportPassword(value) => {
+                if let Some(ImportState::EnterCredentials { password, .. }) = self.import.as_mut() {
+                    *password = value;
+                }
+            }
+            Message::SubmitImportCredentials => {
+                let Some(tx) = self.nm_sender.as_ref() else {
+                    return Command::none();
+                };
+                if let Some(ImportState::EnterCredentials {
+                    path,
+                    identity,
+                    password,
+                }) = self.import.take()
+                {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::ImportProfile(
+                        path,
+                        Some((identity, password)),
+                    ));
+                    self.import = Some(ImportState::Waiting);
+                }
+            }
+            Message::CancelImport => {
+                self.import = None;
+            }
+            Message::ImportDndEnter => {
+                return Command::batch(vec![
+                    accept_mime_type(Some(DND_MIME_TYPE.to_string())),
+                    set_actions(DndAction::Copy, DndAction::all()),
+                    request_dnd_data(DND_MIME_TYPE.to_string()),
+                ]);
+            }
+            Message::ImportDndExit => {
+                self.import_dnd_path = None;
+                return accept_mime_type(None);
+            }
+            Message::ImportDndData(path) => {
+                self.import_dnd_path = Some(path);
+            }
+            Message::ImportDndDrop => {
+                // the data is requested on enter, so it should be here by the time of the drop
+                let path = self.import_dnd_path.take();
+                return Command::batch(vec![self.update(Message::ImportFile(path)), finish_dnd()]);
+            }
+            Message::Ignore => {}
+            Message::ToggleAdvanced => {
+                self.show_advanced = !self.show_advanced;
+            }
+            Message::ToggleDnsOverTls(enabled) => {
+                if let Some(tx) = self.nm_sender.as_ref() {
+                    let _ = tx.unbounded_send(NetworkManagerRequest::SetDnsOverTls(
+                        enabled.then_some(self.dns_preset),
+                    ));
+                }
+            }
+            Message::SelectDnsPreset(preset) => {
+                self.dns_preset = preset;
+                if self.nm_state.dns_over_tls {
+                    if let Some(tx) = self.nm_sender.as_ref() {
+                        let _ =
+                            tx.unbounded_send(NetworkManagerRequest::SetDnsOverTls(Some(preset)));
+                    }
+                }
+            }
+            Message::ToggleThroughput(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_throughput(enabled, &config);
+                }
+                if !enabled {
+                    self.throughput.reset();
+                }
+            }
+            Message::SampleThroughput => {
+                self.throughput.sample();
+            }
+            Message::ConfigUpdated(config) => {
+                if !config.show_throughput {
+                    self.throughput.reset();
+                }
+                self.config = config;
+            }
             Message::Password(entered_pw) => match &mut self.new_connection {
                 Some(NewConnectionState::EnterPassword { password, .. }) => {
                     *password = entered_pw;
@@ -391,29 +1279,76 @@ impl cosmic::Application for CosmicNetworkApplet {
     }
 
     fn view(&self) -> Element<Message> {
-        self.core
-            .applet_helper
-            .icon_button(&self.icon_name)
+        let metered = self.nm_state.metered;
+        if !metered && !self.config.show_throughput {
+            return self
+                .core
+                .applet_helper
+                .icon_button(&self.icon_name)
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+
+        let icon_size = self.core.applet_helper.suggested_size().0;
+        let horizontal = matches!(
+            self.core.applet_helper.anchor,
+            PanelAnchor::Top | PanelAnchor::Bottom
+        );
+        let mut content = vec![icon(&self.icon_name, icon_size).style(Svg::Symbolic).into()];
+        if metered {
+            content.push(text(fl!("metered-badge")).size(10).into());
+        }
+        if self.config.show_throughput {
+            let (rx, tx) = if horizontal {
+                (
+                    format_rate(self.throughput.rx_rate),
+                    format_rate(self.throughput.tx_rate),
+                )
+            } else {
+                (
+                    format_compact(self.throughput.rx_rate),
+                    format_compact(self.throughput.tx_rate),
+                )
+            };
+            content.push(
+                column![
+                    text(format!("↓ {rx}")).size(10),
+                    text(format!("↑ {tx}")).size(10)
+                ]
+                .align_items(if horizontal {
+                    Alignment::Start
+                } else {
+                    Alignment::Center
+                })
+                .into(),
+            );
+        }
+        let content: Element<Message> = if horizontal {
+            Row::with_children(content)
+                .align_items(Alignment::Center)
+                .spacing(4)
+                .into()
+        } else {
+            Column::with_children(content)
+                .align_items(Alignment::Center)
+                .spacing(2)
+                .into()
+        };
+        button(applet_button_theme())
+            .custom(vec![content])
+            .padding(8)
             .on_press(Message::TogglePopup)
             .into()
     }
 
     fn view_window(&self, _id: window::Id) -> Element<Message> {
-        let button_style = || Button::Custom {
-            active: Box::new(|t| iced_style::button::Appearance {
-                border_radius: 0.0.into(),
-                ..t.active(&Button::Text)
-            }),
-            hover: Box::new(|t| iced_style::button::Appearance {
-                border_radius: 0.0.into(),
-                ..t.hovered(&Button::Text)
-            }),
-        };
         let mut vpn_ethernet_col = column![];
         let mut known_wifi = column![];
         for conn in &self.nm_state.active_conns {
             match conn {
-                ActiveConnectionInfo::Vpn { name, ip_addresses } => {
+                ActiveConnectionInfo::Vpn {
+                    name, ip_addresses, ..
+                } => {
                     let mut ipv4 = Vec::with_capacity(ip_addresses.len());
                     for addr in ip_addresses {
                         ipv4.push(
@@ -422,14 +1357,17 @@ impl cosmic::Application for CosmicNetworkApplet {
                                 .into(),
                         );
                     }
+                    ipv4.extend(self.data_usage_text(name));
                     vpn_ethernet_col = vpn_ethernet_col
-                        .push(column![text(name), Column::with_children(ipv4)].spacing(4));
+                        .push(column![text(name), Column::with_children(ipv4)].spacing(4))
+                        .push(self.connection_details(conn));
                 }
                 ActiveConnectionInfo::Wired {
                     name,
-                    hw_address: _,
+                    hw_address,
                     speed,
                     ip_addresses,
+                    ..
                 } => {
                     let mut ipv4 = Vec::with_capacity(ip_addresses.len());
                     for addr in ip_addresses {
@@ -439,17 +1377,16 @@ impl cosmic::Application for CosmicNetworkApplet {
                                 .into(),
                         );
                     }
+                    ipv4.extend(self.data_usage_text(name));
                     vpn_ethernet_col = vpn_ethernet_col.push(
                         column![
-                            row![
-                                text(name),
-                                text(format!("{speed} {}", fl!("megabits-per-second")))
-                            ]
-                            .spacing(16),
+                            row![text(name), text(self.link_speed_text(*speed, hw_address))]
+                                .spacing(16),
                             Column::with_children(ipv4),
                         ]
                         .spacing(4),
                     );
+                    vpn_ethernet_col = vpn_ethernet_col.push(self.connection_details(conn));
                 }
                 ActiveConnectionInfo::WiFi {
                     name,
@@ -466,6 +1403,7 @@ impl cosmic::Application for CosmicNetworkApplet {
                                 .into(),
                         );
                     }
+                    ipv4.extend(self.data_usage_text(name));
                     let mut btn_content = vec![
                         icon(wifi_icon(*strength), 24).style(Svg::Symbolic).into(),
                         column![text(name).size(14), Column::with_children(ipv4)]
@@ -500,10 +1438,22 @@ impl cosmic::Application for CosmicNetworkApplet {
                             .on_press(Message::Disconnect(name.clone()))]
                         .align_items(Alignment::Center),
                     );
+                    known_wifi = known_wifi.push(self.connection_details(conn));
                 }
             };
         }
 
+        if !self.nm_state.wired_devices.is_empty() {
+            vpn_ethernet_col = vpn_ethernet_col
+                .push(self.wired_devices())
+                .push(divider::horizontal::light());
+        }
+        #[cfg(feature = "tailscale")]
+        if let Some(tailscale) = self.tailscale_view() {
+            vpn_ethernet_col = vpn_ethernet_col
+                .push(tailscale)
+                .push(divider::horizontal::light());
+        }
         let mut content = column![
             vpn_ethernet_col,
             container(
@@ -534,6 +1484,17 @@ impl cosmic::Application for CosmicNetworkApplet {
             )
             .padding([0, 12]),
             divider::horizontal::light(),
+            container(
+                toggler(
+                    fl!("show-throughput"),
+                    self.config.show_throughput,
+                    |enable| { Message::ToggleThroughput(enable) }
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 12]),
+            divider::horizontal::light(),
         ]
         .align_items(Alignment::Center)
         .spacing(8)
@@ -755,6 +1716,259 @@ impl cosmic::Application for CosmicNetworkApplet {
                     .push(scrollable(Column::with_children(list_col)).height(Length::Fixed(300.0)));
             }
         }
+        if !self.nm_state.airplane_mode {
+            let dropdown_icon = if self.show_saved_networks {
+                "go-down-symbolic"
+            } else {
+                "go-next-symbolic"
+            };
+            let saved_networks_btn = button(Button::Secondary)
+                .custom(
+                    vec![
+                        text(fl!("known-networks"))
+                            .size(14)
+                            .width(Length::Fill)
+                            .height(Length::Fixed(24.0))
+                            .vertical_alignment(Vertical::Center)
+                            .into(),
+                        container(icon(dropdown_icon, 14).style(Svg::Symbolic))
+                            .align_x(Horizontal::Center)
+                            .align_y(Vertical::Center)
+                            .width(Length::Fixed(24.0))
+                            .height(Length::Fixed(24.0))
+                            .into(),
+                    ]
+                    .into(),
+                )
+                .padding([8, 24])
+                .style(button_style())
+                .on_press(Message::ToggleSavedNetworks);
+            content = content.push(saved_networks_btn);
+        }
+        if !self.nm_state.airplane_mode {
+            let dropdown_icon = if self.show_advanced {
+                "go-down-symbolic"
+            } else {
+                "go-next-symbolic"
+            };
+            content = content.push(
+                button(Button::Secondary)
+                    .custom(
+                        vec![
+                            text(fl!("advanced"))
+                                .size(14)
+                                .width(Length::Fill)
+                                .height(Length::Fixed(24.0))
+                                .vertical_alignment(Vertical::Center)
+                                .into(),
+                            container(icon(dropdown_icon, 14).style(Svg::Symbolic))
+                                .align_x(Horizontal::Center)
+                                .align_y(Vertical::Center)
+                                .width(Length::Fixed(24.0))
+                                .height(Length::Fixed(24.0))
+                                .into(),
+                        ]
+                        .into(),
+                    )
+                    .padding([8, 24])
+                    .style(button_style())
+                    .on_press(Message::ToggleAdvanced),
+            );
+        }
+        if self.show_advanced && !self.nm_state.airplane_mode {
+            let mut presets = column![text(fl!("dns-resolver")).size(12)].spacing(4);
+            for preset in DnsPreset::ALL {
+                presets = presets.push(
+                    button(button_style())
+                        .custom(vec![row![
+                            text(preset.name()).size(14).width(Length::Fill),
+                            icon("emblem-ok-symbolic", 12).size(12).style(
+                                if preset == self.dns_preset {
+                                    Svg::SymbolicActive
+                                } else {
+                                    Svg::Default
+                                }
+                            ),
+                        ]
+                        .align_items(Alignment::Center)
+                        .into()])
+                        .padding([4, 12])
+                        .width(Length::Fill)
+                        .on_press(Message::SelectDnsPreset(preset)),
+                );
+            }
+            content = content.push(
+                column![
+                    toggler(fl!("dns-over-tls"), self.nm_state.dns_over_tls, |enabled| {
+                        Message::ToggleDnsOverTls(enabled)
+                    })
+                    .text_size(14)
+                    .width(Length::Fill),
+                    presets,
+                ]
+                .spacing(8)
+                .padding([0, 24]),
+            );
+            let import: Element<_> = match self.import.as_ref() {
+                Some(ImportState::EnterCredentials {
+                    identity, password, ..
+                }) => column![
+                    text(fl!("enter-credentials")).size(14),
+                    text_input(&fl!("identity"), identity)
+                        .on_input(Message::ImportIdentity)
+                        .on_paste(Message::ImportIdentity),
+                    text_input(&fl!("password"), password)
+                        .on_input(Message::ImportPassword)
+                        .on_paste(Message::ImportPassword)
+                        .on_submit(Message::SubmitImportCredentials)
+                        .password(),
+                    row![
+                        button(Button::Secondary)
+                            .custom(vec![container(text(fl!("cancel"))).padding([0, 24]).into()])
+                            .on_press(Message::CancelImport),
+                        button(Button::Secondary)
+                            .custom(vec![container(text(fl!("import"))).padding([0, 24]).into()])
+                            .on_press(Message::SubmitImportCredentials),
+                    ]
+                    .spacing(24)
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .into(),
+                Some(ImportState::Waiting) => text(fl!("importing")).size(12).into(),
+                state => {
+                    let mut col = column![button(Button::Secondary)
+                        .custom(vec![row![
+                            icon("document-open-symbolic", 16).style(Svg::Symbolic),
+                            text(fl!("import-profile")).size(14),
+                        ]
+                        .spacing(8)
+                        .align_items(Alignment::Center)
+                        .into()])
+                        .padding([8, 12])
+                        .on_press(Message::ChooseImportFile)]
+                    .spacing(4)
+                    .align_items(Alignment::Center);
+                    col = col.push(
+                        text(match state {
+                            Some(ImportState::Success) => fl!("import-success"),
+                            Some(ImportState::Failure) => fl!("import-failure"),
+                            _ => fl!("import-drop-hint"),
+                        })
+                        .size(12),
+                    );
+                    col.into()
+                }
+            };
+            content = content.push(container(import).width(Length::Fill).padding([0, 24]));
+            content = content.push(self.proxy_view());
+        }
+        if self.show_saved_networks && !self.nm_state.airplane_mode {
+            let saved_len = self.nm_state.saved_conns.len();
+            let mut list_col = Vec::with_capacity(saved_len);
+            for (i, saved) in self.nm_state.saved_conns.iter().enumerate() {
+                if self.forget_confirm.as_ref() == Some(&saved.uuid) {
+                    list_col.push(
+                        column![
+                            text(fl!("forget-network-question", name = saved.id.as_str())).size(14),
+                            row![
+                                button(Button::Secondary)
+                                    .custom(vec![container(text(fl!("cancel")))
+                                        .padding([0, 24])
+                                        .into()])
+                                    .on_press(Message::CancelForget),
+                                button(Button::Destructive)
+                                    .custom(vec![container(text(fl!("forget")))
+                                        .padding([0, 24])
+                                        .into()])
+                                    .on_press(Message::Forget(saved.uuid.clone())),
+                            ]
+                            .spacing(24)
+                        ]
+                        .spacing(8)
+                        .padding([8, 24])
+                        .align_items(Alignment::Center)
+                        .into(),
+                    );
+                    continue;
+                }
+                let type_icon = if saved.is_wifi() {
+                    "network-wireless-symbolic"
+                } else if saved.conn_type == "802-3-ethernet" {
+                    "network-wired-symbolic"
+                } else {
+                    "network-vpn-symbolic"
+                };
+                let uuid = saved.uuid.clone();
+                let mut move_up = button(Button::Text)
+                    .custom(vec![icon("go-up-symbolic", 12).style(Svg::Symbolic).into()])
+                    .padding(2);
+                if i > 0 {
+                    move_up =
+                        move_up.on_press(Message::MoveSavedConnection(saved.uuid.clone(), true));
+                }
+                let mut move_down = button(Button::Text)
+                    .custom(vec![icon("go-down-symbolic", 12)
+                        .style(Svg::Symbolic)
+                        .into()])
+                    .padding(2);
+                if i + 1 < saved_len {
+                    move_down =
+                        move_down.on_press(Message::MoveSavedConnection(saved.uuid.clone(), false));
+                }
+                list_col.push(
+                    row![
+                        column![move_up, move_down],
+                        icon(type_icon, 16).style(Svg::Symbolic),
+                        text(&saved.id).size(14).width(Length::Fill),
+                        toggler(fl!("autoconnect"), saved.autoconnect, move |autoconnect| {
+                            Message::SetAutoconnect(uuid.clone(), autoconnect)
+                        })
+                        .text_size(12)
+                        .width(Length::Shrink),
+                        button(Button::Text)
+                            .custom(vec![icon("user-trash-symbolic", 16)
+                                .style(Svg::Symbolic)
+                                .into()])
+                            .padding(4)
+                            .on_press(Message::ConfirmForget(saved.uuid.clone())),
+                    ]
+                    .align_items(Alignment::Center)
+                    .spacing(8)
+                    .padding([4, 24])
+                    .into(),
+                );
+            }
+            content = content
+                .push(
+                    container(text(fl!("known-networks-order")).size(12))
+                        .width(Length::Fill)
+                        .padding([0, 24]),
+                )
+                .push(scrollable(Column::with_children(list_col)).height(Length::Fixed(200.0)));
+        }
+        let content = dnd_listener(content)
+            .on_enter(|_actions, mime_types, _location| {
+                if mime_types.iter().any(|m| m == DND_MIME_TYPE) {
+                    Message::ImportDndEnter
+                } else {
+                    Message::Ignore
+                }
+            })
+            .on_exit(Message::ImportDndExit)
+            .on_drop(Message::ImportDndDrop)
+            .on_data(|mime_type, data| {
+                if mime_type == DND_MIME_TYPE {
+                    // only the first file of the list is imported
+                    String::from_utf8(data)
+                        .ok()
+                        .and_then(|s| s.lines().next().and_then(|l| Url::from_str(l).ok()))
+                        .and_then(|u| u.to_file_path().ok())
+                        .map_or(Message::Ignore, Message::ImportDndData)
+                } else {
+                    Message::Ignore
+                }
+            });
         self.core.applet_helper.popup_container(content).into()
     }
 
@@ -765,20 +1979,38 @@ impl cosmic::Application for CosmicNetworkApplet {
             .as_subscription()
             .map(|(_, now)| Message::Frame(now));
 
+        let config_sub = cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+            |(_, config)| match config {
+                Ok(config) => Message::ConfigUpdated(config),
+                Err((errors, config)) => {
+                    for error in errors {
+                        log::error!("{:?}", error);
+                    }
+                    Message::ConfigUpdated(config)
+                }
+            },
+        );
+        let mut subscriptions = vec![timeline, network_sub, config_sub];
+        #[cfg(feature = "tailscale")]
+        subscriptions.push(tailscale::tailscale_subscription().map(Message::TailscaleStatus));
+        if self.config.show_throughput {
+            subscriptions
+                .push(time::every(Duration::from_secs(1)).map(|_| Message::SampleThroughput));
+        }
+
         if let Some(conn) = self.conn.as_ref() {
-            Subscription::batch(vec![
-                timeline,
-                network_sub,
+            subscriptions.extend([
                 active_conns_subscription(self.toggle_wifi_ctr, conn.clone())
                     .map(Message::NetworkManagerEvent),
                 devices_subscription(self.toggle_wifi_ctr, conn.clone())
                     .map(Message::NetworkManagerEvent),
                 wireless_enabled_subscription(self.toggle_wifi_ctr, conn.clone())
                     .map(Message::NetworkManagerEvent),
-            ])
-        } else {
-            Subscription::batch(vec![timeline, network_sub])
+                data_usage_subscription("data-usage", conn.clone())
+                    .map(Message::NetworkManagerEvent),
+            ]);
         }
+        Subscription::batch(subscriptions)
     }
 
     fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
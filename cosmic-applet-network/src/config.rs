@@ -1,3 +1,42 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+use crate::proxy::ProxyProfile;
+
 pub const APP_ID: &str = "com.system76.CosmicAppletNetwork";
 pub const PROFILE: &str = "";
 pub const VERSION: &str = "0.1.0";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct NetworkAppletConfig {
+    /// Show the current download and upload rates next to the panel icon
+    pub show_throughput: bool,
+    /// Saved proxy configurations to switch between
+    pub proxy_profiles: Vec<ProxyProfile>,
+    /// Name of the proxy profile in use, or `None` for a direct connection
+    pub active_proxy_profile: Option<String>,
+}
+
+impl NetworkAppletConfig {
+    pub fn load() -> NetworkAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| NetworkAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_show_throughput(&mut self, show_throughput: bool, config: &Config) {
+        self.show_throughput = show_throughput;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_proxy_profiles(&mut self, proxy_profiles: Vec<ProxyProfile>, config: &Config) {
+        self.proxy_profiles = proxy_profiles;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_active_proxy_profile(&mut self, name: Option<String>, config: &Config) {
+        self.active_proxy_profile = name;
+        let _ = self.write_entry(config);
+    }
+}
@@ -4,6 +4,9 @@ mod app;
 mod config;
 mod localize;
 mod network_manager;
+mod proxy;
+#[cfg(feature = "tailscale")]
+mod tailscale;
 
 use log::info;
 
@@ -5,7 +5,7 @@ use cosmic_dbus_networkmanager::{
     device::SpecificDevice,
     interface::enums::{ActiveConnectionState, ApFlags, ApSecurityFlags},
 };
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 pub async fn active_connections(
     active_connections: Vec<ActiveConnection<'_>>,
@@ -19,6 +19,7 @@ pub async fn active_connections(
             .await
             .unwrap_or_default();
         let addresses: Vec<_> = ipv4.iter().map(|d| d.address).collect();
+        let details = connection_details(&connection).await;
         let state = connection
             .state()
             .await
@@ -28,6 +29,7 @@ pub async fn active_connections(
             info.push(ActiveConnectionInfo::Vpn {
                 name: connection.id().await?,
                 ip_addresses: addresses.clone(),
+                details: details.clone(),
             });
             continue;
         }
@@ -44,6 +46,7 @@ pub async fn active_connections(
                         hw_address: wired_device.hw_address().await?,
                         speed: wired_device.speed().await?,
                         ip_addresses: addresses.clone(),
+                        details: details.clone(),
                     });
                 }
                 Some(SpecificDevice::Wireless(wireless_device)) => {
@@ -57,6 +60,10 @@ pub async fn active_connections(
                             wpa_flags: access_point.wpa_flags().await?,
                             state,
                             strength: access_point.strength().await.unwrap_or_default(),
+                            bitrate: wireless_device.bitrate().await.unwrap_or_default(),
+                            bssid: access_point.hw_address().await.unwrap_or_default(),
+                            frequency: access_point.frequency().await.unwrap_or_default(),
+                            details: details.clone(),
                         });
                     }
                 }
@@ -64,6 +71,7 @@ pub async fn active_connections(
                     info.push(ActiveConnectionInfo::Vpn {
                         name: connection.id().await?,
                         ip_addresses: addresses.clone(),
+                        details: details.clone(),
                     });
                 }
                 _ => {}
@@ -83,6 +91,61 @@ pub async fn active_connections(
     Ok(info)
 }
 
+async fn connection_details(connection: &ActiveConnection<'_>) -> ConnectionDetails {
+    let mut details = ConnectionDetails {
+        uuid: connection.uuid().await.unwrap_or_default(),
+        ..Default::default()
+    };
+    if let Ok(ip4_config) = connection.ip4_config().await {
+        details.ipv4_gateway = ip4_config
+            .gateway()
+            .await
+            .ok()
+            .filter(|gateway| !gateway.is_empty());
+        details.dns_servers.extend(
+            ip4_config
+                .nameserver_data()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| IpAddr::V4(d.address)),
+        );
+    }
+    if let Ok(ip6_config) = connection.ip6_config().await {
+        details.ipv6_addresses = ip6_config
+            .address_data()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|d| d.address)
+            .collect();
+        details.ipv6_gateway = ip6_config
+            .gateway()
+            .await
+            .ok()
+            .filter(|gateway| !gateway.is_empty());
+        details.dns_servers.extend(
+            ip6_config
+                .nameservers()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(IpAddr::V6),
+        );
+    }
+    details
+}
+
+/// Addressing and identity of an active connection, beyond what is shown in the list
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionDetails {
+    pub uuid: String,
+    pub ipv6_addresses: Vec<Ipv6Addr>,
+    pub ipv4_gateway: Option<String>,
+    pub ipv6_gateway: Option<String>,
+    pub dns_servers: Vec<IpAddr>,
+}
+
 #[derive(Debug, Clone)]
 pub enum ActiveConnectionInfo {
     Wired {
@@ -90,6 +153,7 @@ pub enum ActiveConnectionInfo {
         hw_address: String,
         speed: u32,
         ip_addresses: Vec<Ipv4Addr>,
+        details: ConnectionDetails,
     },
     WiFi {
         name: String,
@@ -100,10 +164,17 @@ pub enum ActiveConnectionInfo {
         wpa_flags: ApSecurityFlags,
         state: ActiveConnectionState,
         strength: u8,
+        bitrate: u32,
+        /// hardware address of the access point
+        bssid: String,
+        /// MHz
+        frequency: u32,
+        details: ConnectionDetails,
     },
     Vpn {
         name: String,
         ip_addresses: Vec<Ipv4Addr>,
+        details: ConnectionDetails,
     },
 }
 
@@ -115,4 +186,12 @@ impl ActiveConnectionInfo {
             ActiveConnectionInfo::Vpn { name, .. } => name.clone(),
         }
     }
+
+    pub fn details(&self) -> &ConnectionDetails {
+        match &self {
+            ActiveConnectionInfo::Wired { details, .. } => details,
+            ActiveConnectionInfo::WiFi { details, .. } => details,
+            ActiveConnectionInfo::Vpn { details, .. } => details,
+        }
+    }
 }
@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::NetworkManagerEvent;
+use crate::config::APP_ID;
+use chrono::{Datelike, Local};
+use cosmic::iced::{self, subscription};
+use cosmic_dbus_networkmanager::nm::NetworkManager;
+use futures::SinkExt;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    fs::{self, File},
+    hash::Hash,
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use xdg::BaseDirectories;
+use zbus::Connection;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+const DAYS_KEPT: usize = 62;
+const MONTHS_KEPT: usize = 24;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageCounter {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl UsageCounter {
+    fn add(&mut self, rx: u64, tx: u64) {
+        self.rx = self.rx.saturating_add(rx);
+        self.tx = self.tx.saturating_add(tx);
+    }
+}
+
+/// Daily and monthly byte counts for a single connection, keyed by `%Y-%m-%d` and `%Y-%m`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionUsage {
+    pub daily: BTreeMap<String, UsageCounter>,
+    pub monthly: BTreeMap<String, UsageCounter>,
+}
+
+impl ConnectionUsage {
+    pub fn today(&self) -> UsageCounter {
+        self.daily.get(&day_key()).copied().unwrap_or_default()
+    }
+
+    pub fn this_month(&self) -> UsageCounter {
+        self.monthly.get(&month_key()).copied().unwrap_or_default()
+    }
+
+    fn add(&mut self, rx: u64, tx: u64) {
+        self.daily.entry(day_key()).or_default().add(rx, tx);
+        self.monthly.entry(month_key()).or_default().add(rx, tx);
+        // keys sort chronologically, so the oldest entries are first
+        while self.daily.len() > DAYS_KEPT {
+            self.daily.pop_first();
+        }
+        while self.monthly.len() > MONTHS_KEPT {
+            self.monthly.pop_first();
+        }
+    }
+}
+
+/// Data usage for every connection that has been active, keyed by connection id
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataUsage {
+    pub connections: HashMap<String, ConnectionUsage>,
+}
+
+impl DataUsage {
+    fn path() -> Option<PathBuf> {
+        BaseDirectories::with_prefix(APP_ID)
+            .ok()
+            .and_then(|dirs| dirs.place_data_file("data_usage.ron").ok())
+    }
+
+    /// The saved data usage, or none yet if it can't be read. A file that can't be parsed is
+    /// kept next to it, so that counting afresh doesn't overwrite it.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    error!("Failed to read data usage: {}", err);
+                }
+                return Self::default();
+            }
+        };
+        match ron::de::from_reader(file) {
+            Ok(usage) => usage,
+            Err(err) => {
+                let corrupt = path.with_extension("ron.corrupt");
+                error!(
+                    "Failed to parse data usage, moving it to {}: {}",
+                    corrupt.display(),
+                    err
+                );
+                if let Err(err) = fs::rename(&path, &corrupt) {
+                    error!("Failed to move data usage: {}", err);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow::anyhow!("No data directory"))?;
+        // write a new file and swap it in, so a crash can't leave half of the counts behind
+        let tmp = path.with_extension("ron.tmp");
+        let file = File::create(&tmp)?;
+        ron::ser::to_writer(file, self)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConnectionUsage> {
+        self.connections.get(name)
+    }
+}
+
+fn day_key() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn month_key() -> String {
+    let now = Local::now();
+    format!("{:04}-{:02}", now.year(), now.month())
+}
+
+/// Read the cumulative (rx, tx) byte counters of a network interface
+pub fn read_interface_counters(interface: &str) -> Option<(u64, u64)> {
+    let read = |counter: &str| {
+        std::fs::read_to_string(format!("/sys/class/net/{interface}/statistics/{counter}"))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+    };
+    Some((read("rx_bytes")?, read("tx_bytes")?))
+}
+
+pub fn data_usage_subscription<I: 'static + Hash + Copy + Send + Sync + Debug>(
+    id: I,
+    conn: Connection,
+) -> iced::Subscription<NetworkManagerEvent> {
+    subscription::channel(id, 50, move |mut output| {
+        let mut state = State::Init(conn.clone());
+
+        async move {
+            loop {
+                state = start_listening(state, &mut output).await;
+                _ = tokio::time::sleep(SAMPLE_INTERVAL).await;
+            }
+        }
+    })
+}
+
+pub enum State {
+    Init(Connection),
+    Continue {
+        network_manager: NetworkManager<'static>,
+        usage: DataUsage,
+        // last counters seen for each (connection id, interface) pair
+        last_counters: HashMap<(String, String), (u64, u64)>,
+        last_persisted: Instant,
+    },
+}
+
+async fn start_listening(
+    state: State,
+    output: &mut futures::channel::mpsc::Sender<NetworkManagerEvent>,
+) -> State {
+    let (network_manager, mut usage, last_counters, mut last_persisted) = match state {
+        State::Init(conn) => match NetworkManager::new(&conn).await {
+            Ok(network_manager) => (
+                network_manager,
+                DataUsage::load(),
+                HashMap::new(),
+                Instant::now(),
+            ),
+            Err(e) => {
+                // tried again on the next sample
+                error!("Failed to connect to NetworkManager: {}", e);
+                return State::Init(conn);
+            }
+        },
+        State::Continue {
+            network_manager,
+            usage,
+            last_counters,
+            last_persisted,
+        } => (network_manager, usage, last_counters, last_persisted),
+    };
+
+    let mut counters = HashMap::with_capacity(last_counters.len());
+    for connection in network_manager
+        .active_connections()
+        .await
+        .unwrap_or_default()
+    {
+        let name = match connection.id().await {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        for device in connection.devices().await.unwrap_or_default() {
+            let interface = match device.ip_interface().await {
+                Ok(interface) if !interface.is_empty() => interface,
+                _ => continue,
+            };
+            let Some((rx, tx)) = read_interface_counters(&interface) else {
+                continue;
+            };
+            let key = (name.clone(), interface);
+            // only count traffic observed between two samples of the same connection
+            if let Some((last_rx, last_tx)) = last_counters.get(&key) {
+                // counters restart from zero when an interface is recreated
+                let rx_delta = rx.checked_sub(*last_rx).unwrap_or(rx);
+                let tx_delta = tx.checked_sub(*last_tx).unwrap_or(tx);
+                if rx_delta > 0 || tx_delta > 0 {
+                    usage
+                        .connections
+                        .entry(name.clone())
+                        .or_default()
+                        .add(rx_delta, tx_delta);
+                }
+            }
+            counters.insert(key, (rx, tx));
+        }
+    }
+
+    if last_persisted.elapsed() >= PERSIST_INTERVAL {
+        if let Err(err) = usage.save() {
+            error!("Failed to save data usage: {}", err);
+        }
+        last_persisted = Instant::now();
+    }
+
+    _ = output
+        .send(NetworkManagerEvent::DataUsage(usage.clone()))
+        .await;
+    State::Continue {
+        network_manager,
+        usage,
+        last_counters: counters,
+        last_persisted,
+    }
+}
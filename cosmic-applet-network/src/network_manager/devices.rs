@@ -5,7 +5,30 @@ use futures::{SinkExt, StreamExt};
 use log::error;
 use std::fmt::Debug;
 use std::hash::Hash;
-use zbus::Connection;
+use zbus::{dbus_proxy, zvariant::ObjectPath, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait DeviceMetered {
+    /// Metered property
+    #[dbus_proxy(property)]
+    fn metered(&self) -> zbus::Result<u32>;
+}
+
+/// Whether NetworkManager treats the device as metered, either because its connection profile
+/// says so or because it guessed so, e.g. from a phone's hotspot
+pub async fn is_device_metered(conn: &Connection, path: &ObjectPath<'_>) -> bool {
+    let Ok(builder) = DeviceMeteredProxy::builder(conn).path(path) else {
+        return false;
+    };
+    let Ok(proxy) = builder.build().await else {
+        return false;
+    };
+    // NMMetered: 1 = yes, 3 = guessed yes
+    matches!(proxy.metered().await, Ok(1 | 3))
+}
 
 pub fn devices_subscription<I: 'static + Hash + Copy + Send + Sync + Debug>(
     id: I,
@@ -0,0 +1,518 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::config::APP_ID;
+use anyhow::{anyhow, bail, Context};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    net::IpAddr,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+use tokio::process::Command;
+use uuid::Uuid;
+use xdg::BaseDirectories;
+use zbus::{
+    dbus_proxy,
+    zvariant::{OwnedObjectPath, Value},
+    Connection,
+};
+
+/// Secret flag telling NetworkManager to leave storing the secret to the user's secret agent,
+/// instead of writing it to the system's connection files in plain text
+const AGENT_OWNED: u32 = 1;
+
+type ConnectionSettings<'a> = HashMap<&'a str, HashMap<&'a str, Value<'a>>>;
+
+#[dbus_proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    /// AddConnection method
+    fn add_connection(&self, connection: ConnectionSettings<'_>) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileKind {
+    OpenVpn,
+    WireGuard,
+    /// eduroam CAT / generic 802.1x provisioning file
+    EapConfig,
+}
+
+impl ProfileKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(OsStr::to_str)? {
+            "ovpn" => Some(Self::OpenVpn),
+            "conf" => Some(Self::WireGuard),
+            "eap-config" => Some(Self::EapConfig),
+            _ => None,
+        }
+    }
+
+    /// Whether the user has to supply an identity and password before the profile can be created
+    pub fn needs_credentials(&self) -> bool {
+        matches!(self, Self::EapConfig)
+    }
+}
+
+/// The parts of an eap-config file needed to create a Wi-Fi 802.1x profile
+#[derive(Debug, Clone, Default)]
+struct EapProfile {
+    ssids: Vec<String>,
+    eap: &'static str,
+    /// The inner authentication setting and method, `phase2-autheap` for inner EAP methods and
+    /// `phase2-auth` for the others
+    phase2: Option<(&'static str, &'static str)>,
+    ca_certs: Vec<String>,
+    server_ids: Vec<String>,
+    anonymous_identity: Option<String>,
+}
+
+impl EapProfile {
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        let doc = roxmltree::Document::parse(input)?;
+        let child_text = |node: roxmltree::Node, name: &str| {
+            node.children()
+                .find(|n| n.has_tag_name(name))
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_string())
+        };
+
+        let mut profile = EapProfile {
+            ssids: doc
+                .descendants()
+                .filter(|n| n.has_tag_name("IEEE80211"))
+                .filter_map(|n| child_text(n, "SSID"))
+                .collect(),
+            ..Default::default()
+        };
+        if profile.ssids.is_empty() {
+            bail!("The provisioning file does not list any Wi-Fi network");
+        }
+
+        // methods are listed in order of preference, use the first one we know how to configure
+        let method = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("AuthenticationMethod"))
+            .find(|n| {
+                n.children()
+                    .find(|n| n.has_tag_name("EAPMethod"))
+                    .and_then(|n| child_text(n, "Type"))
+                    .map_or(false, |t| t == "21" || t == "25")
+            })
+            .ok_or_else(|| anyhow!("The provisioning file has no supported EAP method"))?;
+        let outer = method
+            .children()
+            .find(|n| n.has_tag_name("EAPMethod"))
+            .and_then(|n| child_text(n, "Type"));
+        profile.eap = if outer.as_deref() == Some("21") {
+            "ttls"
+        } else {
+            "peap"
+        };
+        if let Some(inner) = method
+            .children()
+            .find(|n| n.has_tag_name("InnerAuthenticationMethod"))
+        {
+            profile.phase2 = inner.children().find_map(|n| {
+                let ty = child_text(n, "Type")?;
+                match (n.tag_name().name(), ty.as_str()) {
+                    ("EAPMethod", "26") => Some(("phase2-autheap", "mschapv2")),
+                    ("NonEAPAuthMethod", "3") => Some(("phase2-auth", "mschapv2")),
+                    ("NonEAPAuthMethod", "2") => Some(("phase2-auth", "mschap")),
+                    ("NonEAPAuthMethod", "1") => Some(("phase2-auth", "pap")),
+                    _ => None,
+                }
+            });
+        }
+        if let Some(server) = method
+            .children()
+            .find(|n| n.has_tag_name("ServerSideCredential"))
+        {
+            for n in server.children() {
+                match n.tag_name().name() {
+                    "CA" => profile
+                        .ca_certs
+                        .extend(n.text().map(|t| t.trim().to_string())),
+                    "ServerID" => profile
+                        .server_ids
+                        .extend(n.text().map(|t| t.trim().to_string())),
+                    _ => {}
+                }
+            }
+        }
+        profile.anonymous_identity = method
+            .children()
+            .find(|n| n.has_tag_name("ClientSideCredential"))
+            .and_then(|n| child_text(n, "OuterIdentity"))
+            .filter(|i| !i.is_empty());
+        Ok(profile)
+    }
+
+    /// Write the CA certificates as a PEM bundle so NetworkManager can reference them by path
+    fn write_ca_bundle(&self) -> anyhow::Result<Option<PathBuf>> {
+        if self.ca_certs.is_empty() {
+            return Ok(None);
+        }
+        let path = BaseDirectories::with_prefix(APP_ID)?
+            .place_data_file(format!("certs/{}-ca.pem", file_name(&self.ssids[0])))?;
+        let mut pem = String::new();
+        for cert in &self.ca_certs {
+            let body: String = cert.chars().filter(|c| !c.is_whitespace()).collect();
+            pem.push_str("-----BEGIN CERTIFICATE-----\n");
+            for line in body.as_bytes().chunks(64) {
+                pem.push_str(std::str::from_utf8(line)?);
+                pem.push('\n');
+            }
+            pem.push_str("-----END CERTIFICATE-----\n");
+        }
+        std::fs::write(&path, pem)?;
+        Ok(Some(path))
+    }
+
+    fn settings(
+        &self,
+        ssid: &str,
+        identity: &str,
+        password: &str,
+        ca_cert: Option<&Path>,
+    ) -> ConnectionSettings<'static> {
+        let mut security = HashMap::from([
+            ("eap", Value::from(vec![self.eap])),
+            ("identity", Value::from(identity.to_string())),
+            ("password", Value::from(password.to_string())),
+            ("password-flags", Value::U32(AGENT_OWNED)),
+        ]);
+        if let Some((key, method)) = self.phase2 {
+            security.insert(key, Value::from(method));
+        }
+        if let Some(anonymous_identity) = self.anonymous_identity.as_ref() {
+            security.insert(
+                "anonymous-identity",
+                Value::from(anonymous_identity.clone()),
+            );
+        }
+        if let Some(ca_cert) = ca_cert {
+            // certificates are passed as a NUL terminated file:// URI
+            let mut uri = b"file://".to_vec();
+            uri.extend_from_slice(ca_cert.as_os_str().as_bytes());
+            uri.push(0);
+            security.insert("ca-cert", Value::from(uri));
+        }
+        if !self.server_ids.is_empty() {
+            security.insert(
+                "domain-suffix-match",
+                Value::from(self.server_ids.join(";")),
+            );
+        }
+        HashMap::from([
+            (
+                "connection",
+                connection_section(ssid, "802-11-wireless", None),
+            ),
+            (
+                "802-11-wireless",
+                HashMap::from([("ssid", Value::from(ssid.as_bytes().to_vec()))]),
+            ),
+            (
+                "802-11-wireless-security",
+                HashMap::from([("key-mgmt", Value::from("wpa-eap"))]),
+            ),
+            ("802-1x", security),
+        ])
+    }
+}
+
+/// A wg-quick configuration file
+#[derive(Debug, Clone, Default)]
+struct WireGuardProfile {
+    private_key: String,
+    listen_port: Option<u32>,
+    mtu: Option<u32>,
+    addresses: Vec<(IpAddr, u32)>,
+    dns: Vec<IpAddr>,
+    dns_search: Vec<String>,
+    peers: Vec<WireGuardPeer>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct WireGuardPeer {
+    public_key: String,
+    preshared_key: Option<String>,
+    endpoint: Option<String>,
+    allowed_ips: Vec<String>,
+    persistent_keepalive: Option<u32>,
+}
+
+impl WireGuardProfile {
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        let mut profile = WireGuardProfile::default();
+        let mut section = "";
+        for line in input.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = &line[1..line.len() - 1];
+                if section == "Peer" {
+                    profile.peers.push(WireGuardPeer::default());
+                }
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("Invalid line: {}", line);
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let list = || value.split(',').map(str::trim).filter(|v| !v.is_empty());
+            match (section, profile.peers.last_mut()) {
+                ("Interface", _) => match key {
+                    "PrivateKey" => profile.private_key = value.to_string(),
+                    "ListenPort" => profile.listen_port = Some(value.parse()?),
+                    "MTU" => profile.mtu = Some(value.parse()?),
+                    "Address" => {
+                        for address in list() {
+                            let (ip, prefix) = address.split_once('/').unwrap_or((address, ""));
+                            let ip: IpAddr = ip.parse()?;
+                            let prefix = match (prefix, ip) {
+                                ("", IpAddr::V4(_)) => 32,
+                                ("", IpAddr::V6(_)) => 128,
+                                (prefix, _) => prefix.parse()?,
+                            };
+                            profile.addresses.push((ip, prefix));
+                        }
+                    }
+                    // wg-quick takes search domains alongside the servers
+                    "DNS" => {
+                        for entry in list() {
+                            match entry.parse() {
+                                Ok(ip) => profile.dns.push(ip),
+                                Err(_) => profile.dns_search.push(entry.to_string()),
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                ("Peer", Some(peer)) => match key {
+                    "PublicKey" => peer.public_key = value.to_string(),
+                    "PresharedKey" => peer.preshared_key = Some(value.to_string()),
+                    "Endpoint" => peer.endpoint = Some(value.to_string()),
+                    "AllowedIPs" => peer.allowed_ips.extend(list().map(str::to_string)),
+                    "PersistentKeepalive" => {
+                        // "off" is the same as leaving it out
+                        peer.persistent_keepalive = value.parse().ok();
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        if profile.private_key.is_empty() {
+            bail!("The configuration has no private key");
+        }
+        Ok(profile)
+    }
+
+    fn settings(&self, name: &str) -> ConnectionSettings<'static> {
+        let mut wireguard = HashMap::from([
+            ("private-key", Value::from(self.private_key.clone())),
+            ("private-key-flags", Value::U32(AGENT_OWNED)),
+        ]);
+        if let Some(listen_port) = self.listen_port {
+            wireguard.insert("listen-port", Value::U32(listen_port));
+        }
+        if let Some(mtu) = self.mtu {
+            wireguard.insert("mtu", Value::U32(mtu));
+        }
+        let peers: Vec<HashMap<&str, Value>> = self
+            .peers
+            .iter()
+            .map(|peer| {
+                let mut settings = HashMap::from([
+                    ("public-key", Value::from(peer.public_key.clone())),
+                    ("allowed-ips", Value::from(peer.allowed_ips.clone())),
+                ]);
+                if let Some(preshared_key) = peer.preshared_key.as_ref() {
+                    settings.insert("preshared-key", Value::from(preshared_key.clone()));
+                    settings.insert("preshared-key-flags", Value::U32(AGENT_OWNED));
+                }
+                if let Some(endpoint) = peer.endpoint.as_ref() {
+                    settings.insert("endpoint", Value::from(endpoint.clone()));
+                }
+                if let Some(keepalive) = peer.persistent_keepalive {
+                    settings.insert("persistent-keepalive", Value::U32(keepalive));
+                }
+                settings
+            })
+            .collect();
+        wireguard.insert("peers", Value::from(peers));
+
+        let mut ipv4 = ip_section(self.addresses.iter().filter(|(ip, _)| ip.is_ipv4()));
+        let mut ipv6 = ip_section(self.addresses.iter().filter(|(ip, _)| ip.is_ipv6()));
+        // NetworkManager wants IPv4 servers as integers holding the address in network order
+        let dns4: Vec<u32> = self
+            .dns
+            .iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V4(ip) => Some(u32::from_ne_bytes(ip.octets())),
+                IpAddr::V6(_) => None,
+            })
+            .collect();
+        let dns6: Vec<Vec<u8>> = self
+            .dns
+            .iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V6(ip) => Some(ip.octets().to_vec()),
+                IpAddr::V4(_) => None,
+            })
+            .collect();
+        if !dns4.is_empty() {
+            ipv4.insert("dns", Value::from(dns4));
+            ipv4.insert("dns-search", Value::from(self.dns_search.clone()));
+        }
+        if !dns6.is_empty() {
+            ipv6.insert("dns", Value::from(dns6));
+            ipv6.insert("dns-search", Value::from(self.dns_search.clone()));
+        }
+        HashMap::from([
+            (
+                "connection",
+                connection_section(name, "wireguard", Some(&interface_name(name))),
+            ),
+            ("wireguard", wireguard),
+            ("ipv4", ipv4),
+            ("ipv6", ipv6),
+        ])
+    }
+}
+
+fn connection_section<'a>(
+    id: &str,
+    ty: &'static str,
+    interface: Option<&str>,
+) -> HashMap<&'a str, Value<'static>> {
+    let mut section = HashMap::from([
+        ("id", Value::from(id.to_string())),
+        ("uuid", Value::from(Uuid::new_v4().to_string())),
+        ("type", Value::from(ty)),
+    ]);
+    if let Some(interface) = interface {
+        section.insert("interface-name", Value::from(interface.to_string()));
+    }
+    section
+}
+
+fn ip_section<'a>(
+    addresses: impl Iterator<Item = &'a (IpAddr, u32)>,
+) -> HashMap<&'static str, Value<'static>> {
+    let addresses: Vec<HashMap<&str, Value>> = addresses
+        .map(|(ip, prefix)| {
+            HashMap::from([
+                ("address", Value::from(ip.to_string())),
+                ("prefix", Value::U32(*prefix)),
+            ])
+        })
+        .collect();
+    if addresses.is_empty() {
+        return HashMap::from([("method", Value::from("disabled"))]);
+    }
+    HashMap::from([
+        ("method", Value::from("manual")),
+        ("address-data", Value::from(addresses)),
+    ])
+}
+
+/// Name usable as a file name, keeping it inside the directory it's placed in
+fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "profile".to_string()
+    } else {
+        name
+    }
+}
+
+/// Network interface names are limited to 15 bytes
+fn interface_name(name: &str) -> String {
+    let mut name = file_name(name);
+    name.truncate(15);
+    name
+}
+
+async fn nmcli<I, S>(args: I) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("nmcli").args(args).output().await?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Create a NetworkManager profile from a VPN config or 802.1x provisioning file.
+/// Secrets are handed to NetworkManager over D-Bus and stored with the profile, which only root
+/// can read, as `nmcli connection import` does.
+pub async fn import_profile(
+    conn: &Connection,
+    path: &Path,
+    credentials: Option<(String, String)>,
+) -> anyhow::Result<()> {
+    let kind = ProfileKind::from_path(path)
+        .ok_or_else(|| anyhow!("Unsupported file type: {}", path.display()))?;
+    match kind {
+        // the OpenVPN plugin knows the format, and the passwords are not part of the file, the
+        // secret agent asks for them on connect
+        ProfileKind::OpenVpn => {
+            nmcli([
+                OsStr::new("connection"),
+                OsStr::new("import"),
+                OsStr::new("type"),
+                OsStr::new("openvpn"),
+                OsStr::new("file"),
+                path.as_os_str(),
+            ])
+            .await?;
+        }
+        ProfileKind::WireGuard => {
+            let profile = WireGuardProfile::parse(&std::fs::read_to_string(path)?)?;
+            let name = path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .context("Invalid file name")?;
+            let settings = SettingsProxy::new(conn).await?;
+            settings.add_connection(profile.settings(name)).await?;
+        }
+        ProfileKind::EapConfig => {
+            let (identity, password) =
+                credentials.context("An identity and password are required")?;
+            let profile = EapProfile::parse(&std::fs::read_to_string(path)?)?;
+            let ca_cert = profile.write_ca_bundle()?;
+            let settings = SettingsProxy::new(conn).await?;
+            for ssid in &profile.ssids {
+                settings
+                    .add_connection(profile.settings(
+                        ssid,
+                        &identity,
+                        &password,
+                        ca_cert.as_deref(),
+                    ))
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
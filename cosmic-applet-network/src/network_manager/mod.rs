@@ -1,10 +1,16 @@
 pub mod active_conns;
 pub mod available_wifi;
 pub mod current_networks;
+pub mod data_usage;
 pub mod devices;
+pub mod import;
+pub mod resolved;
+pub mod saved_conns;
+pub mod throughput;
+pub mod wired;
 pub mod wireless_enabled;
 
-use std::{collections::HashMap, fmt::Debug, ops::Deref, time::Duration};
+use std::{collections::HashMap, fmt::Debug, ops::Deref, path::PathBuf, time::Duration};
 
 use cosmic::iced::{self, subscription};
 use cosmic_dbus_networkmanager::{
@@ -30,6 +36,15 @@ use zbus::{
 use self::{
     available_wifi::{handle_wireless_device, AccessPoint},
     current_networks::{active_connections, ActiveConnectionInfo},
+    data_usage::DataUsage,
+    devices::is_device_metered,
+    import::import_profile,
+    resolved::{dns_over_tls_enabled, set_dns_over_tls, DnsPreset},
+    saved_conns::{
+        connection_id, connection_uuid, edit_connection, forget_connection, is_metered,
+        parse_hw_address, reactivate_connection, SavedConnection, WifiBand,
+    },
+    wired::{wired_device, WiredDevice},
 };
 
 #[derive(Debug)]
@@ -472,6 +487,230 @@ async fn start_listening(
                         })
                         .await;
                 }
+                Some(NetworkManagerRequest::SetMetered(id, metered)) => {
+                    let success = edit_connection(
+                        &conn,
+                        |settings| connection_id(settings).as_ref() == Some(&id),
+                        |settings| {
+                            if let Some(connection) = settings.get_mut("connection") {
+                                // NMMetered: 1 = yes, 0 = unknown, which leaves it to
+                                // NetworkManager to guess again
+                                connection.insert(
+                                    "metered".into(),
+                                    Value::I32(if metered { 1 } else { 0 }).to_owned(),
+                                );
+                            }
+                        },
+                    )
+                    .await;
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetMetered(id, metered),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetAutoconnect(uuid, autoconnect)) => {
+                    let success = edit_connection(
+                        &conn,
+                        |settings| connection_uuid(settings).as_ref() == Some(&uuid),
+                        |settings| {
+                            if let Some(connection) = settings.get_mut("connection") {
+                                connection.insert(
+                                    "autoconnect".into(),
+                                    Value::Bool(autoconnect).to_owned(),
+                                );
+                            }
+                        },
+                    )
+                    .await;
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetAutoconnect(uuid, autoconnect),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetPriorities(priorities)) => {
+                    let mut success = true;
+                    for (uuid, priority) in &priorities {
+                        success &= edit_connection(
+                            &conn,
+                            |settings| connection_uuid(settings).as_ref() == Some(uuid),
+                            |settings| {
+                                if let Some(connection) = settings.get_mut("connection") {
+                                    connection.insert(
+                                        "autoconnect-priority".into(),
+                                        Value::I32(*priority).to_owned(),
+                                    );
+                                }
+                            },
+                        )
+                        .await;
+                    }
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetPriorities(priorities),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetWiredEnabled(interface, enabled)) => {
+                    let mut success = false;
+                    for device in network_manager.devices().await.ok().unwrap_or_default() {
+                        if device.interface().await.ok().as_ref() != Some(&interface) {
+                            continue;
+                        }
+                        success = if enabled {
+                            // an empty connection path lets NetworkManager pick the best profile
+                            network_manager
+                                .deref()
+                                .activate_connection(
+                                    &ObjectPath::try_from("/").unwrap(),
+                                    device.path(),
+                                    &ObjectPath::try_from("/").unwrap(),
+                                )
+                                .await
+                                .is_ok()
+                        } else {
+                            device.disconnect().await.is_ok()
+                        };
+                        break;
+                    }
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetWiredEnabled(interface, enabled),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetIpv6(id, enabled)) => {
+                    let mut success = edit_connection(
+                        &conn,
+                        |settings| connection_id(settings).as_ref() == Some(&id),
+                        |settings| {
+                            settings.entry("ipv6".into()).or_default().insert(
+                                "method".into(),
+                                Value::Str(if enabled { "auto" } else { "disabled" }.into())
+                                    .to_owned(),
+                            );
+                        },
+                    )
+                    .await;
+                    if success {
+                        success = reactivate_connection(&conn, &network_manager, &id).await;
+                    }
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetIpv6(id, enabled),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetWifiBand(id, band)) => {
+                    let mut success = edit_connection(
+                        &conn,
+                        |settings| connection_id(settings).as_ref() == Some(&id),
+                        |settings| {
+                            let wireless = settings.entry("802-11-wireless".into()).or_default();
+                            match band.setting() {
+                                Some(band) => {
+                                    wireless
+                                        .insert("band".into(), Value::Str(band.into()).to_owned());
+                                }
+                                None => {
+                                    wireless.remove("band");
+                                }
+                            }
+                        },
+                    )
+                    .await;
+                    if success {
+                        success = reactivate_connection(&conn, &network_manager, &id).await;
+                    }
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetWifiBand(id, band),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetBssidLock(id, bssid)) => {
+                    let mut success = edit_connection(
+                        &conn,
+                        |settings| connection_id(settings).as_ref() == Some(&id),
+                        |settings| {
+                            let wireless = settings.entry("802-11-wireless".into()).or_default();
+                            match bssid.as_deref().and_then(parse_hw_address) {
+                                Some(bssid) => {
+                                    wireless.insert("bssid".into(), Value::from(bssid).to_owned());
+                                }
+                                None => {
+                                    wireless.remove("bssid");
+                                }
+                            }
+                        },
+                    )
+                    .await;
+                    if success {
+                        success = reactivate_connection(&conn, &network_manager, &id).await;
+                    }
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetBssidLock(id, bssid),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::SetDnsOverTls(preset)) => {
+                    let success = match set_dns_over_tls(&conn, &network_manager, preset).await {
+                        Ok(()) => true,
+                        Err(err) => {
+                            log::error!("Failed to set DNS over TLS: {}", err);
+                            false
+                        }
+                    };
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::SetDnsOverTls(preset),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::ImportProfile(path, credentials)) => {
+                    let success = match import_profile(&conn, &path, credentials.clone()).await {
+                        Ok(()) => true,
+                        Err(err) => {
+                            log::error!("Failed to import {}: {}", path.display(), err);
+                            false
+                        }
+                    };
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::ImportProfile(path, credentials),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
+                Some(NetworkManagerRequest::Forget(uuid)) => {
+                    let success = forget_connection(&conn, &uuid).await;
+                    _ = output
+                        .send(NetworkManagerEvent::RequestResponse {
+                            req: NetworkManagerRequest::Forget(uuid),
+                            success,
+                            state: NetworkManagerState::new(&conn).await.unwrap_or_default(),
+                        })
+                        .await;
+                }
                 _ => {
                     return State::Finished;
                 }
@@ -490,6 +729,18 @@ pub enum NetworkManagerRequest {
     SelectAccessPoint(String),
     Disconnect(String),
     Password(String, String),
+    SetMetered(String, bool),
+    SetAutoconnect(String, bool),
+    Forget(String),
+    SetPriorities(Vec<(String, i32)>),
+    SetWiredEnabled(String, bool),
+    SetIpv6(String, bool),
+    SetDnsOverTls(Option<DnsPreset>),
+    SetWifiBand(String, WifiBand),
+    /// Restrict a Wi-Fi profile to the access point with the given BSSID, or lift the restriction
+    SetBssidLock(String, Option<String>),
+    /// Import a VPN or 802.1x provisioning file, with an identity and password if the file needs one
+    ImportProfile(PathBuf, Option<(String, String)>),
 }
 
 #[derive(Debug, Clone)]
@@ -507,6 +758,7 @@ pub enum NetworkManagerEvent {
     WiFiEnabled(NetworkManagerState),
     WirelessAccessPoints(NetworkManagerState),
     ActiveConns(NetworkManagerState),
+    DataUsage(DataUsage),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -516,6 +768,13 @@ pub struct NetworkManagerState {
     pub known_access_points: Vec<AccessPoint>,
     pub wifi_enabled: bool,
     pub airplane_mode: bool,
+    /// ids of the connection profiles marked as metered
+    pub metered_conns: Vec<String>,
+    /// whether a device with an active connection is metered, as NetworkManager decided
+    pub metered: bool,
+    pub saved_conns: Vec<SavedConnection>,
+    pub wired_devices: Vec<WiredDevice>,
+    pub dns_over_tls: bool,
 }
 
 impl NetworkManagerState {
@@ -531,6 +790,7 @@ impl NetworkManagerState {
         let airplane_mode = std::str::from_utf8(&airplaine_mode.stdout).unwrap_or_default();
         _self.wifi_enabled = network_manager.wireless_enabled().await.unwrap_or_default();
         _self.airplane_mode = airplane_mode.contains("Soft blocked: yes") && !_self.wifi_enabled;
+        _self.dns_over_tls = dns_over_tls_enabled(conn, &network_manager).await;
 
         let s = NetworkManagerSettings::new(&conn).await?;
         _ = s.load_connections(&[]).await;
@@ -552,6 +812,17 @@ impl NetworkManagerState {
             helper(a).cmp(&helper(b))
         });
         let devices = network_manager.devices().await.ok().unwrap_or_default();
+        for device in &devices {
+            _self.wired_devices.extend(wired_device(device).await);
+            if matches!(device.state().await, Ok(enums::DeviceState::Activated))
+                && is_device_metered(conn, device.path()).await
+            {
+                _self.metered = true;
+            }
+        }
+        _self
+            .wired_devices
+            .sort_by(|a, b| a.interface.cmp(&b.interface));
         let wireless_access_point_futures: Vec<_> = devices
             .into_iter()
             .map(|device| async move {
@@ -574,6 +845,12 @@ impl NetworkManagerState {
         let mut known_ssid = Vec::with_capacity(known_conns.len());
         for c in known_conns {
             let s = c.get_settings().await.unwrap();
+            _self.saved_conns.extend(SavedConnection::from_settings(&s));
+            if is_metered(&s) {
+                if let Some(id) = connection_id(&s) {
+                    _self.metered_conns.push(id);
+                }
+            }
             let s = Settings::new(s);
             if let Some(cur_ssid) = s
                 .wifi
@@ -595,9 +872,16 @@ impl NetworkManagerState {
         _self.wireless_access_points = wireless_access_points;
         _self.active_conns = active_conns;
         _self.known_access_points = known_access_points;
+        _self
+            .saved_conns
+            .sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.id.cmp(&b.id)));
         Ok(_self)
     }
 
+    pub fn is_metered(&self, id: &str) -> bool {
+        self.metered_conns.iter().any(|c| c == id)
+    }
+
     #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.active_conns = Vec::new();
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Per-link DNS-over-TLS control through systemd-resolved
+//!
+//! Settings made here last until NetworkManager reconfigures the link, e.g. when the
+//! connection is activated again. Reverting a link also drops the DNS servers pushed by
+//! NetworkManager until it reconfigures the link.
+
+use cosmic_dbus_networkmanager::nm::NetworkManager;
+use std::net::IpAddr;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.resolve1.Manager",
+    default_service = "org.freedesktop.resolve1",
+    default_path = "/org/freedesktop/resolve1"
+)]
+trait Resolve1Manager {
+    /// GetLink method
+    fn get_link(&self, ifindex: i32) -> zbus::Result<OwnedObjectPath>;
+
+    /// SetLinkDNSEx method
+    #[dbus_proxy(name = "SetLinkDNSEx")]
+    fn set_link_dns_ex(
+        &self,
+        ifindex: i32,
+        addresses: &[(i32, Vec<u8>, u16, &str)],
+    ) -> zbus::Result<()>;
+
+    /// SetLinkDNSOverTLS method
+    #[dbus_proxy(name = "SetLinkDNSOverTLS")]
+    fn set_link_dns_over_tls(&self, ifindex: i32, mode: &str) -> zbus::Result<()>;
+
+    /// RevertLink method
+    fn revert_link(&self, ifindex: i32) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.resolve1.Link",
+    default_service = "org.freedesktop.resolve1"
+)]
+trait Resolve1Link {
+    /// DNSOverTLS property
+    #[dbus_proxy(property, name = "DNSOverTLS")]
+    fn dns_over_tls(&self) -> zbus::Result<String>;
+}
+
+// address families as expected by resolved
+const AF_INET: i32 = 2;
+const AF_INET6: i32 = 10;
+// DNS-over-TLS port
+const DOT_PORT: u16 = 853;
+
+/// Public resolvers that support DNS-over-TLS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DnsPreset {
+    #[default]
+    Cloudflare,
+    Quad9,
+    Google,
+}
+
+impl DnsPreset {
+    pub const ALL: [DnsPreset; 3] = [DnsPreset::Cloudflare, DnsPreset::Quad9, DnsPreset::Google];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DnsPreset::Cloudflare => "Cloudflare",
+            DnsPreset::Quad9 => "Quad9",
+            DnsPreset::Google => "Google",
+        }
+    }
+
+    fn server_name(self) -> &'static str {
+        match self {
+            DnsPreset::Cloudflare => "cloudflare-dns.com",
+            DnsPreset::Quad9 => "dns.quad9.net",
+            DnsPreset::Google => "dns.google",
+        }
+    }
+
+    fn addresses(self) -> [IpAddr; 2] {
+        match self {
+            DnsPreset::Cloudflare => [
+                "1.1.1.1".parse().unwrap(),
+                "2606:4700:4700::1111".parse().unwrap(),
+            ],
+            DnsPreset::Quad9 => ["9.9.9.9".parse().unwrap(), "2620:fe::fe".parse().unwrap()],
+            DnsPreset::Google => [
+                "8.8.8.8".parse().unwrap(),
+                "2001:4860:4860::8888".parse().unwrap(),
+            ],
+        }
+    }
+}
+
+/// Interface indices of the devices used by active connections
+async fn active_ifindexes(network_manager: &NetworkManager<'_>) -> Vec<i32> {
+    let mut ifindexes = Vec::new();
+    for connection in network_manager
+        .active_connections()
+        .await
+        .unwrap_or_default()
+    {
+        for device in connection.devices().await.unwrap_or_default() {
+            let Ok(interface) = device.ip_interface().await else {
+                continue;
+            };
+            if let Some(ifindex) =
+                std::fs::read_to_string(format!("/sys/class/net/{interface}/ifindex"))
+                    .ok()
+                    .and_then(|i| i.trim().parse().ok())
+            {
+                if !ifindexes.contains(&ifindex) {
+                    ifindexes.push(ifindex);
+                }
+            }
+        }
+    }
+    ifindexes
+}
+
+/// Whether DNS-over-TLS is enforced on every link used by an active connection
+pub async fn dns_over_tls_enabled(conn: &Connection, network_manager: &NetworkManager<'_>) -> bool {
+    let Ok(manager) = Resolve1ManagerProxy::new(conn).await else {
+        return false;
+    };
+    let ifindexes = active_ifindexes(network_manager).await;
+    if ifindexes.is_empty() {
+        return false;
+    }
+    for ifindex in ifindexes {
+        let Ok(path) = manager.get_link(ifindex).await else {
+            return false;
+        };
+        let Ok(link) = Resolve1LinkProxy::builder(conn).path(path) else {
+            return false;
+        };
+        let Ok(link) = link.build().await else {
+            return false;
+        };
+        if link.dns_over_tls().await.ok().as_deref() != Some("yes") {
+            return false;
+        }
+    }
+    true
+}
+
+/// Use `preset` over TLS on every active link, or revert the links to their defaults
+pub async fn set_dns_over_tls(
+    conn: &Connection,
+    network_manager: &NetworkManager<'_>,
+    preset: Option<DnsPreset>,
+) -> zbus::Result<()> {
+    let manager = Resolve1ManagerProxy::new(conn).await?;
+    for ifindex in active_ifindexes(network_manager).await {
+        match preset {
+            Some(preset) => {
+                let addresses: Vec<_> = preset
+                    .addresses()
+                    .into_iter()
+                    .map(|addr| match addr {
+                        IpAddr::V4(v4) => (
+                            AF_INET,
+                            v4.octets().to_vec(),
+                            DOT_PORT,
+                            preset.server_name(),
+                        ),
+                        IpAddr::V6(v6) => (
+                            AF_INET6,
+                            v6.octets().to_vec(),
+                            DOT_PORT,
+                            preset.server_name(),
+                        ),
+                    })
+                    .collect();
+                manager.set_link_dns_ex(ifindex, &addresses).await?;
+                manager.set_link_dns_over_tls(ifindex, "yes").await?;
+            }
+            None => manager.revert_link(ifindex).await?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use cosmic_dbus_networkmanager::{nm::NetworkManager, settings::NetworkManagerSettings};
+use std::{collections::HashMap, ops::Deref};
+use zbus::{
+    zvariant::{ObjectPath, OwnedValue},
+    Connection,
+};
+
+pub type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+/// A connection profile stored by NetworkManager
+#[derive(Debug, Clone)]
+pub struct SavedConnection {
+    pub id: String,
+    pub uuid: String,
+    pub conn_type: String,
+    pub autoconnect: bool,
+    /// `autoconnect-priority`, higher values are preferred when several profiles are available
+    pub priority: i32,
+    /// `ipv6.method`, e.g. `auto`, `manual` or `disabled`
+    pub ipv6_method: Option<String>,
+    /// `802-11-wireless.band`
+    pub band: WifiBand,
+    /// `802-11-wireless.bssid`, the only access point this profile may connect to
+    pub bssid: Option<String>,
+}
+
+/// Frequency band a Wi-Fi profile is restricted to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WifiBand {
+    #[default]
+    Any,
+    /// 2.4 GHz
+    Bg,
+    /// 5 GHz, NetworkManager has no separate value for 6 GHz
+    A,
+}
+
+impl WifiBand {
+    pub const ALL: [WifiBand; 3] = [WifiBand::Any, WifiBand::Bg, WifiBand::A];
+
+    fn from_setting(band: Option<&str>) -> Self {
+        match band {
+            Some("bg") => WifiBand::Bg,
+            Some("a") => WifiBand::A,
+            _ => WifiBand::Any,
+        }
+    }
+
+    pub fn setting(&self) -> Option<&'static str> {
+        match self {
+            WifiBand::Any => None,
+            WifiBand::Bg => Some("bg"),
+            WifiBand::A => Some("a"),
+        }
+    }
+}
+
+impl SavedConnection {
+    pub fn from_settings(settings: &ConnectionSettings) -> Option<Self> {
+        Some(Self {
+            id: connection_id(settings)?,
+            uuid: connection_str(settings, "uuid")?,
+            conn_type: connection_str(settings, "type").unwrap_or_default(),
+            // NetworkManager omits the key when it has its default value
+            autoconnect: settings
+                .get("connection")
+                .and_then(|c| c.get("autoconnect"))
+                .cloned()
+                .and_then(|a| bool::try_from(a).ok())
+                .unwrap_or(true),
+            priority: settings
+                .get("connection")
+                .and_then(|c| c.get("autoconnect-priority"))
+                .cloned()
+                .and_then(|p| i32::try_from(p).ok())
+                .unwrap_or_default(),
+            ipv6_method: settings
+                .get("ipv6")
+                .and_then(|c| c.get("method"))
+                .cloned()
+                .and_then(|m| m.try_into().ok()),
+            band: WifiBand::from_setting(wireless_str(settings, "band").as_deref()),
+            bssid: settings
+                .get("802-11-wireless")
+                .and_then(|c| c.get("bssid"))
+                .cloned()
+                .and_then(|b| Vec::<u8>::try_from(b).ok())
+                .filter(|b| !b.is_empty())
+                .map(|b| format_hw_address(&b)),
+        })
+    }
+
+    pub fn ipv6_enabled(&self) -> bool {
+        !matches!(self.ipv6_method.as_deref(), Some("disabled" | "ignore"))
+    }
+
+    pub fn is_wifi(&self) -> bool {
+        self.conn_type == "802-11-wireless"
+    }
+}
+
+fn connection_str(settings: &ConnectionSettings, key: &str) -> Option<String> {
+    settings
+        .get("connection")
+        .and_then(|c| c.get(key))
+        .cloned()
+        .and_then(|v| v.try_into().ok())
+}
+
+fn wireless_str(settings: &ConnectionSettings, key: &str) -> Option<String> {
+    settings
+        .get("802-11-wireless")
+        .and_then(|c| c.get(key))
+        .cloned()
+        .and_then(|v| v.try_into().ok())
+}
+
+fn format_hw_address(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Parse a `AA:BB:CC:DD:EE:FF` hardware address into the byte form used by connection settings
+pub fn parse_hw_address(address: &str) -> Option<Vec<u8>> {
+    address
+        .split(':')
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect()
+}
+
+pub fn connection_id(settings: &ConnectionSettings) -> Option<String> {
+    connection_str(settings, "id")
+}
+
+pub fn connection_uuid(settings: &ConnectionSettings) -> Option<String> {
+    connection_str(settings, "uuid")
+}
+
+pub fn is_metered(settings: &ConnectionSettings) -> bool {
+    settings
+        .get("connection")
+        .and_then(|c| c.get("metered"))
+        .cloned()
+        .and_then(|metered| i32::try_from(metered).ok())
+        // NMMetered: 1 = yes, 3 = guessed yes
+        .map_or(false, |metered| metered == 1 || metered == 3)
+}
+
+/// Apply `edit` to the first saved connection accepted by `matches` and store the result.
+/// Returns whether a connection was found and updated.
+pub async fn edit_connection(
+    conn: &Connection,
+    matches: impl Fn(&ConnectionSettings) -> bool,
+    edit: impl FnOnce(&mut ConnectionSettings),
+) -> bool {
+    let s = match NetworkManagerSettings::new(conn).await {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    for c in s.list_connections().await.unwrap_or_default() {
+        let mut settings = match c.get_settings().await.ok() {
+            Some(s) => s,
+            None => continue,
+        };
+        if !matches(&settings) {
+            continue;
+        }
+        edit(&mut settings);
+        let settings: HashMap<_, _> = settings
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.as_str(),
+                    v.iter()
+                        .map(|(k, v)| (k.as_str(), v.into()))
+                        .collect::<HashMap<_, _>>(),
+                )
+            })
+            .collect();
+        return c.update(settings).await.is_ok();
+    }
+    false
+}
+
+/// Activate the saved connection with the given id again so that changed settings apply
+pub async fn reactivate_connection(
+    conn: &Connection,
+    network_manager: &NetworkManager<'_>,
+    id: &str,
+) -> bool {
+    let s = match NetworkManagerSettings::new(conn).await {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    for c in s.list_connections().await.unwrap_or_default() {
+        let settings = match c.get_settings().await.ok() {
+            Some(s) => s,
+            None => continue,
+        };
+        if connection_id(&settings).as_deref() != Some(id) {
+            continue;
+        }
+        return network_manager
+            .deref()
+            .activate_connection(
+                c.deref().path(),
+                &ObjectPath::try_from("/").unwrap(),
+                &ObjectPath::try_from("/").unwrap(),
+            )
+            .await
+            .is_ok();
+    }
+    false
+}
+
+/// Delete the saved connection with the given uuid
+pub async fn forget_connection(conn: &Connection, uuid: &str) -> bool {
+    let s = match NetworkManagerSettings::new(conn).await {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    for c in s.list_connections().await.unwrap_or_default() {
+        let settings = match c.get_settings().await.ok() {
+            Some(s) => s,
+            None => continue,
+        };
+        if connection_uuid(&settings).as_deref() == Some(uuid) {
+            return c.delete().await.is_ok();
+        }
+    }
+    false
+}
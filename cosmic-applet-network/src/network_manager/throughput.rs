@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::data_usage::read_interface_counters;
+use std::{fs, time::Instant};
+
+/// Computes transfer rates from the byte counters of the physical network interfaces
+#[derive(Debug, Default)]
+pub struct ThroughputSampler {
+    last: Option<(Instant, u64, u64)>,
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+}
+
+impl ThroughputSampler {
+    pub fn sample(&mut self) {
+        let now = Instant::now();
+        let (rx, tx) = physical_interface_counters();
+        if let Some((last_time, last_rx, last_tx)) = self.last {
+            let elapsed = now.duration_since(last_time).as_secs_f64();
+            if elapsed > 0.0 {
+                self.rx_rate = (rx.saturating_sub(last_rx) as f64 / elapsed) as u64;
+                self.tx_rate = (tx.saturating_sub(last_tx) as f64 / elapsed) as u64;
+            }
+        }
+        self.last = Some((now, rx, tx));
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Sum the counters of interfaces backed by a device, skipping loopback and virtual
+/// interfaces (tunnels, bridges) whose traffic is already counted on the physical link
+fn physical_interface_counters() -> (u64, u64) {
+    let entries = match fs::read_dir("/sys/class/net") {
+        Ok(entries) => entries,
+        Err(_) => return (0, 0),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("device").exists())
+        .filter_map(|entry| read_interface_counters(&entry.file_name().to_string_lossy()))
+        .fold((0, 0), |(rx, tx), (d_rx, d_tx)| {
+            (rx.saturating_add(d_rx), tx.saturating_add(d_tx))
+        })
+}
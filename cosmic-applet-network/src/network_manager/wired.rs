@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use cosmic_dbus_networkmanager::{
+    device::{Device, SpecificDevice},
+    interface::enums::DeviceState,
+};
+
+/// An ethernet interface, whether or not it currently has a connection
+#[derive(Debug, Clone)]
+pub struct WiredDevice {
+    pub interface: String,
+    pub hw_address: String,
+    /// link speed in Mb/s, 0 when there is no link
+    pub speed: u32,
+    pub duplex: Option<String>,
+    pub carrier: bool,
+    pub state: DeviceState,
+}
+
+impl WiredDevice {
+    pub fn is_enabled(&self) -> bool {
+        !matches!(
+            self.state,
+            DeviceState::Disconnected
+                | DeviceState::Deactivating
+                | DeviceState::Unavailable
+                | DeviceState::Unmanaged
+                | DeviceState::Failed
+                | DeviceState::Unknown
+        )
+    }
+}
+
+pub async fn wired_device(device: &Device<'_>) -> Option<WiredDevice> {
+    let Ok(Some(SpecificDevice::Wired(wired_device))) = device.downcast_to_device().await else {
+        return None;
+    };
+    let interface = device.interface().await.ok()?;
+    Some(WiredDevice {
+        duplex: read_duplex(&interface),
+        hw_address: wired_device.hw_address().await.unwrap_or_default(),
+        speed: wired_device.speed().await.unwrap_or_default(),
+        carrier: wired_device.carrier().await.unwrap_or_default(),
+        state: device.state().await.unwrap_or(DeviceState::Unknown),
+        interface,
+    })
+}
+
+/// NetworkManager does not expose the negotiated duplex, so read it from sysfs
+fn read_duplex(interface: &str) -> Option<String> {
+    std::fs::read_to_string(format!("/sys/class/net/{interface}/duplex"))
+        .ok()
+        .map(|duplex| duplex.trim().to_string())
+        .filter(|duplex| duplex == "full" || duplex == "half")
+}
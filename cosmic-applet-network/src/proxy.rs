@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! System proxy profiles
+//!
+//! The proxy is set through the `org.gnome.system.proxy` GSettings schemas, since COSMIC has no
+//! proxy setting of its own. Only applications that read those schemas pick it up, mainly GIO and
+//! libproxy based ones and browsers that follow the GNOME settings. Programs reading the
+//! `http_proxy` environment variables, and the system services, are not affected.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// How connections reach the network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProxyMode {
+    Direct,
+    Manual {
+        host: String,
+        port: u16,
+    },
+    /// Proxy auto-config (PAC) script
+    Auto {
+        url: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyProfile {
+    pub name: String,
+    pub mode: ProxyMode,
+}
+
+const PROXY: &str = "org.gnome.system.proxy";
+
+async fn gsettings(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("gsettings").args(args).output().await?;
+    if !output.status.success() {
+        bail!(
+            "gsettings {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn set(schema: &str, key: &str, value: &str) -> anyhow::Result<()> {
+    gsettings(&["set", schema, key, value]).await.map(|_| ())
+}
+
+/// Update the desktop proxy settings
+pub async fn apply(mode: ProxyMode) -> anyhow::Result<()> {
+    let expected = match mode {
+        ProxyMode::Direct => "none",
+        ProxyMode::Manual { .. } => "manual",
+        ProxyMode::Auto { .. } => "auto",
+    };
+    write(mode).await?;
+    // gsettings still succeeds when it falls back to its memory backend, e.g. without dconf,
+    // so make sure the change reached the settings other programs read
+    let mode = gsettings(&["get", PROXY, "mode"]).await?;
+    if mode.trim_matches('\'') != expected {
+        bail!(
+            "The proxy mode is still {}, the settings were not saved",
+            mode
+        );
+    }
+    Ok(())
+}
+
+async fn write(mode: ProxyMode) -> anyhow::Result<()> {
+    match mode {
+        ProxyMode::Direct => set(PROXY, "mode", "none").await,
+        ProxyMode::Manual { host, port } => {
+            for schema in [
+                "org.gnome.system.proxy.http",
+                "org.gnome.system.proxy.https",
+            ] {
+                set(schema, "host", &host).await?;
+                set(schema, "port", &port.to_string()).await?;
+            }
+            set(PROXY, "mode", "manual").await
+        }
+        ProxyMode::Auto { url } => {
+            set(PROXY, "autoconfig-url", &url).await?;
+            set(PROXY, "mode", "auto").await
+        }
+    }
+}
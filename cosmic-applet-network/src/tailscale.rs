@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Status of the Tailscale mesh VPN, read from the tailscaled local API
+
+use anyhow::{anyhow, bail};
+use cosmic::iced::{self, subscription};
+use cosmic_applet_common::http::{self, header, Body, Method, Request};
+use futures::SinkExt;
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, time::Duration};
+
+const SOCKETS: [&str; 2] = [
+    "/run/tailscale/tailscaled.sock",
+    "/var/run/tailscale/tailscaled.sock",
+];
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TailscaleStatus {
+    /// `Running`, `Stopped`, `NeedsLogin`, ...
+    pub backend_state: String,
+    pub host_name: String,
+    pub ips: Vec<String>,
+    /// Host name of the peer all traffic is routed through
+    pub exit_node: Option<String>,
+}
+
+impl TailscaleStatus {
+    pub fn is_running(&self) -> bool {
+        self.backend_state == "Running"
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawStatus {
+    backend_state: String,
+    #[serde(rename = "Self")]
+    self_: Option<RawPeer>,
+    #[serde(default)]
+    peer: HashMap<String, RawPeer>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawPeer {
+    #[serde(default)]
+    host_name: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Vec<String>,
+    #[serde(default)]
+    exit_node: bool,
+}
+
+async fn request(method: Method, path: &str, body: Option<String>) -> anyhow::Result<String> {
+    let socket = SOCKETS
+        .iter()
+        .find(|s| Path::new(s).exists())
+        .ok_or_else(|| anyhow!("tailscaled is not running"))?;
+    // tailscaled only answers requests for this host
+    let request = Request::builder()
+        .method(method)
+        .uri(path)
+        .header(header::HOST, "local-tailscaled.sock")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.map_or_else(Body::empty, Body::from))?;
+    let response = http::send_unix(Path::new(socket), request, TIMEOUT).await?;
+    let body = String::from_utf8_lossy(&response.body);
+    if !response.status.is_success() {
+        bail!("tailscaled returned {}: {}", response.status, body.trim());
+    }
+    Ok(body.into_owned())
+}
+
+pub async fn status() -> anyhow::Result<TailscaleStatus> {
+    let raw: RawStatus =
+        serde_json::from_str(&request(Method::GET, "/localapi/v0/status", None).await?)?;
+    let (host_name, ips) = raw
+        .self_
+        .map(|s| (s.host_name, s.tailscale_ips))
+        .unwrap_or_default();
+    Ok(TailscaleStatus {
+        backend_state: raw.backend_state,
+        host_name,
+        ips,
+        exit_node: raw
+            .peer
+            .into_values()
+            .find(|p| p.exit_node)
+            .map(|p| p.host_name),
+    })
+}
+
+/// Bring the tailnet connection up or down, like `tailscale up` and `tailscale down`.
+/// This requires the user to be the tailscaled operator.
+pub async fn set_running(running: bool) -> anyhow::Result<()> {
+    let prefs = format!(r#"{{"WantRunning":{running},"WantRunningSet":true}}"#);
+    request(Method::PATCH, "/localapi/v0/prefs", Some(prefs)).await?;
+    Ok(())
+}
+
+/// Emits the status whenever it changes, or `None` while tailscaled is unavailable
+pub fn tailscale_subscription() -> iced::Subscription<Option<TailscaleStatus>> {
+    struct TailscaleWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<TailscaleWorker>(),
+        10,
+        |mut output| async move {
+            let mut last = None;
+            loop {
+                let status = status().await.ok();
+                if status != last {
+                    _ = output.send(status.clone()).await;
+                    last = status;
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        },
+    )
+}
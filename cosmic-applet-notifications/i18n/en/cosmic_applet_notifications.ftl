@@ -10,4 +10,39 @@ show-more = Show {$more} More
 clear-all = Clear All Notifications
 do-not-disturb = Do Not Disturb
 notification-settings = Notification Settings...
-no-notifications = No Notifications
\ No newline at end of file
+no-notifications = No Notifications
+notifications = Notifications
+weekly-digest = Weekly Summary
+digest-summary = { NUMBER($count) ->
+    [1] 1 notification last week
+    *[other] {$count} notifications last week
+}
+digest-body = Most notifications came from {$apps}. {$unread} were dismissed without being read.
+digest-last-week = Last week: {$count} notifications, {$unread} dismissed unread
+dnd-schedule = Scheduled
+dnd-from = From
+dnd-to = to
+dnd-screen-sharing = While sharing the screen
+history = History ({$count})
+clear = Clear
+clear-history = Clear History
+applications = Applications
+app-sounds = Sounds
+hold-fullscreen-banners = Hide all banners while an app is fullscreen, critical ones too
+fullscreen-summary-setting = Summarize them afterwards
+fullscreen-summary = { NUMBER($count) ->
+    [1] 1 notification arrived while in fullscreen
+    *[other] {$count} notifications arrived while in fullscreen
+}
+history-keep = Keep up to
+history-keep-days = notifications for
+history-days = days (0 for no limit)
+snooze-for = Snooze for
+snooze-minutes = { $minutes ->
+    [60] 1 hour
+    *[other] {$minutes} minutes
+}
+urgency-settings = Sounds by urgency
+urgency-low = Low urgency
+urgency-normal = Normal urgency
+urgency-critical = Critical
@@ -0,0 +1,206 @@
+use crate::subscriptions::notifications::Urgency;
+use chrono::{NaiveTime, Timelike};
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletNotifications";
+
+/// Settings of the applet itself, the shared notification settings live in `NotificationsConfig`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct AppletConfig {
+    /// Summarize the previous week's notifications in the popup and as a notification
+    pub weekly_digest: bool,
+    /// Turn on Do Not Disturb every day between `dnd_start` and `dnd_end`
+    pub dnd_schedule: bool,
+    /// Minutes after midnight
+    pub dnd_start: u32,
+    /// Minutes after midnight, before `dnd_start` for schedules that span midnight
+    pub dnd_end: u32,
+    /// Turn on Do Not Disturb while the screen is being shared
+    pub dnd_screen_sharing: bool,
+    /// Do Not Disturb was turned on by the schedule, screen sharing or a fullscreen window, and
+    /// is turned off with it. Kept here so that it's still turned off after a restart.
+    pub automatic_dnd: bool,
+    /// Turn on Do Not Disturb while a window is fullscreen, so that no banners cover it. The
+    /// daemon's Do Not Disturb holds back every banner, critical ones included, so it's off by
+    /// default.
+    pub hold_fullscreen_banners: bool,
+    /// Show a summary of the notifications that arrived meanwhile once no window is fullscreen
+    /// anymore
+    pub fullscreen_summary: bool,
+    /// Per-app overrides, keyed by app name
+    pub apps: BTreeMap<String, AppNotificationSettings>,
+    pub history_retention: HistoryRetention,
+    pub low_urgency: UrgencySettings,
+    pub normal_urgency: UrgencySettings,
+    pub critical_urgency: UrgencySettings,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UrgencySettings {
+    pub sound: bool,
+}
+
+/// How much of the notification history is kept on disk
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct HistoryRetention {
+    pub max_entries: u32,
+    /// Entries older than this are dropped, 0 keeps them regardless of age
+    pub max_days: u32,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            max_entries: 200,
+            max_days: 30,
+        }
+    }
+}
+
+impl HistoryRetention {
+    pub fn max_age(&self) -> Option<Duration> {
+        (self.max_days > 0).then(|| Duration::from_secs(u64::from(self.max_days) * 24 * 60 * 60))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AppNotificationSettings {
+    pub sounds: bool,
+}
+
+impl Default for AppNotificationSettings {
+    fn default() -> Self {
+        Self { sounds: true }
+    }
+}
+
+impl Default for AppletConfig {
+    fn default() -> Self {
+        Self {
+            weekly_digest: false,
+            dnd_schedule: false,
+            dnd_start: 22 * 60,
+            dnd_end: 8 * 60,
+            dnd_screen_sharing: false,
+            automatic_dnd: false,
+            hold_fullscreen_banners: false,
+            fullscreen_summary: true,
+            apps: BTreeMap::new(),
+            history_retention: HistoryRetention::default(),
+            low_urgency: UrgencySettings { sound: false },
+            normal_urgency: UrgencySettings { sound: true },
+            critical_urgency: UrgencySettings { sound: true },
+        }
+    }
+}
+
+impl AppletConfig {
+    pub fn load() -> AppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| AppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_weekly_digest(&mut self, weekly_digest: bool, config: &Config) {
+        self.weekly_digest = weekly_digest;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_dnd_schedule(&mut self, dnd_schedule: bool, config: &Config) {
+        self.dnd_schedule = dnd_schedule;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_dnd_times(&mut self, start: u32, end: u32, config: &Config) {
+        self.dnd_start = start;
+        self.dnd_end = end;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_dnd_screen_sharing(&mut self, dnd_screen_sharing: bool, config: &Config) {
+        self.dnd_screen_sharing = dnd_screen_sharing;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_automatic_dnd(&mut self, automatic_dnd: bool, config: &Config) {
+        self.automatic_dnd = automatic_dnd;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_hold_fullscreen_banners(&mut self, hold: bool, config: &Config) {
+        self.hold_fullscreen_banners = hold;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_fullscreen_summary(&mut self, fullscreen_summary: bool, config: &Config) {
+        self.fullscreen_summary = fullscreen_summary;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_history_retention(&mut self, retention: HistoryRetention, config: &Config) {
+        self.history_retention = retention;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn urgency(&self, urgency: Urgency) -> UrgencySettings {
+        match urgency {
+            Urgency::Low => self.low_urgency,
+            Urgency::Normal => self.normal_urgency,
+            Urgency::Critical => self.critical_urgency,
+        }
+    }
+
+    pub fn set_urgency(&mut self, urgency: Urgency, settings: UrgencySettings, config: &Config) {
+        match urgency {
+            Urgency::Low => self.low_urgency = settings,
+            Urgency::Normal => self.normal_urgency = settings,
+            Urgency::Critical => self.critical_urgency = settings,
+        }
+        let _ = self.write_entry(config);
+    }
+
+    pub fn app(&self, app_name: &str) -> AppNotificationSettings {
+        self.apps.get(app_name).copied().unwrap_or_default()
+    }
+
+    pub fn set_app(
+        &mut self,
+        app_name: String,
+        settings: AppNotificationSettings,
+        config: &Config,
+    ) {
+        if settings == AppNotificationSettings::default() {
+            self.apps.remove(&app_name);
+        } else {
+            self.apps.insert(app_name, settings);
+        }
+        let _ = self.write_entry(config);
+    }
+
+    /// Whether the scheduled Do Not Disturb period includes the given time
+    pub fn in_dnd_schedule(&self, time: NaiveTime) -> bool {
+        if !self.dnd_schedule {
+            return false;
+        }
+        let minutes = time.hour() * 60 + time.minute();
+        if self.dnd_start <= self.dnd_end {
+            (self.dnd_start..self.dnd_end).contains(&minutes)
+        } else {
+            minutes >= self.dnd_start || minutes < self.dnd_end
+        }
+    }
+}
+
+/// Format minutes after midnight as `HH:MM`
+pub fn format_minutes(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Parse `HH:MM` into minutes after midnight
+pub fn parse_minutes(input: &str) -> Option<u32> {
+    let time = NaiveTime::parse_from_str(input.trim(), "%H:%M").ok()?;
+    Some(time.hour() * 60 + time.minute())
+}
@@ -0,0 +1,146 @@
+//! Rich notification content: markup bodies, images and progress
+
+use std::collections::HashMap;
+use zbus::zvariant::Value;
+
+/// Images are scaled down to fit a square of this size
+pub const MAX_IMAGE_SIZE: f32 = 64.0;
+/// Lines of the body shown in the popup
+pub const BODY_LINES: usize = 3;
+
+/// The plain text of a body using the markup subset of the notification spec.
+/// Tags are dropped, `<br>` becomes a line break and entities are decoded. Anything that does
+/// not parse as markup is kept as it is.
+pub fn body_text(body: &str) -> String {
+    let mut res = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(i) = rest.find(|c| c == '<' || c == '&') {
+        res.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(tag) = rest.strip_prefix('<') {
+            // a lone `<` is not a tag
+            if !tag.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+                res.push('<');
+                rest = tag;
+                continue;
+            }
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let tag = rest[1..end].trim().trim_end_matches('/').trim_end();
+            if tag.eq_ignore_ascii_case("br") {
+                res.push('\n');
+            }
+            rest = &rest[end + 1..];
+        } else {
+            let entity = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+                let c = match &rest[1..end] {
+                    "amp" => '&',
+                    "lt" => '<',
+                    "gt" => '>',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "nbsp" => '\u{a0}',
+                    num => {
+                        let code = match num.strip_prefix("#x").or(num.strip_prefix("#X")) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => num.strip_prefix('#')?.parse().ok()?,
+                        };
+                        char::from_u32(code)?
+                    }
+                };
+                Some((c, end))
+            });
+            match entity {
+                Some((c, end)) => {
+                    res.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    res.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+/// The first lines of the body, without markup
+pub fn body_preview(body: &str) -> String {
+    body_text(body)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .take(BODY_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The size to show an image at, keeping its aspect ratio
+pub fn clamp_size(width: u32, height: u32) -> (f32, f32) {
+    let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+    let scale = (MAX_IMAGE_SIZE / width.max(height)).min(1.0);
+    (width * scale, height * scale)
+}
+
+/// Whether raw RGBA image data matches its size, broken hints are not drawn
+pub fn is_valid_image(width: u32, height: u32, data: &[u8]) -> bool {
+    width > 0 && height > 0 && data.len() as u64 == u64::from(width) * u64::from(height) * 4
+}
+
+/// The percentage of the `value` hint, used by downloads and other long running tasks
+pub fn progress(hints: &HashMap<&str, Value<'_>>) -> Option<u8> {
+    let value = match hints.get("value")? {
+        Value::I32(v) => i64::from(*v),
+        Value::U32(v) => i64::from(*v),
+        Value::I64(v) => *v,
+        Value::U8(v) => i64::from(*v),
+        _ => return None,
+    };
+    Some(value.clamp(0, 100) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_breaks() {
+        assert_eq!(
+            body_text("one<br>two<br/>three<BR />four"),
+            "one\ntwo\nthree\nfour"
+        );
+    }
+
+    #[test]
+    fn nested_tags() {
+        assert_eq!(
+            body_text("<b><i>bold</i> and <a href=\"https://example.com\">link</a></b>"),
+            "bold and link"
+        );
+    }
+
+    #[test]
+    fn lone_less_than() {
+        assert_eq!(body_text("1 < 2"), "1 < 2");
+        assert_eq!(body_text("a <3"), "a <3");
+        assert_eq!(body_text("trailing <"), "trailing <");
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(body_text("&lt;b&gt; &amp; &quot;"), "<b> & \"");
+        assert_eq!(body_text("&#65;&#x263A;&#X42;"), "A\u{263a}B");
+        // not a character
+        assert_eq!(body_text("&#xZZ;"), "&#xZZ;");
+    }
+
+    #[test]
+    fn unterminated_ampersand() {
+        assert_eq!(body_text("Tom & Jerry"), "Tom & Jerry");
+        assert_eq!(body_text("AT&T; more"), "AT&T; more");
+        assert_eq!(body_text("ends with &amp"), "ends with &amp");
+    }
+}
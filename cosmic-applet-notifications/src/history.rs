@@ -0,0 +1,150 @@
+use crate::config::{HistoryRetention, APP_ID};
+use cosmic_notifications_util::Notification;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use xdg::BaseDirectories;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unique in the history, unlike notification ids which start over with the daemon
+    pub key: u64,
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub time: SystemTime,
+    /// Sent with the `transient` hint, kept for this session only
+    #[serde(skip)]
+    pub transient: bool,
+}
+
+impl HistoryEntry {
+    pub fn duration_since(&self) -> Option<Duration> {
+        SystemTime::now().duration_since(self.time).ok()
+    }
+}
+
+/// Notifications that were dismissed or closed before they were read, oldest first.
+/// Saved to disk on every change, so it survives restarts and crashes of the applet.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    retention: HistoryRetention,
+}
+
+impl History {
+    fn path() -> Option<PathBuf> {
+        BaseDirectories::with_prefix(APP_ID)
+            .ok()
+            .and_then(|dirs| dirs.place_data_file("history.ron").ok())
+    }
+
+    pub fn load(retention: HistoryRetention) -> Self {
+        let entries = Self::path()
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| ron::de::from_reader(file).ok())
+            .unwrap_or_default();
+        let mut history = Self { entries, retention };
+        history.apply_retention();
+        history
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let entries: Vec<_> = self.entries.iter().filter(|e| !e.transient).collect();
+        // write a new file and swap it in, so a crash can't leave half of the history behind
+        let tmp = path.with_extension("ron.tmp");
+        let res = File::create(&tmp)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(ron::ser::to_writer(file, &entries)?))
+            .and_then(|_| Ok(fs::rename(&tmp, &path)?));
+        if let Err(err) = res {
+            tracing::error!("Failed to save notification history: {}", err);
+        }
+    }
+
+    pub fn set_retention(&mut self, retention: HistoryRetention) {
+        if self.retention != retention {
+            self.retention = retention;
+            self.apply_retention();
+            self.save();
+        }
+    }
+
+    fn apply_retention(&mut self) {
+        if let Some(max_age) = self.retention.max_age() {
+            self.entries
+                .retain(|e| e.duration_since().map_or(true, |d| d <= max_age));
+        }
+        let excess = self
+            .entries
+            .len()
+            .saturating_sub(self.retention.max_entries as usize);
+        self.entries.drain(..excess);
+    }
+
+    pub fn push(&mut self, n: &Notification, transient: bool) {
+        let key = self.entries.last().map_or(0, |e| e.key + 1);
+        self.entries.push(HistoryEntry {
+            key,
+            app_name: n.app_name.clone(),
+            app_icon: n.app_icon.clone(),
+            summary: n.summary.clone(),
+            body: n.body.clone(),
+            time: n
+                .duration_since()
+                .and_then(|d| SystemTime::now().checked_sub(d))
+                .unwrap_or_else(SystemTime::now),
+            transient,
+        });
+        self.apply_retention();
+        if !transient {
+            self.save();
+        }
+    }
+
+    pub fn get(&self, key: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|e| e.key == key)
+    }
+
+    pub fn remove(&mut self, key: u64) {
+        self.entries.retain(|e| e.key != key);
+        self.save();
+    }
+
+    pub fn clear_app(&mut self, app_name: &str) {
+        self.entries.retain(|e| e.app_name != app_name);
+        self.save();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries grouped by app, the app with the latest entry first and the newest entries first
+    pub fn by_app(&self) -> Vec<(&str, Vec<&HistoryEntry>)> {
+        let mut groups: Vec<(&str, Vec<&HistoryEntry>)> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            match groups.iter_mut().find(|(name, _)| *name == entry.app_name) {
+                Some((_, entries)) => entries.push(entry),
+                None => groups.push((&entry.app_name, vec![entry])),
+            }
+        }
+        groups
+    }
+}
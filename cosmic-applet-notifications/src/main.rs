@@ -1,12 +1,23 @@
+mod config;
+mod content;
+mod history;
 mod localize;
+mod screen_sharing;
+mod snooze;
+mod sound;
+mod stats;
 mod subscriptions;
 
-use cosmic::app::{applet::applet_button_theme, Command};
+use cosmic::app::{
+    applet::{applet_button_theme, cosmic_panel_config::PanelAnchor},
+    Command,
+};
 use cosmic::cosmic_config::{config_subscription, Config, CosmicConfigEntry};
 use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
 use cosmic::iced::Limits;
 use cosmic::iced::{
-    widget::{button, column, row, text, Row},
+    time,
+    widget::{button, column, progress_bar, row, text, text_input, Row},
     window, Alignment, Length, Subscription,
 };
 use cosmic::iced_core::alignment::Horizontal;
@@ -17,19 +28,28 @@ use cosmic::iced_style::application;
 
 use cosmic::iced_widget::{horizontal_rule, scrollable, Column};
 use cosmic::theme::Svg;
-use cosmic::widget::{container, icon};
+use cosmic::widget::{container, icon, toggler};
 use cosmic::Renderer;
-use cosmic::{Element, Theme};
+use cosmic::{Application, Element, Theme};
 use cosmic_notifications_config::NotificationsConfig;
 use cosmic_notifications_util::{Image, Notification};
 use cosmic_time::{anim, chain, id, once_cell::sync::Lazy, Instant, Timeline};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 use tokio::sync::mpsc::Sender;
 use tracing::info;
 
+use crate::config::{
+    format_minutes, parse_minutes, AppNotificationSettings, AppletConfig, HistoryRetention,
+};
+use crate::history::History;
+use crate::snooze::{SnoozeQueue, Snoozed, SNOOZE_MINUTES};
+use crate::stats::{week_key, NotificationStats};
+use crate::subscriptions::notifications::{Details, Urgency};
+
 #[tokio::main(flavor = "current_thread")]
 pub async fn main() -> cosmic::iced::Result {
     tracing_subscriber::fmt::init();
@@ -42,6 +62,13 @@ pub async fn main() -> cosmic::iced::Result {
 }
 
 static DO_NOT_DISTURB: Lazy<id::Toggler> = Lazy::new(id::Toggler::unique);
+/// Expanded stacks that grow past this are collapsed again, e.g. for busy chats
+const AUTO_COLLAPSE_THRESHOLD: usize = 10;
+/// Larger unread counts show as `9+` on the panel icon
+const MAX_UNREAD_SHOWN: usize = 9;
+/// Statistics changed by incoming notifications are saved at most this often
+const STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Default)]
 struct Notifications {
     core: cosmic::app::Core,
@@ -53,7 +80,36 @@ struct Notifications {
     // notifications: Vec<Notification>,
     timeline: Timeline,
     dbus_sender: Option<Sender<subscriptions::dbus::Input>>,
+    /// Progress of notifications with a `value` hint, in percent
+    progress: HashMap<u32, u8>,
     cards: Vec<(id::Cards, Vec<Notification>, bool, String)>,
+    applet_config: AppletConfig,
+    stats: NotificationStats,
+    /// Notifications that arrived since the popup was last opened, with their app name
+    unread: HashMap<u32, String>,
+    /// The user turned Do Not Disturb off while it was wanted on automatically
+    dnd_overridden: bool,
+    screen_sharing: bool,
+    dnd_start_input: String,
+    dnd_end_input: String,
+    history: History,
+    show_history: bool,
+    history_max_entries_input: String,
+    history_max_days_input: String,
+    /// Notifications sent with the `transient` hint, they are not saved in the history
+    transient: HashSet<u32>,
+    snoozed: SnoozeQueue,
+    /// The notification or history entry whose snooze durations are shown
+    snooze_menu: Option<SnoozeTarget>,
+    show_app_settings: bool,
+    fullscreen: bool,
+    /// Notifications that arrived while Do Not Disturb was on for a fullscreen window, with their
+    /// app name
+    held: Vec<(u32, String)>,
+    show_urgency_settings: bool,
+    api_sender: Option<Sender<subscriptions::applet_api::State>>,
+    /// The state last published on D-Bus
+    api_state: Option<subscriptions::applet_api::State>,
 }
 
 impl Notifications {
@@ -79,6 +135,185 @@ impl Notifications {
         }
         .to_string();
     }
+
+    fn set_do_not_disturb(&mut self, do_not_disturb: bool) {
+        let chain = if do_not_disturb {
+            chain::Toggler::on(DO_NOT_DISTURB.clone(), 1.)
+        } else {
+            chain::Toggler::off(DO_NOT_DISTURB.clone(), 1.)
+        };
+        self.timeline.set_chain(chain).start();
+        self.config.do_not_disturb = do_not_disturb;
+        if let Some(helper) = &self.config_helper {
+            if let Err(err) = self.config.write_entry(helper) {
+                tracing::error!("{:?}", err);
+            }
+        }
+    }
+
+    fn set_automatic_dnd(&mut self, automatic_dnd: bool) {
+        if self.applet_config.automatic_dnd == automatic_dnd {
+            return;
+        }
+        match Config::new(config::APP_ID, 1) {
+            Ok(config) => self.applet_config.set_automatic_dnd(automatic_dnd, &config),
+            Err(err) => {
+                tracing::error!("{:?}", err);
+                self.applet_config.automatic_dnd = automatic_dnd;
+            }
+        }
+    }
+
+    /// Whether the schedule, screen sharing or a fullscreen window wants Do Not Disturb on.
+    /// The daemon doesn't show any banners while it is on, critical ones included, which is how
+    /// fullscreen windows are kept free of them.
+    fn wants_dnd(&self) -> bool {
+        self.applet_config
+            .in_dnd_schedule(chrono::Local::now().time())
+            || (self.applet_config.dnd_screen_sharing && self.screen_sharing)
+            || (self.applet_config.hold_fullscreen_banners && self.fullscreen)
+    }
+
+    /// Turn Do Not Disturb on when the schedule, screen sharing or fullscreen starts, and off
+    /// again when it ends, unless the user changed it in between
+    fn update_automatic_dnd(&mut self) {
+        if !self.wants_dnd() {
+            self.dnd_overridden = false;
+            if self.applet_config.automatic_dnd {
+                self.set_automatic_dnd(false);
+                self.set_do_not_disturb(false);
+            }
+        } else if !self.dnd_overridden && !self.config.do_not_disturb {
+            self.set_automatic_dnd(true);
+            self.set_do_not_disturb(true);
+        }
+    }
+
+    /// Keep a notification that is about to be removed from the cards in the history
+    fn archive(&mut self, id: u32) {
+        if let Some(n) = self
+            .cards
+            .iter()
+            .flat_map(|c| c.1.iter())
+            .find(|n| n.id == id)
+        {
+            self.history.push(n, self.transient.remove(&id));
+        }
+    }
+
+    /// Close the notification in the daemon
+    fn dismiss(&mut self, id: u32) {
+        if let Some(tx) = &self.dbus_sender {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Err(err) = tx.send(subscriptions::dbus::Input::Dismiss(id)).await {
+                    tracing::error!("{:?}", err);
+                }
+            });
+        }
+    }
+
+    /// Publish the state on D-Bus if it changed
+    fn publish_state(&mut self) {
+        let Some(tx) = self.api_sender.clone() else {
+            return;
+        };
+        let state = subscriptions::applet_api::State {
+            unread: self.unread.len() as u32,
+            do_not_disturb: self.config.do_not_disturb,
+            history: self.history.len() as u32,
+        };
+        if self.api_state == Some(state) {
+            return;
+        }
+        self.api_state = Some(state);
+        tokio::spawn(async move {
+            if let Err(err) = tx.send(state).await {
+                tracing::error!("{:?}", err);
+            }
+        });
+    }
+
+    /// Count a notification that goes away as dismissed unread if the popup was not opened since
+    /// it arrived
+    fn notification_closed(&mut self, id: u32) {
+        if let Some(app_name) = self.unread.remove(&id) {
+            self.stats.dismissed_unread(&app_name);
+        }
+        self.progress.remove(&id);
+    }
+
+    /// Send the digest of last week once, when it is enabled and there is something to report
+    fn check_digest(&mut self) {
+        if !self.applet_config.weekly_digest {
+            return;
+        }
+        let week = week_key(1);
+        if self.stats.last_digest.as_ref() == Some(&week) {
+            return;
+        }
+        let Some(summary) = self.stats.summary(&week).filter(|s| s.received > 0) else {
+            return;
+        };
+        let top_apps = summary
+            .apps
+            .iter()
+            .take(3)
+            .map(|(name, s)| format!("{name} ({})", s.received))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let body = fl!(
+            "digest-body",
+            apps = top_apps,
+            unread = summary.dismissed_unread
+        );
+        let summary = fl!("digest-summary", count = summary.received);
+        if !self.notify(summary, body) {
+            return;
+        }
+        self.stats.last_digest = Some(week);
+        self.stats.save();
+    }
+
+    /// Send a notification from the applet itself, returns false if the daemon isn't connected
+    fn notify(&self, summary: String, body: String) -> bool {
+        let Some(tx) = self.dbus_sender.clone() else {
+            return false;
+        };
+        tokio::spawn(async move {
+            if let Err(err) = tx
+                .send(subscriptions::dbus::Input::Notify(summary, body))
+                .await
+            {
+                tracing::error!("{:?}", err);
+            }
+        });
+        true
+    }
+
+    /// Summarize the notifications that were held while a window was fullscreen
+    fn release_held(&mut self) {
+        let held = std::mem::take(&mut self.held);
+        if held.is_empty() || !self.applet_config.fullscreen_summary || self.config.do_not_disturb {
+            return;
+        }
+        let mut apps: Vec<&str> = Vec::new();
+        for (_, app_name) in &held {
+            if !apps.contains(&app_name.as_str()) {
+                apps.push(app_name);
+            }
+        }
+        let summary = fl!("fullscreen-summary", count = held.len());
+        let body = apps.join(", ");
+        self.notify(summary, body);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnoozeTarget {
+    Notification(u32),
+    /// The key of a history entry
+    History(u64),
 }
 
 #[derive(Debug, Clone)]
@@ -87,19 +322,48 @@ enum Message {
     DoNotDisturb(chain::Toggler, bool),
     Settings,
     Frame(Instant),
-    NotificationEvent(Notification),
+    NotificationEvent(Notification, Details),
     Config(NotificationsConfig),
     DbusEvent(subscriptions::dbus::Output),
     Dismissed(u32),
     ClearAll(String),
     CardsToggled(String, bool),
+    ToggleWeeklyDigest(bool),
+    AppletConfig(AppletConfig),
+    CheckDigest,
+    ToggleDndSchedule(bool),
+    DndStartInput(String),
+    DndEndInput(String),
+    SubmitDndTimes,
+    ToggleDndScreenSharing(bool),
+    CheckDnd,
+    ScreenSharing(bool),
+    ToggleHistory,
+    DismissHistory(u64),
+    HistoryMaxEntriesInput(String),
+    HistoryMaxDaysInput(String),
+    SubmitHistoryRetention,
+    ClearHistoryApp(String),
+    ClearHistory,
+    ToggleHoldFullscreenBanners(bool),
+    ToggleFullscreenSummary(bool),
+    Fullscreen(bool),
+    ToggleSnoozeMenu(SnoozeTarget),
+    Snooze(SnoozeTarget, u64),
+    CheckSnoozed,
+    SaveStats,
+    Api(subscriptions::applet_api::Output),
+    ToggleUrgencySettings,
+    SetUrgencySound(Urgency, bool),
+    ToggleAppSettings,
+    SetAppSettings(String, AppNotificationSettings),
 }
 
-impl cosmic::Application for Notifications {
+impl Application for Notifications {
     type Message = Message;
     type Executor = cosmic::SingleThreadExecutor;
     type Flags = ();
-    const APP_ID: &'static str = "com.system76.CosmicAppletNotifications";
+    const APP_ID: &'static str = config::APP_ID;
 
     fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
         let helper = Config::new(
@@ -119,14 +383,24 @@ impl cosmic::Application for Notifications {
                 })
             })
             .unwrap_or_default();
+        let applet_config = AppletConfig::load();
         let mut _self = Notifications {
             core,
             config_helper: helper,
             config,
+            dnd_start_input: format_minutes(applet_config.dnd_start),
+            dnd_end_input: format_minutes(applet_config.dnd_end),
+            history: History::load(applet_config.history_retention),
+            history_max_entries_input: applet_config.history_retention.max_entries.to_string(),
+            history_max_days_input: applet_config.history_retention.max_days.to_string(),
+            applet_config,
+            stats: NotificationStats::load(),
+            snoozed: SnoozeQueue::load(),
             ..Default::default()
         };
         _self.update_icon();
-        (_self, Command::none())
+        let check_dnd = _self.update(Message::CheckDnd);
+        (_self, check_dnd)
     }
 
     fn core(&self) -> &cosmic::app::Core {
@@ -142,7 +416,7 @@ impl cosmic::Application for Notifications {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             config_subscription::<u64, NotificationsConfig>(
                 0,
                 cosmic_notifications_config::ID.into(),
@@ -161,8 +435,38 @@ impl cosmic::Application for Notifications {
                 .as_subscription()
                 .map(|(_, now)| Message::Frame(now)),
             subscriptions::dbus::proxy().map(Message::DbusEvent),
-            subscriptions::notifications::notifications().map(Message::NotificationEvent),
-        ])
+            subscriptions::applet_api::applet_api().map(Message::Api),
+            subscriptions::notifications::notifications()
+                .map(|(n, details)| Message::NotificationEvent(n, details)),
+            config_subscription::<u64, AppletConfig>(0, config::APP_ID.into(), 1).map(
+                |(_, res)| match res {
+                    Ok(config) => Message::AppletConfig(config),
+                    Err((errors, config)) => {
+                        for err in errors {
+                            tracing::error!("{:?}", err);
+                        }
+                        Message::AppletConfig(config)
+                    }
+                },
+            ),
+        ];
+        if self.applet_config.dnd_schedule || self.applet_config.dnd_screen_sharing {
+            subscriptions.push(time::every(Duration::from_secs(30)).map(|_| Message::CheckDnd));
+        }
+        if self.applet_config.hold_fullscreen_banners {
+            subscriptions.push(subscriptions::fullscreen::fullscreen().map(Message::Fullscreen));
+        }
+        if !self.snoozed.is_empty() {
+            subscriptions.push(time::every(Duration::from_secs(15)).map(|_| Message::CheckSnoozed));
+        }
+        if self.applet_config.weekly_digest {
+            subscriptions
+                .push(time::every(Duration::from_secs(60 * 60)).map(|_| Message::CheckDigest));
+        }
+        if self.stats.has_unsaved() {
+            subscriptions.push(time::every(STATS_SAVE_INTERVAL).map(|_| Message::SaveStats));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
@@ -174,6 +478,9 @@ impl cosmic::Application for Notifications {
                 if let Some(p) = self.popup.take() {
                     return destroy_popup(p);
                 } else {
+                    // everything in the list has been seen now
+                    self.unread.clear();
+                    self.publish_state();
                     self.id_ctr += 1;
                     let new_id = window::Id(self.id_ctr);
                     self.popup.replace(new_id);
@@ -194,6 +501,9 @@ impl cosmic::Application for Notifications {
                 }
             }
             Message::DoNotDisturb(chain, b) => {
+                // a manual change wins over the schedule until it ends
+                self.dnd_overridden = self.wants_dnd() || self.applet_config.automatic_dnd;
+                self.set_automatic_dnd(false);
                 self.timeline.set_chain(chain).start();
                 self.config.do_not_disturb = b;
                 if let Some(helper) = &self.config_helper {
@@ -205,7 +515,35 @@ impl cosmic::Application for Notifications {
             Message::Settings => {
                 let _ = process::Command::new("cosmic-settings notifications").spawn();
             }
-            Message::NotificationEvent(n) => {
+            Message::NotificationEvent(n, details) => {
+                if details.transient {
+                    self.transient.insert(n.id);
+                }
+                match details.progress {
+                    Some(progress) => self.progress.insert(n.id, progress),
+                    None => self.progress.remove(&n.id),
+                };
+                let is_new = !self
+                    .cards
+                    .iter()
+                    .any(|c| c.1.iter().any(|notif| notif.id == n.id));
+                if is_new {
+                    self.stats.received(&n.app_name);
+                    if self.popup.is_none() {
+                        self.unread.insert(n.id, n.app_name.clone());
+                    }
+                    let settings = self.applet_config.app(&n.app_name);
+                    if self.fullscreen
+                        && self.applet_config.automatic_dnd
+                        && self.config.do_not_disturb
+                    {
+                        self.held.push((n.id, n.app_name.clone()));
+                    }
+                    let urgency = self.applet_config.urgency(details.urgency);
+                    if settings.sounds && urgency.sound && !self.config.do_not_disturb {
+                        sound::play();
+                    }
+                }
                 if let Some(c) = self
                     .cards
                     .iter_mut()
@@ -219,6 +557,11 @@ impl cosmic::Application for Notifications {
                             "show-more",
                             HashMap::from_iter(vec![("more", c.1.len().saturating_sub(1))])
                         );
+                        if c.2 && c.1.len() > AUTO_COLLAPSE_THRESHOLD {
+                            c.2 = false;
+                            let id = c.0.clone();
+                            self.update_cards(id);
+                        }
                     }
                 } else {
                     self.cards.push((
@@ -234,25 +577,24 @@ impl cosmic::Application for Notifications {
             }
             Message::Dismissed(id) => {
                 info!("Dismissed {}", id);
+                self.notification_closed(id);
+                self.archive(id);
                 for c in &mut self.cards {
                     c.1.retain(|n| n.id != id);
                 }
                 self.cards.retain(|c| !c.1.is_empty());
-
-                if let Some(tx) = &self.dbus_sender {
-                    let tx = tx.clone();
-                    tokio::spawn(async move {
-                        if let Err(err) = tx.send(subscriptions::dbus::Input::Dismiss(id)).await {
-                            tracing::error!("{:?}", err);
-                        }
-                    });
-                }
+                self.dismiss(id);
             }
             Message::DbusEvent(e) => match e {
                 subscriptions::dbus::Output::Ready(tx) => {
                     self.dbus_sender.replace(tx);
+                    self.check_digest();
+                    // deliver what became due while the applet wasn't running
+                    return self.update(Message::CheckSnoozed);
                 }
                 subscriptions::dbus::Output::CloseEvent(id) => {
+                    self.notification_closed(id);
+                    self.archive(id);
                     for c in &mut self.cards {
                         c.1.retain(|n| n.id != id);
                         c.3 = fl!(
@@ -270,16 +612,10 @@ impl cosmic::Application for Notifications {
                     .position(|c| c.1.iter().any(|notif| app_name == notif.app_name))
                 {
                     for n in self.cards.remove(pos).1 {
-                        if let Some(tx) = &self.dbus_sender {
-                            let tx = tx.clone();
-                            tokio::spawn(async move {
-                                if let Err(err) =
-                                    tx.send(subscriptions::dbus::Input::Dismiss(n.id)).await
-                                {
-                                    tracing::error!("{:?}", err);
-                                }
-                            });
-                        }
+                        self.notification_closed(n.id);
+                        let transient = self.transient.remove(&n.id);
+                        self.history.push(&n, transient);
+                        self.dismiss(n.id);
                     }
                 }
             }
@@ -296,15 +632,256 @@ impl cosmic::Application for Notifications {
                 };
                 self.update_cards(id);
             }
+            Message::ToggleWeeklyDigest(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config.set_weekly_digest(enabled, &config);
+                }
+                self.check_digest();
+            }
+            Message::AppletConfig(config) => {
+                self.dnd_start_input = format_minutes(config.dnd_start);
+                self.dnd_end_input = format_minutes(config.dnd_end);
+                self.history_max_entries_input = config.history_retention.max_entries.to_string();
+                self.history_max_days_input = config.history_retention.max_days.to_string();
+                self.history.set_retention(config.history_retention);
+                self.applet_config = config;
+            }
+            Message::ToggleDndSchedule(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config.set_dnd_schedule(enabled, &config);
+                }
+                return self.update(Message::CheckDnd);
+            }
+            Message::DndStartInput(input) => {
+                self.dnd_start_input = input;
+            }
+            Message::DndEndInput(input) => {
+                self.dnd_end_input = input;
+            }
+            Message::SubmitDndTimes => {
+                let start = parse_minutes(&self.dnd_start_input);
+                let end = parse_minutes(&self.dnd_end_input);
+                if let (Some(start), Some(end), Ok(config)) =
+                    (start, end, Config::new(config::APP_ID, 1))
+                {
+                    self.applet_config.set_dnd_times(start, end, &config);
+                }
+                self.dnd_start_input = format_minutes(self.applet_config.dnd_start);
+                self.dnd_end_input = format_minutes(self.applet_config.dnd_end);
+                return self.update(Message::CheckDnd);
+            }
+            Message::ToggleDndScreenSharing(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config.set_dnd_screen_sharing(enabled, &config);
+                }
+                return self.update(Message::CheckDnd);
+            }
+            Message::CheckDnd => {
+                if self.applet_config.dnd_screen_sharing {
+                    return cosmic::iced::Command::perform(screen_sharing::is_sharing(), |s| {
+                        cosmic::app::message::app(Message::ScreenSharing(s))
+                    });
+                }
+                self.screen_sharing = false;
+                self.update_automatic_dnd();
+            }
+            Message::ScreenSharing(sharing) => {
+                self.screen_sharing = sharing;
+                self.update_automatic_dnd();
+            }
+            Message::ToggleHistory => {
+                self.show_history = !self.show_history;
+            }
+            Message::DismissHistory(key) => {
+                self.history.remove(key);
+            }
+            Message::HistoryMaxEntriesInput(input) => {
+                self.history_max_entries_input = input;
+            }
+            Message::HistoryMaxDaysInput(input) => {
+                self.history_max_days_input = input;
+            }
+            Message::SubmitHistoryRetention => {
+                let max_entries = self.history_max_entries_input.trim().parse();
+                let max_days = self.history_max_days_input.trim().parse();
+                if let (Ok(max_entries), Ok(max_days), Ok(config)) =
+                    (max_entries, max_days, Config::new(config::APP_ID, 1))
+                {
+                    let retention = HistoryRetention {
+                        max_entries,
+                        max_days,
+                    };
+                    self.applet_config.set_history_retention(retention, &config);
+                    self.history.set_retention(retention);
+                }
+                self.history_max_entries_input =
+                    self.applet_config.history_retention.max_entries.to_string();
+                self.history_max_days_input =
+                    self.applet_config.history_retention.max_days.to_string();
+            }
+            Message::ClearHistoryApp(app_name) => {
+                self.history.clear_app(&app_name);
+            }
+            Message::ClearHistory => {
+                self.history.clear();
+            }
+            Message::ToggleHoldFullscreenBanners(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config
+                        .set_hold_fullscreen_banners(enabled, &config);
+                }
+                if !enabled {
+                    self.fullscreen = false;
+                    self.update_automatic_dnd();
+                    self.release_held();
+                }
+            }
+            Message::ToggleFullscreenSummary(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config.set_fullscreen_summary(enabled, &config);
+                }
+            }
+            Message::Fullscreen(fullscreen) => {
+                self.fullscreen = fullscreen;
+                self.update_automatic_dnd();
+                if !fullscreen {
+                    self.release_held();
+                }
+            }
+            Message::ToggleSnoozeMenu(target) => {
+                self.snooze_menu = if self.snooze_menu == Some(target) {
+                    None
+                } else {
+                    Some(target)
+                };
+            }
+            Message::Snooze(target, minutes) => {
+                self.snooze_menu = None;
+                match target {
+                    SnoozeTarget::Notification(id) => {
+                        let Some(n) = self
+                            .cards
+                            .iter()
+                            .flat_map(|c| c.1.iter())
+                            .find(|n| n.id == id)
+                        else {
+                            return Command::none();
+                        };
+                        self.snoozed.push(Snoozed::from_notification(n, minutes));
+                        self.notification_closed(id);
+                        self.transient.remove(&id);
+                        for c in &mut self.cards {
+                            c.1.retain(|n| n.id != id);
+                        }
+                        self.cards.retain(|c| !c.1.is_empty());
+                        self.dismiss(id);
+                    }
+                    SnoozeTarget::History(key) => {
+                        let Some(entry) = self.history.get(key) else {
+                            return Command::none();
+                        };
+                        self.snoozed.push(Snoozed::from_history(entry, minutes));
+                        self.history.remove(key);
+                    }
+                }
+            }
+            Message::SaveStats => {
+                self.stats.save();
+            }
+            Message::CheckSnoozed => {
+                if let Some(tx) = self.dbus_sender.clone() {
+                    for snoozed in self.snoozed.take_due() {
+                        let tx = tx.clone();
+                        tokio::spawn(async move {
+                            if let Err(err) = tx
+                                .send(subscriptions::dbus::Input::Redeliver(snoozed))
+                                .await
+                            {
+                                tracing::error!("{:?}", err);
+                            }
+                        });
+                    }
+                }
+            }
+            Message::Api(subscriptions::applet_api::Output::Ready(tx)) => {
+                self.api_sender = Some(tx);
+                self.api_state = None;
+            }
+            Message::Api(subscriptions::applet_api::Output::Request(request)) => {
+                use subscriptions::applet_api::Request;
+                match request {
+                    Request::SetDoNotDisturb(enabled) => {
+                        self.dnd_overridden = self.wants_dnd() || self.applet_config.automatic_dnd;
+                        self.set_automatic_dnd(false);
+                        self.set_do_not_disturb(enabled);
+                    }
+                    Request::ToggleDoNotDisturb => {
+                        self.dnd_overridden = self.wants_dnd() || self.applet_config.automatic_dnd;
+                        self.set_automatic_dnd(false);
+                        self.set_do_not_disturb(!self.config.do_not_disturb);
+                    }
+                    Request::ClearHistory => self.history.clear(),
+                }
+            }
+            Message::ToggleUrgencySettings => {
+                self.show_urgency_settings = !self.show_urgency_settings;
+            }
+            Message::SetUrgencySound(urgency, sound) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config.set_urgency(
+                        urgency,
+                        config::UrgencySettings { sound },
+                        &config,
+                    );
+                }
+            }
+            Message::ToggleAppSettings => {
+                self.show_app_settings = !self.show_app_settings;
+            }
+            Message::SetAppSettings(app_name, settings) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.applet_config.set_app(app_name, settings, &config);
+                }
+            }
+            Message::CheckDigest => {
+                self.check_digest();
+            }
         };
         self.update_icon();
+        self.publish_state();
         Command::none()
     }
 
     fn view(&self) -> Element<Message> {
-        self.core
-            .applet_helper
-            .icon_button(&self.icon_name)
+        if self.unread.is_empty() {
+            return self
+                .core
+                .applet_helper
+                .icon_button(&self.icon_name)
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+
+        let icon_size = self.core.applet_helper.suggested_size().0;
+        let unread = if self.unread.len() > MAX_UNREAD_SHOWN {
+            format!("{MAX_UNREAD_SHOWN}+")
+        } else {
+            self.unread.len().to_string()
+        };
+        let content = vec![text_icon(&self.icon_name, icon_size).into(), badge(unread)];
+        let content: Element<Message> = match self.core.applet_helper.anchor {
+            PanelAnchor::Top | PanelAnchor::Bottom => Row::with_children(content)
+                .align_items(Alignment::Center)
+                .spacing(2)
+                .into(),
+            PanelAnchor::Left | PanelAnchor::Right => Column::with_children(content)
+                .align_items(Alignment::Center)
+                .spacing(2)
+                .into(),
+        };
+        cosmic::widget::button(applet_button_theme())
+            .custom(vec![content])
+            .padding(8)
             .on_press(Message::TogglePopup)
             .into()
     }
@@ -320,6 +897,66 @@ impl cosmic::Application for Notifications {
         .width(Length::Fill)]
         .padding([0, 24]);
 
+        let mut dnd_automation = column![toggler(
+            fl!("dnd-schedule"),
+            self.applet_config.dnd_schedule,
+            Message::ToggleDndSchedule
+        )
+        .text_size(14)
+        .width(Length::Fill)]
+        .spacing(8)
+        .padding([0, 24]);
+        if self.applet_config.dnd_schedule {
+            dnd_automation = dnd_automation.push(
+                row![
+                    text(fl!("dnd-from")).size(14),
+                    text_input("HH:MM", &self.dnd_start_input)
+                        .on_input(Message::DndStartInput)
+                        .on_submit(Message::SubmitDndTimes)
+                        .size(14)
+                        .width(Length::Fixed(72.0)),
+                    text(fl!("dnd-to")).size(14),
+                    text_input("HH:MM", &self.dnd_end_input)
+                        .on_input(Message::DndEndInput)
+                        .on_submit(Message::SubmitDndTimes)
+                        .size(14)
+                        .width(Length::Fixed(72.0)),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        dnd_automation = dnd_automation.push(
+            toggler(
+                fl!("dnd-screen-sharing"),
+                self.applet_config.dnd_screen_sharing,
+                Message::ToggleDndScreenSharing,
+            )
+            .text_size(14)
+            .width(Length::Fill),
+        );
+
+        let mut fullscreen = column![toggler(
+            fl!("hold-fullscreen-banners"),
+            self.applet_config.hold_fullscreen_banners,
+            Message::ToggleHoldFullscreenBanners
+        )
+        .text_size(14)
+        .width(Length::Fill)]
+        .spacing(8)
+        .padding([0, 24]);
+        if self.applet_config.hold_fullscreen_banners {
+            fullscreen = fullscreen.push(
+                toggler(
+                    fl!("fullscreen-summary-setting"),
+                    self.applet_config.fullscreen_summary,
+                    Message::ToggleFullscreenSummary,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            );
+        }
+
         let settings =
             row_button(vec![text(fl!("notification-settings")).into()]).on_press(Message::Settings);
 
@@ -342,86 +979,85 @@ impl cosmic::Application for Notifications {
                     continue;
                 }
                 let name = c.1[0].app_name.clone();
-                let notif_elems: Vec<_> =
-                    c.1.iter()
-                        .rev()
-                        .map(|n| {
-                            let app_name = text(if n.app_name.len() > 24 {
-                                Cow::from(format!(
-                                    "{:.26}...",
-                                    n.app_name.lines().next().unwrap_or_default()
-                                ))
-                            } else {
-                                Cow::from(&n.app_name)
-                            })
-                            .size(12)
-                            .width(Length::Fill);
-
-                            let duration_since = text(duration_ago_msg(n)).size(12);
-
-                            let close_notif =
-                                button(icon("window-close-symbolic", 16).style(Svg::Symbolic))
-                                    .on_press(Message::Dismissed(n.id))
-                                    .style(cosmic::theme::Button::Text);
-                            Element::from(
-                                column!(
-                                    match n.image() {
-                                        Some(cosmic_notifications_util::Image::File(path)) => {
-                                            row![
-                                                icon(path.as_path(), 16),
-                                                app_name,
-                                                duration_since,
-                                                close_notif
-                                            ]
-                                            .spacing(8)
-                                            .align_items(Alignment::Center)
-                                        }
-                                        Some(cosmic_notifications_util::Image::Name(name)) => {
-                                            row![
-                                                icon(name.as_str(), 16),
-                                                app_name,
-                                                duration_since,
-                                                close_notif
-                                            ]
-                                            .spacing(8)
-                                            .align_items(Alignment::Center)
-                                        }
-                                        Some(cosmic_notifications_util::Image::Data {
-                                            width,
-                                            height,
-                                            data,
-                                        }) => {
-                                            let handle = image::Handle::from_pixels(
-                                                *width,
-                                                *height,
-                                                data.clone(),
-                                            );
-                                            row![
-                                                icon(handle, 16),
-                                                app_name,
-                                                duration_since,
-                                                close_notif
-                                            ]
-                                            .spacing(8)
-                                            .align_items(Alignment::Center)
-                                        }
-                                        None => row![app_name, duration_since, close_notif]
-                                            .spacing(8)
-                                            .align_items(Alignment::Center),
-                                    },
-                                    column![
-                                        text(n.summary.lines().next().unwrap_or_default())
-                                            .width(Length::Fill)
-                                            .size(14),
-                                        text(n.body.lines().next().unwrap_or_default())
-                                            .width(Length::Fill)
-                                            .size(12)
-                                    ]
-                                )
-                                .width(Length::Fill),
-                            )
+                let count = c.1.len();
+                let notif_elems: Vec<_> = c
+                    .1
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(i, n)| {
+                        let app_name = text(if n.app_name.len() > 24 {
+                            Cow::from(format!(
+                                "{:.26}...",
+                                n.app_name.lines().next().unwrap_or_default()
+                            ))
+                        } else {
+                            Cow::from(&n.app_name)
                         })
-                        .collect();
+                        .size(12)
+                        .width(Length::Fill);
+
+                        let duration_since = text(duration_ago_msg(n.duration_since())).size(12);
+
+                        let close_notif =
+                            button(icon("window-close-symbolic", 16).style(Svg::Symbolic))
+                                .on_press(Message::Dismissed(n.id))
+                                .style(cosmic::theme::Button::Text);
+                        let mut header = Row::new().spacing(8).align_items(Alignment::Center);
+                        match n.image() {
+                            Some(Image::File(path)) => {
+                                header = header.push(icon(path.as_path(), 16));
+                            }
+                            Some(Image::Name(name)) => {
+                                header = header.push(icon(name.as_str(), 16));
+                            }
+                            Some(Image::Data {
+                                width,
+                                height,
+                                data,
+                            }) if content::is_valid_image(*width, *height, data) => {
+                                let handle =
+                                    image::Handle::from_pixels(*width, *height, data.clone());
+                                header = header.push(icon(handle, 16));
+                            }
+                            Some(Image::Data { .. }) | None => {}
+                        }
+                        header = header.push(app_name);
+                        // the newest notification is the top of the stack
+                        if i == 0 && count > 1 {
+                            header = header.push(badge(count.to_string()));
+                        }
+                        header = header
+                            .push(duration_since)
+                            .push(snooze_button(SnoozeTarget::Notification(n.id)))
+                            .push(close_notif);
+                        let mut content = column![
+                            text(n.summary.lines().next().unwrap_or_default())
+                                .width(Length::Fill)
+                                .size(14),
+                            text(content::body_preview(&n.body))
+                                .width(Length::Fill)
+                                .size(12)
+                        ];
+                        if let Some(progress) = self.progress.get(&n.id) {
+                            content = content.push(
+                                progress_bar(0.0..=100.0, f32::from(*progress))
+                                    .height(Length::Fixed(4.0)),
+                            );
+                        }
+                        let content = match thumbnail(n) {
+                            Some(thumbnail) => row![content.width(Length::Fill), thumbnail]
+                                .spacing(8)
+                                .align_items(Alignment::Center),
+                            None => row![content],
+                        };
+                        let mut notif = column![header, content].width(Length::Fill);
+                        if self.snooze_menu == Some(SnoozeTarget::Notification(n.id)) {
+                            notif = notif.push(snooze_menu(SnoozeTarget::Notification(n.id)));
+                        }
+                        Element::from(notif)
+                    })
+                    .collect();
                 let show_more_icon = c.1.last().and_then(|n| {
                     info!("app_icon: {:?}", &n.app_icon);
                     if n.app_icon.is_empty() {
@@ -436,14 +1072,20 @@ impl cosmic::Application for Notifications {
                                 width,
                                 height,
                                 data,
-                            }) => Some(cosmic::widget::IconSource::Handle(icon::Handle::Image(
-                                Handle::from_pixels(width, height, data),
-                            ))),
-                            None => None,
+                            }) if content::is_valid_image(width, height, &data) => {
+                                Some(cosmic::widget::IconSource::Handle(icon::Handle::Image(
+                                    Handle::from_pixels(width, height, data),
+                                )))
+                            }
+                            Some(Image::Data { .. }) | None => None,
                         }
                     } else if let Some(path) = url::Url::parse(&n.app_icon)
                         .ok()
                         .and_then(|u| u.to_file_path().ok())
+                        .or_else(|| {
+                            let path = PathBuf::from(&n.app_icon);
+                            path.is_absolute().then_some(path)
+                        })
                     {
                         Some(cosmic::widget::IconSource::Path(Cow::Owned(path)))
                     } else {
@@ -479,15 +1121,242 @@ impl cosmic::Application for Notifications {
             .padding([0, 24])
             .spacing(12);
 
-        let content = column![do_not_disturb, main_content, settings]
-            .align_items(Alignment::Start)
-            .spacing(12)
-            .padding([16, 0]);
+        let history = self.history_view();
+        let app_settings = self.app_settings_view();
+        let urgency_settings = self.urgency_settings_view();
+
+        let mut digest = column![toggler(
+            fl!("weekly-digest"),
+            self.applet_config.weekly_digest,
+            Message::ToggleWeeklyDigest
+        )
+        .text_size(14)
+        .width(Length::Fill)]
+        .spacing(4)
+        .padding([0, 24]);
+        if self.applet_config.weekly_digest {
+            if let Some(summary) = self.stats.summary(&week_key(1)) {
+                digest = digest.push(
+                    text(fl!(
+                        "digest-last-week",
+                        count = summary.received,
+                        unread = summary.dismissed_unread
+                    ))
+                    .size(12),
+                );
+                for (name, s) in summary.apps.iter().take(5) {
+                    digest = digest.push(
+                        row![
+                            text(name).size(12).width(Length::Fill),
+                            text(s.received.to_string()).size(12),
+                        ]
+                        .padding([0, 12]),
+                    );
+                }
+            }
+        }
+
+        let content = column![
+            do_not_disturb,
+            dnd_automation,
+            fullscreen,
+            main_content,
+            history,
+            digest,
+            urgency_settings,
+            app_settings,
+            settings
+        ]
+        .align_items(Alignment::Start)
+        .spacing(12)
+        .padding([16, 0]);
 
         self.core.applet_helper.popup_container(content).into()
     }
 }
 
+impl Notifications {
+    fn history_view(&self) -> Element<Message> {
+        let expand_icon = if self.show_history {
+            "go-down-symbolic"
+        } else {
+            "go-next-symbolic"
+        };
+        let mut history = column![row_button(vec![
+            text(fl!("history", count = self.history.len()))
+                .size(14)
+                .width(Length::Fill)
+                .into(),
+            text_icon(expand_icon, 14).into(),
+        ])
+        .on_press(Message::ToggleHistory)];
+        if !self.show_history {
+            return history.into();
+        }
+        let retention = row![
+            text(fl!("history-keep")).size(12),
+            text_input("200", &self.history_max_entries_input)
+                .on_input(Message::HistoryMaxEntriesInput)
+                .on_submit(Message::SubmitHistoryRetention)
+                .size(12)
+                .width(Length::Fixed(56.0)),
+            text(fl!("history-keep-days")).size(12),
+            text_input("30", &self.history_max_days_input)
+                .on_input(Message::HistoryMaxDaysInput)
+                .on_submit(Message::SubmitHistoryRetention)
+                .size(12)
+                .width(Length::Fixed(56.0)),
+            text(fl!("history-days")).size(12),
+        ]
+        .spacing(8)
+        .padding([0, 24])
+        .align_items(Alignment::Center);
+        if self.history.is_empty() {
+            return history.push(retention).spacing(8).into();
+        }
+
+        let mut groups = column![].spacing(12);
+        for (app_name, entries) in self.history.by_app() {
+            let mut group = column![row![
+                text(app_name).size(12).width(Length::Fill),
+                button(text(fl!("clear")).size(12))
+                    .on_press(Message::ClearHistoryApp(app_name.to_string()))
+                    .style(cosmic::theme::Button::Text),
+            ]
+            .align_items(Alignment::Center)]
+            .spacing(4);
+            for entry in entries {
+                let target = SnoozeTarget::History(entry.key);
+                group = group.push(
+                    row![
+                        column![
+                            text(entry.summary.lines().next().unwrap_or_default())
+                                .width(Length::Fill)
+                                .size(14),
+                            text(
+                                content::body_text(&entry.body)
+                                    .lines()
+                                    .next()
+                                    .unwrap_or_default()
+                                    .to_string()
+                            )
+                            .width(Length::Fill)
+                            .size(12),
+                        ]
+                        .width(Length::Fill),
+                        text(duration_ago_msg(entry.duration_since())).size(12),
+                        snooze_button(target),
+                        button(icon("window-close-symbolic", 16).style(Svg::Symbolic))
+                            .on_press(Message::DismissHistory(entry.key))
+                            .style(cosmic::theme::Button::Text),
+                    ]
+                    .spacing(8)
+                    .align_items(Alignment::Center),
+                );
+                if self.snooze_menu == Some(target) {
+                    group = group.push(snooze_menu(target));
+                }
+            }
+            groups = groups.push(group);
+        }
+        history = history
+            .push(
+                container(scrollable(groups).height(Length::Shrink))
+                    .padding([0, 24])
+                    .max_height(400),
+            )
+            .push(
+                row_button(vec![text(fl!("clear-history")).size(14).into()])
+                    .on_press(Message::ClearHistory),
+            )
+            .push(retention);
+        history.spacing(8).into()
+    }
+}
+
+impl Notifications {
+    fn urgency_settings_view(&self) -> Element<Message> {
+        let expand_icon = if self.show_urgency_settings {
+            "go-down-symbolic"
+        } else {
+            "go-next-symbolic"
+        };
+        let mut urgency_settings = column![row_button(vec![
+            text(fl!("urgency-settings"))
+                .size(14)
+                .width(Length::Fill)
+                .into(),
+            text_icon(expand_icon, 14).into(),
+        ])
+        .on_press(Message::ToggleUrgencySettings)];
+        if !self.show_urgency_settings {
+            return urgency_settings.into();
+        }
+
+        let mut levels = column![].spacing(8);
+        for (urgency, label) in [
+            (Urgency::Low, fl!("urgency-low")),
+            (Urgency::Normal, fl!("urgency-normal")),
+            (Urgency::Critical, fl!("urgency-critical")),
+        ] {
+            let settings = self.applet_config.urgency(urgency);
+            levels = levels.push(
+                toggler(label, settings.sound, move |sound| {
+                    Message::SetUrgencySound(urgency, sound)
+                })
+                .text_size(12)
+                .width(Length::Fill),
+            );
+        }
+        urgency_settings = urgency_settings.push(container(levels).padding([0, 24]));
+        urgency_settings.spacing(8).into()
+    }
+
+    fn app_settings_view(&self) -> Element<Message> {
+        let expand_icon = if self.show_app_settings {
+            "go-down-symbolic"
+        } else {
+            "go-next-symbolic"
+        };
+        let mut app_settings = column![row_button(vec![
+            text(fl!("applications"))
+                .size(14)
+                .width(Length::Fill)
+                .into(),
+            text_icon(expand_icon, 14).into(),
+        ])
+        .on_press(Message::ToggleAppSettings)];
+        if !self.show_app_settings {
+            return app_settings.into();
+        }
+
+        let mut app_names = self.stats.app_names();
+        app_names.extend(self.applet_config.apps.keys().map(String::as_str));
+        let mut apps = column![].spacing(12);
+        for app_name in app_names {
+            let settings = self.applet_config.app(app_name);
+            let name = app_name.to_string();
+            apps = apps.push(
+                column![
+                    text(app_name).size(14),
+                    toggler(fl!("app-sounds"), settings.sounds, move |sounds| {
+                        Message::SetAppSettings(name.clone(), AppNotificationSettings { sounds })
+                    })
+                    .text_size(12)
+                    .width(Length::Fill),
+                ]
+                .spacing(4),
+            );
+        }
+        app_settings = app_settings.push(
+            container(scrollable(apps).height(Length::Shrink))
+                .padding([0, 24])
+                .max_height(400),
+        );
+        app_settings.spacing(8).into()
+    }
+}
+
 // todo put into libcosmic doing so will fix the row_button's border radius
 fn row_button(content: Vec<Element<Message>>) -> cosmic::iced::widget::Button<Message, Renderer> {
     button(
@@ -501,12 +1370,77 @@ fn row_button(content: Vec<Element<Message>>) -> cosmic::iced::widget::Button<Me
     .style(applet_button_theme())
 }
 
+fn snooze_button(target: SnoozeTarget) -> Element<'static, Message> {
+    button(icon("alarm-symbolic", 16).style(Svg::Symbolic))
+        .on_press(Message::ToggleSnoozeMenu(target))
+        .style(cosmic::theme::Button::Text)
+        .into()
+}
+
+fn snooze_menu(target: SnoozeTarget) -> Element<'static, Message> {
+    let mut menu = row![text(fl!("snooze-for")).size(12)]
+        .spacing(8)
+        .align_items(Alignment::Center);
+    for minutes in SNOOZE_MINUTES {
+        menu = menu.push(
+            button(text(fl!("snooze-minutes", minutes = minutes)).size(12))
+                .on_press(Message::Snooze(target, minutes)),
+        );
+    }
+    menu.into()
+}
+
+/// A preview of the image of the notification, unless it is a themed icon
+fn thumbnail(n: &Notification) -> Option<Element<'static, Message>> {
+    match n.image()? {
+        Image::File(path) => Some(
+            cosmic::iced::widget::image(image::Handle::from_path(path.clone()))
+                .width(Length::Fixed(content::MAX_IMAGE_SIZE))
+                .height(Length::Fixed(content::MAX_IMAGE_SIZE))
+                .into(),
+        ),
+        Image::Data {
+            width,
+            height,
+            data,
+        } if content::is_valid_image(*width, *height, data) => {
+            let (w, h) = content::clamp_size(*width, *height);
+            Some(
+                cosmic::iced::widget::image(image::Handle::from_pixels(
+                    *width,
+                    *height,
+                    data.clone(),
+                ))
+                .width(Length::Fixed(w))
+                .height(Length::Fixed(h))
+                .into(),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn badge(label: String) -> Element<'static, Message> {
+    container(text(label).size(10))
+        .padding([0, 6])
+        .style(cosmic::theme::Container::custom(|theme| {
+            cosmic::iced_style::container::Appearance {
+                text_color: Some(theme.cosmic().accent.on.into()),
+                background: Some(cosmic::iced::Color::from(theme.cosmic().accent_color()).into()),
+                border_radius: 8.0.into(),
+                border_width: 0.0,
+                border_color: cosmic::iced::Color::TRANSPARENT,
+            }
+        }))
+        .into()
+}
+
 fn text_icon(name: &str, size: u16) -> cosmic::widget::Icon {
     icon(name, size).style(Svg::Symbolic)
 }
 
-fn duration_ago_msg(notification: &Notification) -> String {
-    if let Some(d) = notification.duration_since() {
+fn duration_ago_msg(duration_since: Option<Duration>) -> String {
+    if let Some(d) = duration_since {
         let min = d.as_secs() / 60;
         let hrs = min / 60;
         if hrs > 0 {
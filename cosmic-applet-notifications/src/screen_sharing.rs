@@ -0,0 +1,43 @@
+//! Screen sharing detection, through the PipeWire video streams the screencast portal creates
+
+use serde::Deserialize;
+use tokio::process::Command;
+
+#[derive(Debug, Deserialize)]
+struct Object {
+    #[serde(rename = "type")]
+    type_: String,
+    info: Option<Info>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Info {
+    state: Option<String>,
+    #[serde(default)]
+    props: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Whether a screen or window is being captured right now.
+/// Screencast streams are video sources without a device behind them, unlike cameras.
+pub async fn is_sharing() -> bool {
+    let output = match Command::new("pw-dump").output().await {
+        Ok(output) if output.status.success() => output,
+        Ok(_) => return false,
+        Err(err) => {
+            tracing::warn!("Failed to list the PipeWire nodes: {}", err);
+            return false;
+        }
+    };
+    let Ok(objects) = serde_json::from_slice::<Vec<Object>>(&output.stdout) else {
+        return false;
+    };
+    objects
+        .iter()
+        .filter(|o| o.type_ == "PipeWire:Interface:Node")
+        .filter_map(|o| o.info.as_ref())
+        .any(|info| {
+            info.state.as_deref() == Some("running")
+                && info.props.get("media.class").and_then(|c| c.as_str()) == Some("Video/Source")
+                && !info.props.contains_key("device.api")
+        })
+}
@@ -0,0 +1,108 @@
+use crate::{config::APP_ID, history::HistoryEntry};
+use cosmic_notifications_util::Notification;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use xdg::BaseDirectories;
+
+/// The snooze durations offered, in minutes
+pub const SNOOZE_MINUTES: [u64; 3] = [5, 15, 60];
+
+/// A notification to send again once it is due
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snoozed {
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub due: SystemTime,
+}
+
+impl Snoozed {
+    pub fn from_notification(n: &Notification, minutes: u64) -> Self {
+        Self {
+            app_name: n.app_name.clone(),
+            app_icon: n.app_icon.clone(),
+            summary: n.summary.clone(),
+            body: n.body.clone(),
+            due: due(minutes),
+        }
+    }
+
+    pub fn from_history(entry: &HistoryEntry, minutes: u64) -> Self {
+        Self {
+            app_name: entry.app_name.clone(),
+            app_icon: entry.app_icon.clone(),
+            summary: entry.summary.clone(),
+            body: entry.body.clone(),
+            due: due(minutes),
+        }
+    }
+}
+
+fn due(minutes: u64) -> SystemTime {
+    SystemTime::now() + Duration::from_secs(minutes * 60)
+}
+
+/// Snoozed notifications, saved on every change so they are delivered after a restart too
+#[derive(Debug, Clone, Default)]
+pub struct SnoozeQueue {
+    entries: Vec<Snoozed>,
+}
+
+impl SnoozeQueue {
+    fn path() -> Option<PathBuf> {
+        BaseDirectories::with_prefix(APP_ID)
+            .ok()
+            .and_then(|dirs| dirs.place_data_file("snoozed.ron").ok())
+    }
+
+    pub fn load() -> Self {
+        Self {
+            entries: Self::path()
+                .and_then(|path| File::open(path).ok())
+                .and_then(|file| ron::de::from_reader(file).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let tmp = path.with_extension("ron.tmp");
+        let res = File::create(&tmp)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(ron::ser::to_writer(file, &self.entries)?))
+            .and_then(|_| Ok(fs::rename(&tmp, &path)?));
+        if let Err(err) = res {
+            tracing::error!("Failed to save snoozed notifications: {}", err);
+        }
+    }
+
+    pub fn push(&mut self, snoozed: Snoozed) {
+        self.entries.push(snoozed);
+        self.save();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove and return the notifications that are due, including the ones that became due
+    /// while the applet wasn't running
+    pub fn take_due(&mut self) -> Vec<Snoozed> {
+        let now = SystemTime::now();
+        let (due, pending) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|s| s.due <= now);
+        self.entries = pending;
+        if !due.is_empty() {
+            self.save();
+        }
+        due
+    }
+}
@@ -0,0 +1,17 @@
+use tokio::process::Command;
+
+/// The freedesktop sound theme event for a new notification
+const NOTIFICATION_SOUND: &str = "message-new-instant";
+
+/// Play the notification sound of the current sound theme without waiting for it to finish
+pub fn play() {
+    tokio::spawn(async {
+        let res = Command::new("canberra-gtk-play")
+            .arg(format!("--id={NOTIFICATION_SOUND}"))
+            .status()
+            .await;
+        if let Err(err) = res {
+            tracing::error!("Failed to play the notification sound: {}", err);
+        }
+    });
+}
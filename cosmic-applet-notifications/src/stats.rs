@@ -0,0 +1,128 @@
+use crate::config::APP_ID;
+use chrono::{Datelike, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    path::PathBuf,
+};
+use xdg::BaseDirectories;
+
+const WEEKS_KEPT: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppStats {
+    pub received: u32,
+    /// Closed or expired before the popup was opened
+    pub dismissed_unread: u32,
+}
+
+/// Per-app notification counts, keyed by ISO week (`2023-W07`) and app name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationStats {
+    pub weeks: BTreeMap<String, BTreeMap<String, AppStats>>,
+    /// The last week a digest notification was sent for
+    pub last_digest: Option<String>,
+    /// Counts changed since the last save, they are saved in batches rather than for every
+    /// notification
+    #[serde(skip)]
+    unsaved: bool,
+}
+
+/// The totals of one week, with apps sorted from the most to the least notifications
+#[derive(Debug, Clone, Default)]
+pub struct WeekSummary {
+    pub received: u32,
+    pub dismissed_unread: u32,
+    pub apps: Vec<(String, AppStats)>,
+}
+
+pub fn week_key(weeks_ago: i64) -> String {
+    let week = (Local::now() - Duration::weeks(weeks_ago)).iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+impl NotificationStats {
+    fn path() -> Option<PathBuf> {
+        BaseDirectories::with_prefix(APP_ID)
+            .ok()
+            .and_then(|dirs| dirs.place_data_file("statistics.ron").ok())
+    }
+
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| ron::de::from_reader(file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&mut self) {
+        self.unsaved = false;
+        let Some(path) = Self::path() else {
+            return;
+        };
+        // write a new file and swap it in, so a crash can't leave half of the statistics behind
+        let tmp = path.with_extension("ron.tmp");
+        let res = File::create(&tmp)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(ron::ser::to_writer(file, self)?))
+            .and_then(|_| Ok(fs::rename(&tmp, &path)?));
+        if let Err(err) = res {
+            tracing::error!("Failed to save notification statistics: {}", err);
+        }
+    }
+
+    fn current(&mut self, app_name: &str) -> &mut AppStats {
+        let week = week_key(0);
+        if !self.weeks.contains_key(&week) {
+            self.weeks.insert(week.clone(), BTreeMap::new());
+            // keys sort chronologically, so the oldest weeks are first
+            while self.weeks.len() > WEEKS_KEPT {
+                self.weeks.pop_first();
+            }
+        }
+        self.weeks
+            .get_mut(&week)
+            .unwrap()
+            .entry(app_name.to_string())
+            .or_default()
+    }
+
+    pub fn received(&mut self, app_name: &str) {
+        self.current(app_name).received += 1;
+        self.unsaved = true;
+    }
+
+    pub fn dismissed_unread(&mut self, app_name: &str) {
+        self.current(app_name).dismissed_unread += 1;
+        self.unsaved = true;
+    }
+
+    pub fn has_unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    /// Every app that sent a notification in the weeks kept
+    pub fn app_names(&self) -> BTreeSet<&str> {
+        self.weeks
+            .values()
+            .flat_map(|apps| apps.keys().map(String::as_str))
+            .collect()
+    }
+
+    pub fn summary(&self, week: &str) -> Option<WeekSummary> {
+        let apps = self.weeks.get(week)?;
+        let mut summary = WeekSummary {
+            apps: apps.iter().map(|(name, s)| (name.clone(), *s)).collect(),
+            ..Default::default()
+        };
+        summary
+            .apps
+            .sort_by(|a, b| b.1.received.cmp(&a.1.received).then(a.0.cmp(&b.0)));
+        for (_, s) in &summary.apps {
+            summary.received += s.received;
+            summary.dismissed_unread += s.dismissed_unread;
+        }
+        Some(summary)
+    }
+}
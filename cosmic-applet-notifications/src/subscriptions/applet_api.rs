@@ -0,0 +1,128 @@
+//! D-Bus interface for status bars, scripts and the lock screen, e.g.
+//! `busctl --user get-property com.system76.CosmicAppletNotifications /com/system76/CosmicAppletNotifications com.system76.CosmicAppletNotifications UnreadCount`
+
+use cosmic::{
+    iced::{
+        futures::{self, channel::mpsc, SinkExt},
+        subscription,
+    },
+    iced_futures::Subscription,
+};
+use cosmic_applet_common::dbus;
+use tokio::sync::mpsc::{channel, Sender};
+use tracing::{error, warn};
+use zbus::{dbus_interface, Connection};
+
+const DBUS_NAME: &str = "com.system76.CosmicAppletNotifications";
+const DBUS_PATH: &str = "/com/system76/CosmicAppletNotifications";
+
+/// The notification state published on the bus
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct State {
+    pub unread: u32,
+    pub do_not_disturb: bool,
+    pub history: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Request {
+    SetDoNotDisturb(bool),
+    ToggleDoNotDisturb,
+    ClearHistory,
+}
+
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// Send the state here whenever it changes
+    Ready(Sender<State>),
+    Request(Request),
+}
+
+struct Api {
+    state: State,
+    output: mpsc::Sender<Output>,
+}
+
+impl Api {
+    async fn request(&self, request: Request) {
+        let _ = self.output.clone().send(Output::Request(request)).await;
+    }
+}
+
+#[dbus_interface(name = "com.system76.CosmicAppletNotifications")]
+impl Api {
+    async fn set_do_not_disturb(&self, enabled: bool) {
+        self.request(Request::SetDoNotDisturb(enabled)).await;
+    }
+
+    async fn toggle_do_not_disturb(&self) {
+        self.request(Request::ToggleDoNotDisturb).await;
+    }
+
+    async fn clear_history(&self) {
+        self.request(Request::ClearHistory).await;
+    }
+
+    /// Notifications that arrived since the popup was last opened
+    #[dbus_interface(property)]
+    fn unread_count(&self) -> u32 {
+        self.state.unread
+    }
+
+    #[dbus_interface(property)]
+    fn do_not_disturb(&self) -> bool {
+        self.state.do_not_disturb
+    }
+
+    #[dbus_interface(property)]
+    fn history_count(&self) -> u32 {
+        self.state.history
+    }
+}
+
+async fn publish(conn: &Connection, state: State) -> zbus::Result<()> {
+    let iface = conn.object_server().interface::<_, Api>(DBUS_PATH).await?;
+    let mut api = iface.get_mut().await;
+    let old = std::mem::replace(&mut api.state, state);
+    let ctxt = iface.signal_context();
+    if old.unread != state.unread {
+        api.unread_count_changed(ctxt).await?;
+    }
+    if old.do_not_disturb != state.do_not_disturb {
+        api.do_not_disturb_changed(ctxt).await?;
+    }
+    if old.history != state.history {
+        api.history_count_changed(ctxt).await?;
+    }
+    Ok(())
+}
+
+pub fn applet_api() -> Subscription<Output> {
+    struct ApiWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<ApiWorker>(),
+        10,
+        |mut output| async move {
+            // with the applet on several panels, the first one publishes its state and takes
+            // the requests, the others keep their counts to themselves
+            let api = Api {
+                state: State::default(),
+                output: output.clone(),
+            };
+            match dbus::serve(DBUS_NAME, DBUS_PATH, api).await {
+                Ok(conn) => {
+                    let (tx, mut rx) = channel(10);
+                    _ = output.send(Output::Ready(tx)).await;
+                    while let Some(state) = rx.recv().await {
+                        if let Err(err) = publish(&conn, state).await {
+                            error!("Failed to publish the notification state: {}", err);
+                        }
+                    }
+                }
+                Err(err) => warn!("Failed to serve the notifications API on D-Bus: {}", err),
+            }
+            futures::future::pending().await
+        },
+    )
+}
@@ -1,3 +1,5 @@
+use crate::fl;
+use crate::snooze::Snoozed;
 use crate::subscriptions::freedesktop_proxy::NotificationsProxy;
 use cosmic::{
     iced::{
@@ -6,6 +8,7 @@ use cosmic::{
     },
     iced_futures::Subscription,
 };
+use std::collections::HashMap;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tracing::{error, warn};
 use zbus::{export::futures_util::StreamExt, Connection};
@@ -17,10 +20,14 @@ pub enum State {
     Finished,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Input {
     Dismiss(u32),
     CloseEvent(u32),
+    /// Send a notification from the applet itself, with a summary and body
+    Notify(String, String),
+    /// Send a snoozed notification again, in the name of its app
+    Redeliver(Snoozed),
 }
 
 #[derive(Debug, Clone)]
@@ -86,6 +93,40 @@ pub fn proxy() -> Subscription<Output> {
                                 error!("Failed to close notification: {}", err);
                             }
                         }
+                        Some(Input::Notify(summary, body)) => {
+                            if let Err(err) = proxy
+                                .notify(
+                                    &fl!("notifications"),
+                                    0,
+                                    "cosmic-applet-notification-symbolic",
+                                    &summary,
+                                    &body,
+                                    &[],
+                                    HashMap::new(),
+                                    -1,
+                                )
+                                .await
+                            {
+                                error!("Failed to send notification: {}", err);
+                            }
+                        }
+                        Some(Input::Redeliver(snoozed)) => {
+                            if let Err(err) = proxy
+                                .notify(
+                                    &snoozed.app_name,
+                                    0,
+                                    &snoozed.app_icon,
+                                    &snoozed.summary,
+                                    &snoozed.body,
+                                    &[],
+                                    HashMap::new(),
+                                    -1,
+                                )
+                                .await
+                            {
+                                error!("Failed to send snoozed notification: {}", err);
+                            }
+                        }
                         Some(Input::CloseEvent(id)) => {
                             _ = output.send(Output::CloseEvent(id)).await;
                         }
@@ -0,0 +1,146 @@
+//! Watches the compositor's toplevels for fullscreen windows, e.g. games and videos
+
+use cctk::{
+    sctk::{
+        self,
+        reexports::{calloop, client::WaylandSource},
+        registry::{ProvidesRegistryState, RegistryState},
+    },
+    toplevel_info::{ToplevelInfoHandler, ToplevelInfoState},
+    wayland_client::{globals::registry_queue_init, Connection, QueueHandle},
+};
+use cosmic::{
+    iced::{
+        futures::{self, channel::mpsc, SinkExt, StreamExt},
+        subscription,
+    },
+    iced_futures::Subscription,
+};
+use cosmic_protocols::toplevel_info::v1::client::zcosmic_toplevel_handle_v1::{
+    self, ZcosmicToplevelHandleV1,
+};
+use std::collections::HashSet;
+use tracing::error;
+
+struct AppData {
+    exit: bool,
+    tx: mpsc::UnboundedSender<bool>,
+    registry_state: RegistryState,
+    toplevel_info_state: ToplevelInfoState,
+    fullscreen: HashSet<ZcosmicToplevelHandleV1>,
+}
+
+impl AppData {
+    fn set_fullscreen(&mut self, toplevel: &ZcosmicToplevelHandleV1, fullscreen: bool) {
+        let was_fullscreen = !self.fullscreen.is_empty();
+        if fullscreen {
+            self.fullscreen.insert(toplevel.clone());
+        } else {
+            self.fullscreen.remove(toplevel);
+        }
+        let is_fullscreen = !self.fullscreen.is_empty();
+        if was_fullscreen != is_fullscreen && self.tx.unbounded_send(is_fullscreen).is_err() {
+            self.exit = true;
+        }
+    }
+
+    fn update(&mut self, toplevel: &ZcosmicToplevelHandleV1) {
+        let fullscreen = self
+            .toplevel_info_state
+            .info(toplevel)
+            .map_or(false, |info| {
+                info.state
+                    .contains(&zcosmic_toplevel_handle_v1::State::Fullscreen)
+            });
+        self.set_fullscreen(toplevel, fullscreen);
+    }
+}
+
+impl ProvidesRegistryState for AppData {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    sctk::registry_handlers!();
+}
+
+impl ToplevelInfoHandler for AppData {
+    fn toplevel_info_state(&mut self) -> &mut ToplevelInfoState {
+        &mut self.toplevel_info_state
+    }
+
+    fn new_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        self.update(toplevel);
+    }
+
+    fn update_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        self.update(toplevel);
+    }
+
+    fn toplevel_closed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        self.set_fullscreen(toplevel, false);
+    }
+}
+
+fn toplevel_handler(tx: mpsc::UnboundedSender<bool>) -> anyhow::Result<()> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, event_queue) = registry_queue_init(&conn)?;
+    let mut event_loop = calloop::EventLoop::<AppData>::try_new()?;
+    let qh = event_queue.handle();
+    WaylandSource::new(event_queue)?
+        .insert(event_loop.handle())
+        .map_err(|err| anyhow::anyhow!("Failed to insert wayland source: {}", err))?;
+
+    let registry_state = RegistryState::new(&globals);
+    let mut app_data = AppData {
+        exit: false,
+        tx,
+        toplevel_info_state: ToplevelInfoState::new(&registry_state, &qh),
+        registry_state,
+        fullscreen: HashSet::new(),
+    };
+    while !app_data.exit {
+        event_loop.dispatch(None, &mut app_data)?;
+    }
+    Ok(())
+}
+
+/// Emits whether any window is fullscreen, each time that changes
+pub fn fullscreen() -> Subscription<bool> {
+    struct FullscreenWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<FullscreenWorker>(),
+        10,
+        |mut output| async move {
+            let (tx, mut rx) = mpsc::unbounded();
+            std::thread::spawn(move || {
+                if let Err(err) = toplevel_handler(tx) {
+                    error!("Failed to watch for fullscreen windows: {}", err);
+                }
+            });
+            while let Some(fullscreen) = rx.next().await {
+                _ = output.send(fullscreen).await;
+            }
+            futures::future::pending().await
+        },
+    )
+}
+
+sctk::delegate_registry!(AppData);
+cctk::delegate_toplevel_info!(AppData);
@@ -1,3 +1,5 @@
+pub mod applet_api;
 pub mod dbus;
 mod freedesktop_proxy;
+pub mod fullscreen;
 pub mod notifications;
@@ -12,6 +12,7 @@ use tracing::{error, info};
 use zbus::{
     dbus_proxy,
     export::futures_util::{SinkExt, StreamExt},
+    zvariant::Value,
     ConnectionBuilder,
 };
 
@@ -22,7 +23,35 @@ pub enum State {
     Finished,
 }
 
-pub fn notifications() -> Subscription<Notification> {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    fn new(hints: &HashMap<&str, Value<'_>>) -> Self {
+        match hints.get("urgency") {
+            Some(Value::U8(0)) => Self::Low,
+            Some(Value::U8(2)) => Self::Critical,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// What the applet reads from the hints besides the `Notification` itself
+#[derive(Debug, Clone, Default)]
+pub struct Details {
+    /// Progress in percent, from the `value` hint
+    pub progress: Option<u8>,
+    pub urgency: Urgency,
+    /// The notification should not be kept once it is gone
+    pub transient: bool,
+}
+
+pub fn notifications() -> Subscription<(Notification, Details)> {
     struct SomeWorker;
 
     subscription::channel(
@@ -60,6 +89,14 @@ pub fn notifications() -> Subscription<Notification> {
                                 error!("Failed to get arguments from notification signal.");
                                 break;
                             };
+                            let details = Details {
+                                progress: crate::content::progress(&args.hints),
+                                urgency: Urgency::new(&args.hints),
+                                transient: matches!(
+                                    args.hints.get("transient"),
+                                    Some(Value::Bool(true))
+                                ),
+                            };
                             let notification = Notification::new(
                                 args.app_name,
                                 args.id,
@@ -70,7 +107,7 @@ pub fn notifications() -> Subscription<Notification> {
                                 args.hints,
                                 args.expire_timeout,
                             );
-                            _ = output.send(notification).await;
+                            _ = output.send((notification, details)).await;
                         }
                     }
                     State::Finished => {
@@ -19,3 +19,14 @@ confirm-question =
         *[other] The selected action
     } will continue in { $countdown } seconds.
 
+guest-session = Guest Session
+kiosk-session = Kiosk Session
+switch-user = Switch User
+blocking-shutdown = { $count ->
+        [one] 1 app is blocking shutdown
+       *[other] { $count } apps are blocking shutdown
+    }
+blocking-suspend = { $count ->
+        [one] 1 app is blocking suspend
+       *[other] { $count } apps are blocking suspend
+    }
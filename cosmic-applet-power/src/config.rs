@@ -0,0 +1,23 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletPower";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct PowerAppletConfig {
+    /// Offer starting a guest session from the display manager
+    pub guest_session: bool,
+    /// User account that the kiosk entry switches to
+    pub kiosk_user: Option<String>,
+    /// Session the kiosk user is started in, the display manager default if unset
+    pub kiosk_session: Option<String>,
+}
+
+impl PowerAppletConfig {
+    pub fn load() -> PowerAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| PowerAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! # DBus interface proxy for: `org.freedesktop.DisplayManager.Seat`
+//!
+//! Implemented by LightDM and compatible display managers. The object path of the
+//! current seat is exported to the session in `XDG_SEAT_PATH`.
+
+use zbus::dbus_proxy;
+
+#[dbus_proxy(
+    interface = "org.freedesktop.DisplayManager.Seat",
+    default_service = "org.freedesktop.DisplayManager"
+)]
+trait Seat {
+    /// SwitchToGreeter method
+    fn switch_to_greeter(&self) -> zbus::Result<()>;
+
+    /// SwitchToGuest method
+    fn switch_to_guest(&self, session_name: &str) -> zbus::Result<()>;
+
+    /// SwitchToUser method
+    fn switch_to_user(&self, username: &str, session_name: &str) -> zbus::Result<()>;
+
+    /// HasGuestAccount property
+    #[dbus_proxy(property)]
+    fn has_guest_account(&self) -> zbus::Result<bool>;
+}
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! # DBus interface proxy for: `org.freedesktop.login1.Manager`
+//!
+//! Only the methods that the `logind-zbus` proxy doesn't have.
+
+use zbus::dbus_proxy;
+
+/// The what, who, why, mode, user and process of an inhibitor lock
+pub type InhibitorLock = (String, String, String, String, u32, u32);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    /// ListInhibitors method
+    fn list_inhibitors(&self) -> zbus::Result<Vec<InhibitorLock>>;
+}
@@ -1,8 +1,10 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::process;
 use std::time::Duration;
 
 use cosmic::app::applet::applet_button_theme;
+use cosmic::cosmic_config;
 use cosmic::iced;
 use cosmic::iced::alignment::{Horizontal, Vertical};
 use cosmic::iced::event::wayland::{self, LayerEvent};
@@ -34,11 +36,17 @@ use nix::unistd::getuid;
 use tokio::time::sleep;
 use zbus::Connection;
 
+mod config;
 pub mod cosmic_session;
+pub mod display_manager;
 mod localize;
+pub mod login_manager;
 pub mod session_manager;
 
+use crate::config::PowerAppletConfig;
 use crate::cosmic_session::CosmicSessionProxy;
+use crate::display_manager::SeatProxy;
+use crate::login_manager::LoginManagerProxy;
 use crate::session_manager::SessionManagerProxy;
 
 pub fn main() -> cosmic::iced::Result {
@@ -52,6 +60,26 @@ struct Power {
     popup: Option<window::Id>,
     id_ctr: u128,
     action_to_confirm: Option<(window::Id, PowerAction)>,
+    config: PowerAppletConfig,
+    /// Set once the display manager is found to export the seat
+    seat: Option<DisplayManagerSeat>,
+    inhibitors: Vec<Inhibitor>,
+}
+
+/// The seat of a display manager implementing `org.freedesktop.DisplayManager.Seat`, such as
+/// LightDM, which can show the greeter and start other sessions
+#[derive(Debug, Clone, Copy)]
+struct DisplayManagerSeat {
+    has_guest_account: bool,
+}
+
+/// An application holding off shutdown or sleep with a logind inhibitor lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Inhibitor {
+    /// Colon-separated list of what is inhibited, such as `shutdown:sleep`
+    what: String,
+    who: String,
+    why: String,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -72,22 +100,31 @@ enum Message {
     Confirm,
     Cancel,
     Zbus(Result<(), zbus::Error>),
+    SwitchUser,
+    GuestSession,
+    KioskSession,
+    Seat(Option<DisplayManagerSeat>),
+    Inhibitors(Vec<Inhibitor>),
+    ConfigUpdated(PowerAppletConfig),
 }
 
 impl cosmic::Application for Power {
     type Message = Message;
     type Executor = cosmic::SingleThreadExecutor;
     type Flags = ();
-    const APP_ID: &'static str = "com.system76.CosmicAppletPower";
+    const APP_ID: &'static str = config::APP_ID;
 
     fn init(core: cosmic::app::Core, _flags: ()) -> (Power, Command<Message>) {
         (
             Power {
                 core,
                 icon_name: "system-shutdown-symbolic".to_string(),
+                config: PowerAppletConfig::load(),
                 ..Default::default()
             },
-            Command::none(),
+            iced::Command::perform(display_manager_seat(), |seat| {
+                cosmic::app::message::app(Message::Seat(seat))
+            }),
         )
     }
 
@@ -104,15 +141,28 @@ impl cosmic::Application for Power {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        events_with(|e, _status| match e {
-            cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
-                wayland::Event::Layer(LayerEvent::Unfocused, ..),
-            )) => Some(Message::Cancel),
-            // cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
-            //     wayland::Event::Seat(wayland::SeatEvent::Leave, _),
-            // )) => Some(Message::Cancel),
-            _ => None,
-        })
+        Subscription::batch(vec![
+            events_with(|e, _status| match e {
+                cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
+                    wayland::Event::Layer(LayerEvent::Unfocused, ..),
+                )) => Some(Message::Cancel),
+                // cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
+                //     wayland::Event::Seat(wayland::SeatEvent::Leave, _),
+                // )) => Some(Message::Cancel),
+                _ => None,
+            }),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            eprintln!("cosmic-applet-power ERROR: '{:?}'", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
@@ -135,9 +185,9 @@ impl cosmic::Application for Power {
                     popup_settings.positioner.size_limits = Limits::NONE
                         .min_width(100.0)
                         .min_height(100.0)
-                        .max_height(400.0)
+                        .max_height(1080.0)
                         .max_width(500.0);
-                    get_popup(popup_settings)
+                    Command::batch(vec![get_popup(popup_settings), read_inhibitors()])
                 }
             }
             Message::Settings => {
@@ -149,6 +199,7 @@ impl cosmic::Application for Power {
                 let id = window::Id(self.id_ctr);
                 self.action_to_confirm = Some((id, action));
                 return Command::batch(vec![
+                    read_inhibitors(),
                     iced::Command::perform(sleep(Duration::from_secs(60)), move |_| {
                         cosmic::app::message::app(Message::Timeout(id))
                     }),
@@ -169,6 +220,34 @@ impl cosmic::Application for Power {
                 }
                 Command::none()
             }
+            Message::SwitchUser => {
+                let msg = |m| cosmic::app::message::app(Message::Zbus(m));
+                iced::Command::perform(switch_user(), msg)
+            }
+            Message::GuestSession => {
+                let msg = |m| cosmic::app::message::app(Message::Zbus(m));
+                iced::Command::perform(switch_to_guest(), msg)
+            }
+            Message::KioskSession => {
+                let Some(user) = self.config.kiosk_user.clone() else {
+                    return Command::none();
+                };
+                let session = self.config.kiosk_session.clone().unwrap_or_default();
+                let msg = |m| cosmic::app::message::app(Message::Zbus(m));
+                iced::Command::perform(switch_to_user(user, session), msg)
+            }
+            Message::Seat(seat) => {
+                self.seat = seat;
+                Command::none()
+            }
+            Message::Inhibitors(inhibitors) => {
+                self.inhibitors = inhibitors;
+                Command::none()
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+                Command::none()
+            }
             Message::Confirm => {
                 if let Some((id, a)) = self.action_to_confirm.take() {
                     let msg = |m| cosmic::app::message::app(Message::Zbus(m));
@@ -227,7 +306,7 @@ impl cosmic::Application for Power {
             let settings =
                 row_button(vec![text(fl!("settings")).size(14).into()]).on_press(Message::Settings);
 
-            let session = column![
+            let mut session = column![
                 row_button(vec![
                     text_icon("system-lock-screen-symbolic", 24).into(),
                     text(fl!("lock-screen")).size(14).into(),
@@ -243,6 +322,33 @@ impl cosmic::Application for Power {
                 ])
                 .on_press(Message::Action(PowerAction::LogOut)),
             ];
+            if self.seat.is_some() {
+                session = session.push(
+                    row_button(vec![
+                        text_icon("system-switch-user-symbolic", 24).into(),
+                        text(fl!("switch-user")).size(14).into(),
+                    ])
+                    .on_press(Message::SwitchUser),
+                );
+            }
+            if self.config.guest_session && self.seat.map_or(false, |s| s.has_guest_account) {
+                session = session.push(
+                    row_button(vec![
+                        text_icon("avatar-default-symbolic", 24).into(),
+                        text(fl!("guest-session")).size(14).into(),
+                    ])
+                    .on_press(Message::GuestSession),
+                );
+            }
+            if self.config.kiosk_user.is_some() && self.seat.is_some() {
+                session = session.push(
+                    row_button(vec![
+                        text_icon("video-display-symbolic", 24).into(),
+                        text(fl!("kiosk-session")).size(14).into(),
+                    ])
+                    .on_press(Message::KioskSession),
+                );
+            }
 
             let power = row![
                 power_buttons("system-lock-screen-symbolic", fl!("suspend"))
@@ -255,7 +361,7 @@ impl cosmic::Application for Power {
             .spacing(24)
             .padding([0, 24]);
 
-            let content = column![
+            let mut content = column![
                 settings,
                 container(divider::horizontal::light())
                     .padding([0, 12])
@@ -269,18 +375,31 @@ impl cosmic::Application for Power {
             .align_items(Alignment::Start)
             .spacing(12)
             .padding([8, 0]);
+            for what in [Inhibited::Shutdown, Inhibited::Sleep] {
+                if let Some(report) = self.inhibitor_report(what) {
+                    content = content.push(container(report).padding([0, 24]));
+                }
+            }
 
             self.core.applet_helper.popup_container(content).into()
         } else if matches!(self.action_to_confirm, Some((c_id, _)) if c_id == id) {
-            let action = match self.action_to_confirm.as_ref().unwrap().1 {
+            let power_action = self.action_to_confirm.as_ref().unwrap().1;
+            let action = match power_action {
                 PowerAction::Lock => "lock-screen",
                 PowerAction::LogOut => "log-out",
                 PowerAction::Suspend => "suspend",
                 PowerAction::Restart => "restart",
                 PowerAction::Shutdown => "shutdown",
             };
+            let report = match power_action {
+                PowerAction::Suspend => self.inhibitor_report(Inhibited::Sleep),
+                PowerAction::Restart | PowerAction::Shutdown => {
+                    self.inhibitor_report(Inhibited::Shutdown)
+                }
+                PowerAction::Lock | PowerAction::LogOut => None,
+            };
             // TODO actual countdown
-            let content = column![
+            let mut content = column![
                 text(fl!(
                     "confirm-question",
                     HashMap::from_iter(vec![("action", action), ("countdown", "60")])
@@ -299,6 +418,9 @@ impl cosmic::Application for Power {
             .align_items(Alignment::Center)
             .spacing(12)
             .padding(24);
+            if let Some(report) = report {
+                content = content.push(report);
+            }
             mouse_area(
                 container(
                     container(content)
@@ -332,6 +454,47 @@ impl cosmic::Application for Power {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum Inhibited {
+    Shutdown,
+    Sleep,
+}
+
+impl Power {
+    /// The applications blocking the action, each listed once with its reason
+    fn inhibitor_report(&self, what: Inhibited) -> Option<Element<Message>> {
+        let lock = match what {
+            Inhibited::Shutdown => "shutdown",
+            Inhibited::Sleep => "sleep",
+        };
+        let mut apps: Vec<&Inhibitor> = Vec::new();
+        for inhibitor in &self.inhibitors {
+            let blocks = inhibitor.what.split(':').any(|w| w == lock);
+            if blocks && !apps.iter().any(|app| app.who == inhibitor.who) {
+                apps.push(inhibitor);
+            }
+        }
+        if apps.is_empty() {
+            return None;
+        }
+        let count = apps.len();
+        let summary = match what {
+            Inhibited::Shutdown => fl!("blocking-shutdown", count = count),
+            Inhibited::Sleep => fl!("blocking-suspend", count = count),
+        };
+        let mut report = column![text(summary).size(14)].spacing(4);
+        for app in apps {
+            let line = if app.why.is_empty() {
+                app.who.clone()
+            } else {
+                format!("{}: {}", app.who, app.why)
+            };
+            report = report.push(text(line).size(12));
+        }
+        Some(report.into())
+    }
+}
+
 // ### UI Helplers
 
 fn row_button(content: Vec<Element<Message>>) -> widget::Button<Message, Renderer> {
@@ -422,3 +585,59 @@ async fn log_out() -> zbus::Result<()> {
     }
     Ok(())
 }
+
+/// Applications with inhibitor locks that block, rather than delay, actions
+async fn inhibitors() -> zbus::Result<Vec<Inhibitor>> {
+    let connection = Connection::system().await?;
+    let locks = LoginManagerProxy::new(&connection)
+        .await?
+        .list_inhibitors()
+        .await?;
+    Ok(locks
+        .into_iter()
+        .filter(|(_, _, _, mode, _, _)| mode == "block")
+        .map(|(what, who, why, ..)| Inhibitor { what, who, why })
+        .collect())
+}
+
+fn read_inhibitors() -> Command<Message> {
+    iced::Command::perform(inhibitors(), |res| {
+        let inhibitors = res.unwrap_or_else(|e| {
+            eprintln!("cosmic-applet-power ERROR: '{}'", e);
+            Vec::new()
+        });
+        cosmic::app::message::app(Message::Inhibitors(inhibitors))
+    })
+}
+
+async fn seat() -> zbus::Result<SeatProxy<'static>> {
+    let connection = Connection::system().await?;
+    let seat_path = std::env::var("XDG_SEAT_PATH")
+        .map_err(|_| zbus::Error::Failure("XDG_SEAT_PATH is not set".into()))?;
+    SeatProxy::builder(&connection)
+        .path(seat_path)?
+        .build()
+        .await
+}
+
+/// The display manager's seat, if it has one. Other display managers, such as GDM, don't set
+/// `XDG_SEAT_PATH` or don't implement the interface, so reading a property fails.
+async fn display_manager_seat() -> Option<DisplayManagerSeat> {
+    let has_guest_account = seat().await.ok()?.has_guest_account().await.ok()?;
+    Some(DisplayManagerSeat { has_guest_account })
+}
+
+async fn switch_user() -> zbus::Result<()> {
+    // the session is locked first, as the greeter can switch back to it
+    lock().await?;
+    seat().await?.switch_to_greeter().await
+}
+
+async fn switch_to_guest() -> zbus::Result<()> {
+    // an empty session name starts the display manager's default session
+    seat().await?.switch_to_guest("").await
+}
+
+async fn switch_to_user(user: String, session: String) -> zbus::Result<()> {
+    seat().await?.switch_to_user(&user, &session).await
+}
@@ -0,0 +1,22 @@
+no-printers = No printers
+no-jobs = No print jobs
+ready = Ready
+printing = Printing
+stopped = Stopped
+waiting = Waiting
+paused = Paused
+pages-printed = { $pages ->
+    [one] { $pages } page printed
+   *[other] { $pages } pages printed
+}
+pause = Pause
+resume = Resume
+cancel = Cancel
+out-of-paper = Out of paper
+paper-jam = Paper jam
+offline = Offline
+door-open = Door open
+out-of-ink = Out of ink or toner
+low-ink = Low on ink or toner
+always-show = Always show in panel
+printer-settings = Printer Settings...
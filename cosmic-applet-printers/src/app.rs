@@ -0,0 +1,295 @@
+use crate::config::{self, PrintersAppletConfig};
+use crate::cups::{self, queue_subscription, Job, JobState, Printer, PrinterState, Queue};
+use crate::fl;
+use cosmic::app::{self, applet::applet_button_theme, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, text, Column, Space},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::{divider, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::process;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Printers>(true, ())
+}
+
+#[derive(Default)]
+struct Printers {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: PrintersAppletConfig,
+    queue: Queue,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Queue(Queue),
+    Pause(i32),
+    Resume(i32),
+    Cancel(i32),
+    Done(Result<(), String>),
+    SetAlwaysShow(bool),
+    OpenSettings,
+    ConfigUpdated(PrintersAppletConfig),
+}
+
+/// A short description of a state reason, or the keyword itself if it isn't a common one
+fn reason_text(reason: &str) -> String {
+    match reason {
+        "media-empty" | "media-needed" => fl!("out-of-paper"),
+        "media-jam" => fl!("paper-jam"),
+        "offline" => fl!("offline"),
+        "paused" => fl!("paused"),
+        "door-open" | "cover-open" | "interlock-open" => fl!("door-open"),
+        "toner-empty" | "marker-supply-empty" => fl!("out-of-ink"),
+        "toner-low" | "marker-supply-low" => fl!("low-ink"),
+        _ => reason.to_string(),
+    }
+}
+
+fn printer_status(printer: &Printer) -> String {
+    if let Some(reason) = printer.reasons.first() {
+        return reason_text(reason);
+    }
+    match printer.state {
+        PrinterState::Idle => fl!("ready"),
+        PrinterState::Processing => fl!("printing"),
+        PrinterState::Stopped => fl!("stopped"),
+    }
+}
+
+fn job_status(job: &Job) -> String {
+    match job.state {
+        JobState::Pending => fl!("waiting"),
+        JobState::Held => fl!("paused"),
+        JobState::Processing if job.pages > 0 => fl!("pages-printed", pages = job.pages),
+        JobState::Processing => fl!("printing"),
+        JobState::Stopped => fl!("stopped"),
+    }
+}
+
+fn action_button<'a>(label: String, message: Message) -> Element<'a, Message> {
+    button(text(label).size(14))
+        .padding([4, 8])
+        .style(theme::Button::Secondary)
+        .on_press(message)
+        .into()
+}
+
+impl Printers {
+    /// Whether a printer is stopped or reports a problem
+    fn has_problem(&self) -> bool {
+        self.queue
+            .printers
+            .iter()
+            .any(|p| p.state == PrinterState::Stopped || !p.reasons.is_empty())
+    }
+
+    fn job_view<'a>(&self, job: &'a Job) -> Element<'a, Message> {
+        let pause = if job.state == JobState::Held {
+            action_button(fl!("resume"), Message::Resume(job.id))
+        } else {
+            action_button(fl!("pause"), Message::Pause(job.id))
+        };
+        row![
+            column![text(&job.name).size(14), text(job_status(job)).size(12)].width(Length::Fill),
+            pause,
+            action_button(fl!("cancel"), Message::Cancel(job.id)),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn printer_view<'a>(&self, printer: &'a Printer) -> Element<'a, Message> {
+        let mut content = Column::new().spacing(8).push(row![
+            text(printer.display_name()).size(14).width(Length::Fill),
+            text(printer_status(printer)).size(12),
+        ]);
+        for job in self.queue.jobs.iter().filter(|j| j.printer == printer.name) {
+            content = content.push(self.job_view(job));
+        }
+        content.into()
+    }
+}
+
+impl cosmic::Application for Printers {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Printers {
+                core,
+                config: PrintersAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                self.error = None;
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Queue(queue) => {
+                self.queue = queue;
+            }
+            Message::Pause(id) => {
+                self.error = None;
+                return cosmic::iced::Command::perform(cups::hold(id), |res| {
+                    app::message::app(Message::Done(res))
+                });
+            }
+            Message::Resume(id) => {
+                self.error = None;
+                return cosmic::iced::Command::perform(cups::release(id), |res| {
+                    app::message::app(Message::Done(res))
+                });
+            }
+            Message::Cancel(id) => {
+                self.error = None;
+                return cosmic::iced::Command::perform(cups::cancel(id), |res| {
+                    app::message::app(Message::Done(res))
+                });
+            }
+            Message::Done(res) => {
+                self.error = res.err();
+            }
+            Message::SetAlwaysShow(always_show) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_always_show(always_show, &config);
+                }
+            }
+            Message::OpenSettings => {
+                let _ = process::Command::new("system-config-printer").spawn();
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        // an open popup keeps its icon, so it doesn't vanish with the last job
+        if self.queue.jobs.is_empty() && !self.config.always_show && self.popup.is_none() {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+        let icon = if self.has_problem() {
+            "printer-error-symbolic"
+        } else {
+            "printer-symbolic"
+        };
+        self.core
+            .applet_helper
+            .icon_button(icon)
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut printers = Column::new().spacing(16);
+        for printer in &self.queue.printers {
+            printers = printers.push(self.printer_view(printer));
+        }
+        if self.queue.printers.is_empty() {
+            printers = printers.push(text(fl!("no-printers")).size(14));
+        } else if self.queue.jobs.is_empty() {
+            printers = printers.push(text(fl!("no-jobs")).size(14));
+        }
+        if let Some(error) = &self.error {
+            printers = printers.push(text(error).size(12));
+        }
+
+        let content = column![
+            container(printers).padding([0, 24]),
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            container(
+                toggler(
+                    fl!("always-show"),
+                    self.config.always_show,
+                    Message::SetAlwaysShow
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 24]),
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            cosmic::widget::button(applet_button_theme())
+                .custom(vec![text(fl!("printer-settings")).size(14).into()])
+                .padding([8, 24])
+                .width(Length::Fill)
+                .on_press(Message::OpenSettings),
+        ]
+        .spacing(8)
+        .padding([8, 0]);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            queue_subscription().map(Message::Queue),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
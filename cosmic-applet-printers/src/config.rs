@@ -0,0 +1,24 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletPrinters";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct PrintersAppletConfig {
+    /// Keep the icon in the panel while there are no print jobs
+    pub always_show: bool,
+}
+
+impl PrintersAppletConfig {
+    pub fn load() -> PrintersAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| PrintersAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_always_show(&mut self, always_show: bool, config: &Config) {
+        self.always_show = always_show;
+        let _ = self.write_entry(config);
+    }
+}
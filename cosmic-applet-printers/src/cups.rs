@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Printers and their unfinished jobs, polled from CUPS
+
+use crate::ipp::{self, Group, Request};
+use cosmic::iced::{futures::SinkExt, subscription, Subscription};
+use std::io;
+use std::time::Duration;
+
+const JOB_GROUP: u8 = 0x02;
+const PRINTER_GROUP: u8 = 0x04;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Pending,
+    Held,
+    Processing,
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: i32,
+    pub name: String,
+    pub printer: String,
+    pub state: JobState,
+    /// Pages printed so far
+    pub pages: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrinterState {
+    Idle,
+    Processing,
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Printer {
+    pub name: String,
+    /// The description set for the printer, if any
+    pub info: String,
+    pub state: PrinterState,
+    /// Keywords such as `media-empty` or `offline`, without severity suffixes
+    pub reasons: Vec<String>,
+}
+
+impl Printer {
+    pub fn display_name(&self) -> &str {
+        if self.info.is_empty() {
+            &self.name
+        } else {
+            &self.info
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Queue {
+    pub printers: Vec<Printer>,
+    pub jobs: Vec<Job>,
+}
+
+fn user() -> String {
+    std::env::var("USER").unwrap_or_else(|_| String::from("anonymous"))
+}
+
+fn text(group: &Group, name: &str) -> String {
+    group
+        .get(name)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn job(group: &Group) -> Option<Job> {
+    let state = match group.get("job-state")?.as_int()? {
+        3 => JobState::Pending,
+        4 => JobState::Held,
+        5 => JobState::Processing,
+        6 => JobState::Stopped,
+        // canceled, aborted, or completed
+        _ => return None,
+    };
+    let printer_uri = text(group, "job-printer-uri");
+    Some(Job {
+        id: group.get("job-id")?.as_int()?,
+        name: text(group, "job-name"),
+        printer: printer_uri
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        state,
+        pages: group
+            .get("job-impressions-completed")
+            .and_then(|v| v.as_int())
+            .unwrap_or(0),
+    })
+}
+
+fn printer(group: &Group) -> Option<Printer> {
+    let state = match group.get("printer-state")?.as_int()? {
+        4 => PrinterState::Processing,
+        5 => PrinterState::Stopped,
+        _ => PrinterState::Idle,
+    };
+    let reasons = group
+        .values("printer-state-reasons")
+        .iter()
+        .filter_map(|v| v.as_str())
+        .filter(|reason| *reason != "none")
+        .map(|reason| {
+            ["-error", "-warning", "-report"]
+                .iter()
+                .find_map(|suffix| reason.strip_suffix(suffix))
+                .unwrap_or(reason)
+                .to_string()
+        })
+        .collect();
+    Some(Printer {
+        name: text(group, "printer-name"),
+        info: text(group, "printer-info"),
+        state,
+        reasons,
+    })
+}
+
+async fn queue() -> io::Result<Queue> {
+    let printers = Request::new(ipp::CUPS_GET_PRINTERS)
+        .attribute(ipp::NAME, "requesting-user-name", &[&user()])
+        .attribute(
+            ipp::KEYWORD,
+            "requested-attributes",
+            &[
+                "printer-name",
+                "printer-info",
+                "printer-state",
+                "printer-state-reasons",
+            ],
+        )
+        .send()
+        .await?;
+    let jobs = Request::new(ipp::GET_JOBS)
+        .attribute(ipp::URI, "printer-uri", &["ipp://localhost/"])
+        .attribute(ipp::NAME, "requesting-user-name", &[&user()])
+        .attribute(ipp::KEYWORD, "which-jobs", &["not-completed"])
+        .attribute(
+            ipp::KEYWORD,
+            "requested-attributes",
+            &[
+                "job-id",
+                "job-name",
+                "job-state",
+                "job-printer-uri",
+                "job-impressions-completed",
+            ],
+        )
+        .send()
+        .await?;
+
+    let mut queue = Queue {
+        printers: printers
+            .iter()
+            .filter(|g| g.tag == PRINTER_GROUP)
+            .filter_map(printer)
+            .collect(),
+        jobs: jobs
+            .iter()
+            .filter(|g| g.tag == JOB_GROUP)
+            .filter_map(job)
+            .collect(),
+    };
+    queue.jobs.sort_by_key(|job| job.id);
+    Ok(queue)
+}
+
+async fn job_operation(operation: u16, id: i32) -> Result<(), String> {
+    Request::new(operation)
+        .attribute(
+            ipp::URI,
+            "job-uri",
+            &[&format!("ipp://localhost/jobs/{}", id)],
+        )
+        .attribute(ipp::NAME, "requesting-user-name", &[&user()])
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+pub async fn cancel(id: i32) -> Result<(), String> {
+    job_operation(ipp::CANCEL_JOB, id).await
+}
+
+pub async fn hold(id: i32) -> Result<(), String> {
+    job_operation(ipp::HOLD_JOB, id).await
+}
+
+pub async fn release(id: i32) -> Result<(), String> {
+    job_operation(ipp::RELEASE_JOB, id).await
+}
+
+/// The printers and unfinished jobs, sent whenever they change
+pub fn queue_subscription() -> Subscription<Queue> {
+    struct QueueWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<QueueWorker>(),
+        1,
+        |mut output| async move {
+            let mut last = None;
+            let mut logged = false;
+            loop {
+                match queue().await {
+                    Ok(queue) => {
+                        logged = false;
+                        if last.as_ref() != Some(&queue) {
+                            last = Some(queue.clone());
+                            _ = output.send(queue).await;
+                        }
+                    }
+                    // CUPS may not be running, or only be started on demand
+                    Err(err) if !logged => {
+                        log::warn!("Failed to read the print queue: {}", err);
+                        logged = true;
+                        if last.take().is_some() {
+                            _ = output.send(Queue::default()).await;
+                        }
+                    }
+                    Err(_) => {}
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        },
+    )
+}
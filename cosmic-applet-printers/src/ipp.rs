@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A minimal IPP client for the local CUPS scheduler, covering the few operations the applet
+//! needs. Requests are sent over HTTP on the scheduler's domain socket, or on localhost:631 if
+//! there isn't one.
+
+use cosmic_applet_common::http::{self, header, Body, StatusCode};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+const SOCKETS: [&str; 2] = ["/run/cups/cups.sock", "/var/run/cups/cups.sock"];
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const GET_JOBS: u16 = 0x000a;
+pub const CANCEL_JOB: u16 = 0x0008;
+pub const HOLD_JOB: u16 = 0x000c;
+pub const RELEASE_JOB: u16 = 0x000d;
+pub const CUPS_GET_PRINTERS: u16 = 0x4002;
+
+const OPERATION_GROUP: u8 = 0x01;
+const END_OF_ATTRIBUTES: u8 = 0x03;
+
+const INTEGER: u8 = 0x21;
+const ENUM: u8 = 0x23;
+const TEXT_WITH_LANGUAGE: u8 = 0x35;
+const NAME_WITH_LANGUAGE: u8 = 0x36;
+pub const NAME: u8 = 0x42;
+pub const KEYWORD: u8 = 0x44;
+pub const URI: u8 = 0x45;
+const CHARSET: u8 = 0x47;
+const NATURAL_LANGUAGE: u8 = 0x48;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Integer(i32),
+    Text(String),
+    /// Values of types the applet doesn't read
+    Other,
+}
+
+impl Value {
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// The attributes of one group in a response, such as one job
+#[derive(Debug, Default, Clone)]
+pub struct Group {
+    pub tag: u8,
+    pub attributes: Vec<(String, Vec<Value>)>,
+}
+
+impl Group {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values(name).first()
+    }
+
+    pub fn values(&self, name: &str) -> &[Value] {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+}
+
+pub struct Request {
+    body: Vec<u8>,
+}
+
+impl Request {
+    pub fn new(operation: u16) -> Self {
+        let mut body = vec![1, 1];
+        body.extend_from_slice(&operation.to_be_bytes());
+        body.extend_from_slice(&1u32.to_be_bytes());
+        body.push(OPERATION_GROUP);
+        Request { body }
+            .attribute(CHARSET, "attributes-charset", &["utf-8"])
+            .attribute(NATURAL_LANGUAGE, "attributes-natural-language", &["en"])
+    }
+
+    /// Adds an operation attribute; values after the first are additional values of the same
+    /// attribute
+    pub fn attribute(mut self, tag: u8, name: &str, values: &[&str]) -> Self {
+        for (i, value) in values.iter().enumerate() {
+            let name = if i == 0 { name } else { "" };
+            self.body.push(tag);
+            self.body
+                .extend_from_slice(&(name.len() as u16).to_be_bytes());
+            self.body.extend_from_slice(name.as_bytes());
+            self.body
+                .extend_from_slice(&(value.len() as u16).to_be_bytes());
+            self.body.extend_from_slice(value.as_bytes());
+        }
+        self
+    }
+
+    /// Sends the request, returning the response's groups
+    pub async fn send(mut self) -> io::Result<Vec<Group>> {
+        self.body.push(END_OF_ATTRIBUTES);
+        let request = http::Request::post("/")
+            .header(header::HOST, "localhost")
+            .header(header::CONTENT_TYPE, "application/ipp")
+            .body(Body::from(self.body))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let response = match std::env::var("CUPS_SERVER") {
+            Ok(server) if server.starts_with('/') => {
+                http::send_unix(Path::new(&server), request, TIMEOUT).await
+            }
+            _ => match SOCKETS.iter().find(|path| Path::new(path).exists()) {
+                Some(path) => http::send_unix(Path::new(path), request, TIMEOUT).await,
+                None => {
+                    let stream = TcpStream::connect("127.0.0.1:631").await?;
+                    http::send(stream, request, TIMEOUT).await
+                }
+            },
+        }?;
+        if response.status != StatusCode::OK {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("CUPS responded with HTTP status {}", response.status),
+            ));
+        }
+        parse(&response.body)
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(invalid("truncated IPP response"));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// A value prefixed with its length
+    fn sized(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+}
+
+fn value(tag: u8, bytes: &[u8]) -> io::Result<Value> {
+    Ok(match tag {
+        INTEGER | ENUM if bytes.len() == 4 => {
+            Value::Integer(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        }
+        TEXT_WITH_LANGUAGE | NAME_WITH_LANGUAGE => {
+            let mut reader = Reader { data: bytes };
+            reader.sized()?;
+            Value::Text(String::from_utf8_lossy(reader.sized()?).into_owned())
+        }
+        // the character string types
+        0x41..=0x49 => Value::Text(String::from_utf8_lossy(bytes).into_owned()),
+        _ => Value::Other,
+    })
+}
+
+fn parse(response: &[u8]) -> io::Result<Vec<Group>> {
+    let mut reader = Reader { data: response };
+    reader.take(2)?;
+    let status = reader.u16()?;
+    reader.take(4)?;
+    // 0x0000 to 0x00ff are the successful status codes
+    if status > 0x00ff {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("CUPS request failed with status {:#06x}", status),
+        ));
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    loop {
+        let tag = reader.u8()?;
+        if tag == END_OF_ATTRIBUTES {
+            return Ok(groups);
+        }
+        if tag < 0x10 {
+            groups.push(Group {
+                tag,
+                attributes: Vec::new(),
+            });
+            continue;
+        }
+        let name = String::from_utf8_lossy(reader.sized()?).into_owned();
+        let value = value(tag, reader.sized()?)?;
+        let group = groups
+            .last_mut()
+            .ok_or_else(|| invalid("attribute outside of a group"))?;
+        match group.attributes.last_mut() {
+            // an additional value of the previous attribute
+            Some((_, values)) if name.is_empty() => values.push(value),
+            _ => group.attributes.push((name, vec![value])),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+mod app;
+mod config;
+mod cups;
+mod ipp;
+mod localize;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
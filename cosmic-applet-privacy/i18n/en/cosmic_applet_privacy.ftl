@@ -0,0 +1,6 @@
+camera = Camera
+microphone = Microphone
+screen-sharing = Screen sharing
+location = Location
+in-use = { $device } in use
+nothing-in-use = Nothing is using the camera, microphone, location, or screen
@@ -0,0 +1,194 @@
+use crate::fl;
+use crate::geoclue::location_subscription;
+use crate::pipewire::{usage_subscription, Usage};
+use cosmic::app::Command;
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, row, text, Column, Row, Space},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::icon;
+use cosmic::{Element, Theme};
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Privacy>(true, ())
+}
+
+#[derive(Default)]
+struct Privacy {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    usage: Usage,
+    location: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Usage(Usage),
+    Location(bool),
+}
+
+struct Indicator<'a> {
+    icon: &'static str,
+    name: String,
+    /// Applications responsible, where they are known
+    apps: &'a [String],
+}
+
+impl Privacy {
+    fn indicators(&self) -> Vec<Indicator> {
+        let mut indicators = Vec::new();
+        if let Some(apps) = &self.usage.camera {
+            indicators.push(Indicator {
+                icon: "camera-web-symbolic",
+                name: fl!("camera"),
+                apps,
+            });
+        }
+        if let Some(apps) = &self.usage.microphone {
+            indicators.push(Indicator {
+                icon: "audio-input-microphone-symbolic",
+                name: fl!("microphone"),
+                apps,
+            });
+        }
+        if let Some(apps) = &self.usage.screen {
+            indicators.push(Indicator {
+                icon: "video-display-symbolic",
+                name: fl!("screen-sharing"),
+                apps,
+            });
+        }
+        if self.location {
+            indicators.push(Indicator {
+                icon: "find-location-symbolic",
+                name: fl!("location"),
+                apps: &[],
+            });
+        }
+        indicators
+    }
+}
+
+impl cosmic::Application for Privacy {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = "com.system76.CosmicAppletPrivacy";
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Privacy {
+                core,
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(250.0)
+                    .max_width(400.0)
+                    .min_height(50.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Usage(usage) => {
+                self.usage = usage;
+            }
+            Message::Location(location) => {
+                self.location = location;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let indicators = self.indicators();
+        // nothing is shown while nothing is in use
+        if indicators.is_empty() && self.popup.is_none() {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+        let icons = indicators.iter().map(|indicator| {
+            icon(indicator.icon, 16)
+                .size(16)
+                .style(Svg::SymbolicActive)
+                .into()
+        });
+        button(
+            Row::with_children(icons.collect())
+                .spacing(4)
+                .align_items(Alignment::Center),
+        )
+        .on_press(Message::TogglePopup)
+        .style(theme::Button::Text)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let indicators = self.indicators();
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        for indicator in &indicators {
+            let mut apps = Column::new().spacing(4);
+            for app in indicator.apps {
+                apps = apps.push(text(app).size(12));
+            }
+            content = content.push(
+                row![
+                    icon(indicator.icon, 16).size(16).style(Svg::SymbolicActive),
+                    column![
+                        text(fl!("in-use", device = indicator.name.as_str())).size(14),
+                        apps
+                    ]
+                    .spacing(4),
+                ]
+                .spacing(12),
+            );
+        }
+        if indicators.is_empty() {
+            content = content.push(text(fl!("nothing-in-use")).size(14));
+        }
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            usage_subscription().map(Message::Usage),
+            location_subscription().map(Message::Location),
+        ])
+    }
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Whether GeoClue is locating the device for any application
+
+use cosmic::iced::{
+    futures::{self, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use zbus::{dbus_proxy, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.GeoClue2.Manager",
+    default_service = "org.freedesktop.GeoClue2",
+    default_path = "/org/freedesktop/GeoClue2/Manager"
+)]
+trait Manager {
+    /// InUse property
+    #[dbus_proxy(property)]
+    fn in_use(&self) -> zbus::Result<bool>;
+}
+
+pub fn location_subscription() -> Subscription<bool> {
+    struct GeoclueWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<GeoclueWorker>(),
+        10,
+        |mut output| async move {
+            let res: zbus::Result<()> = async {
+                let conn = Connection::system().await?;
+                let proxy = ManagerProxy::new(&conn).await?;
+                _ = output.send(proxy.in_use().await.unwrap_or(false)).await;
+                let mut changes = proxy.receive_in_use_changed().await;
+                while let Some(change) = changes.next().await {
+                    _ = output.send(change.get().await?).await;
+                }
+                Ok(())
+            }
+            .await;
+            // GeoClue isn't installed everywhere, and without it nothing uses location
+            if let Err(err) = res {
+                log::info!("Not watching location use: {}", err);
+            }
+            futures::future::pending().await
+        },
+    )
+}
@@ -0,0 +1,10 @@
+mod app;
+mod geoclue;
+mod localize;
+mod pipewire;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Microphone, camera, and screen capture streams, from the PipeWire graph as printed by
+//! `pw-dump --monitor`
+
+use cosmic::iced::{
+    futures::{self, channel::mpsc, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufReader;
+use std::process::{Command, Stdio};
+
+const NODE: &str = "PipeWire:Interface:Node";
+const LINK: &str = "PipeWire:Interface:Link";
+
+/// Applications using each device, or `None` while it's unused
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub microphone: Option<Vec<String>>,
+    pub camera: Option<Vec<String>>,
+    pub screen: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+struct Node {
+    class: String,
+    running: bool,
+    /// Set for hardware devices, such as `v4l2` or `libcamera` for cameras
+    device_api: Option<String>,
+    /// Streams recording an output, such as level meters
+    captures_sink: bool,
+    app: String,
+}
+
+#[derive(Debug, Default)]
+struct Graph {
+    nodes: HashMap<u64, Node>,
+    /// Output and input node of each link
+    links: HashMap<u64, (u64, u64)>,
+}
+
+fn prop<'a>(props: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    props.get(key)?.as_str()
+}
+
+impl Graph {
+    /// Applies an object from the dump; objects without info were removed
+    fn update(&mut self, object: &Value) {
+        let Some(id) = object.get("id").and_then(Value::as_u64) else {
+            return;
+        };
+        let info = match object.get("info") {
+            Some(info) if !info.is_null() => info,
+            _ => {
+                self.nodes.remove(&id);
+                self.links.remove(&id);
+                return;
+            }
+        };
+        match object.get("type").and_then(Value::as_str) {
+            Some(NODE) => {
+                let Some(props) = info.get("props").and_then(Value::as_object) else {
+                    return;
+                };
+                let app = [
+                    "application.name",
+                    "application.process.binary",
+                    "node.name",
+                ]
+                .iter()
+                .find_map(|key| prop(props, key))
+                .unwrap_or_default()
+                .to_string();
+                self.nodes.insert(
+                    id,
+                    Node {
+                        class: prop(props, "media.class").unwrap_or_default().to_string(),
+                        running: info.get("state").and_then(Value::as_str) == Some("running"),
+                        device_api: prop(props, "device.api").map(String::from),
+                        captures_sink: match props.get("stream.capture.sink") {
+                            Some(Value::Bool(b)) => *b,
+                            Some(Value::String(s)) => s == "true",
+                            _ => false,
+                        },
+                        app,
+                    },
+                );
+            }
+            Some(LINK) => {
+                let output = info.get("output-node-id").and_then(Value::as_u64);
+                let input = info.get("input-node-id").and_then(Value::as_u64);
+                if let (Some(output), Some(input)) = (output, input) {
+                    self.links.insert(id, (output, input));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Applications reading from running video sources, selected by `is_source`
+    fn video_consumers(&self, is_source: impl Fn(&Node) -> bool) -> Option<Vec<String>> {
+        let sources: Vec<u64> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.class == "Video/Source" && node.running && is_source(node))
+            .map(|(id, _)| *id)
+            .collect();
+        if sources.is_empty() {
+            return None;
+        }
+        let apps: BTreeSet<String> = self
+            .links
+            .values()
+            .filter(|(output, _)| sources.contains(output))
+            .filter_map(|(_, input)| self.nodes.get(input))
+            .map(|node| node.app.clone())
+            .collect();
+        Some(apps.into_iter().collect())
+    }
+
+    fn usage(&self) -> Usage {
+        let recording: BTreeSet<String> = self
+            .nodes
+            .values()
+            .filter(|node| {
+                node.class == "Stream/Input/Audio" && node.running && !node.captures_sink
+            })
+            .map(|node| node.app.clone())
+            .collect();
+        Usage {
+            microphone: (!recording.is_empty()).then(|| recording.into_iter().collect()),
+            camera: self.video_consumers(|node| node.device_api.is_some()),
+            // screen casts are sources provided by the portal rather than a device
+            screen: self.video_consumers(|node| node.device_api.is_none()),
+        }
+    }
+}
+
+/// Reads the dump until `pw-dump` exits, sending the usage whenever it changes
+fn monitor(tx: mpsc::UnboundedSender<Usage>) -> std::io::Result<()> {
+    let mut child = Command::new("pw-dump")
+        .args(["--monitor", "--no-colors"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("piped stdout");
+
+    let mut graph = Graph::default();
+    let mut last = Usage::default();
+    // each update is a JSON array of the changed objects
+    for update in serde_json::Deserializer::from_reader(BufReader::new(stdout)).into_iter() {
+        let update: Vec<Value> = update?;
+        for object in &update {
+            graph.update(object);
+        }
+        let usage = graph.usage();
+        if usage != last {
+            last = usage.clone();
+            if tx.unbounded_send(usage).is_err() {
+                break;
+            }
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok(())
+}
+
+pub fn usage_subscription() -> Subscription<Usage> {
+    struct PipewireWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<PipewireWorker>(),
+        10,
+        |mut output| async move {
+            let (tx, mut rx) = mpsc::unbounded();
+            std::thread::spawn(move || {
+                if let Err(err) = monitor(tx) {
+                    log::error!("Failed to monitor PipeWire: {}", err);
+                }
+            });
+            while let Some(usage) = rx.next().await {
+                _ = output.send(usage).await;
+            }
+            futures::future::pending().await
+        },
+    )
+}
@@ -0,0 +1,14 @@
+screenshot = Screenshot
+capture-screen = Screen
+capture-selection = Region or Window
+record-screen = Record Screen
+record-cursor = Show pointer in recordings
+save = Save As...
+copy = Copy
+annotate = Annotate
+open = Open
+interval-capture = Capture at Intervals
+interval-every = Every
+interval-for = seconds for
+interval-minutes = minutes
+interval-folder = Choose Folder...
@@ -0,0 +1,643 @@
+use crate::config::{self, ScreenshotAppletConfig};
+use crate::fl;
+use crate::portal::{self, RecordingEvent};
+use ashpd::desktop::file_chooser::SelectedFiles;
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::futures::channel::mpsc::UnboundedSender;
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    time,
+    widget::{button, column, container, row, text, text_input, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::{self, Stdio};
+use std::time::{Duration, Instant};
+
+/// Time for the popup to disappear before the screen is captured
+const POPUP_CLOSE_DELAY: Duration = Duration::from_millis(200);
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<ScreenshotApplet>(true, ())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureKind {
+    Image,
+    Video,
+}
+
+#[derive(Debug, Clone)]
+struct Capture {
+    path: PathBuf,
+    kind: CaptureKind,
+}
+
+#[derive(Debug)]
+struct Recording {
+    id: u32,
+    /// Set once the source is chosen and the pipeline runs
+    started: Option<Instant>,
+    stop: Option<UnboundedSender<()>>,
+}
+
+/// Screenshots taken every few seconds, e.g. for a timelapse
+#[derive(Debug)]
+struct IntervalCapture {
+    interval: Duration,
+    taken: u32,
+    count: u32,
+    folder: PathBuf,
+    /// The screenshots are named after the start of the capture
+    name: String,
+    /// Ticks are skipped while a screenshot is still being taken
+    capturing: bool,
+}
+
+#[derive(Default)]
+struct ScreenshotApplet {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: ScreenshotAppletConfig,
+    last_capture: Option<Capture>,
+    recording: Option<Recording>,
+    recording_ctr: u32,
+    interval_capture: Option<IntervalCapture>,
+    interval_secs_input: String,
+    interval_minutes_input: String,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Capture(bool),
+    Captured(Result<Option<PathBuf>, String>),
+    SetRecording(bool),
+    Recording(RecordingEvent),
+    Tick,
+    Save,
+    Saved(Result<(), String>),
+    Copy,
+    Annotate,
+    Open,
+    SetRecordCursor(bool),
+    SetIntervalCapture(bool),
+    IntervalTick,
+    IntervalCaptured(Result<PathBuf, String>),
+    IntervalSecsInput(String),
+    IntervalMinutesInput(String),
+    SubmitInterval,
+    ChooseIntervalFolder,
+    IntervalFolderChosen(Option<PathBuf>),
+    ConfigUpdated(ScreenshotAppletConfig),
+}
+
+fn action_button<'a>(label: String, message: Message) -> Element<'a, Message> {
+    button(text(label).size(14))
+        .padding([4, 8])
+        .style(theme::Button::Secondary)
+        .on_press(message)
+        .into()
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+async fn save_as(path: PathBuf, title: String) -> Result<(), String> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let files = SelectedFiles::save_file()
+        .title(title.as_str())
+        .current_name(name.as_str())
+        .modal(false)
+        .send()
+        .await
+        .and_then(|r| r.response());
+    let Some(target) = files
+        .ok()
+        .and_then(|files| files.uris().first()?.to_file_path().ok())
+    else {
+        return Ok(());
+    };
+    tokio::fs::copy(&path, &target)
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+async fn choose_folder(title: String) -> Option<PathBuf> {
+    let files = SelectedFiles::open_file()
+        .title(title.as_str())
+        .directory(true)
+        .modal(false)
+        .send()
+        .await
+        .and_then(|r| r.response())
+        .ok()?;
+    files.uris().first()?.to_file_path().ok()
+}
+
+impl ScreenshotApplet {
+    fn close_popup(&mut self) -> Command<Message> {
+        match self.popup.take() {
+            Some(p) => destroy_popup(p),
+            None => Command::none(),
+        }
+    }
+
+    /// Saves the interval typed into the popup, or shows the saved one again if it's not a number
+    fn submit_interval(&mut self) {
+        let secs = self
+            .interval_secs_input
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|s| *s > 0);
+        let minutes = self
+            .interval_minutes_input
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|m| *m > 0);
+        if let (Some(secs), Some(minutes), Ok(config)) =
+            (secs, minutes, Config::new(config::APP_ID, 1))
+        {
+            self.config.set_interval(secs, minutes, &config);
+        }
+        self.interval_secs_input = self.config.interval_secs.to_string();
+        self.interval_minutes_input = self.config.interval_minutes.to_string();
+    }
+
+    /// Takes the next screenshot of the interval capture, after `delay`
+    fn interval_screenshot(&mut self, delay: Duration) -> Command<Message> {
+        let Some(capture) = self.interval_capture.as_mut() else {
+            return Command::none();
+        };
+        capture.taken += 1;
+        capture.capturing = true;
+        let folder = capture.folder.clone();
+        let name = capture.name.clone();
+        let number = capture.taken;
+        cosmic::iced::Command::perform(
+            async move {
+                tokio::time::sleep(delay).await;
+                portal::interval_screenshot(folder, name, number).await
+            },
+            |res| app::message::app(Message::IntervalCaptured(res)),
+        )
+    }
+
+    /// Puts the capture on the clipboard, as an image or as a file for videos
+    fn copy(capture: &Capture) -> std::io::Result<()> {
+        match capture.kind {
+            CaptureKind::Image => {
+                process::Command::new("wl-copy")
+                    .args(["--type", "image/png"])
+                    .stdin(Stdio::from(File::open(&capture.path)?))
+                    .spawn()?;
+            }
+            CaptureKind::Video => {
+                // the default file names have spaces, which the URI has to escape
+                let uri = url::Url::from_file_path(&capture.path).map_err(|_| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "the capture path is not absolute",
+                    )
+                })?;
+                process::Command::new("wl-copy")
+                    .args(["--type", "text/uri-list"])
+                    .arg(uri.as_str())
+                    .spawn()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl cosmic::Application for ScreenshotApplet {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        let config = ScreenshotAppletConfig::load();
+        (
+            ScreenshotApplet {
+                interval_secs_input: config.interval_secs.to_string(),
+                interval_minutes_input: config.interval_minutes.to_string(),
+                core,
+                config,
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                self.error = None;
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Capture(interactive) => {
+                let capture = cosmic::iced::Command::perform(
+                    async move {
+                        tokio::time::sleep(POPUP_CLOSE_DELAY).await;
+                        portal::screenshot(interactive).await
+                    },
+                    |res| app::message::app(Message::Captured(res)),
+                );
+                return Command::batch(vec![self.close_popup(), capture]);
+            }
+            Message::Captured(Ok(path)) => {
+                if let Some(path) = path {
+                    self.last_capture = Some(Capture {
+                        path,
+                        kind: CaptureKind::Image,
+                    });
+                }
+            }
+            Message::Captured(Err(err)) => {
+                log::error!("Failed to take a screenshot: {}", err);
+                self.error = Some(err);
+            }
+            Message::SetRecording(true) => {
+                if self.recording.is_none() {
+                    self.recording_ctr += 1;
+                    self.recording = Some(Recording {
+                        id: self.recording_ctr,
+                        started: None,
+                        stop: None,
+                    });
+                    return self.close_popup();
+                }
+            }
+            Message::SetRecording(false) => {
+                // while a source is still being chosen, the portal's dialog can cancel
+                if let Some(stop) = self.recording.as_mut().and_then(|r| r.stop.take()) {
+                    let _ = stop.unbounded_send(());
+                }
+            }
+            Message::Recording(RecordingEvent::Started(stop)) => {
+                if let Some(recording) = self.recording.as_mut() {
+                    recording.started = Some(Instant::now());
+                    recording.stop = Some(stop);
+                }
+            }
+            Message::Recording(RecordingEvent::Finished(path)) => {
+                self.recording = None;
+                if let Some(path) = path {
+                    self.last_capture = Some(Capture {
+                        path,
+                        kind: CaptureKind::Video,
+                    });
+                }
+            }
+            Message::Recording(RecordingEvent::Failed(err)) => {
+                log::error!("Failed to record the screen: {}", err);
+                self.recording = None;
+                self.error = Some(err);
+            }
+            Message::Tick => {}
+            Message::Save => {
+                if let Some(capture) = &self.last_capture {
+                    return cosmic::iced::Command::perform(
+                        save_as(capture.path.clone(), fl!("save")),
+                        |res| app::message::app(Message::Saved(res)),
+                    );
+                }
+            }
+            Message::Saved(res) => {
+                self.error = res.err();
+            }
+            Message::Copy => {
+                if let Some(capture) = &self.last_capture {
+                    if let Err(err) = Self::copy(capture) {
+                        self.error = Some(err.to_string());
+                    }
+                }
+            }
+            Message::Annotate => {
+                let mut command = self.config.annotate_command.split_whitespace();
+                if let (Some(capture), Some(program)) = (&self.last_capture, command.next()) {
+                    if let Err(err) = process::Command::new(program)
+                        .args(command)
+                        .arg(&capture.path)
+                        .spawn()
+                    {
+                        self.error = Some(format!("{}: {}", program, err));
+                    }
+                }
+            }
+            Message::Open => {
+                if let Some(capture) = &self.last_capture {
+                    let _ = process::Command::new("xdg-open").arg(&capture.path).spawn();
+                }
+            }
+            Message::SetRecordCursor(record_cursor) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_record_cursor(record_cursor, &config);
+                }
+            }
+            Message::SetIntervalCapture(true) => {
+                if self.interval_capture.is_none() {
+                    self.submit_interval();
+                    let name = chrono::Local::now()
+                        .format("Interval capture from %Y-%m-%d %H-%M-%S")
+                        .to_string();
+                    self.interval_capture = Some(IntervalCapture {
+                        interval: self.config.interval(),
+                        taken: 0,
+                        count: self.config.interval_count(),
+                        folder: self.config.interval_folder(),
+                        name,
+                        capturing: false,
+                    });
+                    let screenshot = self.interval_screenshot(POPUP_CLOSE_DELAY);
+                    return Command::batch(vec![self.close_popup(), screenshot]);
+                }
+            }
+            Message::SetIntervalCapture(false) => {
+                self.interval_capture = None;
+            }
+            Message::IntervalTick => {
+                if let Some(capture) = &self.interval_capture {
+                    if !capture.capturing {
+                        return self.interval_screenshot(Duration::ZERO);
+                    }
+                }
+            }
+            Message::IntervalCaptured(Ok(path)) => {
+                if let Some(capture) = self.interval_capture.as_mut() {
+                    capture.capturing = false;
+                    if capture.taken >= capture.count {
+                        self.interval_capture = None;
+                    }
+                }
+                self.last_capture = Some(Capture {
+                    path,
+                    kind: CaptureKind::Image,
+                });
+            }
+            Message::IntervalCaptured(Err(err)) => {
+                log::error!("Failed to take an interval screenshot: {}", err);
+                self.interval_capture = None;
+                self.error = Some(err);
+            }
+            Message::IntervalSecsInput(input) => {
+                self.interval_secs_input = input;
+            }
+            Message::IntervalMinutesInput(input) => {
+                self.interval_minutes_input = input;
+            }
+            Message::SubmitInterval => {
+                self.submit_interval();
+            }
+            Message::ChooseIntervalFolder => {
+                return cosmic::iced::Command::perform(
+                    choose_folder(fl!("interval-folder")),
+                    |folder| app::message::app(Message::IntervalFolderChosen(folder)),
+                );
+            }
+            Message::IntervalFolderChosen(folder) => {
+                if let (Some(folder), Ok(config)) = (folder, Config::new(config::APP_ID, 1)) {
+                    self.config.set_interval_folder(folder, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.interval_secs_input = config.interval_secs.to_string();
+                self.interval_minutes_input = config.interval_minutes.to_string();
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let started = self.recording.as_ref().and_then(|r| r.started);
+        if started.is_none() && self.interval_capture.is_none() {
+            return self
+                .core
+                .applet_helper
+                .icon_button("camera-photo-symbolic")
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+        let indicator = |icon_name: &'static str, label: String| {
+            button(
+                row![
+                    icon(icon_name, 16).size(16).style(Svg::SymbolicActive),
+                    text(label).size(14),
+                ]
+                .spacing(4)
+                .align_items(Alignment::Center),
+            )
+            .on_press(Message::TogglePopup)
+            .style(theme::Button::Text)
+        };
+        let mut indicators = Row::new().spacing(4).align_items(Alignment::Center);
+        if let Some(started) = started {
+            indicators = indicators.push(indicator(
+                "media-record-symbolic",
+                format_elapsed(started.elapsed()),
+            ));
+        }
+        if let Some(capture) = &self.interval_capture {
+            indicators = indicators
+                .push(indicator(
+                    "camera-photo-symbolic",
+                    format!("{}/{}", capture.taken, capture.count),
+                ))
+                .push(
+                    button(icon("media-playback-stop-symbolic", 16).size(16))
+                        .on_press(Message::SetIntervalCapture(false))
+                        .style(theme::Button::Text),
+                );
+        }
+        indicators.into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let captures = row![
+            action_button(fl!("capture-screen"), Message::Capture(false)),
+            action_button(fl!("capture-selection"), Message::Capture(true)),
+        ]
+        .spacing(8);
+
+        let mut content = column![
+            container(captures).padding([0, 24]),
+            container(
+                toggler(
+                    fl!("record-screen"),
+                    self.recording.is_some(),
+                    Message::SetRecording
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 24]),
+            container(
+                toggler(
+                    fl!("record-cursor"),
+                    self.config.record_cursor,
+                    Message::SetRecordCursor
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 24]),
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            container(
+                toggler(
+                    fl!("interval-capture"),
+                    self.interval_capture.is_some(),
+                    Message::SetIntervalCapture
+                )
+                .text_size(14)
+                .width(Length::Fill)
+            )
+            .padding([0, 24]),
+            container(
+                row![
+                    text(fl!("interval-every")).size(14),
+                    text_input("10", &self.interval_secs_input)
+                        .on_input(Message::IntervalSecsInput)
+                        .on_submit(Message::SubmitInterval)
+                        .size(14)
+                        .width(Length::Fixed(56.0)),
+                    text(fl!("interval-for")).size(14),
+                    text_input("10", &self.interval_minutes_input)
+                        .on_input(Message::IntervalMinutesInput)
+                        .on_submit(Message::SubmitInterval)
+                        .size(14)
+                        .width(Length::Fixed(56.0)),
+                    text(fl!("interval-minutes")).size(14),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center)
+            )
+            .padding([0, 24]),
+            container(
+                row![
+                    text(self.config.interval_folder().display().to_string())
+                        .size(14)
+                        .width(Length::Fill),
+                    action_button(fl!("interval-folder"), Message::ChooseIntervalFolder),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center)
+            )
+            .padding([0, 24]),
+        ]
+        .spacing(8)
+        .padding([8, 0]);
+
+        if let Some(capture) = &self.last_capture {
+            let name = capture
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let mut actions = Row::new()
+                .spacing(8)
+                .push(action_button(fl!("save"), Message::Save))
+                .push(action_button(fl!("copy"), Message::Copy));
+            if capture.kind == CaptureKind::Image {
+                actions = actions.push(action_button(fl!("annotate"), Message::Annotate));
+            }
+            actions = actions.push(action_button(fl!("open"), Message::Open));
+            content = content
+                .push(
+                    container(divider::horizontal::light())
+                        .width(Length::Fill)
+                        .padding([0, 12]),
+                )
+                .push(container(column![text(name).size(14), actions].spacing(8)).padding([0, 24]));
+        }
+        if let Some(error) = &self.error {
+            content = content.push(container(text(error).size(12)).padding([0, 24]));
+        }
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions =
+            vec![
+                cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                    |(_, config)| match config {
+                        Ok(config) => Message::ConfigUpdated(config),
+                        Err((errors, config)) => {
+                            for error in errors {
+                                log::error!("{:?}", error);
+                            }
+                            Message::ConfigUpdated(config)
+                        }
+                    },
+                ),
+            ];
+        if let Some(recording) = &self.recording {
+            subscriptions.push(
+                portal::recording(recording.id, self.config.record_cursor).map(Message::Recording),
+            );
+            if recording.started.is_some() {
+                subscriptions.push(time::every(Duration::from_secs(1)).map(|_| Message::Tick));
+            }
+        }
+        if let Some(capture) = &self.interval_capture {
+            subscriptions.push(time::every(capture.interval).map(|_| Message::IntervalTick));
+        }
+        Subscription::batch(subscriptions)
+    }
+}
@@ -0,0 +1,72 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const APP_ID: &str = "com.system76.CosmicAppletScreenshot";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct ScreenshotAppletConfig {
+    /// Program and arguments that open a screenshot for annotation; the file is appended
+    pub annotate_command: String,
+    /// Draw the pointer into recordings
+    pub record_cursor: bool,
+    /// Seconds between the screenshots of an interval capture
+    pub interval_secs: u32,
+    /// Minutes an interval capture runs for
+    pub interval_minutes: u32,
+    /// Folder interval captures are stored in, the pictures folder if unset
+    pub interval_folder: Option<PathBuf>,
+}
+
+impl Default for ScreenshotAppletConfig {
+    fn default() -> Self {
+        Self {
+            annotate_command: String::from("swappy -f"),
+            record_cursor: true,
+            interval_secs: 10,
+            interval_minutes: 10,
+            interval_folder: None,
+        }
+    }
+}
+
+impl ScreenshotAppletConfig {
+    pub fn load() -> ScreenshotAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| ScreenshotAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_record_cursor(&mut self, record_cursor: bool, config: &Config) {
+        self.record_cursor = record_cursor;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_interval(&mut self, secs: u32, minutes: u32, config: &Config) {
+        self.interval_secs = secs;
+        self.interval_minutes = minutes;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_interval_folder(&mut self, folder: PathBuf, config: &Config) {
+        self.interval_folder = Some(folder);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.interval_secs.max(1)))
+    }
+
+    /// The number of screenshots an interval capture takes, the first one right away
+    pub fn interval_count(&self) -> u32 {
+        (self.interval_minutes.saturating_mul(60) / self.interval_secs.max(1)).max(1)
+    }
+
+    pub fn interval_folder(&self) -> PathBuf {
+        self.interval_folder
+            .clone()
+            .unwrap_or_else(|| dirs::picture_dir().unwrap_or_else(std::env::temp_dir))
+    }
+}
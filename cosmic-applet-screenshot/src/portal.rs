@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Screenshots and screen recordings through the desktop portal. Recordings stream the
+//! portal's PipeWire node into a `gst-launch-1.0` pipeline writing WebM.
+
+use ashpd::desktop::screencast::{CursorMode, PersistMode, Screencast, SourceType};
+use ashpd::desktop::screenshot::Screenshot;
+use ashpd::desktop::ResponseError;
+use ashpd::WindowIdentifier;
+use cosmic::iced::{
+    futures::{
+        self,
+        channel::mpsc::{self, UnboundedSender},
+        future::Either,
+        SinkExt, StreamExt,
+    },
+    subscription, Subscription,
+};
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Takes a screenshot of the whole screen, or of a region or window chosen in the portal's
+/// interface. Returns `None` if the user cancels.
+pub async fn screenshot(interactive: bool) -> std::result::Result<Option<PathBuf>, String> {
+    let response = Screenshot::request()
+        .interactive(interactive)
+        .modal(false)
+        .send()
+        .await
+        .and_then(|r| r.response());
+    match response {
+        Ok(screenshot) => screenshot
+            .uri()
+            .to_file_path()
+            .map(Some)
+            .map_err(|_| format!("Screenshot isn't a local file: {}", screenshot.uri())),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Takes a screenshot of the whole screen for an interval capture and moves it into `folder`,
+/// named after the capture and its number
+pub async fn interval_screenshot(
+    folder: PathBuf,
+    name: String,
+    number: u32,
+) -> std::result::Result<PathBuf, String> {
+    let path = screenshot(false)
+        .await?
+        .ok_or_else(|| String::from("The screenshot was cancelled"))?;
+    tokio::fs::create_dir_all(&folder)
+        .await
+        .map_err(|err| err.to_string())?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let target = folder.join(format!("{} {:04}.{}", name, number, extension));
+    // the portal saves to its own folder, which can be on another file system
+    if tokio::fs::rename(&path, &target).await.is_err() {
+        tokio::fs::copy(&path, &target)
+            .await
+            .map_err(|err| err.to_string())?;
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+    Ok(target)
+}
+
+#[derive(Debug, Clone)]
+pub enum RecordingEvent {
+    /// Recording has begun; sending on the channel stops it
+    Started(UnboundedSender<()>),
+    /// The finished recording, or `None` if the user cancelled the source selection
+    Finished(Option<PathBuf>),
+    Failed(String),
+}
+
+fn recording_path() -> PathBuf {
+    let name = chrono::Local::now()
+        .format("Screencast from %Y-%m-%d %H-%M-%S.webm")
+        .to_string();
+    dirs::video_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(name)
+}
+
+async fn record(
+    output: &mut mpsc::Sender<RecordingEvent>,
+    cursor: bool,
+) -> Result<Option<PathBuf>> {
+    let proxy = Screencast::new().await?;
+    let session = proxy.create_session().await?;
+    let cursor_mode = if cursor {
+        CursorMode::Embedded
+    } else {
+        CursorMode::Hidden
+    };
+    proxy
+        .select_sources(
+            &session,
+            cursor_mode,
+            SourceType::Monitor | SourceType::Window,
+            false,
+            None,
+            PersistMode::DoNot,
+        )
+        .await?;
+    let streams = match proxy
+        .start(&session, &WindowIdentifier::default())
+        .await?
+        .response()
+    {
+        Ok(streams) => streams,
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let node = streams
+        .streams()
+        .first()
+        .ok_or("The portal didn't return a stream")?
+        .pipe_wire_node_id();
+
+    // the pipeline connects to PipeWire through the portal's fd, so it has to be inherited
+    let fd = proxy.open_pipe_wire_remote(&session).await?;
+    fcntl(fd, FcntlArg::F_SETFD(FdFlag::empty()))?;
+    let path = recording_path();
+    let spawned = tokio::process::Command::new("gst-launch-1.0")
+        .arg("-e")
+        .args([
+            "pipewiresrc",
+            &format!("fd={}", fd),
+            &format!("path={}", node),
+            "do-timestamp=true",
+            "keepalive-time=1000",
+            "resend-last=true",
+        ])
+        .args(["!", "videoconvert", "!", "queue"])
+        .args(["!", "vp8enc", "deadline=1", "cpu-used=8"])
+        .args(["!", "queue", "!", "webmmux", "!", "filesink"])
+        .arg(format!("location={}", path.display()))
+        .stdout(Stdio::null())
+        .spawn();
+    let _ = nix::unistd::close(fd);
+    let mut child = spawned?;
+
+    let (stop_tx, mut stop_rx) = mpsc::unbounded();
+    _ = output.send(RecordingEvent::Started(stop_tx)).await;
+    let exited = match futures::future::select(stop_rx.next(), Box::pin(child.wait())).await {
+        Either::Left(_) => None,
+        Either::Right((status, _)) => Some(status),
+    };
+    let status = match exited {
+        Some(status) => status?,
+        None => {
+            // with `-e`, an interrupt finishes the file before exiting
+            if let Some(pid) = child.id() {
+                kill(Pid::from_raw(pid as i32), Signal::SIGINT)?;
+            }
+            child.wait().await?
+        }
+    };
+    let _ = session.close().await;
+
+    // a failed pipeline can leave a file behind, which isn't a usable recording
+    if !status.success() {
+        return Err(format!("gst-launch-1.0 failed with {}", status).into());
+    }
+    Ok(Some(path))
+}
+
+/// Records the screen while subscribed, identified by `id` so each recording starts afresh
+pub fn recording(id: u32, cursor: bool) -> Subscription<RecordingEvent> {
+    struct Recording;
+
+    subscription::channel(
+        (std::any::TypeId::of::<Recording>(), id),
+        1,
+        move |mut output| async move {
+            let event = match record(&mut output, cursor).await {
+                Ok(path) => RecordingEvent::Finished(path),
+                Err(err) => RecordingEvent::Failed(err.to_string()),
+            };
+            _ = output.send(event).await;
+            futures::future::pending().await
+        },
+    )
+}
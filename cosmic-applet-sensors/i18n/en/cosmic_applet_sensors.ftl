@@ -0,0 +1,12 @@
+sensors = Sensors
+cpu = CPU
+gpu = GPU
+drive = Drive { $device }
+rpm = { $rpm } RPM
+no-sensors = No sensors found
+automatic = Show the Hottest CPU Temperature
+automatic-description = Select a sensor to show it in the panel instead.
+notify = Notify When Running Hot
+warning-at = Warning Threshold
+running-hot = Running hot
+running-hot-body = { $sensor } is at { $temperature }.
@@ -0,0 +1,432 @@
+use crate::config::{self, SensorsAppletConfig, THRESHOLDS};
+use crate::fl;
+use crate::hwmon::{chips, Chip, Component, Fan, Temperature};
+use crate::notification;
+use cosmic::app::{self, applet::cosmic_panel_config::PanelAnchor, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, scrollable, text, Column, Row},
+    window, Alignment, Color, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+const ICON: &str = "cosmic-applet-sensors-symbolic";
+/// How far a temperature has to fall below the warning threshold to warn again, so one
+/// hovering around it doesn't notify on every reading
+const HYSTERESIS: f32 = 5.0;
+/// Sensors listed in the popup before it scrolls
+const MAX_ROWS: usize = 12;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Sensors>(true, ())
+}
+
+#[derive(Default)]
+struct Sensors {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: SensorsAppletConfig,
+    chips: Vec<Chip>,
+    /// Temperature sensors that were warned about
+    hot: HashSet<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Chips(Vec<Chip>),
+    SetHeadline(Option<String>),
+    SetAutomatic(bool),
+    SetWarningCelsius(u32),
+    SetNotify(bool),
+    ConfigUpdated(SensorsAppletConfig),
+    Ignore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+enum Reading<'a> {
+    Temperature(&'a Temperature),
+    Fan(&'a Fan),
+}
+
+fn chip_name(chip: &Chip) -> String {
+    match chip.component {
+        Component::Cpu => fl!("cpu"),
+        Component::Gpu => fl!("gpu"),
+        Component::Nvme => fl!("drive", device = chip.device.as_deref().unwrap_or_default()),
+        Component::Other => chip.name.clone(),
+    }
+}
+
+fn format_celsius(celsius: f32) -> String {
+    format!("{celsius:.0}°C")
+}
+
+fn level_color(level: Level) -> Option<Color> {
+    match level {
+        Level::Normal => None,
+        Level::Warning => Some(Color::from_rgb(0.9, 0.6, 0.1)),
+        Level::Critical => Some(Color::from_rgb(0.8, 0.2, 0.2)),
+    }
+}
+
+/// A value, colored by its level
+fn value<'a>(value: String, level: Level) -> Element<'a, Message> {
+    let value = text(value).size(14);
+    match level_color(level) {
+        Some(color) => value.style(theme::Text::Color(color)).into(),
+        None => value.into(),
+    }
+}
+
+impl Sensors {
+    fn level(&self, temperature: &Temperature) -> Level {
+        if temperature
+            .critical
+            .map_or(false, |critical| temperature.celsius >= critical)
+        {
+            Level::Critical
+        } else if temperature.celsius >= self.config.warning_celsius as f32 {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    fn worst_level(&self) -> Level {
+        self.chips
+            .iter()
+            .flat_map(|chip| &chip.temperatures)
+            .map(|temperature| self.level(temperature))
+            .max()
+            .unwrap_or(Level::Normal)
+    }
+
+    /// The configured sensor, or the hottest CPU temperature, or the hottest of any
+    fn headline(&self) -> Option<(String, Reading)> {
+        if let Some(id) = &self.config.headline {
+            for chip in &self.chips {
+                if let Some(temperature) = chip
+                    .temperatures
+                    .iter()
+                    .find(|t| chip.sensor_id(&t.input) == *id)
+                {
+                    return Some((id.clone(), Reading::Temperature(temperature)));
+                }
+                if let Some(fan) = chip.fans.iter().find(|f| chip.sensor_id(&f.input) == *id) {
+                    return Some((id.clone(), Reading::Fan(fan)));
+                }
+            }
+        }
+        let hottest = |cpu_only: bool| {
+            self.chips
+                .iter()
+                .filter(|chip| !cpu_only || chip.component == Component::Cpu)
+                .flat_map(|chip| {
+                    chip.temperatures
+                        .iter()
+                        .map(move |t| (chip.sensor_id(&t.input), t))
+                })
+                .max_by(|(_, a), (_, b)| a.celsius.total_cmp(&b.celsius))
+        };
+        hottest(true)
+            .or_else(|| hottest(false))
+            .map(|(id, temperature)| (id, Reading::Temperature(temperature)))
+    }
+
+    fn sensor_row<'a>(
+        &self,
+        id: String,
+        label: &str,
+        reading: Element<'a, Message>,
+    ) -> Element<'a, Message> {
+        let mut content = row![
+            text(label.to_string()).size(14).width(Length::Fill),
+            reading
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+        if self.config.headline.as_ref() == Some(&id) {
+            content = content.push(icon("object-select-symbolic", 16).style(Svg::Symbolic));
+        }
+        button(content)
+            .padding([4, 8])
+            .style(theme::Button::Text)
+            .on_press(Message::SetHeadline(Some(id)))
+            .into()
+    }
+}
+
+impl cosmic::Application for Sensors {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Sensors {
+                core,
+                config: SensorsAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Chips(chips) => {
+                let warning = self.config.warning_celsius as f32;
+                let mut notifications = Vec::new();
+                for chip in &chips {
+                    for temperature in &chip.temperatures {
+                        let id = chip.sensor_id(&temperature.input);
+                        if temperature.celsius >= warning {
+                            if self.hot.insert(id) && self.config.notify {
+                                notifications.push(cosmic::iced::Command::perform(
+                                    notification::notify(
+                                        fl!("running-hot"),
+                                        fl!(
+                                            "running-hot-body",
+                                            sensor = format!(
+                                                "{} {}",
+                                                chip_name(chip),
+                                                temperature.label
+                                            ),
+                                            temperature = format_celsius(temperature.celsius)
+                                        ),
+                                    ),
+                                    |_| app::message::app(Message::Ignore),
+                                ));
+                            }
+                        } else if temperature.celsius < warning - HYSTERESIS {
+                            self.hot.remove(&id);
+                        }
+                    }
+                }
+                self.chips = chips;
+                return Command::batch(notifications);
+            }
+            Message::SetHeadline(headline) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_headline(headline, &config);
+                }
+            }
+            Message::SetAutomatic(automatic) => {
+                // keeps showing the same sensor until another is selected
+                let headline = if automatic {
+                    None
+                } else {
+                    self.headline().map(|(id, _)| id)
+                };
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_headline(headline, &config);
+                }
+            }
+            Message::SetWarningCelsius(warning_celsius) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_warning_celsius(warning_celsius, &config);
+                }
+            }
+            Message::SetNotify(notify) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_notify(notify, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+            Message::Ignore => {}
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let Some((_, reading)) = self.headline() else {
+            return self
+                .core
+                .applet_helper
+                .icon_button(ICON)
+                .on_press(Message::TogglePopup)
+                .into();
+        };
+        let (label, level) = match reading {
+            Reading::Temperature(temperature) => {
+                (format_celsius(temperature.celsius), self.level(temperature))
+            }
+            Reading::Fan(fan) => (fl!("rpm", rpm = fan.rpm), Level::Normal),
+        };
+        let mut children = vec![
+            icon(ICON, 16).style(Svg::Symbolic).into(),
+            value(label, level),
+        ];
+        // another sensor is hotter than the one shown
+        if self.worst_level() > level {
+            children.push(
+                icon("dialog-warning-symbolic", 16)
+                    .style(Svg::Symbolic)
+                    .into(),
+            );
+        }
+        let content: Element<_> = if matches!(
+            self.core.applet_helper.anchor,
+            PanelAnchor::Top | PanelAnchor::Bottom
+        ) {
+            Row::with_children(children)
+                .spacing(4)
+                .align_items(Alignment::Center)
+                .into()
+        } else {
+            Column::with_children(children)
+                .spacing(4)
+                .align_items(Alignment::Center)
+                .into()
+        };
+        button(content)
+            .on_press(Message::TogglePopup)
+            .style(theme::Button::Text)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut sensors = Column::new().spacing(4);
+        let mut rows = 0;
+        for chip in &self.chips {
+            sensors = sensors.push(container(text(chip_name(chip)).size(12)).padding([8, 8, 0, 8]));
+            for temperature in &chip.temperatures {
+                sensors = sensors.push(self.sensor_row(
+                    chip.sensor_id(&temperature.input),
+                    &temperature.label,
+                    value(format_celsius(temperature.celsius), self.level(temperature)),
+                ));
+            }
+            for fan in &chip.fans {
+                sensors = sensors.push(self.sensor_row(
+                    chip.sensor_id(&fan.input),
+                    &fan.label,
+                    value(fl!("rpm", rpm = fan.rpm), Level::Normal),
+                ));
+            }
+            rows += chip.temperatures.len() + chip.fans.len();
+        }
+        let sensors: Element<_> = if self.chips.is_empty() {
+            container(text(fl!("no-sensors")).size(14))
+                .padding([0, 24])
+                .into()
+        } else if rows > MAX_ROWS {
+            scrollable(sensors.padding([0, 16]))
+                .height(Length::Fixed(360.0))
+                .into()
+        } else {
+            sensors.padding([0, 16]).into()
+        };
+
+        let mut thresholds = Row::new().spacing(8).align_items(Alignment::Center);
+        thresholds = thresholds.push(text(fl!("warning-at")).size(14).width(Length::Fill));
+        for warning_celsius in THRESHOLDS {
+            let style = if warning_celsius == self.config.warning_celsius {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            thresholds = thresholds.push(
+                button(text(format_celsius(warning_celsius as f32)).size(14))
+                    .padding([4, 8])
+                    .style(style)
+                    .on_press(Message::SetWarningCelsius(warning_celsius)),
+            );
+        }
+
+        let content = column![
+            sensors,
+            container(divider::horizontal::light())
+                .width(Length::Fill)
+                .padding([0, 12]),
+            column![
+                toggler(
+                    fl!("automatic"),
+                    self.config.headline.is_none(),
+                    Message::SetAutomatic
+                )
+                .text_size(14)
+                .width(Length::Fill),
+                text(fl!("automatic-description")).size(12),
+                toggler(fl!("notify"), self.config.notify, Message::SetNotify)
+                    .text_size(14)
+                    .width(Length::Fill),
+                thresholds,
+            ]
+            .spacing(12)
+            .padding([0, 24]),
+        ]
+        .spacing(12)
+        .padding([8, 0]);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            chips().map(Message::Chips),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
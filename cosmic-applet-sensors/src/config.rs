@@ -0,0 +1,52 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletSensors";
+
+/// Warning thresholds offered in the popup, in degrees Celsius
+pub const THRESHOLDS: [u32; 3] = [70, 80, 90];
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct SensorsAppletConfig {
+    /// The sensor shown in the panel, as given by `Chip::sensor_id`, or `None` for the hottest
+    /// CPU temperature
+    pub headline: Option<String>,
+    /// Temperatures from this on are highlighted, in degrees Celsius
+    pub warning_celsius: u32,
+    /// Whether to notify when a temperature reaches the warning threshold
+    pub notify: bool,
+}
+
+impl Default for SensorsAppletConfig {
+    fn default() -> Self {
+        Self {
+            headline: None,
+            warning_celsius: 80,
+            notify: true,
+        }
+    }
+}
+
+impl SensorsAppletConfig {
+    pub fn load() -> SensorsAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| SensorsAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_headline(&mut self, headline: Option<String>, config: &Config) {
+        self.headline = headline;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_warning_celsius(&mut self, warning_celsius: u32, config: &Config) {
+        self.warning_celsius = warning_celsius;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_notify(&mut self, notify: bool, config: &Config) {
+        self.notify = notify;
+        let _ = self.write_entry(config);
+    }
+}
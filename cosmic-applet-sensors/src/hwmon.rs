@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Temperatures and fan speeds, read from the hwmon interface in sysfs that lm-sensors also uses
+
+use cosmic::iced::{self, futures::SinkExt, subscription};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const HWMON: &str = "/sys/class/hwmon";
+/// Time between readings
+const INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Component {
+    Cpu,
+    Gpu,
+    Nvme,
+    Other,
+}
+
+impl Component {
+    /// Guesses the component from the name of the chip's driver
+    fn from_chip(name: &str) -> Self {
+        match name {
+            "coretemp" | "k10temp" | "zenpower" | "cpu_thermal" => Component::Cpu,
+            "amdgpu" | "radeon" | "nouveau" | "i915" | "xe" => Component::Gpu,
+            "nvme" => Component::Nvme,
+            _ => Component::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Temperature {
+    /// Unique among the chip's sensors, e.g. `temp1`
+    pub input: String,
+    pub label: String,
+    pub celsius: f32,
+    /// The chip's critical temperature, where it reports one
+    pub critical: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fan {
+    /// Unique among the chip's sensors, e.g. `fan1`
+    pub input: String,
+    pub label: String,
+    pub rpm: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chip {
+    /// Driver and device, which unlike the hwmon numbering stay the same across boots
+    pub id: String,
+    /// Name of the driver
+    pub name: String,
+    /// Name of the device, such as `nvme0`
+    pub device: Option<String>,
+    pub component: Component,
+    pub temperatures: Vec<Temperature>,
+    pub fans: Vec<Fan>,
+}
+
+impl Chip {
+    /// Id of one of the chip's sensors, as stored in the config
+    pub fn sensor_id(&self, input: &str) -> String {
+        format!("{}/{}", self.id, input)
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<i64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// The inputs of one type, such as `temp`, in numeric order
+fn inputs(dir: &Path, prefix: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut inputs: Vec<(u32, String)> = entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name();
+            let input = file_name.to_str()?.strip_suffix("_input")?;
+            let n = input.strip_prefix(prefix)?.parse().ok()?;
+            Some((n, input.to_string()))
+        })
+        .collect();
+    inputs.sort();
+    inputs.into_iter().map(|(_, input)| input).collect()
+}
+
+fn read_chip(dir: &Path) -> Option<Chip> {
+    let name = read_trimmed(&dir.join("name"))?;
+    let device = fs::canonicalize(dir.join("device"))
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_str()?.to_string()));
+
+    let label = |input: &str| read_trimmed(&dir.join(format!("{input}_label")));
+    let temperatures = inputs(dir, "temp")
+        .into_iter()
+        .filter_map(|input| {
+            // sensors that are absent or failing don't read
+            let millidegrees = read_number(&dir.join(format!("{input}_input")))?;
+            Some(Temperature {
+                label: label(&input).unwrap_or_else(|| input.clone()),
+                celsius: millidegrees as f32 / 1000.0,
+                critical: read_number(&dir.join(format!("{input}_crit")))
+                    .filter(|c| *c > 0)
+                    .map(|c| c as f32 / 1000.0),
+                input,
+            })
+        })
+        .collect();
+    let fans = inputs(dir, "fan")
+        .into_iter()
+        .filter_map(|input| {
+            let rpm = read_number(&dir.join(format!("{input}_input")))?;
+            Some(Fan {
+                label: label(&input).unwrap_or_else(|| input.clone()),
+                rpm: rpm.max(0) as u32,
+                input,
+            })
+        })
+        .collect();
+
+    Some(Chip {
+        id: match &device {
+            Some(device) => format!("{name}@{device}"),
+            None => name.clone(),
+        },
+        component: Component::from_chip(&name),
+        name,
+        device,
+        temperatures,
+        fans,
+    })
+}
+
+/// Chips with any readable sensor, CPUs first
+fn read_chips() -> Vec<Chip> {
+    let Ok(entries) = fs::read_dir(HWMON) else {
+        return Vec::new();
+    };
+    let mut chips: Vec<Chip> = entries
+        .filter_map(|entry| read_chip(&entry.ok()?.path()))
+        .filter(|chip| !chip.temperatures.is_empty() || !chip.fans.is_empty())
+        .collect();
+    chips.sort_by(|a, b| (a.component, &a.id).cmp(&(b.component, &b.id)));
+    chips
+}
+
+/// Reads the sensors in a background task, every [`INTERVAL`]
+pub fn chips() -> iced::Subscription<Vec<Chip>> {
+    struct Sensors;
+
+    subscription::channel(
+        std::any::TypeId::of::<Sensors>(),
+        1,
+        |mut output| async move {
+            loop {
+                _ = output.send(read_chips()).await;
+                tokio::time::sleep(INTERVAL).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,11 @@
+mod app;
+mod config;
+mod hwmon;
+mod localize;
+mod notification;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
@@ -0,0 +1,8 @@
+search = Search hosts
+no-hosts = No hosts in ~/.ssh/config
+no-matches = No matching hosts
+note = Note
+save = Save
+terminal = Terminal
+terminal-command = Terminal command, such as cosmic-term -e
+no-terminal = No terminal command is set
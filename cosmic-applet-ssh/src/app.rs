@@ -0,0 +1,322 @@
+use crate::config::{self, SshAppletConfig};
+use crate::fl;
+use crate::ssh_config::{self, Host};
+use cosmic::app::Command;
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, scrollable, text, text_input, Column},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::process;
+
+const ICON: &str = "utilities-terminal-symbolic";
+/// Hosts listed before the list scrolls
+const MAX_UNSCROLLED: usize = 8;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Ssh>(true, ())
+}
+
+#[derive(Default)]
+struct Ssh {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: SshAppletConfig,
+    hosts: Vec<Host>,
+    search: String,
+    /// Alias of the host whose note is being edited, and the edited note
+    editing: Option<(String, String)>,
+    terminal_input: String,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Search(String),
+    Connect(String),
+    EditNote(String),
+    NoteInput(String),
+    SaveNote,
+    TerminalInput(String),
+    SaveTerminal,
+    ConfigUpdated(SshAppletConfig),
+}
+
+impl Ssh {
+    /// Hosts matching the search, the recently connected to first and the others in the order
+    /// of the configuration
+    fn listed(&self) -> Vec<&Host> {
+        let search = self.search.trim().to_lowercase();
+        let mut listed: Vec<&Host> = self
+            .hosts
+            .iter()
+            .filter(|host| {
+                search.is_empty()
+                    || host.alias.to_lowercase().contains(&search)
+                    || host
+                        .hostname
+                        .as_ref()
+                        .map_or(false, |h| h.to_lowercase().contains(&search))
+                    || self
+                        .config
+                        .notes
+                        .get(&host.alias)
+                        .map_or(false, |n| n.to_lowercase().contains(&search))
+            })
+            .collect();
+        listed.sort_by_key(|host| {
+            self.config
+                .recent
+                .iter()
+                .position(|alias| *alias == host.alias)
+                .unwrap_or(usize::MAX)
+        });
+        listed
+    }
+
+    fn host_view<'a>(&'a self, host: &'a Host) -> Element<'a, Message> {
+        let mut details = column![text(&host.alias).size(14)].spacing(2);
+        if let Some(destination) = host.destination() {
+            details = details.push(text(destination).size(12));
+        }
+        if let Some(note) = self.config.notes.get(&host.alias) {
+            details = details.push(text(note).size(12));
+        }
+        let host_row = row![
+            button(details)
+                .on_press(Message::Connect(host.alias.clone()))
+                .style(theme::Button::Text)
+                .width(Length::Fill),
+            button(icon("document-edit-symbolic", 16).style(Svg::Symbolic))
+                .on_press(Message::EditNote(host.alias.clone()))
+                .style(theme::Button::Text),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+
+        match &self.editing {
+            Some((alias, note)) if *alias == host.alias => column![
+                host_row,
+                row![
+                    text_input(&fl!("note"), note)
+                        .on_input(Message::NoteInput)
+                        .on_submit(Message::SaveNote)
+                        .size(14)
+                        .width(Length::Fill),
+                    button(text(fl!("save")).size(14))
+                        .on_press(Message::SaveNote)
+                        .style(theme::Button::Text),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            ]
+            .spacing(4)
+            .into(),
+            _ => host_row.into(),
+        }
+    }
+}
+
+impl cosmic::Application for Ssh {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Ssh {
+                core,
+                config: SshAppletConfig::load(),
+                hosts: ssh_config::hosts(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                // the configuration may have been edited since it was last read
+                self.hosts = ssh_config::hosts();
+                self.search.clear();
+                self.editing = None;
+                self.terminal_input = self.config.terminal_command.clone();
+                self.error = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Search(search) => {
+                self.search = search;
+            }
+            Message::Connect(alias) => {
+                let mut command = self.config.terminal_command.split_whitespace();
+                let Some(program) = command.next() else {
+                    self.error = Some(fl!("no-terminal"));
+                    return Command::none();
+                };
+                if let Err(err) = process::Command::new(program)
+                    .args(command)
+                    .args(["ssh", &alias])
+                    .spawn()
+                {
+                    self.error = Some(format!("{}: {}", program, err));
+                    return Command::none();
+                }
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_recent(&alias, &config);
+                }
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+            }
+            Message::EditNote(alias) => {
+                if self.editing.as_ref().map_or(false, |(a, _)| *a == alias) {
+                    self.editing = None;
+                } else {
+                    let note = self.config.notes.get(&alias).cloned().unwrap_or_default();
+                    self.editing = Some((alias, note));
+                }
+            }
+            Message::NoteInput(input) => {
+                if let Some((_, note)) = &mut self.editing {
+                    *note = input;
+                }
+            }
+            Message::SaveNote => {
+                if let Some((alias, note)) = self.editing.take() {
+                    if let Ok(config) = Config::new(config::APP_ID, 1) {
+                        self.config.set_note(&alias, &note, &config);
+                    }
+                }
+            }
+            Message::TerminalInput(input) => {
+                self.terminal_input = input;
+            }
+            Message::SaveTerminal => {
+                let terminal_command = self.terminal_input.trim();
+                if !terminal_command.is_empty() {
+                    if let Ok(config) = Config::new(config::APP_ID, 1) {
+                        self.config
+                            .set_terminal_command(terminal_command.to_string(), &config);
+                    }
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        self.core
+            .applet_helper
+            .icon_button(ICON)
+            .on_press(Message::TogglePopup)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        if self.hosts.is_empty() {
+            content = content.push(text(fl!("no-hosts")).size(14));
+        } else {
+            content = content.push(
+                text_input(&fl!("search"), &self.search)
+                    .on_input(Message::Search)
+                    .size(14)
+                    .width(Length::Fill),
+            );
+            let listed = self.listed();
+            if listed.is_empty() {
+                content = content.push(text(fl!("no-matches")).size(14));
+            }
+            let hosts: Vec<Element<Message>> =
+                listed.iter().map(|host| self.host_view(host)).collect();
+            if hosts.len() > MAX_UNSCROLLED {
+                content = content.push(
+                    scrollable(Column::with_children(hosts).spacing(4))
+                        .height(Length::Fixed(400.0)),
+                );
+            } else {
+                content = content.push(Column::with_children(hosts).spacing(4));
+            }
+        }
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(12));
+        }
+        content = content
+            .push(container(divider::horizontal::light()).width(Length::Fill))
+            .push(text(fl!("terminal")).size(12))
+            .push(
+                row![
+                    text_input(&fl!("terminal-command"), &self.terminal_input)
+                        .on_input(Message::TerminalInput)
+                        .on_submit(Message::SaveTerminal)
+                        .size(14)
+                        .width(Length::Fill),
+                    button(text(fl!("save")).size(14))
+                        .on_press(Message::SaveTerminal)
+                        .style(theme::Button::Text),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(|(_, config)| {
+            match config {
+                Ok(config) => Message::ConfigUpdated(config),
+                Err((errors, config)) => {
+                    for error in errors {
+                        log::error!("{:?}", error);
+                    }
+                    Message::ConfigUpdated(config)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const APP_ID: &str = "com.system76.CosmicAppletSsh";
+
+/// Hosts remembered as recently connected to
+const MAX_RECENT: usize = 20;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct SshAppletConfig {
+    /// Terminal that runs `ssh`, which is given as further arguments
+    pub terminal_command: String,
+    /// Aliases of the hosts connected to, most recent first
+    pub recent: Vec<String>,
+    /// Notes on the hosts, by alias
+    pub notes: BTreeMap<String, String>,
+}
+
+impl Default for SshAppletConfig {
+    fn default() -> Self {
+        Self {
+            terminal_command: "cosmic-term -e".to_string(),
+            recent: Vec::new(),
+            notes: BTreeMap::new(),
+        }
+    }
+}
+
+impl SshAppletConfig {
+    pub fn load() -> SshAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| SshAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_terminal_command(&mut self, terminal_command: String, config: &Config) {
+        self.terminal_command = terminal_command;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn add_recent(&mut self, alias: &str, config: &Config) {
+        self.recent.retain(|a| a != alias);
+        self.recent.insert(0, alias.to_string());
+        self.recent.truncate(MAX_RECENT);
+        let _ = self.write_entry(config);
+    }
+
+    /// An empty note removes the host's note
+    pub fn set_note(&mut self, alias: &str, note: &str, config: &Config) {
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(alias);
+        } else {
+            self.notes.insert(alias.to_string(), note.to_string());
+        }
+        let _ = self.write_entry(config);
+    }
+}
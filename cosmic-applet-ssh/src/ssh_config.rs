@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Hosts named in the OpenSSH client configuration, `~/.ssh/config` and the files it includes
+
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Includes are followed this deep, like `ssh` does
+const MAX_INCLUDE_DEPTH: u8 = 16;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Host {
+    /// The name given to `ssh`
+    pub alias: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<String>,
+}
+
+impl Host {
+    /// Where the alias leads, such as `admin@example.com:2222`, if it's configured
+    pub fn destination(&self) -> Option<String> {
+        if self.hostname.is_none() && self.user.is_none() && self.port.is_none() {
+            return None;
+        }
+        let mut destination = String::new();
+        if let Some(user) = &self.user {
+            destination.push_str(user);
+            destination.push('@');
+        }
+        destination.push_str(self.hostname.as_deref().unwrap_or(&self.alias));
+        if let Some(port) = &self.port {
+            destination.push(':');
+            destination.push_str(port);
+        }
+        Some(destination)
+    }
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".ssh"))
+}
+
+/// Matches `*` and `?` wildcards, as in host patterns and include paths
+fn matches(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            matches(&pattern[1..], name) || (!name.is_empty() && matches(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => matches(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => matches(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Files of an include, which may start with `~` or be relative to `~/.ssh`, and may have
+/// wildcards in the file name
+fn include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => ssh_dir.parent().unwrap_or(ssh_dir).join(rest),
+        None => ssh_dir.join(pattern),
+    };
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(Ok(entries)) = path.parent().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| matches(file_name.as_bytes(), entry.file_name().as_bytes()))
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches('"').to_string()
+}
+
+fn parse_file(path: &Path, ssh_dir: &Path, hosts: &mut Vec<Host>, depth: u8) {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+    // the hosts of the current `Host` block
+    let mut current: Vec<usize> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, args) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+        // the first value given for an option is the one that applies
+        let set = |hosts: &mut Vec<Host>, field: fn(&mut Host) -> &mut Option<String>| {
+            for i in &current {
+                let value = field(&mut hosts[*i]);
+                if value.is_none() {
+                    *value = Some(unquote(args));
+                }
+            }
+        };
+        match keyword.to_ascii_lowercase().as_str() {
+            "host" => {
+                current.clear();
+                // patterns and negations apply to many hosts rather than naming one
+                for alias in args.split_whitespace().map(unquote) {
+                    if alias.contains(['*', '?', '!']) {
+                        continue;
+                    }
+                    let i = match hosts.iter().position(|h| h.alias == alias) {
+                        Some(i) => i,
+                        None => {
+                            hosts.push(Host {
+                                alias,
+                                ..Default::default()
+                            });
+                            hosts.len() - 1
+                        }
+                    };
+                    current.push(i);
+                }
+            }
+            // conditional blocks don't name hosts
+            "match" => current.clear(),
+            "hostname" => set(hosts, |h| &mut h.hostname),
+            "user" => set(hosts, |h| &mut h.user),
+            "port" => set(hosts, |h| &mut h.port),
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in args.split_whitespace().map(unquote) {
+                    for file in include(&pattern, ssh_dir) {
+                        parse_file(&file, ssh_dir, hosts, depth + 1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The hosts, in the order they're first named
+pub fn hosts() -> Vec<Host> {
+    let mut hosts = Vec::new();
+    if let Some(ssh_dir) = ssh_dir() {
+        parse_file(&ssh_dir.join("config"), &ssh_dir, &mut hosts, 0);
+    }
+    hosts
+}
@@ -141,7 +141,9 @@ impl cosmic::Application for App {
                 };
                 // Reuse popup if a different menu is opened.
                 // Had issue creating new one. Does it make a difference?
-                if self.open_menu.is_some() {
+                if let Some(menu) = self.open_menu.and_then(|id| self.menus.get_mut(&id)) {
+                    menu.clear_filter();
+                    let focus = iced::widget::text_input::focus(status_menu::FILTER_INPUT.clone());
                     if self.popup.is_none() {
                         let id = self.next_popup_id();
                         let popup_settings = self.core.applet_helper.get_popup_settings(
@@ -152,8 +154,9 @@ impl cosmic::Application for App {
                             None,
                         );
                         self.popup = Some(id);
-                        return get_popup(popup_settings);
+                        return Command::batch([get_popup(popup_settings), focus]);
                     }
+                    return focus;
                 } else if let Some(id) = self.popup {
                     return destroy_popup(id);
                 }
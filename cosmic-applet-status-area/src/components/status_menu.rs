@@ -1,17 +1,31 @@
 use cosmic::{iced, theme};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
 
-use crate::subscriptions::status_notifier_item::{Layout, StatusNotifierItem};
+use crate::subscriptions::status_notifier_item::{get_layout, Layout, StatusNotifierItem};
+
+/// Menus with more entries than this get a filter field
+const FILTER_THRESHOLD: usize = 15;
+
+pub static FILTER_INPUT: Lazy<iced::widget::text_input::Id> =
+    Lazy::new(iced::widget::text_input::Id::unique);
 
 #[derive(Clone, Debug)]
 pub enum Msg {
-    Layout(Result<Layout, String>),
+    /// The children of the menu with this id changed, 0 being the root menu
+    LayoutUpdated(i32),
+    Layout(i32, Result<Layout, String>),
     Click(i32, bool),
+    Filter(String),
 }
 
 pub struct State {
     item: StatusNotifierItem,
     layout: Option<Layout>,
+    /// Submenus are only fetched once they are expanded or searched
+    submenus: HashMap<i32, Layout>,
     expanded: Option<i32>,
+    filter: String,
 }
 
 impl State {
@@ -20,18 +34,50 @@ impl State {
             Self {
                 item,
                 layout: None,
+                submenus: HashMap::new(),
                 expanded: None,
+                filter: String::new(),
             },
             iced::Command::none(),
         )
     }
 
+    fn fetch(&self, id: i32, about_to_show: bool) -> iced::Command<Msg> {
+        let menu_proxy = self.item.menu_proxy().clone();
+        iced::Command::perform(
+            async move {
+                if about_to_show {
+                    let _ = menu_proxy.about_to_show(id).await;
+                }
+                get_layout(menu_proxy, id).await
+            },
+            move |layout| Msg::Layout(id, layout),
+        )
+    }
+
     pub fn update(&mut self, message: Msg) -> iced::Command<Msg> {
         match message {
-            Msg::Layout(layout) => {
+            Msg::LayoutUpdated(parent) => {
+                // Changes inside submenus that were never fetched don't matter
+                if parent == 0 || self.submenus.contains_key(&parent) {
+                    self.fetch(parent, false)
+                } else {
+                    iced::Command::none()
+                }
+            }
+            Msg::Layout(id, layout) => {
                 match layout {
-                    Ok(layout) => {
+                    Ok(layout) if id == 0 => {
+                        // Refresh the fetched submenus that still exist
+                        self.submenus
+                            .retain(|id, _| layout.children().iter().any(|i| i.id() == *id));
                         self.layout = Some(layout);
+                        return iced::Command::batch(
+                            self.submenus.keys().map(|id| self.fetch(*id, false)),
+                        );
+                    }
+                    Ok(layout) => {
+                        self.submenus.insert(id, layout);
                     }
                     Err(err) => eprintln!("Error getting layout from icon: {}", err),
                 }
@@ -48,14 +94,47 @@ impl State {
                     } else {
                         None
                     };
+                    if self.expanded.is_some() && !self.submenus.contains_key(&id) {
+                        return self.fetch(id, true);
+                    }
                 } else {
                     // TODO: Close menu?
                 }
                 iced::Command::none()
             }
+            Msg::Filter(filter) => {
+                let started = self.filter.is_empty() && !filter.is_empty();
+                self.filter = filter;
+                // Searching looks one level into the submenus, fetch the ones still missing
+                match self.layout.as_ref().filter(|_| started) {
+                    Some(layout) => iced::Command::batch(
+                        layout
+                            .children()
+                            .iter()
+                            .filter(|i| is_submenu(i) && !self.submenus.contains_key(&i.id()))
+                            .map(|i| self.fetch(i.id(), true))
+                            .collect::<Vec<_>>(),
+                    ),
+                    None => iced::Command::none(),
+                }
+            }
         }
     }
 
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+    }
+
+    /// Whether the menu is long enough to need the filter field
+    pub fn is_long(&self) -> bool {
+        self.layout
+            .iter()
+            .chain(self.submenus.values())
+            .any(|layout| {
+                layout.children().iter().filter(|i| i.visible()).count() > FILTER_THRESHOLD
+            })
+    }
+
     pub fn name(&self) -> &str {
         self.item.name()
     }
@@ -65,19 +144,108 @@ impl State {
     }
 
     pub fn popup_view(&self) -> cosmic::Element<Msg> {
-        if let Some(layout) = self.layout.as_ref() {
-            layout_view(layout, self.expanded)
-        } else {
-            iced::widget::text("").into()
+        let Some(layout) = self.layout.as_ref() else {
+            return iced::widget::text("").into();
+        };
+        if !self.is_long() {
+            return layout_view(layout, &self.submenus, self.expanded);
         }
+        let menu = if self.filter.is_empty() {
+            layout_view(layout, &self.submenus, self.expanded)
+        } else {
+            let mut items = Vec::new();
+            filtered_items(
+                layout,
+                &self.submenus,
+                &self.filter.to_lowercase(),
+                0,
+                &mut items,
+            );
+            iced::widget::column(items).into()
+        };
+        iced::widget::column![
+            iced::widget::container(
+                iced::widget::text_input("Search…", &self.filter)
+                    .id(FILTER_INPUT.clone())
+                    .on_input(Msg::Filter)
+            )
+            .padding([8, 24]),
+            iced::widget::scrollable(menu),
+        ]
+        .into()
     }
 
     pub fn subscription(&self) -> iced::Subscription<Msg> {
-        self.item.layout_subscription().map(Msg::Layout)
+        self.item.layout_subscription().map(Msg::LayoutUpdated)
     }
 }
 
-fn layout_view(layout: &Layout, expanded: Option<i32>) -> cosmic::Element<Msg> {
+fn is_submenu(i: &Layout) -> bool {
+    i.children_display() == Some("submenu")
+}
+
+// Strip _ when not doubled
+// TODO: interpret as "access key"? And label with underline.
+fn strip_mnemonic(label: &str) -> String {
+    let mut is_underscore = false;
+    label
+        .chars()
+        .filter(|c| {
+            let prev_is_underscore = is_underscore;
+            is_underscore = !is_underscore && *c == '_';
+            *c != '_' || prev_is_underscore
+        })
+        .collect()
+}
+
+fn item_view(i: &Layout, label: String, is_expanded: bool) -> cosmic::Element<Msg> {
+    let is_submenu = is_submenu(i);
+
+    let text = iced::widget::text(label).width(iced::Length::Fill);
+
+    let mut children: Vec<cosmic::Element<_>> = vec![text.into()];
+    if is_submenu {
+        let icon = cosmic::widget::icon(
+            if is_expanded {
+                "go-down-symbolic"
+            } else {
+                "go-next-symbolic"
+            },
+            14,
+        )
+        .style(theme::Svg::Symbolic);
+        children.push(icon.into());
+    }
+    if let Some(icon_data) = i.icon_data() {
+        let handle = iced::widget::image::Handle::from_memory(icon_data.to_vec());
+        children.insert(0, iced::widget::Image::new(handle).into());
+    } else if let Some(icon_name) = i.icon_name() {
+        let icon = cosmic::widget::icon(icon_name, 14).style(theme::Svg::Symbolic);
+        children.insert(0, icon.into());
+    }
+    if i.toggle_state() == Some(1) {
+        let icon = cosmic::widget::icon("emblem-ok-symbolic", 14).style(theme::Svg::Symbolic);
+        children.push(icon.into());
+    }
+    row_button(children)
+        .on_press(Msg::Click(i.id(), is_submenu))
+        .into()
+}
+
+fn indented(content: cosmic::Element<Msg>, depth: u16) -> cosmic::Element<Msg> {
+    iced::widget::container(content)
+        .padding(iced::Padding {
+            left: 12. * f32::from(depth),
+            ..iced::Padding::ZERO
+        })
+        .into()
+}
+
+fn layout_view<'a>(
+    layout: &'a Layout,
+    submenus: &'a HashMap<i32, Layout>,
+    expanded: Option<i32>,
+) -> cosmic::Element<'a, Msg> {
     iced::widget::column(
         layout
             .children()
@@ -88,66 +256,18 @@ fn layout_view(layout: &Layout, expanded: Option<i32>) -> cosmic::Element<Msg> {
                 } else if i.type_().as_deref() == Some("separator") {
                     Some(iced::widget::horizontal_rule(2).into())
                 } else if let Some(label) = i.label() {
-                    // Strip _ when not doubled
-                    // TODO: interpret as "access key"? And label with underline.
-                    let mut is_underscore = false;
-                    let label = label
-                        .chars()
-                        .filter(|c| {
-                            let prev_is_underscore = is_underscore;
-                            is_underscore = !is_underscore && *c == '_';
-                            *c != '_' || prev_is_underscore
-                        })
-                        .collect::<String>();
-
-                    let is_submenu = i.children_display().as_deref() == Some("submenu");
                     let is_expanded = expanded == Some(i.id());
+                    let button = item_view(i, strip_mnemonic(label), is_expanded);
 
-                    let text = iced::widget::text(label).width(iced::Length::Fill);
-
-                    let mut children: Vec<cosmic::Element<_>> = vec![text.into()];
-                    if is_submenu {
-                        let icon = cosmic::widget::icon(
-                            if is_expanded {
-                                "go-down-symbolic"
-                            } else {
-                                "go-next-symbolic"
-                            },
-                            14,
-                        )
-                        .style(theme::Svg::Symbolic);
-                        children.push(icon.into());
-                    }
-                    if let Some(icon_data) = i.icon_data() {
-                        let handle = iced::widget::image::Handle::from_memory(icon_data.to_vec());
-                        children.insert(0, iced::widget::Image::new(handle).into());
-                    } else if let Some(icon_name) = i.icon_name() {
-                        let icon = cosmic::widget::icon(icon_name, 14).style(theme::Svg::Symbolic);
-                        children.insert(0, icon.into());
-                    }
-                    if i.toggle_state() == Some(1) {
-                        let icon = cosmic::widget::icon("emblem-ok-symbolic", 14)
-                            .style(theme::Svg::Symbolic);
-                        children.push(icon.into());
-                    }
-                    let button = row_button(children).on_press(Msg::Click(i.id(), is_submenu));
-
-                    if is_submenu && is_expanded {
-                        Some(
-                            iced::widget::column![
-                                button,
-                                // XXX nested
-                                iced::widget::container(layout_view(i, None)).padding(
-                                    iced::Padding {
-                                        left: 12.,
-                                        ..iced::Padding::ZERO
-                                    }
-                                )
-                            ]
-                            .into(),
-                        )
+                    if is_submenu(i) && is_expanded {
+                        // XXX nested
+                        let submenu = match submenus.get(&i.id()) {
+                            Some(submenu) => layout_view(submenu, submenus, None),
+                            None => row_button(vec![iced::widget::text("…").into()]).into(),
+                        };
+                        Some(iced::widget::column![button, indented(submenu, 1)].into())
                     } else {
-                        Some(button.into())
+                        Some(button)
                     }
                 } else {
                     None
@@ -158,6 +278,33 @@ fn layout_view(layout: &Layout, expanded: Option<i32>) -> cosmic::Element<Msg> {
     .into()
 }
 
+/// Items whose label contains the filter, along with the matches inside fetched submenus
+fn filtered_items<'a>(
+    layout: &'a Layout,
+    submenus: &'a HashMap<i32, Layout>,
+    filter: &str,
+    depth: u16,
+    items: &mut Vec<cosmic::Element<'a, Msg>>,
+) {
+    for i in layout.children().iter().filter(|i| i.visible()) {
+        let Some(label) = i.label().map(strip_mnemonic) else {
+            continue;
+        };
+        let submenu = submenus.get(&i.id()).filter(|_| is_submenu(i));
+        let mut submenu_items = Vec::new();
+        if let Some(submenu) = submenu {
+            filtered_items(submenu, submenus, filter, depth + 1, &mut submenu_items);
+        }
+        if !submenu_items.is_empty() || label.to_lowercase().contains(filter) {
+            items.push(indented(
+                item_view(i, label, !submenu_items.is_empty()),
+                depth,
+            ));
+            items.append(&mut submenu_items);
+        }
+    }
+}
+
 fn row_button(content: Vec<cosmic::Element<Msg>>) -> iced::widget::Button<Msg, cosmic::Renderer> {
     cosmic::widget::button(cosmic::app::applet::applet_button_theme())
         .custom(vec![iced::widget::Row::with_children(content)
@@ -49,15 +49,16 @@ impl StatusNotifierItem {
         &self.icon_name
     }
 
-    // TODO: Only fetch changed part of layout, if that's any faster
-    pub fn layout_subscription(&self) -> iced::Subscription<Result<Layout, String>> {
+    /// Emits the ids of the menus whose children changed, starting with the root menu (0)
+    pub fn layout_subscription(&self) -> iced::Subscription<i32> {
         let menu_proxy = self.menu_proxy.clone();
         iced::subscription::run_with_id(
             format!("status-notifier-item-{}", &self.name),
             async move {
-                let initial = futures::stream::once(get_layout(menu_proxy.clone()));
+                let initial = futures::stream::once(async { 0 });
                 let layout_updated_stream = menu_proxy.receive_layout_updated().await.unwrap();
-                let updates = layout_updated_stream.then(move |_| get_layout(menu_proxy.clone()));
+                let updates = layout_updated_stream
+                    .filter_map(|signal| async move { signal.args().ok().map(|a| *a.parent()) });
                 initial.chain(updates)
             }
             .flatten_stream(),
@@ -69,8 +70,9 @@ impl StatusNotifierItem {
     }
 }
 
-async fn get_layout(menu_proxy: DBusMenuProxy<'static>) -> Result<Layout, String> {
-    match menu_proxy.get_layout(0, -1, &[]).await {
+/// Fetch a menu along with its direct children, submenus are fetched separately when needed
+pub async fn get_layout(menu_proxy: DBusMenuProxy<'static>, parent: i32) -> Result<Layout, String> {
+    match menu_proxy.get_layout(parent, 1, &[]).await {
         Ok((_, layout)) => Ok(layout),
         Err(err) => Err(err.to_string()),
     }
@@ -202,6 +204,8 @@ trait DBusMenu {
     fn event(&self, id: i32, event_id: &str, data: &OwnedValue, timestamp: u32)
         -> zbus::Result<()>;
 
+    fn about_to_show(&self, id: i32) -> zbus::Result<bool>;
+
     #[dbus_proxy(signal)]
     fn layout_updated(&self, revision: u32, parent: i32) -> zbus::Result<()>;
 }
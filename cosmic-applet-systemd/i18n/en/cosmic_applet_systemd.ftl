@@ -0,0 +1,13 @@
+system = System
+user = User
+manager = Manager
+start = Start
+stop = Stop
+restart = Restart
+journal = Journal
+loading = Loading…
+no-entries = No journal entries
+not-found = Not found
+no-units = No units are watched. Add units by their name, such as sshd.service.
+unit-name = Unit name
+add = Add
@@ -0,0 +1,357 @@
+use crate::config::{self, Scope, SystemdAppletConfig, WatchedUnit};
+use crate::fl;
+use crate::systemd::{self, units_subscription, Action, UnitState};
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, text, text_input, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Systemd>(true, ())
+}
+
+#[derive(Default)]
+struct Systemd {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: SystemdAppletConfig,
+    states: Vec<UnitState>,
+    /// Units with an action in progress
+    busy: HashSet<WatchedUnit>,
+    error: Option<String>,
+    /// The unit whose journal is shown, with its entries once read
+    journal: Option<(WatchedUnit, Option<Result<String, String>>)>,
+    unit_input: String,
+    scope_input: Option<Scope>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    States(Vec<UnitState>),
+    Act(WatchedUnit, Action),
+    Done(WatchedUnit, Result<(), String>),
+    ToggleJournal(WatchedUnit),
+    Journal(WatchedUnit, Result<String, String>),
+    UnitInput(String),
+    ScopeInput(Scope),
+    AddUnit,
+    RemoveUnit(WatchedUnit),
+    ConfigUpdated(SystemdAppletConfig),
+}
+
+fn action_button<'a>(label: String, message: Option<Message>) -> Element<'a, Message> {
+    let mut action = button(text(label).size(12))
+        .padding([4, 8])
+        .style(theme::Button::Secondary);
+    if let Some(message) = message {
+        action = action.on_press(message);
+    }
+    action.into()
+}
+
+impl Systemd {
+    fn failed(&self) -> usize {
+        self.states.iter().filter(|state| state.failed()).count()
+    }
+
+    fn unit_view(&self, state: &UnitState) -> Element<Message> {
+        let unit = &state.unit;
+        let status_icon = if state.failed() {
+            "dialog-error-symbolic"
+        } else if state.active() {
+            "media-playback-start-symbolic"
+        } else {
+            "media-playback-stop-symbolic"
+        };
+        let status = if state.load_state == "not-found" {
+            fl!("not-found")
+        } else {
+            format!("{} ({})", state.active_state, state.sub_state)
+        };
+        let description = if state.description.is_empty() || state.description == unit.name {
+            status
+        } else {
+            format!("{} · {}", state.description, status)
+        };
+        let scope = match unit.scope {
+            Scope::System => fl!("system"),
+            Scope::User => fl!("user"),
+        };
+
+        let busy = self.busy.contains(unit);
+        let act = |action| (!busy).then(|| Message::Act(unit.clone(), action));
+        let mut actions = Row::new().spacing(8).align_items(Alignment::Center);
+        if state.active() {
+            actions = actions
+                .push(action_button(fl!("stop"), act(Action::Stop)))
+                .push(action_button(fl!("restart"), act(Action::Restart)));
+        } else {
+            actions = actions.push(action_button(fl!("start"), act(Action::Start)));
+        }
+        actions = actions.push(action_button(
+            fl!("journal"),
+            Some(Message::ToggleJournal(unit.clone())),
+        ));
+
+        let mut content = column![
+            row![
+                icon(status_icon, 16).style(Svg::Symbolic),
+                column![
+                    text(format!("{} ({})", unit.name, scope)).size(14),
+                    text(description).size(12),
+                ]
+                .spacing(2)
+                .width(Length::Fill),
+                button(icon("edit-delete-symbolic", 16).style(Svg::Symbolic))
+                    .on_press(Message::RemoveUnit(unit.clone()))
+                    .style(theme::Button::Text),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+            actions,
+        ]
+        .spacing(8);
+
+        if let Some((_, journal)) = self.journal.as_ref().filter(|(u, _)| u == unit) {
+            let entries = match journal {
+                None => fl!("loading"),
+                Some(Ok(entries)) if entries.is_empty() => fl!("no-entries"),
+                Some(Ok(entries)) => entries.clone(),
+                Some(Err(err)) => err.clone(),
+            };
+            content = content.push(text(entries).size(10));
+        }
+        content.into()
+    }
+}
+
+impl cosmic::Application for Systemd {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Systemd {
+                core,
+                config: SystemdAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.error = None;
+                self.journal = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(450.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::States(states) => {
+                self.states = states;
+            }
+            Message::Act(unit, action) => {
+                self.busy.insert(unit.clone());
+                self.error = None;
+                return cosmic::iced::Command::perform(
+                    systemd::act(unit.clone(), action),
+                    move |res| app::message::app(Message::Done(unit, res)),
+                );
+            }
+            Message::Done(unit, res) => {
+                self.busy.remove(&unit);
+                if let Err(err) = res {
+                    self.error = Some(err);
+                }
+            }
+            Message::ToggleJournal(unit) => {
+                if self.journal.as_ref().map_or(false, |(u, _)| *u == unit) {
+                    self.journal = None;
+                } else {
+                    self.journal = Some((unit.clone(), None));
+                    return cosmic::iced::Command::perform(
+                        systemd::journal(unit.clone()),
+                        move |res| app::message::app(Message::Journal(unit, res)),
+                    );
+                }
+            }
+            Message::Journal(unit, res) => {
+                // ignored if another unit's journal was opened in the meantime
+                if let Some((u, journal)) = &mut self.journal {
+                    if *u == unit {
+                        *journal = Some(res);
+                    }
+                }
+            }
+            Message::UnitInput(input) => {
+                self.unit_input = input;
+            }
+            Message::ScopeInput(scope) => {
+                self.scope_input = Some(scope);
+            }
+            Message::AddUnit => {
+                let name = self.unit_input.trim();
+                if name.is_empty() {
+                    return Command::none();
+                }
+                // like systemctl, a name without a type is a service
+                let name = if name.contains('.') {
+                    name.to_string()
+                } else {
+                    format!("{name}.service")
+                };
+                let unit = WatchedUnit {
+                    scope: self.scope_input.unwrap_or(Scope::System),
+                    name,
+                };
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_unit(unit, &config);
+                }
+                self.unit_input.clear();
+            }
+            Message::RemoveUnit(unit) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.remove_unit(&unit, &config);
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let failed = self.failed();
+        if failed == 0 {
+            return self
+                .core
+                .applet_helper
+                .icon_button("emblem-system-symbolic")
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+        button(
+            row![
+                icon("dialog-error-symbolic", 16).style(Svg::SymbolicActive),
+                text(failed.to_string()).size(14)
+            ]
+            .spacing(4)
+            .align_items(Alignment::Center),
+        )
+        .on_press(Message::TogglePopup)
+        .style(theme::Button::Text)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let mut units = Column::new().spacing(16);
+        for state in &self.states {
+            units = units.push(self.unit_view(state));
+        }
+        if self.config.units.is_empty() {
+            units = units.push(text(fl!("no-units")).size(14));
+        }
+
+        let scope_input = self.scope_input.unwrap_or(Scope::System);
+        let mut scopes = Row::new().spacing(8).align_items(Alignment::Center);
+        scopes = scopes.push(text(fl!("manager")).size(14).width(Length::Fill));
+        for (scope, label) in [(Scope::System, fl!("system")), (Scope::User, fl!("user"))] {
+            let style = if scope == scope_input {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            scopes = scopes.push(
+                button(text(label).size(14))
+                    .padding([4, 8])
+                    .style(style)
+                    .on_press(Message::ScopeInput(scope)),
+            );
+        }
+
+        let mut content = column![units].spacing(12).padding([8, 24]);
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(12));
+        }
+        content = content.push(container(divider::horizontal::light()).width(Length::Fill));
+        content = content.push(
+            row![
+                text_input(&fl!("unit-name"), &self.unit_input)
+                    .on_input(Message::UnitInput)
+                    .on_submit(Message::AddUnit)
+                    .size(14)
+                    .width(Length::Fill),
+                button(text(fl!("add")).size(14))
+                    .on_press(Message::AddUnit)
+                    .style(theme::Button::Text),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+        content = content.push(scopes);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            units_subscription(self.config.units.clone()).map(Message::States),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
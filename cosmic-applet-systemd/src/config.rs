@@ -0,0 +1,46 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletSystemd";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Scope {
+    /// Units of the system manager
+    System,
+    /// Units of the user's own manager
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct WatchedUnit {
+    pub scope: Scope,
+    /// Full name, such as `sshd.service`
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct SystemdAppletConfig {
+    /// Units listed in the popup, in the order they were added
+    pub units: Vec<WatchedUnit>,
+}
+
+impl SystemdAppletConfig {
+    pub fn load() -> SystemdAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| SystemdAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn add_unit(&mut self, unit: WatchedUnit, config: &Config) {
+        if !self.units.contains(&unit) {
+            self.units.push(unit);
+            let _ = self.write_entry(config);
+        }
+    }
+
+    pub fn remove_unit(&mut self, unit: &WatchedUnit, config: &Config) {
+        self.units.retain(|u| u != unit);
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The watched units, through the D-Bus API of the system and user service managers
+
+use crate::config::{Scope, WatchedUnit};
+use cosmic::iced::{self, futures::SinkExt, subscription};
+use std::time::Duration;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection, MethodFlags};
+
+/// Time between updates of the units' states
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// Journal entries shown for a unit
+const JOURNAL_LINES: &str = "15";
+
+/// Name, description, load, active and sub state, followed by fields that aren't used
+type UnitInfo = (
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+    OwnedObjectPath,
+    u32,
+    String,
+    OwnedObjectPath,
+);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    /// ListUnitsByNames method
+    fn list_units_by_names(&self, names: &[&str]) -> zbus::Result<Vec<UnitInfo>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitState {
+    pub unit: WatchedUnit,
+    pub description: String,
+    /// `loaded`, or `not-found` for units that don't exist
+    pub load_state: String,
+    /// Such as `active`, `inactive` or `failed`
+    pub active_state: String,
+    /// Such as `running` or `exited`
+    pub sub_state: String,
+}
+
+impl UnitState {
+    pub fn failed(&self) -> bool {
+        self.active_state == "failed"
+    }
+
+    /// Whether the unit is running or about to, and so can be stopped
+    pub fn active(&self) -> bool {
+        matches!(
+            self.active_state.as_str(),
+            "active" | "activating" | "reloading"
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl Action {
+    fn method(self) -> &'static str {
+        match self {
+            Action::Start => "StartUnit",
+            Action::Stop => "StopUnit",
+            Action::Restart => "RestartUnit",
+        }
+    }
+}
+
+async fn manager(scope: Scope) -> zbus::Result<ManagerProxy<'static>> {
+    let conn = match scope {
+        Scope::System => Connection::system().await?,
+        Scope::User => Connection::session().await?,
+    };
+    ManagerProxy::new(&conn).await
+}
+
+/// Queues a job for the unit, which polkit may ask to authorize for system units
+pub async fn act(unit: WatchedUnit, action: Action) -> Result<(), String> {
+    let res: zbus::Result<()> = async {
+        let manager = manager(unit.scope).await?;
+        manager
+            .inner()
+            .call_with_flags::<_, _, OwnedObjectPath>(
+                action.method(),
+                MethodFlags::AllowInteractiveAuth.into(),
+                &(unit.name.as_str(), "replace"),
+            )
+            .await?;
+        Ok(())
+    }
+    .await;
+    res.map_err(|err| err.to_string())
+}
+
+/// The unit's latest journal entries
+pub async fn journal(unit: WatchedUnit) -> Result<String, String> {
+    let unit_arg = match unit.scope {
+        Scope::System => "--unit",
+        Scope::User => "--user-unit",
+    };
+    let output = tokio::process::Command::new("journalctl")
+        .args([unit_arg, &unit.name, "--lines", JOURNAL_LINES])
+        .args(["--no-pager", "--quiet", "--output", "short"])
+        .output()
+        .await
+        .map_err(|err| err.to_string())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// States of the units, in the order given; units of a manager that can't be reached are left
+/// out
+async fn states(
+    units: &[WatchedUnit],
+    managers: &[(Scope, Option<ManagerProxy<'static>>)],
+) -> Vec<UnitState> {
+    let mut states = Vec::new();
+    for (scope, manager) in managers {
+        let Some(manager) = manager else {
+            continue;
+        };
+        let scoped: Vec<&WatchedUnit> = units.iter().filter(|u| u.scope == *scope).collect();
+        if scoped.is_empty() {
+            continue;
+        }
+        let names: Vec<&str> = scoped.iter().map(|u| u.name.as_str()).collect();
+        match manager.list_units_by_names(&names).await {
+            // in the order of the names, which may differ from the units' own for aliases
+            Ok(infos) => states.extend(scoped.into_iter().zip(infos).map(
+                |(unit, (_, description, load_state, active_state, sub_state, ..))| UnitState {
+                    unit: unit.clone(),
+                    description,
+                    load_state,
+                    active_state,
+                    sub_state,
+                },
+            )),
+            Err(err) => log::error!("Failed to list {:?} units: {}", scope, err),
+        }
+    }
+    states.sort_by_key(|state| units.iter().position(|u| *u == state.unit));
+    states
+}
+
+pub fn units_subscription(units: Vec<WatchedUnit>) -> iced::Subscription<Vec<UnitState>> {
+    struct Units;
+
+    subscription::channel(
+        (std::any::TypeId::of::<Units>(), units.clone()),
+        1,
+        move |mut output| async move {
+            let mut managers = Vec::new();
+            for scope in [Scope::System, Scope::User] {
+                let manager = match manager(scope).await {
+                    Ok(manager) => Some(manager),
+                    Err(err) => {
+                        log::error!("Failed to connect to the {:?} manager: {}", scope, err);
+                        None
+                    }
+                };
+                managers.push((scope, manager));
+            }
+            loop {
+                _ = output.send(states(&units, &managers).await).await;
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,15 @@
+new-task = New task, due:today or due:2023-08-31
+add = Add
+no-tasks = No tasks
+due = Due { $date }
+due-today = Due today
+overdue = Overdue since { $date }
+show-done = Show Completed Tasks
+storage = Storage…
+file = File
+caldav = CalDAV
+file-path = todo.txt file, ~/.local/share/cosmic-applet-tasks/todo.txt if empty
+caldav-url = Task list URL
+caldav-user = User name
+caldav-password = The password is kept by the Secret Service. Store it with: { $command }
+save = Save
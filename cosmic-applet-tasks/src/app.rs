@@ -0,0 +1,406 @@
+use crate::caldav;
+use crate::config::{self, Store, TasksAppletConfig};
+use crate::fl;
+use crate::tasks::{self, tasks_subscription, Change, Task};
+use chrono::NaiveDate;
+use cosmic::app::{self, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    widget::{button, column, container, row, scrollable, text, text_input, Column},
+    window, Alignment, Color, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme::{self, Svg};
+use cosmic::widget::{divider, icon, toggler};
+use cosmic::{Element, Theme};
+use std::borrow::Cow;
+
+const ICON: &str = "checkbox-checked-symbolic";
+const OVERDUE_COLOR: Color = Color::from_rgb(0.8, 0.2, 0.2);
+/// Tasks listed before the list scrolls
+const MAX_UNSCROLLED: usize = 10;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Tasks>(true, ())
+}
+
+#[derive(Default)]
+struct Tasks {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: TasksAppletConfig,
+    tasks: Vec<Task>,
+    input: String,
+    /// Whether a change is being made, during which no other is started
+    busy: bool,
+    error: Option<String>,
+    /// The store being edited, while the storage settings are shown
+    store_input: Option<Store>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Tasks(Result<Vec<Task>, String>),
+    Input(String),
+    Add,
+    Change(Change),
+    Changed(Result<Vec<Task>, String>),
+    SetShowDone(bool),
+    ToggleStoreSettings,
+    StoreInput(Store),
+    SaveStore,
+    ConfigUpdated(TasksAppletConfig),
+}
+
+impl Tasks {
+    fn task_view<'a>(&self, task: &'a Task, today: NaiveDate) -> Element<'a, Message> {
+        let check_icon = if task.done {
+            "checkbox-checked-symbolic"
+        } else {
+            "checkbox-symbolic"
+        };
+        let mut check_button =
+            button(icon(check_icon, 16).style(Svg::Symbolic)).style(theme::Button::Text);
+        let mut delete_button = button(icon("edit-delete-symbolic", 16).style(Svg::Symbolic))
+            .style(theme::Button::Text);
+        if !self.busy {
+            check_button =
+                check_button.on_press(Message::Change(Change::SetDone(task.clone(), !task.done)));
+            delete_button = delete_button.on_press(Message::Change(Change::Delete(task.clone())));
+        }
+
+        let mut details = column![text(&task.summary).size(14)]
+            .spacing(2)
+            .width(Length::Fill);
+        if let Some(due) = task.due.filter(|_| !task.done) {
+            let label = if due == today {
+                fl!("due-today")
+            } else if due < today {
+                fl!("overdue", date = due.to_string())
+            } else {
+                fl!("due", date = due.to_string())
+            };
+            let label = text(label).size(12);
+            details = details.push(if task.overdue(today) {
+                label.style(theme::Text::Color(OVERDUE_COLOR))
+            } else {
+                label
+            });
+        }
+
+        row![check_button, details, delete_button]
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .into()
+    }
+
+    fn store_view<'a>(&self, store: &'a Store) -> Element<'a, Message> {
+        let mut file_button = button(text(fl!("file")).size(12)).padding([4, 8]);
+        let mut caldav_button = button(text(fl!("caldav")).size(12)).padding([4, 8]);
+        match store {
+            Store::File { .. } => {
+                file_button = file_button.style(theme::Button::Primary);
+                caldav_button =
+                    caldav_button
+                        .style(theme::Button::Secondary)
+                        .on_press(Message::StoreInput(Store::CalDav {
+                            url: String::new(),
+                            user: String::new(),
+                        }));
+            }
+            Store::CalDav { .. } => {
+                file_button = file_button
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::StoreInput(Store::default()));
+                caldav_button = caldav_button.style(theme::Button::Primary);
+            }
+        }
+        let mut content = column![row![file_button, caldav_button].spacing(8)].spacing(8);
+        match store {
+            Store::File { path } => {
+                content = content.push(
+                    text_input(&fl!("file-path"), path)
+                        .on_input(|path| Message::StoreInput(Store::File { path }))
+                        .on_submit(Message::SaveStore)
+                        .size(14)
+                        .width(Length::Fill),
+                );
+            }
+            Store::CalDav { url, user } => {
+                let user_input = user.clone();
+                let url_input = url.clone();
+                content = content
+                    .push(
+                        text_input(&fl!("caldav-url"), url)
+                            .on_input(move |url| {
+                                Message::StoreInput(Store::CalDav {
+                                    url,
+                                    user: user_input.clone(),
+                                })
+                            })
+                            .on_submit(Message::SaveStore)
+                            .size(14)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        text_input(&fl!("caldav-user"), user)
+                            .on_input(move |user| {
+                                Message::StoreInput(Store::CalDav {
+                                    url: url_input.clone(),
+                                    user,
+                                })
+                            })
+                            .on_submit(Message::SaveStore)
+                            .size(14)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        text(fl!(
+                            "caldav-password",
+                            command = caldav::store_password_command(url)
+                        ))
+                        .size(12),
+                    );
+            }
+        }
+        content
+            .push(
+                button(text(fl!("save")).size(12))
+                    .padding([4, 8])
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::SaveStore),
+            )
+            .into()
+    }
+}
+
+impl cosmic::Application for Tasks {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        (
+            Tasks {
+                core,
+                config: TasksAppletConfig::load(),
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.store_input = None;
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                // the tasks may have been changed by another program since they were last read
+                return Command::batch([
+                    get_popup(popup_settings),
+                    cosmic::iced::Command::perform(tasks::list(self.config.store.clone()), |res| {
+                        app::message::app(Message::Tasks(res))
+                    }),
+                ]);
+            }
+            Message::Tasks(res) => match res {
+                Ok(tasks) => {
+                    self.tasks = tasks;
+                    self.error = None;
+                }
+                Err(err) => self.error = Some(err),
+            },
+            Message::Input(input) => {
+                self.input = input;
+            }
+            Message::Add => {
+                let (summary, due) = tasks::parse_new(&self.input);
+                if summary.is_empty() {
+                    return Command::none();
+                }
+                self.input.clear();
+                return self.update(Message::Change(Change::Add { summary, due }));
+            }
+            Message::Change(change) => {
+                self.busy = true;
+                return cosmic::iced::Command::perform(
+                    tasks::apply(self.config.store.clone(), change),
+                    |res| app::message::app(Message::Changed(res)),
+                );
+            }
+            Message::Changed(res) => {
+                self.busy = false;
+                return self.update(Message::Tasks(res));
+            }
+            Message::SetShowDone(show_done) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_done(show_done, &config);
+                }
+            }
+            Message::ToggleStoreSettings => {
+                self.store_input = match self.store_input {
+                    Some(_) => None,
+                    None => Some(self.config.store.clone()),
+                };
+            }
+            Message::StoreInput(store) => {
+                self.store_input = Some(store);
+            }
+            Message::SaveStore => {
+                if let Some(store) = self.store_input.take() {
+                    if let Ok(config) = Config::new(config::APP_ID, 1) {
+                        self.config.set_store(store, &config);
+                    }
+                    self.tasks.clear();
+                }
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let today = tasks::today();
+        let pressing = self.tasks.iter().filter(|t| t.pressing(today)).count();
+        if pressing == 0 {
+            return self
+                .core
+                .applet_helper
+                .icon_button(ICON)
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+        let count = text(pressing.to_string()).size(14);
+        let count = if self.tasks.iter().any(|t| t.overdue(today)) {
+            count.style(theme::Text::Color(OVERDUE_COLOR))
+        } else {
+            count
+        };
+        button(
+            row![icon(ICON, 16).style(Svg::Symbolic), count]
+                .spacing(4)
+                .align_items(Alignment::Center),
+        )
+        .on_press(Message::TogglePopup)
+        .style(theme::Button::Text)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let today = tasks::today();
+        let mut content = Column::new().spacing(12).padding([8, 24]);
+        content = content.push(
+            row![
+                text_input(&fl!("new-task"), &self.input)
+                    .on_input(Message::Input)
+                    .on_submit(Message::Add)
+                    .size(14)
+                    .width(Length::Fill),
+                button(text(fl!("add")).size(14))
+                    .on_press(Message::Add)
+                    .style(theme::Button::Text),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+
+        // open tasks by due date, then completed ones, each in the order of the store
+        let mut listed: Vec<&Task> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.done || self.config.show_done)
+            .collect();
+        listed.sort_by_key(|t| (t.done, t.due.is_none(), t.due));
+        let rows: Vec<Element<Message>> = listed
+            .into_iter()
+            .map(|task| self.task_view(task, today))
+            .collect();
+        if rows.is_empty() {
+            content = content.push(text(fl!("no-tasks")).size(14));
+        } else if rows.len() > MAX_UNSCROLLED {
+            content = content.push(
+                scrollable(Column::with_children(rows).spacing(4)).height(Length::Fixed(400.0)),
+            );
+        } else {
+            content = content.push(Column::with_children(rows).spacing(4));
+        }
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(12));
+        }
+
+        content = content
+            .push(container(divider::horizontal::light()).width(Length::Fill))
+            .push(
+                toggler(
+                    fl!("show-done"),
+                    self.config.show_done,
+                    Message::SetShowDone,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            )
+            .push(
+                button(text(fl!("storage")).size(14))
+                    .on_press(Message::ToggleStoreSettings)
+                    .style(theme::Button::Text),
+            );
+        if let Some(store) = &self.store_input {
+            content = content.push(self.store_view(store));
+        }
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            tasks_subscription(self.config.store.clone()).map(Message::Tasks),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
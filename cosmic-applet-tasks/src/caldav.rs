@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tasks of a CalDAV task list, the VTODO components of a calendar collection
+
+use crate::config::APP_ID;
+use crate::tasks::{Change, Source, Task};
+use chrono::{NaiveDate, Utc};
+use reqwest::{header, Client, Method, RequestBuilder, Url};
+
+const DAV: &str = "DAV:";
+const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+
+const QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#;
+
+/// The command that stores the password where it's looked up
+pub fn store_password_command(url: &str) -> String {
+    format!("secret-tool store --label=Tasks application {APP_ID} url {url}")
+}
+
+async fn password(url: &str) -> Result<String, String> {
+    let output = tokio::process::Command::new("secret-tool")
+        .args(["lookup", "application", APP_ID, "url", url])
+        .output()
+        .await
+        .map_err(|err| format!("secret-tool: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "no password is stored, store it with: {}",
+            store_password_command(url)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+struct Session {
+    client: Client,
+    url: Url,
+    user: String,
+    password: String,
+}
+
+impl Session {
+    async fn new(url: &str, user: &str) -> Result<Session, String> {
+        // resources are resolved against the collection, which has to end with a slash for that
+        let collection = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{url}/")
+        };
+        Ok(Session {
+            client: Client::new(),
+            url: Url::parse(&collection).map_err(|err| format!("{url}: {err}"))?,
+            user: user.to_string(),
+            password: password(url).await?,
+        })
+    }
+
+    fn request(&self, method: Method, href: &str) -> Result<RequestBuilder, String> {
+        let url = self.url.join(href).map_err(|err| err.to_string())?;
+        Ok(self
+            .client
+            .request(method, url)
+            .basic_auth(&self.user, Some(&self.password)))
+    }
+}
+
+async fn send(request: RequestBuilder) -> Result<String, String> {
+    let response = request.send().await.map_err(|err| err.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        // a conflicting ETag means another client changed the task
+        return Err(if status == reqwest::StatusCode::PRECONDITION_FAILED {
+            "the task was changed by another program".to_string()
+        } else {
+            format!("HTTP status {}", status)
+        });
+    }
+    response.text().await.map_err(|err| err.to_string())
+}
+
+/// Joins the continuation lines of the iCalendar data to the lines they continue
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Name of the line's property, without its parameters
+fn property(line: &str) -> String {
+    line.split([':', ';'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+fn value(line: &str) -> &str {
+    line.split_once(':').map_or("", |(_, value)| value)
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn parse_task(href: String, etag: Option<String>, ics: String) -> Option<Task> {
+    let mut in_todo = false;
+    let mut found = false;
+    let mut summary = String::new();
+    let mut done = false;
+    let mut due = None;
+    for line in unfold(&ics) {
+        match (property(&line).as_str(), value(&line)) {
+            ("BEGIN", "VTODO") => {
+                in_todo = true;
+                found = true;
+            }
+            ("END", "VTODO") => break,
+            _ if !in_todo => {}
+            ("SUMMARY", value) => summary = unescape(value),
+            ("STATUS", value) => done = value == "COMPLETED",
+            ("COMPLETED", _) => done = true,
+            // a date, or the date of a date-time
+            ("DUE", value) => {
+                due = value
+                    .get(..8)
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok());
+            }
+            _ => {}
+        }
+    }
+    found.then_some(Task {
+        summary,
+        done,
+        due,
+        source: Source::Resource { href, etag, ics },
+    })
+}
+
+fn child_text(node: roxmltree::Node, name: (&str, &str)) -> Option<String> {
+    node.descendants()
+        .find(|n| n.has_tag_name(name))
+        .and_then(|n| n.text())
+        .map(str::to_string)
+}
+
+pub async fn list(url: &str, user: &str) -> Result<Vec<Task>, String> {
+    let session = Session::new(url, user).await?;
+    let body = send(
+        session
+            .request(Method::from_bytes(b"REPORT").unwrap(), "")?
+            .header("Depth", "1")
+            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(QUERY),
+    )
+    .await?;
+    let doc = roxmltree::Document::parse(&body).map_err(|err| err.to_string())?;
+    Ok(doc
+        .descendants()
+        .filter(|node| node.has_tag_name((DAV, "response")))
+        .filter_map(|response| {
+            parse_task(
+                child_text(response, (DAV, "href"))?,
+                child_text(response, (DAV, "getetag")),
+                child_text(response, (CALDAV, "calendar-data"))?,
+            )
+        })
+        .collect())
+}
+
+fn timestamp() -> String {
+    Utc::now().format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn new_ics(uid: &str, summary: &str, due: Option<NaiveDate>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//System76//COSMIC Tasks Applet//EN".to_string(),
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{uid}"),
+        format!("DTSTAMP:{}", timestamp()),
+        format!("SUMMARY:{}", escape(summary)),
+        "STATUS:NEEDS-ACTION".to_string(),
+    ];
+    if let Some(due) = due {
+        lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+    }
+    lines.extend(["END:VTODO".to_string(), "END:VCALENDAR".to_string()]);
+    lines.join("\r\n") + "\r\n"
+}
+
+/// The iCalendar data with the task's completion replaced, keeping what other clients set
+fn set_done(ics: &str, done: bool) -> String {
+    let mut lines = Vec::new();
+    let mut in_todo = false;
+    for line in unfold(ics) {
+        match (property(&line).as_str(), value(&line)) {
+            ("BEGIN", "VTODO") => in_todo = true,
+            ("END", "VTODO") if in_todo => {
+                in_todo = false;
+                if done {
+                    lines.push("STATUS:COMPLETED".to_string());
+                    lines.push("PERCENT-COMPLETE:100".to_string());
+                    lines.push(format!("COMPLETED:{}", timestamp()));
+                } else {
+                    lines.push("STATUS:NEEDS-ACTION".to_string());
+                }
+            }
+            ("STATUS" | "PERCENT-COMPLETE" | "COMPLETED", _) if in_todo => continue,
+            ("LAST-MODIFIED", _) if in_todo => {
+                lines.push(format!("LAST-MODIFIED:{}", timestamp()));
+                continue;
+            }
+            _ => {}
+        }
+        lines.push(line);
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+/// Makes the request conditional on the resource being unchanged since it was read
+fn if_match(request: RequestBuilder, etag: &Option<String>) -> RequestBuilder {
+    match etag {
+        Some(etag) => request.header(header::IF_MATCH, etag),
+        None => request,
+    }
+}
+
+pub async fn apply(url: &str, user: &str, change: Change) -> Result<(), String> {
+    let session = Session::new(url, user).await?;
+    let request = match change {
+        Change::Add { summary, due } => {
+            let uid = format!(
+                "{}-{}@cosmic-applet-tasks",
+                Utc::now().format("%Y%m%dT%H%M%S%.9f"),
+                std::process::id()
+            );
+            session
+                .request(Method::PUT, &format!("{uid}.ics"))?
+                .header(header::IF_NONE_MATCH, "*")
+                .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+                .body(new_ics(&uid, &summary, due))
+        }
+        Change::SetDone(task, done) => {
+            let Source::Resource { href, etag, ics } = task.source else {
+                return Ok(());
+            };
+            if_match(session.request(Method::PUT, &href)?, &etag)
+                .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+                .body(set_done(&ics, done))
+        }
+        Change::Delete(task) => {
+            let Source::Resource { href, etag, .. } = task.source else {
+                return Ok(());
+            };
+            if_match(session.request(Method::DELETE, &href)?, &etag)
+        }
+    };
+    send(request).await.map(|_| ())
+}
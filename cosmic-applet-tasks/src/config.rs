@@ -0,0 +1,55 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletTasks";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Store {
+    /// A todo.txt file, at the default location if the path is empty
+    File { path: String },
+    /// A CalDAV task list, whose password is kept by the Secret Service
+    CalDav { url: String, user: String },
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store::File {
+            path: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct TasksAppletConfig {
+    pub store: Store,
+    /// Whether completed tasks are listed under the others
+    pub show_done: bool,
+}
+
+impl Default for TasksAppletConfig {
+    fn default() -> Self {
+        Self {
+            store: Store::default(),
+            show_done: true,
+        }
+    }
+}
+
+impl TasksAppletConfig {
+    pub fn load() -> TasksAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| TasksAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_store(&mut self, store: Store, config: &Config) {
+        self.store = store;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_show_done(&mut self, show_done: bool, config: &Config) {
+        self.show_done = show_done;
+        let _ = self.write_entry(config);
+    }
+}
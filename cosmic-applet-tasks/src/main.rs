@@ -0,0 +1,12 @@
+mod app;
+mod caldav;
+mod config;
+mod localize;
+mod tasks;
+mod todo_txt;
+
+pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
+    app::run()
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tasks of the configured store, a todo.txt file or a CalDAV task list
+
+use crate::config::Store;
+use crate::{caldav, todo_txt};
+use chrono::{Duration as Days, Local, NaiveDate};
+use cosmic::iced::{futures::SinkExt, subscription, Subscription};
+use std::io;
+use std::time::Duration;
+
+/// Time between reads of the store, which may be changed by other programs
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Index and contents of the task's line of the file
+    Line(usize, String),
+    /// The task's resource in the task list, with its ETag and iCalendar data
+    Resource {
+        href: String,
+        etag: Option<String>,
+        ics: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub summary: String,
+    pub done: bool,
+    pub due: Option<NaiveDate>,
+    pub source: Source,
+}
+
+impl Task {
+    pub fn overdue(&self, today: NaiveDate) -> bool {
+        !self.done && self.due.map_or(false, |due| due < today)
+    }
+
+    /// Whether the task is due today or overdue, and so shown on the panel
+    pub fn pressing(&self, today: NaiveDate) -> bool {
+        !self.done && self.due.map_or(false, |due| due <= today)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Add {
+        summary: String,
+        due: Option<NaiveDate>,
+    },
+    SetDone(Task, bool),
+    Delete(Task),
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Splits the due date off the text of a new task, given as a `due:` word with a date such as
+/// `2023-08-31`, or `today` or `tomorrow`
+pub fn parse_new(input: &str) -> (String, Option<NaiveDate>) {
+    let mut due = None;
+    let mut words = Vec::new();
+    for word in input.split_whitespace() {
+        let date = word.strip_prefix("due:").and_then(|date| match date {
+            "today" => Some(today()),
+            "tomorrow" => Some(today() + Days::days(1)),
+            _ => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        });
+        match date {
+            Some(date) => due = Some(date),
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), due)
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(res) => res.map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+pub async fn list(store: Store) -> Result<Vec<Task>, String> {
+    match store {
+        Store::File { path } => blocking(move || todo_txt::list(&todo_txt::path(&path))).await,
+        Store::CalDav { url, user } => caldav::list(&url, &user).await,
+    }
+}
+
+/// Makes the change, and returns the tasks as they are after it
+pub async fn apply(store: Store, change: Change) -> Result<Vec<Task>, String> {
+    match &store {
+        Store::File { path } => {
+            let path = todo_txt::path(path);
+            blocking(move || todo_txt::apply(&path, change)).await?
+        }
+        Store::CalDav { url, user } => caldav::apply(url, user, change).await?,
+    }
+    list(store).await
+}
+
+pub fn tasks_subscription(store: Store) -> Subscription<Result<Vec<Task>, String>> {
+    struct TasksWorker;
+
+    subscription::channel(
+        (std::any::TypeId::of::<TasksWorker>(), store.clone()),
+        1,
+        move |mut output| async move {
+            loop {
+                _ = output.send(list(store.clone()).await).await;
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tasks kept in a file of the todo.txt format, one task a line, with `x` in front of completed
+//! tasks and the due date as a `due:` tag
+
+use crate::tasks::{self, Change, Source, Task};
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The configured path, or `todo.txt` in the applet's data directory
+pub fn path(configured: &str) -> PathBuf {
+    if let Some(rest) = configured.strip_prefix("~/") {
+        if let Some(home) = std::env::var_os("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    if !configured.is_empty() {
+        return PathBuf::from(configured);
+    }
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_default();
+    data_dir.join("cosmic-applet-tasks").join("todo.txt")
+}
+
+fn is_date(word: &str) -> bool {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).is_ok()
+}
+
+fn parse_line(index: usize, line: &str) -> Option<Task> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let (done, rest) = match trimmed.strip_prefix("x ") {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let mut due = None;
+    let mut words = Vec::new();
+    for (i, word) in rest.split_whitespace().enumerate() {
+        // the dates of completion and creation, which lead the line
+        if i < 2 && words.is_empty() && is_date(word) {
+            continue;
+        }
+        match word
+            .strip_prefix("due:")
+            .and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+        {
+            Some(date) => due = Some(date),
+            None => words.push(word),
+        }
+    }
+    Some(Task {
+        summary: words.join(" "),
+        done,
+        due,
+        source: Source::Line(index, line.to_string()),
+    })
+}
+
+/// The file's tasks; a file that doesn't exist yet has none
+pub fn list(path: &Path) -> io::Result<Vec<Task>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| parse_line(index, line))
+        .collect())
+}
+
+/// The line with completion marked or unmarked
+fn set_done(line: &str, done: bool) -> String {
+    match line.strip_prefix("x ") {
+        Some(_) if done => line.to_string(),
+        Some(rest) => {
+            let rest = rest.trim_start();
+            match rest.split_once(' ') {
+                Some((date, rest)) if is_date(date) => rest.to_string(),
+                _ => rest.to_string(),
+            }
+        }
+        None if done => format!("x {} {}", tasks::today().format(DATE_FORMAT), line),
+        None => line.to_string(),
+    }
+}
+
+fn write(path: &Path, lines: &[String]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    // replaced at once, so that other programs never read half of it
+    let tmp = path.with_extension("txt.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+pub fn apply(path: &Path, change: Change) -> io::Result<()> {
+    let mut lines: Vec<String> = match fs::read_to_string(path) {
+        Ok(contents) => contents.lines().map(str::to_string).collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err),
+    };
+    // a task is only changed if its line is still the one that was read
+    let index = |task: &Task| match &task.source {
+        Source::Line(index, line) if lines.get(*index) == Some(line) => Ok(*index),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "the task was changed by another program",
+        )),
+    };
+    match change {
+        Change::Add { summary, due } => {
+            lines.push(match due {
+                Some(due) => format!("{} due:{}", summary, due.format(DATE_FORMAT)),
+                None => summary,
+            });
+        }
+        Change::SetDone(task, done) => {
+            let index = index(&task)?;
+            lines[index] = set_done(&lines[index], done);
+        }
+        Change::Delete(task) => {
+            let index = index(&task)?;
+            lines.remove(index);
+        }
+    }
+    write(path, &lines)
+}
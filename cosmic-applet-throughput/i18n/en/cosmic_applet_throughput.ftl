@@ -0,0 +1,8 @@
+download = Download
+upload = Upload
+session-total = This Session
+reset = Reset
+interfaces = Interfaces
+interfaces-description = Physical interfaces are counted until others are selected.
+no-interfaces = No network interfaces
+show-total = Show Session Total in the Panel
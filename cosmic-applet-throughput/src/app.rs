@@ -0,0 +1,275 @@
+use crate::config::{self, ThroughputAppletConfig};
+use crate::fl;
+use crate::throughput::{Counters, ThroughputSampler};
+use cosmic::app::{applet::cosmic_panel_config::PanelAnchor, Command};
+use cosmic::cosmic_config::{self, Config};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::{
+    time,
+    widget::{button, column, container, row, text, Column, Row},
+    window, Alignment, Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
+use cosmic::iced_style::application;
+use cosmic::theme;
+use cosmic::widget::{divider, toggler};
+use cosmic::{Element, Theme};
+use cosmic_applet_common::format::{format_bytes, format_compact, format_rate};
+use std::borrow::Cow;
+use std::time::Duration;
+
+pub fn run() -> cosmic::iced::Result {
+    cosmic::app::applet::run::<Throughput>(true, ())
+}
+
+#[derive(Default)]
+struct Throughput {
+    core: cosmic::app::Core,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    config: ThroughputAppletConfig,
+    throughput: ThroughputSampler,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    TogglePopup,
+    Sample,
+    SetSelected(String, bool),
+    SetShowTotal(bool),
+    ResetTotals,
+    ConfigUpdated(ThroughputAppletConfig),
+}
+
+/// Received and sent values, one above the other
+fn rx_tx<'a>(
+    counters: Counters,
+    format: fn(u64) -> String,
+    align: Alignment,
+) -> Element<'a, Message> {
+    column![
+        text(format!("↓ {}", format(counters.rx))).size(10),
+        text(format!("↑ {}", format(counters.tx))).size(10)
+    ]
+    .align_items(align)
+    .into()
+}
+
+/// A name with its value on the right
+fn heading<'a>(name: String, value: String) -> Element<'a, Message> {
+    row![
+        text(name).size(14).width(Length::Fill),
+        text(value).size(14)
+    ]
+    .into()
+}
+
+impl cosmic::Application for Throughput {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::app::Core, _flags: ()) -> (Self, Command<Message>) {
+        let mut throughput = ThroughputSampler::default();
+        throughput.sample();
+        (
+            Throughput {
+                core,
+                config: ThroughputAppletConfig::load(),
+                throughput,
+                ..Default::default()
+            },
+            Command::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::app::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::app::Core {
+        &mut self.core
+    }
+
+    fn style(&self) -> Option<<Theme as application::StyleSheet>::Style> {
+        Some(cosmic::app::applet::style())
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::TogglePopup => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.id_ctr += 1;
+                let new_id = window::Id(self.id_ctr);
+                self.popup.replace(new_id);
+                let mut popup_settings = self.core.applet_helper.get_popup_settings(
+                    window::Id(0),
+                    new_id,
+                    None,
+                    None,
+                    None,
+                );
+                popup_settings.positioner.size_limits = Limits::NONE
+                    .min_width(300.0)
+                    .max_width(400.0)
+                    .min_height(100.0)
+                    .max_height(1080.0);
+                return get_popup(popup_settings);
+            }
+            Message::Sample => {
+                self.throughput.sample();
+            }
+            Message::SetSelected(interface, selected) => {
+                // selecting from the implicitly counted physical interfaces keeps the others
+                let mut interfaces: Vec<String> = self
+                    .throughput
+                    .counted(&self.config.interfaces)
+                    .map(|(name, _)| name.clone())
+                    .filter(|name| *name != interface)
+                    .collect();
+                if selected {
+                    interfaces.push(interface);
+                    interfaces.sort();
+                }
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_interfaces(interfaces, &config);
+                }
+            }
+            Message::SetShowTotal(show_total) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_total(show_total, &config);
+                }
+            }
+            Message::ResetTotals => {
+                self.throughput.reset_totals();
+            }
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let (rate, total) = self.throughput.sum(&self.config.interfaces);
+        let horizontal = matches!(
+            self.core.applet_helper.anchor,
+            PanelAnchor::Top | PanelAnchor::Bottom
+        );
+        let mut content = Vec::new();
+        if horizontal {
+            content.push(rx_tx(rate, format_rate, Alignment::Start));
+            if self.config.show_total {
+                content.push(rx_tx(total, format_bytes, Alignment::Start));
+            }
+        } else {
+            content.push(rx_tx(rate, format_compact, Alignment::Center));
+            if self.config.show_total {
+                content.push(rx_tx(total, format_compact, Alignment::Center));
+            }
+        }
+        let content: Element<_> = if horizontal {
+            Row::with_children(content)
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .into()
+        } else {
+            Column::with_children(content)
+                .spacing(4)
+                .align_items(Alignment::Center)
+                .into()
+        };
+        button(content)
+            .on_press(Message::TogglePopup)
+            .style(theme::Button::Text)
+            .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        let (rate, total) = self.throughput.sum(&self.config.interfaces);
+
+        let mut interfaces = Column::new().spacing(8);
+        for (name, interface) in &self.throughput.interfaces {
+            let counted = self
+                .throughput
+                .counted(&self.config.interfaces)
+                .any(|(counted, _)| counted == name);
+            let interface_name = name.clone();
+            interfaces = interfaces.push(
+                column![
+                    toggler(name.clone(), counted, move |selected| {
+                        Message::SetSelected(interface_name.clone(), selected)
+                    })
+                    .text_size(14)
+                    .width(Length::Fill),
+                    text(format!(
+                        "↓ {}  ↑ {}",
+                        format_rate(interface.rate.rx),
+                        format_rate(interface.rate.tx)
+                    ))
+                    .size(12)
+                ]
+                .spacing(4),
+            );
+        }
+        if self.throughput.interfaces.is_empty() {
+            interfaces = interfaces.push(text(fl!("no-interfaces")).size(14));
+        }
+
+        let content = column![
+            heading(fl!("download"), format_rate(rate.rx)),
+            heading(fl!("upload"), format_rate(rate.tx)),
+            row![
+                text(fl!("session-total")).size(14).width(Length::Fill),
+                text(format!(
+                    "↓ {}  ↑ {}",
+                    format_bytes(total.rx),
+                    format_bytes(total.tx)
+                ))
+                .size(14),
+                button(text(fl!("reset")).size(14))
+                    .padding([4, 8])
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::ResetTotals),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+            container(divider::horizontal::light()).width(Length::Fill),
+            text(fl!("interfaces")).size(14),
+            text(fl!("interfaces-description")).size(12),
+            interfaces,
+            container(divider::horizontal::light()).width(Length::Fill),
+            toggler(
+                fl!("show-total"),
+                self.config.show_total,
+                Message::SetShowTotal
+            )
+            .text_size(14)
+            .width(Length::Fill),
+        ]
+        .spacing(12)
+        .padding([8, 24]);
+
+        self.core.applet_helper.popup_container(content).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            time::every(Duration::from_secs(1)).map(|_| Message::Sample),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            log::error!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ])
+    }
+}
@@ -0,0 +1,31 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
+pub const APP_ID: &str = "com.system76.CosmicAppletThroughput";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct ThroughputAppletConfig {
+    /// Interfaces counted by name, or every physical one if empty
+    pub interfaces: Vec<String>,
+    /// Whether the panel also shows the bytes transferred this session
+    pub show_total: bool,
+}
+
+impl ThroughputAppletConfig {
+    pub fn load() -> ThroughputAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| ThroughputAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_interfaces(&mut self, interfaces: Vec<String>, config: &Config) {
+        self.interfaces = interfaces;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_show_total(&mut self, show_total: bool, config: &Config) {
+        self.show_total = show_total;
+        let _ = self.write_entry(config);
+    }
+}
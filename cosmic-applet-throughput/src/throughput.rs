@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Transfer rates and totals of the network interfaces, from their byte counters in sysfs
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+const NET: &str = "/sys/class/net";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl Counters {
+    fn add(&mut self, other: Counters) {
+        self.rx = self.rx.saturating_add(other.rx);
+        self.tx = self.tx.saturating_add(other.tx);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Interface {
+    /// Backed by a device, unlike loopback and virtual interfaces such as tunnels and bridges,
+    /// whose traffic is also counted on a physical link
+    pub physical: bool,
+    /// In bytes per second
+    pub rate: Counters,
+    /// Bytes transferred since the applet started or the totals were reset
+    pub total: Counters,
+    last: Option<(Instant, Counters)>,
+}
+
+/// Computes the rates and totals of every interface
+#[derive(Debug, Default)]
+pub struct ThroughputSampler {
+    /// By name, interfaces that went away are dropped
+    pub interfaces: BTreeMap<String, Interface>,
+}
+
+fn read_counters(path: &Path) -> Option<Counters> {
+    let read = |counter: &str| {
+        fs::read_to_string(path.join("statistics").join(counter))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+    };
+    Some(Counters {
+        rx: read("rx_bytes")?,
+        tx: read("tx_bytes")?,
+    })
+}
+
+impl ThroughputSampler {
+    pub fn sample(&mut self) {
+        let now = Instant::now();
+        let Ok(entries) = fs::read_dir(NET) else {
+            self.interfaces.clear();
+            return;
+        };
+        let mut interfaces = BTreeMap::new();
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let Some(counters) = read_counters(&path) else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let mut interface = self.interfaces.remove(&name).unwrap_or_default();
+            interface.physical = path.join("device").exists();
+            if let Some((last_time, last)) = interface.last {
+                // counters restart when a device is reset, which only loses one interval
+                let delta = Counters {
+                    rx: counters.rx.saturating_sub(last.rx),
+                    tx: counters.tx.saturating_sub(last.tx),
+                };
+                let elapsed = now.duration_since(last_time).as_secs_f64();
+                if elapsed > 0.0 {
+                    interface.rate = Counters {
+                        rx: (delta.rx as f64 / elapsed) as u64,
+                        tx: (delta.tx as f64 / elapsed) as u64,
+                    };
+                }
+                interface.total.add(delta);
+            }
+            interface.last = Some((now, counters));
+            interfaces.insert(name, interface);
+        }
+        self.interfaces = interfaces;
+    }
+
+    pub fn reset_totals(&mut self) {
+        for interface in self.interfaces.values_mut() {
+            interface.total = Counters::default();
+        }
+    }
+
+    /// The given interfaces, or the physical ones if none are given
+    pub fn counted<'a>(
+        &'a self,
+        selected: &'a [String],
+    ) -> impl Iterator<Item = (&'a String, &'a Interface)> {
+        self.interfaces.iter().filter(move |(name, interface)| {
+            if selected.is_empty() {
+                interface.physical
+            } else {
+                selected.contains(*name)
+            }
+        })
+    }
+
+    /// Rate and total of the counted interfaces together
+    pub fn sum(&self, selected: &[String]) -> (Counters, Counters) {
+        let mut rate = Counters::default();
+        let mut total = Counters::default();
+        for (_, interface) in self.counted(selected) {
+            rate.add(interface.rate);
+            total.add(interface.total);
+        }
+        (rate, total)
+    }
+}
@@ -0,0 +1,87 @@
+time = Time
+holidays = Public holidays
+holidays-none = None
+notify-holidays = Notify the day before a holiday
+holiday-tomorrow = Tomorrow is a holiday
+region-au = Australia
+region-ca = Canada
+region-de = Germany
+region-es = Spain
+region-fr = France
+region-gb = United Kingdom
+region-ie = Ireland
+region-it = Italy
+region-nl = Netherlands
+region-se = Sweden
+region-us = United States
+today = Today
+all-day = All day
+no-events = No events
+upcoming = Upcoming
+calendars = Calendars
+calendar-file = Path to an .ics file
+add = Add
+personal-calendar = Personal
+world-clocks = World clocks
+world-clocks-in-panel = Show world clocks in the panel
+search-timezone = Add a time zone, e.g. Tokyo
+yesterday = Yesterday
+tomorrow = Tomorrow
+panel-format = Panel format
+tooltip-format = Tooltip format
+invalid-format = Invalid format
+first-day = Weeks start on
+first-day-locale = Region default
+monday = Monday
+saturday = Saturday
+sunday = Sunday
+week-numbers = Show week numbers
+hour-format = Time format
+hour-format-locale = Region default
+twelve-hour = 12-hour
+twenty-four-hour = 24-hour
+timer = Timer
+stopwatch = Stopwatch
+minutes = {$minutes} min
+start = Start
+pause = Pause
+resume = Resume
+cancel = Cancel
+reset = Reset
+timer-done = Timer finished
+timer-done-body = The {$duration} timer is up
+pomodoro = Focus timer
+pomodoro-setting = Show the focus timer
+pomodoro-work = Focus
+pomodoro-short-break = Short break
+pomodoro-long-break = Long break
+pomodoro-stats = Today: {$sessions} sessions, {$minutes} min of focus
+pomodoro-focus = Back to focus
+pomodoro-focus-body = Next session: {$minutes} min
+pomodoro-break = Time for a break
+pomodoro-break-body = Take {$minutes} min off
+skip = Skip
+stop = Stop
+alarm = Alarm
+alarms = Alarms
+alarm-once = Once
+alarm-label = Label
+alarm-soon = Alarm in {$minutes} min
+snooze-minutes = Snooze {$minutes} min
+dismiss = Dismiss
+tuesday = Tuesday
+wednesday = Wednesday
+thursday = Thursday
+friday = Friday
+show-seconds = Show seconds
+vertical-date = Date in a vertical panel
+vertical-date-stacked = Below the time
+vertical-date-on-hover = On hover
+ntp-off = Automatic time synchronization is off
+ntp-synchronized = Clock synchronized
+ntp-not-synchronized = Clock not synchronized
+ntp-last-sync = Last synchronized at {$time}
+date-time-settings = Date & Time settings
+holiday-file = Path to an .ics file of holidays
+settings-missing = COSMIC Settings is not installed
+settings-failed = Failed to open COSMIC Settings: {$error}
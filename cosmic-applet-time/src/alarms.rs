@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! One-off and weekly alarms
+
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Notification actions of a ringing alarm
+pub const DISMISS_ACTION: &str = "dismiss";
+pub const SNOOZE_ACTION: &str = "snooze";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Alarm {
+    pub id: u32,
+    pub time: NaiveTime,
+    pub label: String,
+    /// Days the alarm rings on every week, empty for an alarm that rings once
+    pub days: Vec<Weekday>,
+    pub enabled: bool,
+}
+
+impl Alarm {
+    /// `true` if the alarm rings in the minute of `at`
+    pub fn rings_at(&self, at: NaiveDateTime) -> bool {
+        self.enabled
+            && self.time.hour() == at.hour()
+            && self.time.minute() == at.minute()
+            && (self.days.is_empty() || self.days.contains(&at.weekday()))
+    }
+
+    /// `true` if the alarm repeats
+    pub fn is_recurring(&self) -> bool {
+        !self.days.is_empty()
+    }
+}
+
+/// A time typed as `HH:MM` or `H:MM AM`
+pub fn parse_time(input: &str) -> Option<NaiveTime> {
+    let input = input.trim();
+    ["%H:%M", "%I:%M %p", "%I:%M%p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(input, format).ok())
+}
@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Events read from iCalendar files: the local calendars of Evolution Data Server and files
+//! added by the user
+
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::{fs, path::PathBuf};
+
+/// How far ahead upcoming events are listed
+pub const UPCOMING_DAYS: i64 = 7;
+/// Recurring events stop being expanded after this many occurrences
+const MAX_OCCURRENCES: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub path: PathBuf,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: NaiveDateTime,
+    pub all_day: bool,
+}
+
+/// The local calendars of Evolution Data Server, followed by the files from the config
+pub fn sources(files: &[String]) -> Vec<Source> {
+    let mut sources = Vec::new();
+    if let Some(dir) = dirs_data().map(|d| d.join("evolution/calendar")) {
+        if let Ok(entries) = fs::read_dir(dir) {
+            let mut eds: Vec<_> = entries
+                .filter_map(Result::ok)
+                .map(|e| e.path().join("calendar.ics"))
+                .filter(|p| p.is_file())
+                .collect();
+            eds.sort();
+            sources.extend(eds.into_iter().map(|path| Source {
+                name: calendar_name(&path).unwrap_or_else(|| {
+                    let dir = path.parent().and_then(|p| p.file_name());
+                    match dir.and_then(|d| d.to_str()) {
+                        Some("system") => crate::fl!("personal-calendar"),
+                        Some(dir) => dir.to_string(),
+                        None => path.display().to_string(),
+                    }
+                }),
+                path,
+            }));
+        }
+    }
+    sources.extend(files.iter().map(|file| {
+        let path = PathBuf::from(file);
+        Source {
+            name: calendar_name(&path).unwrap_or_else(|| {
+                path.file_stem()
+                    .map_or_else(|| file.clone(), |s| s.to_string_lossy().into_owned())
+            }),
+            path,
+        }
+    }));
+    sources
+}
+
+fn dirs_data() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+}
+
+fn calendar_name(path: &PathBuf) -> Option<String> {
+    let ics = fs::read_to_string(path).ok()?;
+    unfold(&ics)
+        .iter()
+        .find_map(|line| property(line, "X-WR-CALNAME").map(|(_, value)| unescape(value)))
+}
+
+/// Events of the sources from `from` up to, but not including, `until`, sorted by start
+pub async fn load(sources: Vec<PathBuf>, from: NaiveDate, until: NaiveDate) -> Vec<Event> {
+    let mut events: Vec<Event> = sources
+        .iter()
+        .filter_map(|path| match fs::read_to_string(path) {
+            Ok(ics) => Some(parse(&ics, from, until)),
+            Err(err) => {
+                eprintln!("Failed to read calendar {}: {}", path.display(), err);
+                None
+            }
+        })
+        .flatten()
+        .collect();
+    events.sort_by_key(|e| (e.start, !e.all_day));
+    events
+}
+
+/// Lines continued with a leading space or tab are joined, as RFC 5545 folds long lines
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// The parameters and value of a property line with the given name
+fn property<'a>(line: &'a str, name: &str) -> Option<(&'a str, &'a str)> {
+    let (key, value) = line.split_once(':')?;
+    let (key_name, params) = key.split_once(';').unwrap_or((key, ""));
+    key_name
+        .eq_ignore_ascii_case(name)
+        .then_some((params, value))
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", "\n")
+        .replace("\\N", "\n")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Times in UTC are converted to local time, times with a time zone are taken as local time
+fn parse_time(params: &str, value: &str) -> Option<(NaiveDateTime, bool)> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(0, 0, 0)?, true));
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            Some((
+                Utc.from_utc_datetime(&time)
+                    .with_timezone(&Local)
+                    .naive_local(),
+                false,
+            ))
+        }
+        None => Some((
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+            false,
+        )),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Rule {
+    freq: Option<String>,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+}
+
+fn parse_rule(value: &str) -> Rule {
+    let mut rule = Rule {
+        interval: 1,
+        ..Default::default()
+    };
+    for part in value.split(';') {
+        match part.split_once('=') {
+            Some(("FREQ", freq)) => rule.freq = Some(freq.to_string()),
+            Some(("INTERVAL", interval)) => rule.interval = interval.parse().unwrap_or(1).max(1),
+            Some(("COUNT", count)) => rule.count = count.parse().ok(),
+            Some(("UNTIL", until)) => rule.until = parse_time("", until).map(|(t, _)| t),
+            _ => {}
+        }
+    }
+    rule
+}
+
+/// The next occurrence of a recurring event, `None` for frequencies that aren't supported
+fn next_occurrence(start: NaiveDateTime, rule: &Rule, n: u32) -> Option<NaiveDateTime> {
+    let step = rule.interval.checked_mul(n)?;
+    match rule.freq.as_deref()? {
+        "DAILY" => start.checked_add_signed(Duration::days(step.into())),
+        "WEEKLY" => start.checked_add_signed(Duration::weeks(step.into())),
+        "MONTHLY" => start.checked_add_months(Months::new(step)),
+        "YEARLY" => start.checked_add_months(Months::new(step.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// The events of a calendar in the date range, expanding simple recurrence rules
+pub fn parse(ics: &str, from: NaiveDate, until: NaiveDate) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut in_event = false;
+    let mut summary = String::new();
+    let mut location = None;
+    let mut start = None;
+    let mut rule = None;
+    for line in unfold(ics) {
+        if line.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            in_event = true;
+            summary.clear();
+            location = None;
+            start = None;
+            rule = None;
+        } else if line.eq_ignore_ascii_case("END:VEVENT") {
+            in_event = false;
+            let Some((first, all_day)) = start else {
+                continue;
+            };
+            let occurrences: Box<dyn Iterator<Item = NaiveDateTime>> = match &rule {
+                Some(rule) => Box::new(
+                    (0..)
+                        .map_while(|n| next_occurrence(first, rule, n))
+                        .take(rule.count.unwrap_or(MAX_OCCURRENCES).min(MAX_OCCURRENCES))
+                        .take_while(|t| rule.until.map_or(true, |until| *t <= until))
+                        .take_while(|t| t.date() < until),
+                ),
+                None => Box::new(std::iter::once(first)),
+            };
+            events.extend(
+                occurrences
+                    .filter(|t| (from..until).contains(&t.date()))
+                    .map(|t| Event {
+                        summary: summary.clone(),
+                        location: location.clone(),
+                        start: t,
+                        all_day,
+                    }),
+            );
+        } else if in_event {
+            if let Some((_, value)) = property(&line, "SUMMARY") {
+                summary = unescape(value);
+            } else if let Some((_, value)) = property(&line, "LOCATION") {
+                location = Some(unescape(value)).filter(|l| !l.is_empty());
+            } else if let Some((params, value)) = property(&line, "DTSTART") {
+                start = parse_time(params, value);
+            } else if let Some((_, value)) = property(&line, "RRULE") {
+                rule = Some(parse_rule(value));
+            }
+        }
+    }
+    events
+}
+
+/// The dates to load events for: the month of the selected day and the upcoming days
+pub fn range(today: NaiveDate, selected: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = selected.with_day(1).unwrap_or(selected);
+    let next_month = first.checked_add_months(Months::new(1)).unwrap_or(selected);
+    (
+        first.min(today),
+        next_month.max(today + Duration::days(UPCOMING_DAYS + 1)),
+    )
+}
@@ -0,0 +1,231 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::alarms::Alarm;
+
+pub const APP_ID: &str = "com.system76.CosmicAppletTime";
+
+/// Where a vertical panel shows the date
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum VerticalDate {
+    /// Day and month below the time
+    #[default]
+    Stacked,
+    /// Only in the tooltip
+    OnHover,
+}
+
+/// The day the weeks of the calendar start on
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum FirstDay {
+    /// From the time locale
+    #[default]
+    Locale,
+    Monday,
+    Saturday,
+    Sunday,
+}
+
+/// 12 or 24-hour clock
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum HourFormat {
+    /// From the time locale
+    #[default]
+    Locale,
+    TwelveHour,
+    TwentyFourHour,
+}
+
+/// Phase lengths of the pomodoro focus timer, in minutes
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PomodoroSettings {
+    pub work_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    /// Work sessions before a long break
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AlarmSettings {
+    /// Minutes before an alarm to send a reminder, 0 sends none
+    pub pre_alarm_minutes: u32,
+    pub snooze_minutes: u32,
+}
+
+impl Default for AlarmSettings {
+    fn default() -> Self {
+        Self {
+            pre_alarm_minutes: 10,
+            snooze_minutes: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct TimeAppletConfig {
+    /// Country code of the bundled holiday calendar, `None` hides holidays
+    pub holiday_region: Option<String>,
+    /// Send a notification the day before a holiday
+    pub notify_holidays: bool,
+    /// iCalendar file whose events are shown as holidays
+    pub holiday_file: Option<String>,
+    /// iCalendar files added next to the calendars of Evolution Data Server
+    pub calendar_files: Vec<String>,
+    /// Paths of the calendars whose events are hidden
+    pub disabled_calendars: BTreeSet<String>,
+    /// IANA names of the time zones shown as world clocks
+    pub world_clocks: Vec<String>,
+    /// Show the world clocks in the panel too, by time zone abbreviation
+    pub world_clocks_in_panel: bool,
+    /// strftime format of the panel label, `None` uses the default
+    pub panel_format: Option<String>,
+    /// strftime format of the panel tooltip, `None` uses the default
+    pub tooltip_format: Option<String>,
+    pub first_day: FirstDay,
+    /// Show ISO 8601 week numbers next to the calendar
+    pub show_week_numbers: bool,
+    pub hour_format: HourFormat,
+    /// Show seconds in the default panel format
+    pub show_seconds: bool,
+    pub vertical_date: VerticalDate,
+    /// Show the pomodoro focus timer in the popup
+    pub pomodoro_enabled: bool,
+    pub pomodoro: PomodoroSettings,
+    pub alarms: Vec<Alarm>,
+    pub alarm_settings: AlarmSettings,
+}
+
+impl TimeAppletConfig {
+    pub fn load() -> TimeAppletConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| TimeAppletConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+
+    pub fn set_holiday_region(&mut self, holiday_region: Option<String>, config: &Config) {
+        self.holiday_region = holiday_region;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_holiday_file(&mut self, holiday_file: Option<String>, config: &Config) {
+        self.holiday_file = holiday_file;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_notify_holidays(&mut self, notify_holidays: bool, config: &Config) {
+        self.notify_holidays = notify_holidays;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_calendar_enabled(&mut self, path: String, enabled: bool, config: &Config) {
+        if enabled {
+            self.disabled_calendars.remove(&path);
+        } else {
+            self.disabled_calendars.insert(path);
+        }
+        let _ = self.write_entry(config);
+    }
+
+    pub fn add_calendar_file(&mut self, path: String, config: &Config) {
+        if !self.calendar_files.contains(&path) {
+            self.calendar_files.push(path);
+            let _ = self.write_entry(config);
+        }
+    }
+
+    pub fn remove_calendar_file(&mut self, path: &str, config: &Config) {
+        self.calendar_files.retain(|p| p != path);
+        self.disabled_calendars.remove(path);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn add_world_clock(&mut self, timezone: String, config: &Config) {
+        if !self.world_clocks.contains(&timezone) {
+            self.world_clocks.push(timezone);
+            let _ = self.write_entry(config);
+        }
+    }
+
+    pub fn remove_world_clock(&mut self, timezone: &str, config: &Config) {
+        self.world_clocks.retain(|tz| tz != timezone);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_world_clocks_in_panel(&mut self, world_clocks_in_panel: bool, config: &Config) {
+        self.world_clocks_in_panel = world_clocks_in_panel;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_panel_format(&mut self, panel_format: Option<String>, config: &Config) {
+        self.panel_format = panel_format;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_tooltip_format(&mut self, tooltip_format: Option<String>, config: &Config) {
+        self.tooltip_format = tooltip_format;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_first_day(&mut self, first_day: FirstDay, config: &Config) {
+        self.first_day = first_day;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_show_week_numbers(&mut self, show_week_numbers: bool, config: &Config) {
+        self.show_week_numbers = show_week_numbers;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_hour_format(&mut self, hour_format: HourFormat, config: &Config) {
+        self.hour_format = hour_format;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_pomodoro_enabled(&mut self, pomodoro_enabled: bool, config: &Config) {
+        self.pomodoro_enabled = pomodoro_enabled;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn add_alarm(&mut self, mut alarm: Alarm, config: &Config) {
+        alarm.id = self.alarms.iter().map(|a| a.id + 1).max().unwrap_or(0);
+        self.alarms.push(alarm);
+        self.alarms.sort_by_key(|a| a.time);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn remove_alarm(&mut self, id: u32, config: &Config) {
+        self.alarms.retain(|a| a.id != id);
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_alarm_enabled(&mut self, id: u32, enabled: bool, config: &Config) {
+        if let Some(alarm) = self.alarms.iter_mut().find(|a| a.id == id) {
+            alarm.enabled = enabled;
+            let _ = self.write_entry(config);
+        }
+    }
+
+    pub fn set_show_seconds(&mut self, show_seconds: bool, config: &Config) {
+        self.show_seconds = show_seconds;
+        let _ = self.write_entry(config);
+    }
+
+    pub fn set_vertical_date(&mut self, vertical_date: VerticalDate, config: &Config) {
+        self.vertical_date = vertical_date;
+        let _ = self.write_entry(config);
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! User formats for the clock, in chrono's strftime syntax
+
+use chrono::{
+    format::{Fixed, Item, Numeric, StrftimeItems},
+    DateTime, TimeZone,
+};
+use std::fmt::{Display, Write};
+
+const DEFAULT_PANEL_FORMAT_12H: &str = "%b %-d %-I:%M %p";
+const DEFAULT_PANEL_FORMAT_24H: &str = "%b %-d %H:%M";
+const DEFAULT_PANEL_FORMAT_12H_SECONDS: &str = "%b %-d %-I:%M:%S %p";
+const DEFAULT_PANEL_FORMAT_24H_SECONDS: &str = "%b %-d %H:%M:%S";
+pub const DEFAULT_TOOLTIP_FORMAT: &str = "%A, %-d %B %Y";
+
+pub fn default_panel_format(twelve_hour: bool, seconds: bool) -> &'static str {
+    match (twelve_hour, seconds) {
+        (true, false) => DEFAULT_PANEL_FORMAT_12H,
+        (false, false) => DEFAULT_PANEL_FORMAT_24H,
+        (true, true) => DEFAULT_PANEL_FORMAT_12H_SECONDS,
+        (false, true) => DEFAULT_PANEL_FORMAT_24H_SECONDS,
+    }
+}
+
+/// `true` if the format shows seconds, and the clock needs to be updated every second
+pub fn has_seconds(format: &str) -> bool {
+    StrftimeItems::new(format).any(|item| {
+        matches!(
+            item,
+            Item::Numeric(Numeric::Second | Numeric::Timestamp, _) | Item::Fixed(Fixed::RFC3339)
+        )
+    })
+}
+
+/// Hours and minutes, for the times shown in the popup
+pub fn time_format(twelve_hour: bool) -> &'static str {
+    if twelve_hour {
+        "%-I:%M %p"
+    } else {
+        "%H:%M"
+    }
+}
+
+/// The formatted time, `None` if the format has an invalid specifier.
+/// chrono panics on those when formatting with `to_string`, so this writes the result instead.
+pub fn format<Tz: TimeZone>(time: &DateTime<Tz>, format: &str) -> Option<String>
+where
+    Tz::Offset: Display,
+{
+    let mut formatted = String::new();
+    write!(formatted, "{}", time.format(format)).ok()?;
+    Some(formatted)
+}
+
+/// The time in the user's format if it is valid, in the default format otherwise
+pub fn format_or<Tz: TimeZone>(time: &DateTime<Tz>, user: Option<&str>, default: &str) -> String
+where
+    Tz::Offset: Display,
+{
+    user.and_then(|user| format(time, user))
+        .or_else(|| format(time, default))
+        .unwrap_or_default()
+}
@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Bundled national public holiday calendars
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// How the date of a holiday is derived for a given year
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Same month and day every year
+    Fixed(u32, u32),
+    /// Days relative to Western Easter Sunday
+    Easter(i64),
+    /// First given weekday on or after the month and day
+    OnOrAfter(u32, u32, Weekday),
+    /// Last given weekday of the month
+    Last(u32, Weekday),
+}
+
+impl Rule {
+    fn date(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Rule::Fixed(month, day) => NaiveDate::from_ymd_opt(year, month, day),
+            Rule::Easter(offset) => Some(easter(year)? + Duration::days(offset)),
+            Rule::OnOrAfter(month, day, weekday) => {
+                let start = NaiveDate::from_ymd_opt(year, month, day)?;
+                let skip = (7 + weekday.num_days_from_monday()
+                    - start.weekday().num_days_from_monday())
+                    % 7;
+                Some(start + Duration::days(skip.into()))
+            }
+            Rule::Last(month, weekday) => {
+                let next_month = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)
+                }?;
+                let end = next_month.pred_opt()?;
+                let skip =
+                    (7 + end.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+                Some(end - Duration::days(skip.into()))
+            }
+        }
+    }
+}
+
+/// Western Easter Sunday, using the anonymous Gregorian algorithm
+fn easter(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+use Rule::*;
+use Weekday::*;
+
+const NEW_YEAR: (Rule, &str) = (Fixed(1, 1), "New Year's Day");
+const GOOD_FRIDAY: (Rule, &str) = (Easter(-2), "Good Friday");
+const EASTER_MONDAY: (Rule, &str) = (Easter(1), "Easter Monday");
+const ASCENSION: (Rule, &str) = (Easter(39), "Ascension Day");
+const WHIT_MONDAY: (Rule, &str) = (Easter(50), "Whit Monday");
+const LABOUR_DAY: (Rule, &str) = (Fixed(5, 1), "Labour Day");
+const CHRISTMAS: (Rule, &str) = (Fixed(12, 25), "Christmas Day");
+const BOXING_DAY: (Rule, &str) = (Fixed(12, 26), "Boxing Day");
+
+/// Country code and the national holidays observed there
+const CALENDARS: &[(&str, &[(Rule, &str)])] = &[
+    (
+        "AU",
+        &[
+            NEW_YEAR,
+            (Fixed(1, 26), "Australia Day"),
+            GOOD_FRIDAY,
+            (Easter(-1), "Easter Saturday"),
+            EASTER_MONDAY,
+            (Fixed(4, 25), "Anzac Day"),
+            CHRISTMAS,
+            BOXING_DAY,
+        ],
+    ),
+    (
+        "CA",
+        &[
+            NEW_YEAR,
+            GOOD_FRIDAY,
+            (OnOrAfter(5, 18, Mon), "Victoria Day"),
+            (Fixed(7, 1), "Canada Day"),
+            (OnOrAfter(9, 1, Mon), "Labour Day"),
+            (Fixed(9, 30), "National Day for Truth and Reconciliation"),
+            (OnOrAfter(10, 8, Mon), "Thanksgiving"),
+            (Fixed(11, 11), "Remembrance Day"),
+            CHRISTMAS,
+            BOXING_DAY,
+        ],
+    ),
+    (
+        "DE",
+        &[
+            NEW_YEAR,
+            GOOD_FRIDAY,
+            EASTER_MONDAY,
+            LABOUR_DAY,
+            ASCENSION,
+            WHIT_MONDAY,
+            (Fixed(10, 3), "German Unity Day"),
+            CHRISTMAS,
+            (Fixed(12, 26), "St. Stephen's Day"),
+        ],
+    ),
+    (
+        "ES",
+        &[
+            NEW_YEAR,
+            (Fixed(1, 6), "Epiphany"),
+            GOOD_FRIDAY,
+            LABOUR_DAY,
+            (Fixed(8, 15), "Assumption Day"),
+            (Fixed(10, 12), "National Day"),
+            (Fixed(11, 1), "All Saints' Day"),
+            (Fixed(12, 6), "Constitution Day"),
+            (Fixed(12, 8), "Immaculate Conception"),
+            CHRISTMAS,
+        ],
+    ),
+    (
+        "FR",
+        &[
+            NEW_YEAR,
+            EASTER_MONDAY,
+            LABOUR_DAY,
+            (Fixed(5, 8), "Victory in Europe Day"),
+            ASCENSION,
+            WHIT_MONDAY,
+            (Fixed(7, 14), "Bastille Day"),
+            (Fixed(8, 15), "Assumption Day"),
+            (Fixed(11, 1), "All Saints' Day"),
+            (Fixed(11, 11), "Armistice Day"),
+            CHRISTMAS,
+        ],
+    ),
+    (
+        "GB",
+        &[
+            NEW_YEAR,
+            GOOD_FRIDAY,
+            EASTER_MONDAY,
+            (OnOrAfter(5, 1, Mon), "Early May Bank Holiday"),
+            (Last(5, Mon), "Spring Bank Holiday"),
+            (Last(8, Mon), "Summer Bank Holiday"),
+            CHRISTMAS,
+            BOXING_DAY,
+        ],
+    ),
+    (
+        "IE",
+        &[
+            NEW_YEAR,
+            (OnOrAfter(2, 1, Mon), "St. Brigid's Day"),
+            (Fixed(3, 17), "St. Patrick's Day"),
+            EASTER_MONDAY,
+            (OnOrAfter(5, 1, Mon), "May Bank Holiday"),
+            (OnOrAfter(6, 1, Mon), "June Bank Holiday"),
+            (OnOrAfter(8, 1, Mon), "August Bank Holiday"),
+            (Last(10, Mon), "October Bank Holiday"),
+            CHRISTMAS,
+            (Fixed(12, 26), "St. Stephen's Day"),
+        ],
+    ),
+    (
+        "IT",
+        &[
+            NEW_YEAR,
+            (Fixed(1, 6), "Epiphany"),
+            EASTER_MONDAY,
+            (Fixed(4, 25), "Liberation Day"),
+            LABOUR_DAY,
+            (Fixed(6, 2), "Republic Day"),
+            (Fixed(8, 15), "Ferragosto"),
+            (Fixed(11, 1), "All Saints' Day"),
+            (Fixed(12, 8), "Immaculate Conception"),
+            CHRISTMAS,
+            (Fixed(12, 26), "St. Stephen's Day"),
+        ],
+    ),
+    (
+        "NL",
+        &[
+            NEW_YEAR,
+            GOOD_FRIDAY,
+            (Easter(0), "Easter Sunday"),
+            EASTER_MONDAY,
+            (Fixed(4, 27), "King's Day"),
+            (Fixed(5, 5), "Liberation Day"),
+            ASCENSION,
+            (Easter(49), "Whit Sunday"),
+            WHIT_MONDAY,
+            CHRISTMAS,
+            (Fixed(12, 26), "Second Day of Christmas"),
+        ],
+    ),
+    (
+        "SE",
+        &[
+            NEW_YEAR,
+            (Fixed(1, 6), "Epiphany"),
+            GOOD_FRIDAY,
+            EASTER_MONDAY,
+            (Fixed(5, 1), "May Day"),
+            ASCENSION,
+            (Fixed(6, 6), "National Day"),
+            (OnOrAfter(6, 19, Fri), "Midsummer Eve"),
+            (OnOrAfter(6, 20, Sat), "Midsummer Day"),
+            (OnOrAfter(10, 31, Sat), "All Saints' Day"),
+            (Fixed(12, 24), "Christmas Eve"),
+            CHRISTMAS,
+            (Fixed(12, 26), "Second Day of Christmas"),
+            (Fixed(12, 31), "New Year's Eve"),
+        ],
+    ),
+    (
+        "US",
+        &[
+            NEW_YEAR,
+            (OnOrAfter(1, 15, Mon), "Martin Luther King Jr. Day"),
+            (OnOrAfter(2, 15, Mon), "Presidents' Day"),
+            (Last(5, Mon), "Memorial Day"),
+            (Fixed(6, 19), "Juneteenth"),
+            (Fixed(7, 4), "Independence Day"),
+            (OnOrAfter(9, 1, Mon), "Labor Day"),
+            (OnOrAfter(10, 8, Mon), "Columbus Day"),
+            (Fixed(11, 11), "Veterans Day"),
+            (OnOrAfter(11, 22, Thu), "Thanksgiving"),
+            CHRISTMAS,
+        ],
+    ),
+];
+
+/// Country codes that have a bundled calendar
+pub fn regions() -> impl Iterator<Item = &'static str> {
+    CALENDARS.iter().map(|(code, _)| *code)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: &'static str,
+}
+
+/// Holidays of the region in the given year, sorted by date
+pub fn holidays(region: &str, year: i32) -> Vec<Holiday> {
+    let Some((_, rules)) = CALENDARS.iter().find(|(code, _)| *code == region) else {
+        return Vec::new();
+    };
+    let mut holidays: Vec<_> = rules
+        .iter()
+        .filter_map(|(rule, name)| {
+            Some(Holiday {
+                date: rule.date(year)?,
+                name: *name,
+            })
+        })
+        .collect();
+    holidays.sort_by_key(|h| h.date);
+    holidays
+}
+
+/// The holiday falling on the given date, if any
+pub fn holiday_on(region: &str, date: NaiveDate) -> Option<Holiday> {
+    holidays(region, date.year())
+        .into_iter()
+        .find(|h| h.date == date)
+}
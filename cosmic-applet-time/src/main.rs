@@ -1,37 +1,177 @@
-use cosmic::app::{self, applet::cosmic_panel_config::PanelAnchor, Command};
+mod alarms;
+mod calendar;
+mod config;
+mod format;
+mod holidays;
+mod localize;
+mod notification;
+mod ntp;
+mod pomodoro;
+mod region;
+mod settings;
+mod sound;
+mod timer;
+mod timezones;
+mod week;
+
+use cosmic::app::{
+    self,
+    applet::{applet_button_theme, cosmic_panel_config::PanelAnchor},
+    Command,
+};
+use cosmic::cosmic_config::{self, Config};
 use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
 use cosmic::iced::{
-    time,
-    widget::{button, column, text, vertical_space},
-    window, Alignment, Length, Rectangle, Subscription,
+    event,
+    keyboard::{self, KeyCode},
+    subscription, time,
+    widget::{
+        button, column, container, pick_list, row, svg, text, text_input, tooltip, vertical_space,
+    },
+    window, Alignment, Color, Length, Rectangle, Subscription,
 };
 use cosmic::iced_style::application;
 use cosmic::theme;
 use cosmic::{
-    widget::{icon, rectangle_tracker::*},
+    widget::{divider, icon, rectangle_tracker::*, toggler},
     Element, Theme,
 };
 
-use chrono::{DateTime, Local, Timelike};
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
 use std::time::Duration;
 
+use crate::config::{FirstDay, HourFormat, TimeAppletConfig, VerticalDate};
+
+/// Calendars are read again this often, to pick up changes made by other programs
+const EVENTS_REFRESH_SECS: u64 = 15 * 60;
+/// The time synchronization status is read again this often
+const NTP_CHECK_SECS: u64 = 5 * 60;
+
 pub fn main() -> cosmic::iced::Result {
+    localize::localize();
+
     cosmic::app::applet::run::<Time>(true, ())
 }
 
+/// Holiday calendar choice, `None` hides holidays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region(Option<&'static str>);
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            None => fl!("holidays-none"),
+            Some("AU") => fl!("region-au"),
+            Some("CA") => fl!("region-ca"),
+            Some("DE") => fl!("region-de"),
+            Some("ES") => fl!("region-es"),
+            Some("FR") => fl!("region-fr"),
+            Some("GB") => fl!("region-gb"),
+            Some("IE") => fl!("region-ie"),
+            Some("IT") => fl!("region-it"),
+            Some("NL") => fl!("region-nl"),
+            Some("SE") => fl!("region-se"),
+            Some("US") => fl!("region-us"),
+            Some(code) => code.to_string(),
+        };
+        f.write_str(&name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HourFormatOption(HourFormat);
+
+impl fmt::Display for HourFormatOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self.0 {
+            HourFormat::Locale => fl!("hour-format-locale"),
+            HourFormat::TwelveHour => fl!("twelve-hour"),
+            HourFormat::TwentyFourHour => fl!("twenty-four-hour"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VerticalDateOption(VerticalDate);
+
+impl fmt::Display for VerticalDateOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self.0 {
+            VerticalDate::Stacked => fl!("vertical-date-stacked"),
+            VerticalDate::OnHover => fl!("vertical-date-on-hover"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FirstDayOption(FirstDay);
+
+impl fmt::Display for FirstDayOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self.0 {
+            FirstDay::Locale => fl!("first-day-locale"),
+            FirstDay::Monday => fl!("monday"),
+            FirstDay::Saturday => fl!("saturday"),
+            FirstDay::Sunday => fl!("sunday"),
+        })
+    }
+}
+
 struct Time {
     core: cosmic::app::Core,
     popup: Option<window::Id>,
     id_ctr: u128,
     update_at: Every,
     now: DateTime<Local>,
-    msg: String,
     rectangle_tracker: Option<RectangleTracker<u32>>,
     rectangle: Rectangle,
+    config: TimeAppletConfig,
+    /// Day on which the upcoming holiday was last checked
+    holiday_checked: Option<NaiveDate>,
+    /// Day focused in the calendar, its month is the one shown
+    selected: NaiveDate,
+    calendars: Vec<calendar::Source>,
+    events: Vec<calendar::Event>,
+    /// Dates the events were loaded for, up to but not including the second one
+    events_range: (NaiveDate, NaiveDate),
+    calendar_file_input: String,
+    timezone_search: String,
+    panel_format_input: String,
+    tooltip_format_input: String,
+    timer: Option<timer::Timer>,
+    timer_input: String,
+    stopwatch: timer::Stopwatch,
+    pomodoro: Option<pomodoro::Pomodoro>,
+    pomodoro_stats: pomodoro::DailyStats,
+    /// Minute in which the alarms were last checked
+    alarms_checked: Option<NaiveDateTime>,
+    ringing: Vec<RingingAlarm>,
+    /// Snoozed alarms and when they ring again
+    snoozed: Vec<(u32, NaiveDateTime)>,
+    alarm_time_input: String,
+    alarm_label_input: String,
+    alarm_days: Vec<Weekday>,
+    /// `None` until read, or if timedated isn't available
+    ntp: Option<ntp::Status>,
+    /// Holidays from the user's iCalendar file, for the year of the selected day
+    ics_holidays: Vec<calendar::Event>,
+    ics_holidays_year: Option<i32>,
+    holiday_file_input: String,
+    /// Why COSMIC Settings couldn't be opened
+    settings_error: Option<String>,
 }
 
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+struct RingingAlarm {
+    alarm: u32,
+    /// The notification of the alarm, once it was sent
+    notification: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Every {
     Minute,
     Second,
@@ -42,15 +182,1040 @@ enum Message {
     TogglePopup,
     Tick,
     Rectangle(RectangleUpdate<u32>),
+    SelectRegion(Region),
+    ToggleHolidayNotifications(bool),
+    ConfigUpdated(TimeAppletConfig),
+    SelectDay(NaiveDate),
+    /// Move the selected day by this many days
+    MoveSelection(i64),
+    PreviousMonth,
+    NextMonth,
+    JumpToToday,
+    RefreshEvents,
+    EventsLoaded(Vec<calendar::Event>),
+    ToggleCalendar(String, bool),
+    CalendarFileInput(String),
+    AddCalendarFile,
+    RemoveCalendarFile(String),
+    TimezoneSearch(String),
+    AddWorldClock(String),
+    RemoveWorldClock(String),
+    ToggleWorldClocksInPanel(bool),
+    PanelFormatInput(String),
+    TooltipFormatInput(String),
+    SubmitFormats,
+    ToggleSeconds(bool),
+    SelectVerticalDate(VerticalDateOption),
+    SelectFirstDay(FirstDayOption),
+    SelectHourFormat(HourFormatOption),
+    ToggleWeekNumbers(bool),
+    StartTimer(Duration),
+    TimerInput(String),
+    SubmitTimer,
+    PauseTimer,
+    ResumeTimer,
+    CancelTimer,
+    StartStopwatch,
+    PauseStopwatch,
+    ResetStopwatch,
+    TogglePomodoro(bool),
+    StartPomodoro,
+    PausePomodoro,
+    ResumePomodoro,
+    SkipPomodoroPhase,
+    StopPomodoro,
+    AlarmTimeInput(String),
+    AlarmLabelInput(String),
+    ToggleAlarmDay(Weekday),
+    AddAlarm,
+    RemoveAlarm(u32),
+    ToggleAlarm(u32, bool),
+    AlarmNotified(u32, Option<u32>),
+    NotificationAction(u32, String),
+    DismissAlarm(u32),
+    SnoozeAlarm(u32),
+    IcsHolidaysLoaded(Vec<calendar::Event>),
+    HolidayFileInput(String),
+    SubmitHolidayFile,
+    CheckNtp,
+    NtpStatus(Option<ntp::Status>),
+    OpenDateTimeSettings,
+    /// Every second while a timer or the shown stopwatch runs
+    TimerTick,
+    Ignore,
+}
+
+impl Time {
+    /// Notify once a day when the next day is a holiday in the selected region
+    fn check_holiday_tomorrow(&mut self) -> Command<Message> {
+        let today = self.now.date_naive();
+        if !self.config.notify_holidays || self.holiday_checked == Some(today) {
+            return Command::none();
+        }
+        self.holiday_checked = Some(today);
+        let Some(tomorrow) = today.succ_opt() else {
+            return Command::none();
+        };
+        let Some(holiday) = self
+            .config
+            .holiday_region
+            .as_deref()
+            .and_then(|region| holidays::holiday_on(region, tomorrow))
+            .map(|h| h.name.to_string())
+            .or_else(|| {
+                self.ics_holidays
+                    .iter()
+                    .find(|e| e.start.date() == tomorrow)
+                    .map(|e| e.summary.clone())
+            })
+        else {
+            return Command::none();
+        };
+        cosmic::iced::Command::perform(
+            notification::notify(fl!("holiday-tomorrow"), holiday),
+            |_| app::message::app(Message::Ignore),
+        )
+    }
+
+    fn calendar(&self) -> Element<Message> {
+        let today = self.now.date_naive();
+        let selected = self.selected;
+        let first = selected.with_day(1).unwrap_or(selected);
+        let holidays: Vec<_> = self
+            .holidays(first.year())
+            .into_iter()
+            .filter(|(date, _)| date.month() == first.month())
+            .collect();
+
+        let cell = |content: String| {
+            container(text(content).size(14))
+                .width(Length::Fixed(36.0))
+                .center_x()
+        };
+        let first_day = match self.config.first_day {
+            FirstDay::Locale => week::locale_first_day(),
+            FirstDay::Monday => Weekday::Mon,
+            FirstDay::Saturday => Weekday::Sat,
+            FirstDay::Sunday => Weekday::Sun,
+        };
+        let show_week_numbers = self.config.show_week_numbers;
+        let columns = if show_week_numbers { 8.0 } else { 7.0 };
+        let start = week::week_start(first, first_day);
+        let mut weekdays = row![];
+        if show_week_numbers {
+            weekdays = weekdays.push(cell(String::new()));
+        }
+        for date in start.iter_days().take(7) {
+            weekdays = weekdays.push(cell(date.format("%a").to_string()));
+        }
+        let header = row![
+            button(icon("go-previous-symbolic", 16).style(theme::Svg::Symbolic))
+                .on_press(Message::PreviousMonth)
+                .style(theme::Button::Text),
+            container(text(first.format("%B %Y").to_string()).size(18))
+                .width(Length::Fill)
+                .center_x(),
+            button(icon("go-next-symbolic", 16).style(theme::Svg::Symbolic))
+                .on_press(Message::NextMonth)
+                .style(theme::Button::Text),
+        ]
+        .width(Length::Fixed(columns * 36.0))
+        .align_items(Alignment::Center);
+        let mut grid = column![header, weekdays]
+            .spacing(4)
+            .align_items(Alignment::Center);
+        let mut days = start.iter_days().peekable();
+        while let Some(&week_start) = days
+            .peek()
+            .filter(|d| d.month() == first.month() || **d < first)
+        {
+            let mut week = row![];
+            if show_week_numbers {
+                week = week.push(
+                    container(text(week::week_number(week_start).to_string()).size(12))
+                        .width(Length::Fixed(36.0))
+                        .center_x(),
+                );
+            }
+            for date in days.by_ref().take(7) {
+                if date.month() != first.month() {
+                    week = week.push(cell(String::new()));
+                    continue;
+                }
+                let is_holiday = holidays.iter().any(|(d, _)| *d == date);
+                let style = day_style(date == today, date == selected, is_holiday);
+                week = week.push(
+                    button(cell(date.day().to_string()).style(style))
+                        .on_press(Message::SelectDay(date))
+                        .padding(0)
+                        .style(theme::Button::Text),
+                );
+            }
+            grid = grid.push(week);
+        }
+        if first.year() != today.year() || first.month() != today.month() || selected != today {
+            grid = grid.push(
+                button(text(fl!("today")).size(14))
+                    .on_press(Message::JumpToToday)
+                    .style(theme::Button::Text),
+            );
+        }
+        for (_, name) in holidays.into_iter().filter(|(date, _)| *date == selected) {
+            grid = grid.push(
+                text(name)
+                    .size(14)
+                    .style(theme::Text::Accent)
+                    .width(Length::Fixed(columns * 36.0)),
+            );
+        }
+        grid.into()
+    }
+
+    /// Holidays of the bundled calendar of the selected region and of the user's iCalendar
+    /// file, sorted by date
+    fn holidays(&self, year: i32) -> Vec<(NaiveDate, String)> {
+        let mut holidays: Vec<_> = self
+            .config
+            .holiday_region
+            .as_deref()
+            .map(|region| holidays::holidays(region, year))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|h| (h.date, h.name.to_string()))
+            .collect();
+        holidays.extend(
+            self.ics_holidays
+                .iter()
+                .filter(|e| e.start.year() == year)
+                .map(|e| (e.start.date(), e.summary.clone())),
+        );
+        holidays.sort_by_key(|(date, _)| *date);
+        holidays
+    }
+
+    /// Read the user's holiday file for the year of the selected day
+    fn refresh_ics_holidays(&mut self) -> Command<Message> {
+        let year = self.selected.year();
+        self.ics_holidays_year = Some(year);
+        let (Some(file), Some(from), Some(until)) = (
+            self.config.holiday_file.clone(),
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        ) else {
+            self.ics_holidays.clear();
+            return Command::none();
+        };
+        cosmic::iced::Command::perform(calendar::load(vec![file.into()], from, until), |holidays| {
+            app::message::app(Message::IcsHolidaysLoaded(holidays))
+        })
+    }
+
+    fn events(&self) -> Element<Message> {
+        let today = self.now.date_naive();
+        let event_row = |event: &calendar::Event, label: String| {
+            let mut details = column![text(event.summary.clone()).size(14)];
+            if let Some(location) = &event.location {
+                details = details.push(text(location.clone()).size(12));
+            }
+            row![
+                text(label).size(12).width(Length::Fixed(72.0)),
+                details.width(Length::Fill),
+            ]
+            .spacing(8)
+        };
+        let time_format = format::time_format(self.twelve_hour());
+        let time_label = |event: &calendar::Event| {
+            if event.all_day {
+                fl!("all-day")
+            } else {
+                event.start.format(time_format).to_string()
+            }
+        };
+
+        let heading = if self.selected == today {
+            fl!("today")
+        } else {
+            self.selected.format("%A, %-d %B").to_string()
+        };
+        let mut list = column![text(heading).size(14)].spacing(8);
+        let mut day = self
+            .events
+            .iter()
+            .filter(|e| e.start.date() == self.selected)
+            .peekable();
+        if day.peek().is_none() {
+            list = list.push(text(fl!("no-events")).size(12));
+        }
+        for event in day {
+            list = list.push(event_row(event, time_label(event)));
+        }
+        if self.selected == today {
+            let upcoming = today.succ_opt().unwrap_or(today)
+                ..today + chrono::Duration::days(calendar::UPCOMING_DAYS + 1);
+            let mut upcoming = self
+                .events
+                .iter()
+                .filter(|e| upcoming.contains(&e.start.date()))
+                .peekable();
+            if upcoming.peek().is_some() {
+                list = list.push(text(fl!("upcoming")).size(14));
+            }
+            for event in upcoming {
+                let label = if event.all_day {
+                    event.start.format("%a %-d").to_string()
+                } else {
+                    format!(
+                        "{} {}",
+                        event.start.format("%a"),
+                        event.start.format(time_format)
+                    )
+                };
+                list = list.push(event_row(event, label));
+            }
+        }
+        list.into()
+    }
+
+    fn calendar_settings(&self) -> Element<Message> {
+        let mut settings = column![text(fl!("calendars")).size(14)].spacing(8);
+        for source in &self.calendars {
+            let path = source.path.to_string_lossy().into_owned();
+            let enabled = !self.config.disabled_calendars.contains(&path);
+            let toggle = toggler(source.name.clone(), enabled, {
+                let path = path.clone();
+                move |enabled| Message::ToggleCalendar(path.clone(), enabled)
+            })
+            .text_size(14)
+            .width(Length::Fill);
+            settings = settings.push(if self.config.calendar_files.contains(&path) {
+                row![
+                    toggle,
+                    button(icon("edit-delete-symbolic", 16).style(theme::Svg::Symbolic))
+                        .on_press(Message::RemoveCalendarFile(path))
+                        .style(theme::Button::Text),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center)
+            } else {
+                row![toggle]
+            });
+        }
+        settings = settings.push(
+            row![
+                text_input(&fl!("calendar-file"), &self.calendar_file_input)
+                    .on_input(Message::CalendarFileInput)
+                    .on_submit(Message::AddCalendarFile)
+                    .size(14)
+                    .width(Length::Fill),
+                button(text(fl!("add")).size(14))
+                    .on_press(Message::AddCalendarFile)
+                    .style(theme::Button::Text),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+        settings.into()
+    }
+
+    fn world_clocks(&self) -> Element<Message> {
+        let today = self.now.date_naive();
+        let time_format = format::time_format(self.twelve_hour());
+        let mut clocks = column![text(fl!("world-clocks")).size(14)].spacing(8);
+        for name in &self.config.world_clocks {
+            let Some(tz) = timezones::parse(name) else {
+                continue;
+            };
+            // chrono-tz resolves the offset for this instant, so DST changes apply on their own
+            let there = self.now.with_timezone(&tz);
+            let day = match there.date_naive().cmp(&today) {
+                Ordering::Less => fl!("yesterday"),
+                Ordering::Equal => fl!("today"),
+                Ordering::Greater => fl!("tomorrow"),
+            };
+            let offset = (there.naive_local() - self.now.naive_local()).num_minutes();
+            clocks = clocks.push(
+                row![
+                    column![
+                        text(timezones::city(name)).size(14),
+                        text(format!("{}, {}", day, offset_label(offset))).size(12),
+                    ]
+                    .width(Length::Fill),
+                    text(there.format(time_format).to_string()).size(14),
+                    button(icon("edit-delete-symbolic", 16).style(theme::Svg::Symbolic))
+                        .on_press(Message::RemoveWorldClock(name.clone()))
+                        .style(theme::Button::Text),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        clocks = clocks.push(
+            text_input(&fl!("search-timezone"), &self.timezone_search)
+                .on_input(Message::TimezoneSearch)
+                .size(14)
+                .width(Length::Fill),
+        );
+        for name in timezones::search(&self.timezone_search) {
+            clocks = clocks.push(
+                button(text(name.replace('_', " ")).size(14))
+                    .on_press(Message::AddWorldClock(name.to_string()))
+                    .width(Length::Fill)
+                    .style(theme::Button::Text),
+            );
+        }
+        if !self.config.world_clocks.is_empty() {
+            clocks = clocks.push(
+                toggler(
+                    fl!("world-clocks-in-panel"),
+                    self.config.world_clocks_in_panel,
+                    Message::ToggleWorldClocksInPanel,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            );
+        }
+        clocks.into()
+    }
+
+    /// The world clocks for the panel, e.g. `EST 9:41 AM`
+    fn panel_world_clocks(&self) -> Vec<String> {
+        if !self.config.world_clocks_in_panel {
+            return Vec::new();
+        }
+        let time_format = format::time_format(self.twelve_hour());
+        self.config
+            .world_clocks
+            .iter()
+            .filter_map(|name| timezones::parse(name))
+            .map(|tz| {
+                let there = self.now.with_timezone(&tz);
+                format!("{} {}", there.format("%Z"), there.format(time_format))
+            })
+            .collect()
+    }
+
+    /// Tick every second only while the panel shows seconds, so the applet doesn't wake up
+    /// every second for nothing
+    fn tick_interval(&self) -> Every {
+        let shows_seconds = match self.config.panel_format.as_deref() {
+            Some(panel_format) => format::has_seconds(panel_format),
+            None => self.config.show_seconds,
+        };
+        // the tracked rectangle stays empty until the label is laid out in a mapped surface
+        let mapped = self.rectangle.width > 0.0 && self.rectangle.height > 0.0;
+        if shows_seconds && mapped {
+            Every::Second
+        } else {
+            Every::Minute
+        }
+    }
+
+    /// The hour format from the config, or the locale's one
+    fn twelve_hour(&self) -> bool {
+        match self.config.hour_format {
+            HourFormat::Locale => region::locale_twelve_hour(),
+            HourFormat::TwelveHour => true,
+            HourFormat::TwentyFourHour => false,
+        }
+    }
+
+    fn timers(&self) -> Element<Message> {
+        let text_button = |label: String, message: Message| {
+            button(text(label).size(14))
+                .on_press(message)
+                .style(theme::Button::Text)
+        };
+        let mut timer = column![text(fl!("timer")).size(14)].spacing(8);
+        timer = timer.push(
+            match &self.timer {
+                Some(t) => row![
+                    text(timer::format_duration(t.remaining()))
+                        .size(24)
+                        .width(Length::Fill),
+                    if t.is_running() {
+                        text_button(fl!("pause"), Message::PauseTimer)
+                    } else {
+                        text_button(fl!("resume"), Message::ResumeTimer)
+                    },
+                    text_button(fl!("cancel"), Message::CancelTimer),
+                ],
+                None => {
+                    let mut presets = row![].spacing(4);
+                    for minutes in timer::TIMER_PRESETS {
+                        presets = presets.push(text_button(
+                            fl!("minutes", minutes = minutes),
+                            Message::StartTimer(Duration::from_secs(minutes * 60)),
+                        ));
+                    }
+                    presets.push(
+                        text_input("MM:SS", &self.timer_input)
+                            .on_input(Message::TimerInput)
+                            .on_submit(Message::SubmitTimer)
+                            .size(14)
+                            .width(Length::Fixed(72.0)),
+                    )
+                }
+            }
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+
+        let stopwatch = row![
+            column![
+                text(fl!("stopwatch")).size(14),
+                text(timer::format_duration(self.stopwatch.elapsed())).size(24),
+            ]
+            .spacing(8)
+            .width(Length::Fill),
+            if self.stopwatch.is_running() {
+                text_button(fl!("pause"), Message::PauseStopwatch)
+            } else {
+                text_button(fl!("start"), Message::StartStopwatch)
+            },
+            text_button(fl!("reset"), Message::ResetStopwatch),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+        column![timer, stopwatch].spacing(12).into()
+    }
+
+    fn pomodoro(&self) -> Element<Message> {
+        let text_button = |label: String, message: Message| {
+            button(text(label).size(14))
+                .on_press(message)
+                .style(theme::Button::Text)
+        };
+        let mut content = column![text(fl!("pomodoro")).size(14)].spacing(8);
+        content = content.push(
+            match &self.pomodoro {
+                Some(p) => row![
+                    column![
+                        text(match p.phase {
+                            pomodoro::Phase::Work => fl!("pomodoro-work"),
+                            pomodoro::Phase::ShortBreak => fl!("pomodoro-short-break"),
+                            pomodoro::Phase::LongBreak => fl!("pomodoro-long-break"),
+                        })
+                        .size(12),
+                        text(timer::format_duration(p.timer.remaining())).size(24),
+                    ]
+                    .width(Length::Fill),
+                    if p.timer.is_running() {
+                        text_button(fl!("pause"), Message::PausePomodoro)
+                    } else {
+                        text_button(fl!("resume"), Message::ResumePomodoro)
+                    },
+                    text_button(fl!("skip"), Message::SkipPomodoroPhase),
+                    text_button(fl!("stop"), Message::StopPomodoro),
+                ],
+                None => row![text_button(fl!("start"), Message::StartPomodoro)],
+            }
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+        let stats = self.pomodoro_stats.on(self.now.date_naive());
+        content
+            .push(
+                text(fl!(
+                    "pomodoro-stats",
+                    sessions = stats.sessions,
+                    minutes = stats.focus.as_secs() / 60
+                ))
+                .size(12),
+            )
+            .into()
+    }
+
+    fn alarms(&self) -> Element<Message> {
+        let time_format = format::time_format(self.twelve_hour());
+        let text_button = |label: String, message: Message| {
+            button(text(label).size(14))
+                .on_press(message)
+                .style(theme::Button::Text)
+        };
+        let mut content = column![text(fl!("alarms")).size(14)].spacing(8);
+        for ringing in &self.ringing {
+            let Some(alarm) = self.config.alarms.iter().find(|a| a.id == ringing.alarm) else {
+                continue;
+            };
+            content = content.push(
+                row![
+                    text(alarm_summary(alarm, time_format))
+                        .size(14)
+                        .width(Length::Fill),
+                    text_button(
+                        fl!(
+                            "snooze-minutes",
+                            minutes = self.config.alarm_settings.snooze_minutes
+                        ),
+                        Message::SnoozeAlarm(alarm.id)
+                    ),
+                    text_button(fl!("dismiss"), Message::DismissAlarm(alarm.id)),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        for alarm in &self.config.alarms {
+            let days = if alarm.is_recurring() {
+                alarms::WEEKDAYS
+                    .iter()
+                    .filter(|d| alarm.days.contains(d))
+                    .map(|d| weekday_name(*d))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                fl!("alarm-once")
+            };
+            let id = alarm.id;
+            content = content.push(
+                row![
+                    column![
+                        text(alarm_summary(alarm, time_format)).size(14),
+                        text(days).size(12),
+                    ]
+                    .width(Length::Fill),
+                    toggler(None, alarm.enabled, move |enabled| {
+                        Message::ToggleAlarm(id, enabled)
+                    }),
+                    button(icon("edit-delete-symbolic", 16).style(theme::Svg::Symbolic))
+                        .on_press(Message::RemoveAlarm(id))
+                        .style(theme::Button::Text),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        let mut days = row![].spacing(2);
+        for day in alarms::WEEKDAYS {
+            let label: String = weekday_name(day).chars().take(2).collect();
+            days = days.push(
+                button(text(label).size(12))
+                    .on_press(Message::ToggleAlarmDay(day))
+                    .style(if self.alarm_days.contains(&day) {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Text
+                    }),
+            );
+        }
+        content
+            .push(
+                row![
+                    text_input("HH:MM", &self.alarm_time_input)
+                        .on_input(Message::AlarmTimeInput)
+                        .on_submit(Message::AddAlarm)
+                        .size(14)
+                        .width(Length::Fixed(72.0)),
+                    text_input(&fl!("alarm-label"), &self.alarm_label_input)
+                        .on_input(Message::AlarmLabelInput)
+                        .on_submit(Message::AddAlarm)
+                        .size(14)
+                        .width(Length::Fill),
+                    text_button(fl!("add"), Message::AddAlarm),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            )
+            .push(days)
+            .into()
+    }
+
+    /// Ring the alarms due in this minute, and remind of the ones coming up
+    fn check_alarms(&mut self) -> Command<Message> {
+        let now = self.now.naive_local();
+        let Some(minute) = now.with_second(0).and_then(|t| t.with_nanosecond(0)) else {
+            return Command::none();
+        };
+        if self.alarms_checked == Some(minute) {
+            return Command::none();
+        }
+        self.alarms_checked = Some(minute);
+
+        let mut commands = Vec::new();
+        let time_format = format::time_format(self.twelve_hour());
+        let pre_alarm = self.config.alarm_settings.pre_alarm_minutes;
+        if pre_alarm > 0 {
+            let soon = minute + chrono::Duration::minutes(pre_alarm.into());
+            for alarm in self.config.alarms.iter().filter(|a| a.rings_at(soon)) {
+                commands.push(self.notify(
+                    fl!("alarm-soon", minutes = pre_alarm),
+                    alarm_summary(alarm, time_format),
+                ));
+            }
+        }
+        let mut due: Vec<u32> = self
+            .config
+            .alarms
+            .iter()
+            .filter(|a| a.rings_at(minute))
+            .map(|a| a.id)
+            .collect();
+        due.extend(
+            self.snoozed
+                .iter()
+                .filter(|(_, at)| *at <= now)
+                .map(|(id, _)| *id),
+        );
+        self.snoozed.retain(|(_, at)| *at > now);
+
+        // alarms without days ring once
+        let once: Vec<u32> = self
+            .config
+            .alarms
+            .iter()
+            .filter(|a| !a.is_recurring() && due.contains(&a.id) && a.enabled)
+            .map(|a| a.id)
+            .collect();
+        if !once.is_empty() {
+            if let Ok(config) = Config::new(config::APP_ID, 1) {
+                for id in once {
+                    self.config.set_alarm_enabled(id, false, &config);
+                }
+            }
+        }
+        for id in due {
+            commands.push(self.ring(id));
+        }
+        Command::batch(commands)
+    }
+
+    fn ring(&mut self, id: u32) -> Command<Message> {
+        let Some(alarm) = self.config.alarms.iter().find(|a| a.id == id) else {
+            return Command::none();
+        };
+        if self.ringing.iter().any(|r| r.alarm == id) {
+            return Command::none();
+        }
+        self.ringing.push(RingingAlarm {
+            alarm: id,
+            notification: None,
+        });
+        sound::play("alarm-clock-elapsed");
+        let summary = alarm_summary(alarm, format::time_format(self.twelve_hour()));
+        let snooze = fl!(
+            "snooze-minutes",
+            minutes = self.config.alarm_settings.snooze_minutes
+        );
+        cosmic::iced::Command::perform(
+            notification::notify_alarm(
+                fl!("alarm"),
+                summary,
+                vec![
+                    (alarms::SNOOZE_ACTION, snooze),
+                    (alarms::DISMISS_ACTION, fl!("dismiss")),
+                ],
+            ),
+            move |res| {
+                if let Err(err) = &res {
+                    eprintln!("Failed to send the alarm notification: {}", err);
+                }
+                app::message::app(Message::AlarmNotified(id, res.ok()))
+            },
+        )
+    }
+
+    /// Stop a ringing alarm, closing its notification unless that is already gone
+    fn stop_ringing(&mut self, id: u32) -> Command<Message> {
+        let Some(i) = self.ringing.iter().position(|r| r.alarm == id) else {
+            return Command::none();
+        };
+        match self.ringing.remove(i).notification {
+            Some(notification) => {
+                cosmic::iced::Command::perform(notification::close(notification), |_| {
+                    app::message::app(Message::Ignore)
+                })
+            }
+            None => Command::none(),
+        }
+    }
+
+    fn snooze_alarm(&mut self, id: u32) -> Command<Message> {
+        let minutes = self.config.alarm_settings.snooze_minutes.max(1);
+        self.snoozed.push((
+            id,
+            Local::now().naive_local() + chrono::Duration::minutes(minutes.into()),
+        ));
+        self.stop_ringing(id)
+    }
+
+    fn ntp_status(&self) -> Option<Element<Message>> {
+        let status = self.ntp?;
+        let (icon_name, label) = if !status.enabled {
+            ("emblem-important-symbolic", fl!("ntp-off"))
+        } else if status.synchronized {
+            ("emblem-ok-symbolic", fl!("ntp-synchronized"))
+        } else {
+            ("dialog-warning-symbolic", fl!("ntp-not-synchronized"))
+        };
+        let mut details = column![text(label).size(14)];
+        if let Some(last_sync) = status.last_sync {
+            let last_sync = DateTime::<Local>::from(last_sync);
+            let sync_time = last_sync.format(format::time_format(self.twelve_hour()));
+            let sync_time = if last_sync.date_naive() == self.now.date_naive() {
+                sync_time.to_string()
+            } else {
+                format!("{} {}", last_sync.format("%-d %b"), sync_time)
+            };
+            details = details.push(text(fl!("ntp-last-sync", time = sync_time)).size(12));
+        }
+        let mut status_row = row![
+            icon(icon_name, 16).style(theme::Svg::Symbolic),
+            details.width(Length::Fill),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+        if status.failed() || !status.enabled {
+            status_row = status_row.push(
+                button(text(fl!("date-time-settings")).size(14))
+                    .on_press(Message::OpenDateTimeSettings)
+                    .style(theme::Button::Text),
+            );
+        }
+        Some(status_row.into())
+    }
+
+    fn notify(&self, summary: String, body: String) -> Command<Message> {
+        cosmic::iced::Command::perform(notification::notify(summary, body), |_| {
+            app::message::app(Message::Ignore)
+        })
+    }
+
+    fn week_settings(&self) -> Element<Message> {
+        let options: Vec<_> = [
+            FirstDay::Locale,
+            FirstDay::Monday,
+            FirstDay::Saturday,
+            FirstDay::Sunday,
+        ]
+        .into_iter()
+        .map(FirstDayOption)
+        .collect();
+        column![
+            row![
+                text(fl!("first-day")).size(14).width(Length::Fill),
+                pick_list(
+                    options,
+                    Some(FirstDayOption(self.config.first_day)),
+                    Message::SelectFirstDay
+                )
+                .text_size(14),
+            ]
+            .align_items(Alignment::Center),
+            toggler(
+                fl!("week-numbers"),
+                self.config.show_week_numbers,
+                Message::ToggleWeekNumbers
+            )
+            .text_size(14)
+            .width(Length::Fill),
+        ]
+        .spacing(8)
+        .into()
+    }
+
+    fn format_settings(&self) -> Element<Message> {
+        let format_input = |placeholder: &str, input: &str, on_input: fn(String) -> Message| {
+            let preview = if input.trim().is_empty() {
+                format::format(&self.now, placeholder)
+            } else {
+                format::format(&self.now, input)
+            };
+            column![
+                text_input(placeholder, input)
+                    .on_input(on_input)
+                    .on_submit(Message::SubmitFormats)
+                    .size(14)
+                    .width(Length::Fill),
+                match preview {
+                    Some(preview) => text(preview).size(12),
+                    None => text(fl!("invalid-format"))
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+                },
+            ]
+            .spacing(4)
+        };
+        let hour_formats: Vec<_> = [
+            HourFormat::Locale,
+            HourFormat::TwelveHour,
+            HourFormat::TwentyFourHour,
+        ]
+        .into_iter()
+        .map(HourFormatOption)
+        .collect();
+        let mut settings = column![
+            row![
+                text(fl!("hour-format")).size(14).width(Length::Fill),
+                pick_list(
+                    hour_formats,
+                    Some(HourFormatOption(self.config.hour_format)),
+                    Message::SelectHourFormat
+                )
+                .text_size(14),
+            ]
+            .align_items(Alignment::Center),
+            toggler(
+                fl!("show-seconds"),
+                self.config.show_seconds,
+                Message::ToggleSeconds
+            )
+            .text_size(14)
+            .width(Length::Fill),
+            text(fl!("panel-format")).size(14),
+            format_input(
+                format::default_panel_format(self.twelve_hour(), self.config.show_seconds),
+                &self.panel_format_input,
+                Message::PanelFormatInput
+            ),
+            text(fl!("tooltip-format")).size(14),
+            format_input(
+                format::DEFAULT_TOOLTIP_FORMAT,
+                &self.tooltip_format_input,
+                Message::TooltipFormatInput
+            ),
+        ]
+        .spacing(8);
+        if matches!(
+            self.core.applet_helper.anchor,
+            PanelAnchor::Left | PanelAnchor::Right
+        ) {
+            let options: Vec<_> = [VerticalDate::Stacked, VerticalDate::OnHover]
+                .into_iter()
+                .map(VerticalDateOption)
+                .collect();
+            settings = settings.push(
+                row![
+                    text(fl!("vertical-date")).size(14).width(Length::Fill),
+                    pick_list(
+                        options,
+                        Some(VerticalDateOption(self.config.vertical_date)),
+                        Message::SelectVerticalDate
+                    )
+                    .text_size(14),
+                ]
+                .align_items(Alignment::Center),
+            );
+        }
+        settings.into()
+    }
+
+    /// Read the enabled calendars again, for the month shown and the upcoming days
+    fn refresh_events(&mut self) -> Command<Message> {
+        self.calendars = calendar::sources(&self.config.calendar_files);
+        let (from, until) = calendar::range(self.now.date_naive(), self.selected);
+        self.events_range = (from, until);
+        let paths = self
+            .calendars
+            .iter()
+            .filter(|s| {
+                !self
+                    .config
+                    .disabled_calendars
+                    .contains(s.path.to_string_lossy().as_ref())
+            })
+            .map(|s| s.path.clone())
+            .collect();
+        cosmic::iced::Command::perform(calendar::load(paths, from, until), |events| {
+            app::message::app(Message::EventsLoaded(events))
+        })
+    }
+
+    /// Select a day, ignoring moves past the dates chrono can represent
+    fn select(&mut self, date: Option<NaiveDate>) -> Command<Message> {
+        let Some(date) = date else {
+            return Command::none();
+        };
+        self.selected = date;
+        let mut commands = Vec::new();
+        let (from, until) = self.events_range;
+        if !(from..until).contains(&date) {
+            commands.push(self.refresh_events());
+        }
+        if self.ics_holidays_year != Some(date.year()) {
+            commands.push(self.refresh_ics_holidays());
+        }
+        Command::batch(commands)
+    }
+}
+
+fn check_ntp() -> Command<Message> {
+    cosmic::iced::Command::perform(ntp::status(), |status| {
+        if let Err(err) = &status {
+            eprintln!("Failed to read the time synchronization status: {}", err);
+        }
+        app::message::app(Message::NtpStatus(status.ok()))
+    })
+}
+
+/// The time and label of an alarm, e.g. `7:00 AM Wake up`
+fn alarm_summary(alarm: &alarms::Alarm, time_format: &str) -> String {
+    let time = alarm.time.format(time_format).to_string();
+    if alarm.label.is_empty() {
+        time
+    } else {
+        format!("{} {}", time, alarm.label)
+    }
+}
+
+fn weekday_name(day: Weekday) -> String {
+    match day {
+        Weekday::Mon => fl!("monday"),
+        Weekday::Tue => fl!("tuesday"),
+        Weekday::Wed => fl!("wednesday"),
+        Weekday::Thu => fl!("thursday"),
+        Weekday::Fri => fl!("friday"),
+        Weekday::Sat => fl!("saturday"),
+        Weekday::Sun => fl!("sunday"),
+    }
+}
+
+/// The difference to local time, e.g. `+9h` or `-3:30h`
+fn offset_label(minutes: i64) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.abs();
+    if minutes % 60 == 0 {
+        format!("{}{}h", sign, minutes / 60)
+    } else {
+        format!("{}{}:{:02}h", sign, minutes / 60, minutes % 60)
+    }
+}
+
+/// Today is filled, holidays are tinted and the selected day is outlined
+fn day_style(is_today: bool, is_selected: bool, is_holiday: bool) -> theme::Container {
+    theme::Container::custom(move |theme| {
+        let accent = theme.cosmic().accent_color();
+        cosmic::iced_style::container::Appearance {
+            text_color: Some(if is_today {
+                theme.cosmic().accent.on.into()
+            } else if is_holiday {
+                accent.into()
+            } else {
+                theme.cosmic().background.on.into()
+            }),
+            background: if is_today {
+                Some(Color::from(accent).into())
+            } else if is_holiday {
+                let mut tint = Color::from(accent);
+                tint.a = 0.15;
+                Some(tint.into())
+            } else {
+                None
+            },
+            border_radius: 18.0.into(),
+            border_width: if is_selected { 1.0 } else { 0.0 },
+            border_color: theme.cosmic().background.on.into(),
+        }
+    })
 }
 
 impl cosmic::Application for Time {
     type Message = Message;
     type Executor = cosmic::SingleThreadExecutor;
     type Flags = ();
-    const APP_ID: &'static str = "com.system76.CosmicAppletTime";
+    const APP_ID: &'static str = config::APP_ID;
 
     fn init(core: cosmic::app::Core, _flags: ()) -> (Self, app::Command<Message>) {
+        let config = TimeAppletConfig::load();
         (
             Time {
                 core,
@@ -58,11 +1223,36 @@ impl cosmic::Application for Time {
                 id_ctr: 0,
                 update_at: Every::Minute,
                 now: Local::now(),
-                msg: String::new(),
                 rectangle_tracker: None,
                 rectangle: Rectangle::default(),
+                holiday_checked: None,
+                selected: Local::now().date_naive(),
+                calendars: Vec::new(),
+                events: Vec::new(),
+                events_range: (NaiveDate::MIN, NaiveDate::MIN),
+                calendar_file_input: String::new(),
+                timezone_search: String::new(),
+                panel_format_input: config.panel_format.clone().unwrap_or_default(),
+                tooltip_format_input: config.tooltip_format.clone().unwrap_or_default(),
+                config,
+                timer: None,
+                timer_input: String::new(),
+                stopwatch: timer::Stopwatch::default(),
+                pomodoro: None,
+                pomodoro_stats: pomodoro::DailyStats::new(Local::now().date_naive()),
+                alarms_checked: None,
+                ringing: Vec::new(),
+                snoozed: Vec::new(),
+                alarm_time_input: String::new(),
+                alarm_label_input: String::new(),
+                alarm_days: Vec::new(),
+                ntp: None,
+                ics_holidays: Vec::new(),
+                ics_holidays_year: None,
+                holiday_file_input: config.holiday_file.clone().unwrap_or_default(),
+                settings_error: None,
             },
-            Command::none(),
+            check_ntp(),
         )
     }
 
@@ -87,19 +1277,68 @@ impl cosmic::Application for Time {
 
         // Calculate the time until next second/minute so we can sleep the thread until then.
         let now = Local::now().time();
-        let next = (now + update_delay)
-            .with_second(0)
-            .expect("Setting seconds to 0 should always be possible")
+        let mut next = now + update_delay;
+        if self.update_at == Every::Minute {
+            next = next
+                .with_second(0)
+                .expect("Setting seconds to 0 should always be possible");
+        }
+        let next = next
             .with_nanosecond(0)
             .expect("Setting nanoseconds to 0 should always be possible.");
         let wait = 1.max((next - now).num_milliseconds());
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             rectangle_tracker_subscription(0).map(|e| Message::Rectangle(e.1)),
             time::every(Duration::from_millis(
                 wait.try_into().unwrap_or(FALLBACK_DELAY),
             ))
             .map(|_| Message::Tick),
-        ])
+            time::every(Duration::from_secs(EVENTS_REFRESH_SECS)).map(|_| Message::RefreshEvents),
+            time::every(Duration::from_secs(NTP_CHECK_SECS)).map(|_| Message::CheckNtp),
+            cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                |(_, config)| match config {
+                    Ok(config) => Message::ConfigUpdated(config),
+                    Err((errors, config)) => {
+                        for error in errors {
+                            eprintln!("{:?}", error);
+                        }
+                        Message::ConfigUpdated(config)
+                    }
+                },
+            ),
+        ];
+        if self.timer.map_or(false, |t| t.is_running())
+            || self.pomodoro.map_or(false, |p| p.timer.is_running())
+            || self.popup.is_some() && self.stopwatch.is_running()
+        {
+            subscriptions.push(time::every(Duration::from_secs(1)).map(|_| Message::TimerTick));
+        }
+        if !self.config.alarms.is_empty() {
+            subscriptions.push(
+                notification::actions().map(|(id, action)| Message::NotificationAction(id, action)),
+            );
+        }
+        if self.popup.is_some() {
+            subscriptions.push(subscription::events_with(|e, status| match e {
+                // keys typed into the text inputs don't move the selection
+                cosmic::iced::Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. })
+                    if status == event::Status::Ignored =>
+                {
+                    match key_code {
+                        KeyCode::Left => Some(Message::MoveSelection(-1)),
+                        KeyCode::Right => Some(Message::MoveSelection(1)),
+                        KeyCode::Up => Some(Message::MoveSelection(-7)),
+                        KeyCode::Down => Some(Message::MoveSelection(7)),
+                        KeyCode::PageUp => Some(Message::PreviousMonth),
+                        KeyCode::PageDown => Some(Message::NextMonth),
+                        KeyCode::Home => Some(Message::JumpToToday),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
@@ -108,20 +1347,13 @@ impl cosmic::Application for Time {
                 if let Some(p) = self.popup.take() {
                     destroy_popup(p)
                 } else {
-                    use std::os::unix::process::ExitStatusExt;
-                    let calendar = std::str::from_utf8(
-                        &std::process::Command::new("happiness")
-                            .output()
-                            .unwrap_or(std::process::Output {
-                                stdout: "`sudo apt install happiness`".as_bytes().to_vec(),
-                                stderr: Vec::new(),
-                                status: std::process::ExitStatus::from_raw(0),
-                            })
-                            .stdout,
-                    )
-                    .unwrap()
-                    .to_string();
-                    self.msg = calendar;
+                    self.settings_error = None;
+                    self.selected = self.now.date_naive();
+                    let refresh = Command::batch(vec![
+                        self.refresh_events(),
+                        self.refresh_ics_holidays(),
+                        check_ntp(),
+                    ]);
                     self.id_ctr += 1;
                     let new_id = window::Id(self.id_ctr);
                     self.popup.replace(new_id);
@@ -145,17 +1377,18 @@ impl cosmic::Application for Time {
                         width: width as i32,
                         height: height as i32,
                     };
-                    get_popup(popup_settings)
+                    Command::batch(vec![refresh, get_popup(popup_settings)])
                 }
             }
             Message::Tick => {
                 self.now = Local::now();
-                Command::none()
+                Command::batch(vec![self.check_holiday_tomorrow(), self.check_alarms()])
             }
             Message::Rectangle(u) => {
                 match u {
                     RectangleUpdate::Rectangle(r) => {
                         self.rectangle = r.1;
+                        self.update_at = self.tick_interval();
                     }
                     RectangleUpdate::Init(tracker) => {
                         self.rectangle_tracker = Some(tracker);
@@ -163,38 +1396,460 @@ impl cosmic::Application for Time {
                 }
                 Command::none()
             }
+            Message::SelectRegion(region) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config
+                        .set_holiday_region(region.0.map(str::to_string), &config);
+                }
+                Command::none()
+            }
+            Message::ToggleHolidayNotifications(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_notify_holidays(enabled, &config);
+                }
+                self.check_holiday_tomorrow()
+            }
+            Message::ConfigUpdated(config) => {
+                let calendars_changed = self.config.calendar_files != config.calendar_files
+                    || self.config.disabled_calendars != config.disabled_calendars;
+                let holiday_file_changed = self.config.holiday_file != config.holiday_file;
+                self.config = config;
+                self.update_at = self.tick_interval();
+                let mut commands = Vec::new();
+                if calendars_changed {
+                    commands.push(self.refresh_events());
+                }
+                if holiday_file_changed {
+                    commands.push(self.refresh_ics_holidays());
+                }
+                Command::batch(commands)
+            }
+            Message::SelectDay(date) => self.select(Some(date)),
+            Message::MoveSelection(days) => self.select(
+                self.selected
+                    .checked_add_signed(chrono::Duration::days(days)),
+            ),
+            Message::PreviousMonth => self.select(self.selected.checked_sub_months(Months::new(1))),
+            Message::NextMonth => self.select(self.selected.checked_add_months(Months::new(1))),
+            Message::JumpToToday => self.select(Some(self.now.date_naive())),
+            Message::RefreshEvents => self.refresh_events(),
+            Message::EventsLoaded(events) => {
+                self.events = events;
+                Command::none()
+            }
+            Message::ToggleCalendar(path, enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_calendar_enabled(path, enabled, &config);
+                }
+                self.refresh_events()
+            }
+            Message::CalendarFileInput(input) => {
+                self.calendar_file_input = input;
+                Command::none()
+            }
+            Message::AddCalendarFile => {
+                let path = self.calendar_file_input.trim();
+                if path.is_empty() {
+                    return Command::none();
+                }
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_calendar_file(path.to_string(), &config);
+                }
+                self.calendar_file_input.clear();
+                self.refresh_events()
+            }
+            Message::RemoveCalendarFile(path) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.remove_calendar_file(&path, &config);
+                }
+                self.refresh_events()
+            }
+            Message::TimezoneSearch(search) => {
+                self.timezone_search = search;
+                Command::none()
+            }
+            Message::AddWorldClock(timezone) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_world_clock(timezone, &config);
+                }
+                self.timezone_search.clear();
+                Command::none()
+            }
+            Message::RemoveWorldClock(timezone) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.remove_world_clock(&timezone, &config);
+                }
+                Command::none()
+            }
+            Message::ToggleWorldClocksInPanel(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_world_clocks_in_panel(enabled, &config);
+                }
+                Command::none()
+            }
+            Message::PanelFormatInput(input) => {
+                self.panel_format_input = input;
+                Command::none()
+            }
+            Message::TooltipFormatInput(input) => {
+                self.tooltip_format_input = input;
+                Command::none()
+            }
+            Message::SubmitFormats => {
+                // invalid formats stay in the inputs with their error, the config keeps the last
+                // valid one
+                let parse = |input: &str| {
+                    let input = input.trim();
+                    if input.is_empty() {
+                        Some(None)
+                    } else {
+                        format::format(&self.now, input).map(|_| Some(input.to_string()))
+                    }
+                };
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    if let Some(panel_format) = parse(&self.panel_format_input) {
+                        self.config.set_panel_format(panel_format, &config);
+                    }
+                    if let Some(tooltip_format) = parse(&self.tooltip_format_input) {
+                        self.config.set_tooltip_format(tooltip_format, &config);
+                    }
+                }
+                Command::none()
+            }
+            Message::ToggleSeconds(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_seconds(enabled, &config);
+                }
+                self.update_at = self.tick_interval();
+                Command::none()
+            }
+            Message::SelectVerticalDate(vertical_date) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_vertical_date(vertical_date.0, &config);
+                }
+                Command::none()
+            }
+            Message::SelectFirstDay(first_day) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_first_day(first_day.0, &config);
+                }
+                Command::none()
+            }
+            Message::SelectHourFormat(hour_format) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_hour_format(hour_format.0, &config);
+                }
+                Command::none()
+            }
+            Message::ToggleWeekNumbers(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_show_week_numbers(enabled, &config);
+                }
+                Command::none()
+            }
+            Message::StartTimer(duration) => {
+                self.timer = Some(timer::Timer::start(duration));
+                Command::none()
+            }
+            Message::TimerInput(input) => {
+                self.timer_input = input;
+                Command::none()
+            }
+            Message::SubmitTimer => {
+                if let Some(duration) = timer::parse_duration(&self.timer_input) {
+                    self.timer = Some(timer::Timer::start(duration));
+                    self.timer_input.clear();
+                }
+                Command::none()
+            }
+            Message::PauseTimer => {
+                if let Some(timer) = self.timer.as_mut() {
+                    timer.pause();
+                }
+                Command::none()
+            }
+            Message::ResumeTimer => {
+                if let Some(timer) = self.timer.as_mut() {
+                    timer.resume();
+                }
+                Command::none()
+            }
+            Message::CancelTimer => {
+                self.timer = None;
+                Command::none()
+            }
+            Message::StartStopwatch => {
+                self.stopwatch.start();
+                Command::none()
+            }
+            Message::PauseStopwatch => {
+                self.stopwatch.pause();
+                Command::none()
+            }
+            Message::ResetStopwatch => {
+                self.stopwatch.reset();
+                Command::none()
+            }
+            Message::AlarmTimeInput(input) => {
+                self.alarm_time_input = input;
+                Command::none()
+            }
+            Message::AlarmLabelInput(input) => {
+                self.alarm_label_input = input;
+                Command::none()
+            }
+            Message::ToggleAlarmDay(day) => {
+                if let Some(i) = self.alarm_days.iter().position(|d| *d == day) {
+                    self.alarm_days.remove(i);
+                } else {
+                    self.alarm_days.push(day);
+                }
+                Command::none()
+            }
+            Message::AddAlarm => {
+                let Some(time) = alarms::parse_time(&self.alarm_time_input) else {
+                    return Command::none();
+                };
+                let alarm = alarms::Alarm {
+                    id: 0,
+                    time,
+                    label: self.alarm_label_input.trim().to_string(),
+                    days: std::mem::take(&mut self.alarm_days),
+                    enabled: true,
+                };
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.add_alarm(alarm, &config);
+                }
+                self.alarm_time_input.clear();
+                self.alarm_label_input.clear();
+                Command::none()
+            }
+            Message::RemoveAlarm(id) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.remove_alarm(id, &config);
+                }
+                self.snoozed.retain(|(alarm, _)| *alarm != id);
+                self.stop_ringing(id)
+            }
+            Message::ToggleAlarm(id, enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_alarm_enabled(id, enabled, &config);
+                }
+                Command::none()
+            }
+            Message::AlarmNotified(id, notification) => {
+                match self.ringing.iter_mut().find(|r| r.alarm == id) {
+                    Some(ringing) => ringing.notification = notification,
+                    // stopped from the popup before the notification was sent
+                    None => {
+                        if let Some(notification) = notification {
+                            return cosmic::iced::Command::perform(
+                                notification::close(notification),
+                                |_| app::message::app(Message::Ignore),
+                            );
+                        }
+                    }
+                }
+                Command::none()
+            }
+            Message::NotificationAction(notification, action) => {
+                let Some(ringing) = self
+                    .ringing
+                    .iter()
+                    .find(|r| r.notification == Some(notification))
+                else {
+                    return Command::none();
+                };
+                let id = ringing.alarm;
+                if action == alarms::SNOOZE_ACTION {
+                    self.snooze_alarm(id)
+                } else {
+                    self.stop_ringing(id)
+                }
+            }
+            Message::DismissAlarm(id) => self.stop_ringing(id),
+            Message::SnoozeAlarm(id) => self.snooze_alarm(id),
+            Message::TogglePomodoro(enabled) => {
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_pomodoro_enabled(enabled, &config);
+                }
+                if !enabled {
+                    self.pomodoro = None;
+                }
+                Command::none()
+            }
+            Message::StartPomodoro => {
+                self.pomodoro = Some(pomodoro::Pomodoro::start(&self.config.pomodoro));
+                Command::none()
+            }
+            Message::PausePomodoro => {
+                if let Some(pomodoro) = self.pomodoro.as_mut() {
+                    pomodoro.timer.pause();
+                }
+                Command::none()
+            }
+            Message::ResumePomodoro => {
+                if let Some(pomodoro) = self.pomodoro.as_mut() {
+                    pomodoro.timer.resume();
+                }
+                Command::none()
+            }
+            Message::SkipPomodoroPhase => {
+                if let Some(pomodoro) = self.pomodoro.as_mut() {
+                    pomodoro.advance(&self.config.pomodoro);
+                }
+                Command::none()
+            }
+            Message::StopPomodoro => {
+                self.pomodoro = None;
+                Command::none()
+            }
+            Message::IcsHolidaysLoaded(holidays) => {
+                self.ics_holidays = holidays;
+                Command::none()
+            }
+            Message::HolidayFileInput(input) => {
+                self.holiday_file_input = input;
+                Command::none()
+            }
+            Message::SubmitHolidayFile => {
+                let file = self.holiday_file_input.trim();
+                let file = (!file.is_empty()).then(|| file.to_string());
+                if let Ok(config) = Config::new(config::APP_ID, 1) {
+                    self.config.set_holiday_file(file, &config);
+                }
+                self.refresh_ics_holidays()
+            }
+            Message::CheckNtp => check_ntp(),
+            Message::NtpStatus(status) => {
+                self.ntp = status;
+                Command::none()
+            }
+            Message::OpenDateTimeSettings => match settings::open_date_time() {
+                Ok(()) => {
+                    self.settings_error = None;
+                    match self.popup.take() {
+                        Some(popup) => destroy_popup(popup),
+                        None => Command::none(),
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Failed to open the date and time settings: {}", err);
+                    self.settings_error = Some(if err.kind() == std::io::ErrorKind::NotFound {
+                        fl!("settings-missing")
+                    } else {
+                        fl!("settings-failed", error = err.to_string())
+                    });
+                    Command::none()
+                }
+            },
+            Message::TimerTick => {
+                let mut commands = Vec::new();
+                if let Some(timer) = self.timer.filter(timer::Timer::is_done) {
+                    self.timer = None;
+                    sound::play("alarm-clock-elapsed");
+                    commands.push(self.notify(
+                        fl!("timer-done"),
+                        fl!(
+                            "timer-done-body",
+                            duration = timer::format_duration(timer.duration)
+                        ),
+                    ));
+                }
+                if let Some(mut pomodoro) = self.pomodoro.filter(|p| p.timer.is_done()) {
+                    if pomodoro.phase == pomodoro::Phase::Work {
+                        self.pomodoro_stats
+                            .record(Local::now().date_naive(), pomodoro.timer.duration);
+                    }
+                    pomodoro.advance(&self.config.pomodoro);
+                    self.pomodoro = Some(pomodoro);
+                    let minutes = pomodoro.timer.duration.as_secs() / 60;
+                    commands.push(match pomodoro.phase {
+                        pomodoro::Phase::Work => self.notify(
+                            fl!("pomodoro-focus"),
+                            fl!("pomodoro-focus-body", minutes = minutes),
+                        ),
+                        pomodoro::Phase::ShortBreak | pomodoro::Phase::LongBreak => self.notify(
+                            fl!("pomodoro-break"),
+                            fl!("pomodoro-break-body", minutes = minutes),
+                        ),
+                    });
+                }
+                Command::batch(commands)
+            }
+            Message::Ignore => Command::none(),
         }
     }
 
     fn view(&self) -> Element<Message> {
+        let twelve_hour = self.twelve_hour();
         let button = button(
             if matches!(
                 self.core.applet_helper.anchor,
                 PanelAnchor::Top | PanelAnchor::Bottom
             ) {
-                column![text(self.now.format("%b %-d %-I:%M %p").to_string()).size(14)]
+                let mut label = format::format_or(
+                    &self.now,
+                    self.config.panel_format.as_deref(),
+                    format::default_panel_format(self.twelve_hour(), self.config.show_seconds),
+                );
+                for clock in self.panel_world_clocks() {
+                    label.push_str(" · ");
+                    label.push_str(&clock);
+                }
+                let mut label_row = row![].spacing(4).align_items(Alignment::Center);
+                if let Some(pomodoro) = &self.pomodoro {
+                    label_row = label_row.push(
+                        svg(pomodoro::ring_icon(pomodoro.progress()))
+                            .width(Length::Fixed(16.0))
+                            .height(Length::Fixed(16.0))
+                            .style(theme::Svg::Symbolic),
+                    );
+                }
+                label_row = label_row.push(text(label).size(14));
+                if self.ntp.map_or(false, |s| s.failed()) {
+                    label_row = label_row
+                        .push(icon("dialog-warning-symbolic", 16).style(theme::Svg::Symbolic));
+                }
+                column![label_row]
             } else {
+                let hour = if twelve_hour { "%I" } else { "%H" };
+                let icon_size = self.core.applet_helper.suggested_size().0;
+                let clock_icon: Element<_> = match &self.pomodoro {
+                    Some(pomodoro) => svg(pomodoro::ring_icon(pomodoro.progress()))
+                        .width(Length::Fixed(icon_size.into()))
+                        .height(Length::Fixed(icon_size.into()))
+                        .style(theme::Svg::Symbolic)
+                        .into(),
+                    None => icon("emoji-recent-symbolic", icon_size)
+                        .style(theme::Svg::Symbolic)
+                        .into(),
+                };
                 let mut date_time_col = column![
-                    icon(
-                        "emoji-recent-symbolic",
-                        self.core.applet_helper.suggested_size().0
-                    )
-                    .style(theme::Svg::Symbolic),
-                    text(self.now.format("%I").to_string()).size(14),
+                    clock_icon,
+                    text(self.now.format(hour).to_string()).size(14),
                     text(self.now.format("%M").to_string()).size(14),
-                    text(self.now.format("%p").to_string()).size(14),
-                    vertical_space(Length::Fixed(4.0)),
-                    // TODO better calendar icon?
-                    icon(
-                        "calendar-go-today-symbolic",
-                        self.core.applet_helper.suggested_size().0
-                    )
-                    .style(theme::Svg::Symbolic),
                 ]
                 .align_items(Alignment::Center)
                 .spacing(4);
-                for d in self.now.format("%x").to_string().split("/") {
-                    date_time_col = date_time_col.push(text(d.to_string()).size(14));
+                if self.config.show_seconds {
+                    date_time_col =
+                        date_time_col.push(text(self.now.format("%S").to_string()).size(14));
+                }
+                if twelve_hour {
+                    date_time_col =
+                        date_time_col.push(text(self.now.format("%p").to_string()).size(14));
+                }
+                if self.ntp.map_or(false, |s| s.failed()) {
+                    date_time_col = date_time_col
+                        .push(icon("dialog-warning-symbolic", 16).style(theme::Svg::Symbolic));
+                }
+                // the date is stacked as day and month, as a locale's full date doesn't fit
+                if self.config.vertical_date == VerticalDate::Stacked {
+                    date_time_col = date_time_col
+                        .push(vertical_space(Length::Fixed(4.0)))
+                        .push(text(self.now.format("%-d").to_string()).size(14))
+                        .push(text(self.now.format("%b").to_string()).size(12));
                 }
                 date_time_col
             },
@@ -202,20 +1857,111 @@ impl cosmic::Application for Time {
         .on_press(Message::TogglePopup)
         .style(theme::Button::Text);
 
-        if let Some(tracker) = self.rectangle_tracker.as_ref() {
+        let button: Element<_> = if let Some(tracker) = self.rectangle_tracker.as_ref() {
             tracker.container(0, button).into()
         } else {
             button.into()
-        }
+        };
+        let position = match self.core.applet_helper.anchor {
+            PanelAnchor::Top => tooltip::Position::Bottom,
+            PanelAnchor::Bottom => tooltip::Position::Top,
+            PanelAnchor::Left => tooltip::Position::Right,
+            PanelAnchor::Right => tooltip::Position::Left,
+        };
+        let tooltip_label = format::format_or(
+            &self.now,
+            self.config.tooltip_format.as_deref(),
+            format::DEFAULT_TOOLTIP_FORMAT,
+        );
+        tooltip(button, tooltip_label, position).into()
     }
 
     fn view_window(&self, _id: window::Id) -> Element<Message> {
-        let content = column![]
+        let selected =
+            Region(holidays::regions().find(|r| self.config.holiday_region.as_deref() == Some(*r)));
+        let regions: Vec<Region> = std::iter::once(Region(None))
+            .chain(holidays::regions().map(|r| Region(Some(r))))
+            .collect();
+        let mut holiday_settings = column![row![
+            text(fl!("holidays")).size(14).width(Length::Fill),
+            pick_list(regions, Some(selected), Message::SelectRegion).text_size(14),
+        ]
+        .align_items(Alignment::Center)]
+        .spacing(8)
+        .push(
+            text_input(&fl!("holiday-file"), &self.holiday_file_input)
+                .on_input(Message::HolidayFileInput)
+                .on_submit(Message::SubmitHolidayFile)
+                .size(14)
+                .width(Length::Fill),
+        );
+        if selected.0.is_some() || self.config.holiday_file.is_some() {
+            holiday_settings = holiday_settings.push(
+                toggler(
+                    fl!("notify-holidays"),
+                    self.config.notify_holidays,
+                    Message::ToggleHolidayNotifications,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            );
+        }
+        let mut content = column![]
             .align_items(Alignment::Start)
             .spacing(12)
             .padding([24, 0])
-            .push(text(&self.msg).size(14))
-            .padding(8);
+            .push(self.calendar())
+            .push(divider::horizontal::light())
+            .push(self.events())
+            .push(divider::horizontal::light())
+            .push(self.world_clocks())
+            .push(divider::horizontal::light())
+            .push(self.timers())
+            .push(divider::horizontal::light())
+            .push(self.alarms())
+            .push(divider::horizontal::light());
+        if self.config.pomodoro_enabled {
+            content = content
+                .push(self.pomodoro())
+                .push(divider::horizontal::light());
+        }
+        if let Some(ntp_status) = self.ntp_status() {
+            content = content.push(ntp_status).push(divider::horizontal::light());
+        }
+        let content = content
+            .push(
+                toggler(
+                    fl!("pomodoro-setting"),
+                    self.config.pomodoro_enabled,
+                    Message::TogglePomodoro,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            )
+            .push(self.week_settings())
+            .push(holiday_settings)
+            .push(self.calendar_settings())
+            .push(self.format_settings())
+            .push(divider::horizontal::light())
+            .push(
+                button(applet_button_theme())
+                    .custom(vec![text(fl!("date-time-settings"))
+                        .size(14)
+                        .width(Length::Fill)
+                        .into()])
+                    .on_press(Message::OpenDateTimeSettings)
+                    .width(Length::Fill)
+                    .padding([8, 24]),
+            );
+        let content = match &self.settings_error {
+            Some(error) => content.push(
+                text(error)
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+            ),
+            None => content,
+        }
+        .padding(8);
 
         self.core.applet_helper.popup_container(content).into()
     }
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Desktop notifications sent by the applet
+
+use cosmic::iced::{
+    futures::{self, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use std::collections::HashMap;
+use zbus::{dbus_proxy, zvariant::Value, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    /// Notify method
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, zbus::zvariant::Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+}
+
+pub async fn notify(summary: String, body: String) -> zbus::Result<u32> {
+    let conn = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&conn).await?;
+    proxy
+        .notify(
+            &crate::fl!("time"),
+            0,
+            "com.system76.CosmicAppletTime",
+            &summary,
+            &body,
+            &[],
+            HashMap::new(),
+            -1,
+        )
+        .await
+}
+
+/// A critical notification that stays until one of its actions, given as key and label pairs,
+/// is invoked
+pub async fn notify_alarm(
+    summary: String,
+    body: String,
+    actions: Vec<(&'static str, String)>,
+) -> zbus::Result<u32> {
+    let conn = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&conn).await?;
+    let actions: Vec<&str> = actions
+        .iter()
+        .flat_map(|(key, label)| [*key, label.as_str()])
+        .collect();
+    let hints = HashMap::from([("urgency", Value::U8(2)), ("resident", Value::Bool(true))]);
+    proxy
+        .notify(
+            &crate::fl!("time"),
+            0,
+            "com.system76.CosmicAppletTime",
+            &summary,
+            &body,
+            &actions,
+            hints,
+            0,
+        )
+        .await
+}
+
+pub async fn close(id: u32) -> zbus::Result<()> {
+    let conn = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&conn).await?;
+    proxy.close_notification(id).await
+}
+
+/// Actions invoked on notifications, as notification id and action key
+pub fn actions() -> Subscription<(u32, String)> {
+    struct ActionsWorker;
+
+    subscription::channel(
+        std::any::TypeId::of::<ActionsWorker>(),
+        10,
+        |mut output| async move {
+            let res: zbus::Result<()> = async {
+                let conn = Connection::session().await?;
+                let proxy = NotificationsProxy::new(&conn).await?;
+                let mut actions = proxy.receive_action_invoked().await?;
+                while let Some(signal) = actions.next().await {
+                    let args = signal.args()?;
+                    _ = output
+                        .send((*args.id(), args.action_key().to_string()))
+                        .await;
+                }
+                Ok(())
+            }
+            .await;
+            if let Err(err) = res {
+                eprintln!("Failed to listen for notification actions: {}", err);
+            }
+            futures::future::pending().await
+        },
+    )
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Network time synchronization status from systemd-timedated and systemd-timesyncd
+
+use std::time::SystemTime;
+use zbus::{dbus_proxy, CacheProperties, Connection};
+
+/// Touched by systemd-timesyncd on every successful synchronization
+const TIMESYNCD_CLOCK: &str = "/var/lib/systemd/timesync/clock";
+
+#[dbus_proxy(
+    interface = "org.freedesktop.timedate1",
+    default_service = "org.freedesktop.timedate1",
+    default_path = "/org/freedesktop/timedate1"
+)]
+trait Timedate {
+    #[dbus_proxy(property, name = "NTP")]
+    fn ntp(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property, name = "NTPSynchronized")]
+    fn ntp_synchronized(&self) -> zbus::Result<bool>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// Network time synchronization is turned on
+    pub enabled: bool,
+    pub synchronized: bool,
+    pub last_sync: Option<SystemTime>,
+}
+
+impl Status {
+    /// Synchronization is on but the clock isn't synchronized
+    pub fn failed(&self) -> bool {
+        self.enabled && !self.synchronized
+    }
+}
+
+pub async fn status() -> zbus::Result<Status> {
+    let conn = Connection::system().await?;
+    // timedated doesn't signal changes of NTPSynchronized, so it is read fresh every time
+    let proxy = TimedateProxy::builder(&conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(Status {
+        enabled: proxy.ntp().await?,
+        synchronized: proxy.ntp_synchronized().await?,
+        last_sync: std::fs::metadata(TIMESYNCD_CLOCK)
+            .and_then(|m| m.modified())
+            .ok(),
+    })
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Pomodoro focus timer: work sessions alternating with short breaks, and a long break after
+//! every few sessions
+
+use crate::{config::PomodoroSettings, timer::Timer};
+use chrono::NaiveDate;
+use cosmic::iced::widget::svg;
+use std::{f32::consts::PI, time::Duration};
+
+/// Steps of the progress ring, so the panel icon is only redrawn this many times per phase
+const RING_STEPS: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pomodoro {
+    pub phase: Phase,
+    pub timer: Timer,
+    /// Work sessions finished since the last long break
+    sessions: u32,
+}
+
+impl Pomodoro {
+    pub fn start(settings: &PomodoroSettings) -> Self {
+        Self {
+            phase: Phase::Work,
+            timer: Timer::start(minutes(settings.work_minutes)),
+            sessions: 0,
+        }
+    }
+
+    /// Start the next phase
+    pub fn advance(&mut self, settings: &PomodoroSettings) {
+        let (phase, duration) = match self.phase {
+            Phase::Work => {
+                self.sessions += 1;
+                if self.sessions >= settings.long_break_every.max(1) {
+                    self.sessions = 0;
+                    (Phase::LongBreak, settings.long_break_minutes)
+                } else {
+                    (Phase::ShortBreak, settings.short_break_minutes)
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => (Phase::Work, settings.work_minutes),
+        };
+        self.phase = phase;
+        self.timer = Timer::start(minutes(duration));
+    }
+
+    /// Part of the phase that has passed, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        let total = self.timer.duration.as_secs_f32();
+        if total == 0.0 {
+            return 1.0;
+        }
+        1.0 - self.timer.remaining().as_secs_f32() / total
+    }
+}
+
+fn minutes(minutes: u32) -> Duration {
+    Duration::from_secs(u64::from(minutes.max(1)) * 60)
+}
+
+/// Focus sessions finished today
+#[derive(Debug, Clone, Copy)]
+pub struct DailyStats {
+    pub date: NaiveDate,
+    pub sessions: u32,
+    pub focus: Duration,
+}
+
+impl DailyStats {
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            sessions: 0,
+            focus: Duration::ZERO,
+        }
+    }
+
+    /// The stats of the given day, starting over when the day changed
+    pub fn on(&self, date: NaiveDate) -> Self {
+        if self.date == date {
+            *self
+        } else {
+            Self::new(date)
+        }
+    }
+
+    pub fn record(&mut self, date: NaiveDate, session: Duration) {
+        *self = self.on(date);
+        self.sessions += 1;
+        self.focus += session;
+    }
+}
+
+/// A clock face with the progress of the phase drawn as a ring around it
+pub fn ring_icon(progress: f32) -> svg::Handle {
+    let progress = (progress.clamp(0.0, 1.0) * RING_STEPS).round() / RING_STEPS;
+    let circumference = 2.0 * PI * 7.0;
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 16 16">
+<circle cx="8" cy="8" r="7" fill="none" stroke="#000" stroke-width="1.5" opacity="0.3"/>
+<circle cx="8" cy="8" r="7" fill="none" stroke="#000" stroke-width="1.5"
+ stroke-dasharray="{dash:.2} {circumference:.2}" transform="rotate(-90 8 8)"/>
+<path d="M8 4.5V8l2.5 1.5" fill="none" stroke="#000" stroke-width="1.5" stroke-linecap="round"/>
+</svg>"#,
+        dash = progress * circumference,
+    );
+    svg::Handle::from_memory(svg.into_bytes())
+}
@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Conventions of the time locale's territory, for the settings that follow the locale
+
+/// Territories that prefer a 12-hour clock, from the Unicode CLDR
+const TWELVE_HOUR_TERRITORIES: &[&str] = &[
+    "AU", "BD", "CA", "CO", "EG", "IN", "JO", "KR", "MX", "MY", "NZ", "PH", "PK", "SA", "SG", "TW",
+    "US",
+];
+
+/// The time locale in the environment, e.g. `en_US.UTF-8`
+pub fn time_locale() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|locale| !locale.is_empty())
+        .unwrap_or_default()
+}
+
+/// The territory of a POSIX locale name, e.g. `US` for `en_US.UTF-8`
+pub fn territory(locale: &str) -> Option<String> {
+    locale
+        .split(['.', '@'])
+        .next()
+        .and_then(|locale| locale.split_once('_'))
+        .map(|(_, territory)| territory.to_ascii_uppercase())
+}
+
+/// `true` if the time locale prefers a 12-hour clock
+pub fn locale_twelve_hour() -> bool {
+    territory(&time_locale()).map_or(false, |t| TWELVE_HOUR_TERRITORIES.contains(&t.as_str()))
+}
@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Pages of COSMIC Settings
+
+use std::process::Command;
+
+/// Open the Date & Time page of COSMIC Settings. Errors starting the process are returned,
+/// a failed exit is only logged as the applet doesn't wait for it.
+pub fn open_date_time() -> std::io::Result<()> {
+    let mut child = Command::new("cosmic-settings").arg("time").spawn()?;
+    // reap the process once it exits, so it doesn't stay behind as a zombie
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => {
+            eprintln!("cosmic-settings exited with {}", status);
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("Failed to wait for cosmic-settings: {}", err),
+    });
+    Ok(())
+}
@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Event sounds of the freedesktop sound theme
+
+use std::process::Command;
+
+/// Play a sound theme event, e.g. `alarm-clock-elapsed`, without waiting for it to finish
+pub fn play(id: &'static str) {
+    std::thread::spawn(move || {
+        if let Err(err) = Command::new("canberra-gtk-play")
+            .arg(format!("--id={id}"))
+            .status()
+        {
+            eprintln!("Failed to play sound {}: {}", id, err);
+        }
+    });
+}
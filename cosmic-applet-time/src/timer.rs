@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Countdown timer and stopwatch, measured with the monotonic clock so they keep counting
+//! independently of the popup and of changes to the system time
+
+use std::time::{Duration, Instant};
+
+/// Timer presets offered in the popup, in minutes
+pub const TIMER_PRESETS: [u64; 5] = [1, 5, 10, 15, 30];
+
+#[derive(Debug, Clone, Copy)]
+enum TimerState {
+    Running { deadline: Instant },
+    Paused { remaining: Duration },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timer {
+    pub duration: Duration,
+    state: TimerState,
+}
+
+impl Timer {
+    pub fn start(duration: Duration) -> Self {
+        Self {
+            duration,
+            state: TimerState::Running {
+                deadline: Instant::now() + duration,
+            },
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        match self.state {
+            TimerState::Running { deadline } => deadline.saturating_duration_since(Instant::now()),
+            TimerState::Paused { remaining } => remaining,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, TimerState::Running { .. })
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.is_running() && self.remaining().is_zero()
+    }
+
+    pub fn pause(&mut self) {
+        self.state = TimerState::Paused {
+            remaining: self.remaining(),
+        };
+    }
+
+    pub fn resume(&mut self) {
+        self.state = TimerState::Running {
+            deadline: Instant::now() + self.remaining(),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stopwatch {
+    /// Time counted before the last start
+    elapsed: Duration,
+    started: Option<Instant>,
+}
+
+impl Stopwatch {
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+            + self
+                .started
+                .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    pub fn pause(&mut self) {
+        self.elapsed = self.elapsed();
+        self.started = None;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A duration typed as minutes, `MM:SS` or `H:MM:SS`
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut secs = 0;
+    let parts: Vec<_> = input.trim().split(':').collect();
+    if parts.len() == 1 {
+        return Some(Duration::from_secs(
+            parts[0].parse::<u64>().ok()?.checked_mul(60)?,
+        ))
+        .filter(|d| !d.is_zero());
+    }
+    if parts.len() > 3 {
+        return None;
+    }
+    for part in parts {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(secs)).filter(|d| !d.is_zero())
+}
+
+/// `M:SS`, or `H:MM:SS` from an hour on
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
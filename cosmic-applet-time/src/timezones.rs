@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Time zones for the world clocks, from the IANA database bundled by chrono-tz
+
+use chrono_tz::{Tz, TZ_VARIANTS};
+
+/// Suggestions shown for a search in the time zone picker
+const MAX_MATCHES: usize = 8;
+
+pub fn parse(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// The last part of the zone name, e.g. `New York` for `America/New_York`
+pub fn city(name: &str) -> String {
+    name.rsplit('/').next().unwrap_or(name).replace('_', " ")
+}
+
+/// Zones whose name contains the query, ignoring case, with spaces matching underscores
+pub fn search(query: &str) -> Vec<&'static str> {
+    let query = query.trim().to_lowercase().replace(' ', "_");
+    if query.is_empty() {
+        return Vec::new();
+    }
+    TZ_VARIANTS
+        .iter()
+        .map(|tz| tz.name())
+        .filter(|name| name.to_lowercase().contains(&query))
+        .take(MAX_MATCHES)
+        .collect()
+}
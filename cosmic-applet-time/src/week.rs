@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The first day of the week and week numbers
+
+use crate::region;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// Territories whose weeks start on Sunday or Saturday, from the Unicode CLDR.
+/// All others start on Monday, as ISO 8601 weeks do.
+const SUNDAY_TERRITORIES: &[&str] = &[
+    "AG", "AS", "BD", "BR", "BS", "BT", "BW", "BZ", "CA", "CN", "CO", "DM", "DO", "ET", "GT", "GU",
+    "HK", "HN", "ID", "IL", "IN", "JM", "JP", "KE", "KH", "KR", "LA", "MH", "MM", "MO", "MT", "MX",
+    "MZ", "NI", "NP", "PA", "PE", "PH", "PK", "PR", "PT", "PY", "SA", "SG", "SV", "TH", "TT", "TW",
+    "UM", "US", "VE", "VI", "WS", "YE", "ZA", "ZW",
+];
+const SATURDAY_TERRITORIES: &[&str] = &[
+    "AE", "AF", "BH", "DJ", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA", "SD", "SY",
+];
+
+/// The first day of the week of the time locale in the environment
+pub fn locale_first_day() -> Weekday {
+    first_day_for_locale(&region::time_locale())
+}
+
+/// The first day of the week for a POSIX locale name like `en_US.UTF-8`
+pub fn first_day_for_locale(locale: &str) -> Weekday {
+    match region::territory(locale).as_deref() {
+        Some(t) if SUNDAY_TERRITORIES.contains(&t) => Weekday::Sun,
+        Some(t) if SATURDAY_TERRITORIES.contains(&t) => Weekday::Sat,
+        _ => Weekday::Mon,
+    }
+}
+
+/// The first day of the week that contains the date
+pub fn week_start(date: NaiveDate, first_day: Weekday) -> NaiveDate {
+    let days = (7 + date.weekday().num_days_from_monday() - first_day.num_days_from_monday()) % 7;
+    date - Duration::days(days.into())
+}
+
+/// The ISO 8601 week number of a row of the calendar. Rows that don't start on Monday take the
+/// number of the ISO week most of their days are in, the one of their Monday.
+pub fn week_number(week_start: NaiveDate) -> u32 {
+    let to_monday = (7 - week_start.weekday().num_days_from_monday()) % 7;
+    (week_start + Duration::days(to_monday.into()))
+        .iso_week()
+        .week()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn first_day_follows_territory() {
+        assert_eq!(first_day_for_locale("en_US.UTF-8"), Weekday::Sun);
+        assert_eq!(first_day_for_locale("de_DE.UTF-8"), Weekday::Mon);
+        assert_eq!(first_day_for_locale("ar_EG.UTF-8"), Weekday::Sat);
+        // no territory
+        assert_eq!(first_day_for_locale("C"), Weekday::Mon);
+    }
+
+    #[test]
+    fn week_start_goes_back_to_first_day() {
+        // Thursday
+        let day = date(2020, 12, 31);
+        assert_eq!(week_start(day, Weekday::Mon), date(2020, 12, 28));
+        assert_eq!(week_start(day, Weekday::Sun), date(2020, 12, 27));
+        assert_eq!(week_start(day, Weekday::Sat), date(2020, 12, 26));
+        // the first day itself starts the week
+        assert_eq!(week_start(date(2021, 1, 4), Weekday::Mon), date(2021, 1, 4));
+    }
+
+    #[test]
+    fn iso_week_numbers_across_the_year_end() {
+        let iso = |day| week_number(week_start(day, Weekday::Mon));
+        assert_eq!(iso(date(2020, 12, 31)), 53);
+        assert_eq!(iso(date(2021, 1, 3)), 53);
+        assert_eq!(iso(date(2021, 1, 4)), 1);
+    }
+
+    #[test]
+    fn rows_take_the_week_of_their_monday() {
+        // a Sunday row starting on 2021-01-03 is mostly in ISO week 1
+        assert_eq!(week_number(week_start(date(2021, 1, 3), Weekday::Sun)), 1);
+        // a Saturday row starting on 2020-12-26 has its Monday on 2020-12-28
+        assert_eq!(
+            week_number(week_start(date(2020, 12, 31), Weekday::Sat)),
+            53
+        );
+    }
+}
@@ -1 +1,6 @@
 cosmic-applet-workspaces = Cosmic Workspaces
+switch-workspace = Type a workspace number or name
+no-matching-workspaces = No matching workspaces
+no-windows = No windows
+remove-workspace = Remove workspace
+overview = Overview
@@ -1,19 +1,35 @@
 use cctk::sctk::reexports::{calloop::channel::SyncSender, client::backend::ObjectId};
 use cosmic::app::{applet::cosmic_panel_config::PanelAnchor, Command};
 use cosmic::iced::alignment::{Horizontal, Vertical};
+use cosmic::iced::keyboard::{self, KeyCode};
 use cosmic::iced::mouse::{self, ScrollDelta};
-use cosmic::iced::widget::{column, container, row, text};
-use cosmic::iced::{subscription, widget::button, Event::Mouse, Length, Subscription};
+use cosmic::iced::wayland::popup::{destroy_popup, get_popup};
+use cosmic::iced::widget::{
+    column, container, image, mouse_area, row, scrollable, text, text_input,
+};
+use cosmic::iced::{
+    event, subscription,
+    widget::button,
+    window,
+    Event::{Keyboard, Mouse},
+    Length, Subscription,
+};
+use cosmic::iced_runtime::core::layout::Limits;
 use cosmic::iced_style::application;
 use cosmic::theme::Button;
+use cosmic::widget::icon;
 use cosmic::{Element, Theme};
+use once_cell::sync::Lazy;
 
 use cosmic_protocols::workspace::v1::client::zcosmic_workspace_handle_v1;
 use std::cmp::Ordering;
 
-use crate::config;
-use crate::wayland::{WorkspaceEvent, WorkspaceList};
+use crate::config::WorkspacesConfig;
+use crate::wayland::{Workspace, WorkspaceEvent, WorkspaceList};
 use crate::wayland_subscription::{workspaces, WorkspacesUpdate};
+use crate::{config, dbus, fl};
+use cosmic::cosmic_config;
+use std::borrow::Cow;
 
 pub fn run() -> cosmic::iced::Result {
     cosmic::app::applet::run::<IcedWorkspacesApplet>(true, ())
@@ -25,19 +41,147 @@ pub enum Layout {
     Column,
 }
 
+/// What the popup shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PopupKind {
+    Switcher,
+    Overview,
+    /// Actions for one workspace, opened by right-clicking its button
+    Workspace(ObjectId),
+}
+
+/// Pixels of smooth scrolling, as from touchpads, that move one workspace
+const SCROLL_PIXELS: f32 = 40.0;
+
+static SWITCHER_INPUT: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+
 struct IcedWorkspacesApplet {
     core: cosmic::app::Core,
     workspaces: WorkspaceList,
     workspace_tx: Option<SyncSender<WorkspaceEvent>>,
     layout: Layout,
+    popup: Option<window::Id>,
+    id_ctr: u128,
+    switcher_query: String,
+    switcher_selected: usize,
+    popup_kind: PopupKind,
+    config: WorkspacesConfig,
+    /// Smooth scrolling that didn't add up to a step yet
+    scroll_pixels: f32,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     WorkspaceUpdate(WorkspacesUpdate),
     WorkspacePressed(ObjectId),
+    WindowPressed(ObjectId),
+    ToggleOverview,
+    WorkspaceMenu(ObjectId),
+    AddWorkspace,
+    RemoveWorkspace(ObjectId),
     WheelScrolled(ScrollDelta),
     Errored,
+    ConfigUpdated(WorkspacesConfig),
+    ShowSwitcher,
+    CloseSwitcher,
+    SwitcherQuery(String),
+    SwitcherMove(isize),
+    SwitcherActivate,
+}
+
+impl IcedWorkspacesApplet {
+    /// Workspaces whose number or name match the switcher query
+    fn switcher_matches(&self) -> Vec<(usize, &Workspace)> {
+        let query = self.switcher_query.trim().to_lowercase();
+        self.workspaces
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (i + 1, w))
+            .filter(|(number, w)| {
+                number.to_string().starts_with(&query) || w.0.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    fn activate(&mut self, id: ObjectId) -> Command<Message> {
+        if let Some(tx) = self.workspace_tx.as_mut() {
+            let _ = tx.try_send(WorkspaceEvent::Activate(id));
+        }
+        self.popup.take().map_or_else(Command::none, destroy_popup)
+    }
+
+    fn open_popup(&mut self, max_width: f32) -> Command<Message> {
+        self.id_ctr += 1;
+        let new_id = window::Id(self.id_ctr);
+        self.popup.replace(new_id);
+
+        let mut popup_settings =
+            self.core
+                .applet_helper
+                .get_popup_settings(window::Id(0), new_id, None, None, None);
+        popup_settings.positioner.size_limits = Limits::NONE
+            .min_height(1.0)
+            .min_width(1.0)
+            .max_height(600.0)
+            .max_width(max_width);
+        get_popup(popup_settings)
+    }
+
+    fn overview(&self) -> Element<Message> {
+        let mut cards = column![].spacing(8);
+        for (i, w) in self.workspaces.iter().enumerate() {
+            let active = matches!(w.1, Some(zcosmic_workspace_handle_v1::State::Active));
+            let mut card = column![button(
+                row![
+                    text((i + 1).to_string())
+                        .size(14)
+                        .width(Length::Fixed(24.0)),
+                    text(w.0.clone()).size(14).width(Length::Fill),
+                ]
+                .spacing(8),
+            )
+            .style(if active {
+                Button::Primary
+            } else {
+                Button::Secondary
+            })
+            .width(Length::Fill)
+            .padding([4, 12])
+            .on_press(Message::WorkspacePressed(w.2.clone()))]
+            .spacing(2);
+            if w.3.is_empty() {
+                card = card.push(container(text(fl!("no-windows")).size(12)).padding([4, 12]));
+            }
+            for window in &w.3 {
+                let title = if window.title.is_empty() {
+                    window.app_id.clone()
+                } else {
+                    window.title.clone()
+                };
+                let mut content = column![].spacing(4);
+                if let Some(thumbnail) = window.thumbnail.clone() {
+                    content = content.push(image(thumbnail).width(Length::Fill));
+                }
+                content = content.push(
+                    row![
+                        icon(window.app_id.as_str(), 16),
+                        text(title).size(12).width(Length::Fill),
+                    ]
+                    .spacing(8)
+                    .align_items(cosmic::iced::Alignment::Center),
+                );
+                card = card.push(
+                    button(content)
+                        .style(Button::Text)
+                        .width(Length::Fill)
+                        .padding([4, 12])
+                        .on_press(Message::WindowPressed(window.id.clone())),
+                );
+            }
+            cards = cards.push(card);
+        }
+        scrollable(cards).height(Length::Shrink).into()
+    }
 }
 
 impl cosmic::Application for IcedWorkspacesApplet {
@@ -56,6 +200,13 @@ impl cosmic::Application for IcedWorkspacesApplet {
                 core,
                 workspaces: Vec::new(),
                 workspace_tx: Default::default(),
+                popup: None,
+                id_ctr: 0,
+                switcher_query: String::new(),
+                switcher_selected: 0,
+                popup_kind: PopupKind::Switcher,
+                config: WorkspacesConfig::load(),
+                scroll_pixels: 0.0,
             },
             Command::none(),
         )
@@ -82,6 +233,17 @@ impl cosmic::Application for IcedWorkspacesApplet {
                         Ordering::Greater => Ordering::Greater,
                     });
                     self.workspaces = list;
+                    // workspaces come and go, also through other clients
+                    self.switcher_selected = self
+                        .switcher_selected
+                        .min(self.switcher_matches().len().saturating_sub(1));
+                    if let PopupKind::Workspace(id) = &self.popup_kind {
+                        if !self.workspaces.iter().any(|w| &w.2 == id) {
+                            if let Some(p) = self.popup.take() {
+                                return destroy_popup(p);
+                            }
+                        }
+                    }
                 }
                 WorkspacesUpdate::Started(tx) => {
                     self.workspace_tx.replace(tx);
@@ -91,20 +253,125 @@ impl cosmic::Application for IcedWorkspacesApplet {
                 }
             },
             Message::WorkspacePressed(id) => {
+                return self.activate(id);
+            }
+            Message::WindowPressed(id) => {
                 if let Some(tx) = self.workspace_tx.as_mut() {
-                    let _ = tx.try_send(WorkspaceEvent::Activate(id));
+                    let _ = tx.try_send(WorkspaceEvent::ActivateWindow(id));
+                }
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
                 }
             }
-            Message::WheelScrolled(delta) => {
-                let delta = match delta {
-                    ScrollDelta::Lines { x, y } => x + y,
-                    ScrollDelta::Pixels { x, y } => x + y,
-                } as f64;
+            Message::ToggleOverview => {
+                let mut commands = Vec::new();
+                if let Some(p) = self.popup.take() {
+                    if matches!(self.popup_kind, PopupKind::Overview) {
+                        return destroy_popup(p);
+                    }
+                    // opened from the workspace menu, which it replaces
+                    commands.push(destroy_popup(p));
+                }
+                self.popup_kind = PopupKind::Overview;
+                if let Some(tx) = self.workspace_tx.as_mut() {
+                    let _ = tx.try_send(WorkspaceEvent::Capture);
+                }
+                commands.push(self.open_popup(400.0));
+                return Command::batch(commands);
+            }
+            Message::WorkspaceMenu(id) => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+                self.popup_kind = PopupKind::Workspace(id);
+                return self.open_popup(300.0);
+            }
+            Message::AddWorkspace => {
                 if let Some(tx) = self.workspace_tx.as_mut() {
-                    let _ = tx.try_send(WorkspaceEvent::Scroll(delta));
+                    let _ = tx.try_send(WorkspaceEvent::Create);
+                }
+            }
+            Message::RemoveWorkspace(id) => {
+                if let Some(tx) = self.workspace_tx.as_mut() {
+                    let _ = tx.try_send(WorkspaceEvent::Remove(id));
+                }
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+            }
+            Message::WheelScrolled(delta) => {
+                if self.popup.is_some() {
+                    return Command::none();
+                }
+                // down and right move to the next workspace, vertical panels only scroll
+                // vertically, horizontal ones take either direction
+                let axis = |x: f32, y: f32| match self.layout {
+                    Layout::Row if x.abs() > y.abs() => x,
+                    _ => -y,
+                };
+                let step = match delta {
+                    ScrollDelta::Lines { x, y } => axis(x, y),
+                    ScrollDelta::Pixels { x, y } => {
+                        self.scroll_pixels += axis(x, y);
+                        if self.scroll_pixels.abs() < SCROLL_PIXELS {
+                            return Command::none();
+                        }
+                        std::mem::take(&mut self.scroll_pixels)
+                    }
+                };
+                if step != 0.0 {
+                    if let Some(tx) = self.workspace_tx.as_mut() {
+                        let _ = tx.try_send(WorkspaceEvent::Scroll(
+                            step.into(),
+                            self.config.wrap_scrolling,
+                        ));
+                    }
                 }
             }
             Message::Errored => {}
+            Message::ConfigUpdated(config) => {
+                self.config = config;
+            }
+            Message::ShowSwitcher => {
+                if self.popup.is_some() {
+                    return Command::none();
+                }
+                self.switcher_query.clear();
+                self.switcher_selected = 0;
+                self.popup_kind = PopupKind::Switcher;
+                return Command::batch(vec![
+                    self.open_popup(300.0),
+                    text_input::focus(SWITCHER_INPUT.clone()),
+                ]);
+            }
+            Message::CloseSwitcher => {
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+            }
+            Message::SwitcherQuery(query) => {
+                self.switcher_query = query;
+                self.switcher_selected = 0;
+            }
+            Message::SwitcherMove(step) => {
+                let len = self.switcher_matches().len();
+                if self.popup.is_some() && self.popup_kind == PopupKind::Switcher && len > 0 {
+                    self.switcher_selected =
+                        (self.switcher_selected as isize + step).rem_euclid(len as isize) as usize;
+                }
+            }
+            Message::SwitcherActivate => {
+                if self.popup_kind != PopupKind::Switcher {
+                    return Command::none();
+                }
+                let selected = self
+                    .switcher_matches()
+                    .get(self.switcher_selected)
+                    .map(|(_, w)| w.2.clone());
+                if let Some(id) = selected {
+                    return self.activate(id);
+                }
+            }
         }
         Command::none()
     }
@@ -113,7 +380,8 @@ impl cosmic::Application for IcedWorkspacesApplet {
         if self.workspaces.is_empty() {
             return row![].padding(8).into();
         }
-        let buttons = self
+        let size = self.core.applet_helper.suggested_size().0 as f32 + 16.0;
+        let mut buttons: Vec<Element<_>> = self
             .workspaces
             .iter()
             .filter_map(|w| {
@@ -125,25 +393,38 @@ impl cosmic::Application for IcedWorkspacesApplet {
                         .width(Length::Fill)
                         .height(Length::Fill),
                 )
-                .width(Length::Fixed(
-                    self.core.applet_helper.suggested_size().0 as f32 + 16.0,
-                ))
-                .height(Length::Fixed(
-                    self.core.applet_helper.suggested_size().0 as f32 + 16.0,
-                ))
+                .width(Length::Fixed(size))
+                .height(Length::Fixed(size))
                 .on_press(Message::WorkspacePressed(w.2.clone()))
                 .padding(0);
                 Some(
-                    btn.style(match w.1 {
+                    mouse_area(btn.style(match w.1 {
                         Some(zcosmic_workspace_handle_v1::State::Active) => Button::Primary,
                         Some(zcosmic_workspace_handle_v1::State::Urgent) => Button::Destructive,
                         None => Button::Secondary,
                         _ => return None,
-                    })
+                    }))
+                    .on_right_press(Message::WorkspaceMenu(w.2.clone()))
                     .into(),
                 )
             })
             .collect();
+        buttons.push(
+            button(
+                text("+")
+                    .size(14)
+                    .horizontal_alignment(Horizontal::Center)
+                    .vertical_alignment(Vertical::Center)
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+            )
+            .style(Button::Text)
+            .width(Length::Fixed(size))
+            .height(Length::Fixed(size))
+            .padding(0)
+            .on_press(Message::AddWorkspace)
+            .into(),
+        );
         let layout_section: Element<_> = match self.layout {
             Layout::Row => row(buttons)
                 .width(Length::Shrink)
@@ -157,21 +438,114 @@ impl cosmic::Application for IcedWorkspacesApplet {
                 .into(),
         };
 
-        container(layout_section)
-            .width(Length::Shrink)
-            .height(Length::Shrink)
-            .padding(0)
-            .into()
+        mouse_area(
+            container(layout_section)
+                .width(Length::Shrink)
+                .height(Length::Shrink)
+                .padding(0),
+        )
+        .on_right_press(Message::ToggleOverview)
+        .into()
+    }
+
+    fn view_window(&self, _id: window::Id) -> Element<Message> {
+        match &self.popup_kind {
+            PopupKind::Switcher => {}
+            PopupKind::Overview => {
+                let content = container(self.overview()).padding(12);
+                return self.core.applet_helper.popup_container(content).into();
+            }
+            PopupKind::Workspace(id) => {
+                let name = self
+                    .workspaces
+                    .iter()
+                    .find(|w| &w.2 == id)
+                    .map(|w| w.0.clone())
+                    .unwrap_or_default();
+                let mut remove = button(text(fl!("remove-workspace")).size(14))
+                    .style(Button::Text)
+                    .width(Length::Fill)
+                    .padding([4, 12]);
+                // the last workspace can't go
+                if self.workspaces.len() > 1 {
+                    remove = remove.on_press(Message::RemoveWorkspace(id.clone()));
+                }
+                let overview = button(text(fl!("overview")).size(14))
+                    .style(Button::Text)
+                    .width(Length::Fill)
+                    .padding([4, 12])
+                    .on_press(Message::ToggleOverview);
+                let content = column![text(name).size(14), overview, remove]
+                    .spacing(8)
+                    .padding(12);
+                return self.core.applet_helper.popup_container(content).into();
+            }
+        }
+        let mut list = column![].spacing(4);
+        let matches = self.switcher_matches();
+        if matches.is_empty() {
+            list = list.push(text(fl!("no-matching-workspaces")).size(14));
+        }
+        for (i, (number, w)) in matches.into_iter().enumerate() {
+            list = list.push(
+                button(
+                    row![
+                        text(number.to_string()).size(14).width(Length::Fixed(24.0)),
+                        text(w.0.clone()).size(14).width(Length::Fill),
+                    ]
+                    .spacing(8),
+                )
+                .style(if i == self.switcher_selected {
+                    Button::Primary
+                } else {
+                    Button::Text
+                })
+                .width(Length::Fill)
+                .padding([4, 12])
+                .on_press(Message::WorkspacePressed(w.2.clone())),
+            );
+        }
+        let content = column![
+            text_input(&fl!("switch-workspace"), &self.switcher_query)
+                .id(SWITCHER_INPUT.clone())
+                .on_input(Message::SwitcherQuery)
+                .on_submit(Message::SwitcherActivate),
+            list,
+        ]
+        .spacing(8)
+        .padding(12);
+        self.core.applet_helper.popup_container(content).into()
     }
 
     fn subscription(&self) -> Subscription<Message> {
         Subscription::batch(
             vec![
                 workspaces(0).map(Message::WorkspaceUpdate),
-                subscription::events_with(|e, _| match e {
-                    Mouse(mouse::Event::WheelScrolled { delta }) => {
+                dbus::switcher_requests().map(|_| Message::ShowSwitcher),
+                cosmic_config::config_subscription(0, Cow::from(config::APP_ID), 1).map(
+                    |(_, config)| match config {
+                        Ok(config) => Message::ConfigUpdated(config),
+                        Err((errors, config)) => {
+                            for error in errors {
+                                log::error!("{:?}", error);
+                            }
+                            Message::ConfigUpdated(config)
+                        }
+                    },
+                ),
+                subscription::events_with(|e, status| match e {
+                    // scrollables in the popup handle their own scrolling
+                    Mouse(mouse::Event::WheelScrolled { delta })
+                        if status == event::Status::Ignored =>
+                    {
                         Some(Message::WheelScrolled(delta))
                     }
+                    Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => match key_code {
+                        KeyCode::Up => Some(Message::SwitcherMove(-1)),
+                        KeyCode::Down => Some(Message::SwitcherMove(1)),
+                        KeyCode::Escape => Some(Message::CloseSwitcher),
+                        _ => None,
+                    },
                     _ => None,
                 }),
             ]
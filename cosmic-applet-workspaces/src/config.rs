@@ -1,3 +1,29 @@
+use cosmic::cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic::cosmic_config::{self, Config, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
 pub const APP_ID: &str = "com.system76.CosmicWorkspacesApplet";
 pub const PROFILE: &str = "";
 pub const VERSION: &str = "0.1.0";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, CosmicConfigEntry)]
+pub struct WorkspacesConfig {
+    /// Scrolling past the last workspace goes to the first one, and the other way around
+    pub wrap_scrolling: bool,
+}
+
+impl Default for WorkspacesConfig {
+    fn default() -> Self {
+        Self {
+            wrap_scrolling: true,
+        }
+    }
+}
+
+impl WorkspacesConfig {
+    pub fn load() -> WorkspacesConfig {
+        Config::new(APP_ID, 1)
+            .map(|config| WorkspacesConfig::get_entry(&config).unwrap_or_else(|(_, c)| c))
+            .unwrap_or_default()
+    }
+}
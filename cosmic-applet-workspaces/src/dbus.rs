@@ -0,0 +1,38 @@
+//! D-Bus interface that lets a keybinding open the workspace switcher, e.g.
+//! `busctl --user call com.system76.CosmicAppletWorkspaces /com/system76/CosmicAppletWorkspaces com.system76.CosmicAppletWorkspaces ShowSwitcher`
+
+use cosmic::iced::{
+    self,
+    futures::{channel::mpsc, SinkExt},
+};
+use cosmic_applet_common::dbus;
+use zbus::dbus_interface;
+
+const DBUS_NAME: &str = "com.system76.CosmicAppletWorkspaces";
+const DBUS_PATH: &str = "/com/system76/CosmicAppletWorkspaces";
+
+struct Switcher {
+    tx: mpsc::Sender<()>,
+}
+
+#[dbus_interface(name = "com.system76.CosmicAppletWorkspaces")]
+impl Switcher {
+    /// Show the keyboard-driven workspace switcher
+    async fn show_switcher(&self) {
+        let _ = self.tx.clone().send(()).await;
+    }
+}
+
+/// Emits whenever the switcher is requested over D-Bus
+pub fn switcher_requests() -> iced::Subscription<()> {
+    struct SwitcherRequests;
+
+    // the workspaces applet of the panel that started first opens the switcher, so it
+    // lists the workspaces of that panel's output
+    dbus::requests(
+        std::any::TypeId::of::<SwitcherRequests>(),
+        DBUS_NAME,
+        DBUS_PATH,
+        |tx| Switcher { tx },
+    )
+}
@@ -1,6 +1,6 @@
 mod components;
-#[rustfmt::skip]
 mod config;
+mod dbus;
 mod localize;
 mod wayland;
 mod wayland_subscription;
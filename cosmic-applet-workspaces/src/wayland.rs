@@ -1,5 +1,6 @@
 use calloop::channel::*;
 use cctk::{
+    screencopy::{BufferInfo, ScreencopyHandler, ScreencopyState},
     sctk::{
         self,
         output::{OutputHandler, OutputState},
@@ -8,26 +9,66 @@ use cctk::{
             client::{self as wayland_client},
         },
         registry::{ProvidesRegistryState, RegistryState},
+        seat::{Capability, SeatHandler, SeatState},
+        shm::{Shm, ShmHandler},
     },
+    toplevel_info::{ToplevelInfoHandler, ToplevelInfoState},
+    toplevel_management::{ToplevelManagerHandler, ToplevelManagerState},
     workspace::{WorkspaceHandler, WorkspaceState},
 };
-use cosmic_protocols::workspace::v1::client::zcosmic_workspace_handle_v1;
+use cosmic::iced::widget::image;
+use cosmic_applet_common::screencopy::Captures;
+use cosmic_protocols::{
+    screencopy::v1::client::zcosmic_screencopy_session_v1,
+    toplevel_info::v1::client::zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1,
+    toplevel_management::v1::client::zcosmic_toplevel_manager_v1,
+    workspace::v1::client::zcosmic_workspace_handle_v1,
+};
 use futures::{channel::mpsc, executor::block_on, SinkExt};
-use std::{env, os::unix::net::UnixStream, path::PathBuf, time::Duration};
+use std::{collections::HashMap, env, os::unix::net::UnixStream, path::PathBuf, time::Duration};
 use wayland_client::backend::ObjectId;
 use wayland_client::{
     globals::registry_queue_init,
-    protocol::wl_output::{self, WlOutput},
-    ConnectError, Proxy, WaylandSource,
+    protocol::{
+        wl_buffer,
+        wl_output::{self, WlOutput},
+        wl_seat::WlSeat,
+    },
+    ConnectError, Dispatch, Proxy, WaylandSource,
 };
 use wayland_client::{Connection, QueueHandle, WEnum};
 
 #[derive(Debug, Clone)]
 pub enum WorkspaceEvent {
     Activate(ObjectId),
-    Scroll(f64),
+    ActivateWindow(ObjectId),
+    /// Move to the next workspace for a positive step, the previous one for a negative
+    /// step, wrapping around at the ends if asked to
+    Scroll(f64, bool),
+    /// Add a workspace at the end of the group on the panel's output
+    Create,
+    Remove(ObjectId),
+    /// Capture thumbnails of the windows on the panel's output, for the overview
+    Capture,
 }
-pub type WorkspaceList = Vec<(String, Option<zcosmic_workspace_handle_v1::State>, ObjectId)>;
+
+/// A window on a workspace, as announced by the compositor's toplevel info
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub id: ObjectId,
+    pub title: String,
+    pub app_id: String,
+    /// The last capture of the window, if the overview was opened since it appeared
+    pub thumbnail: Option<image::Handle>,
+}
+
+pub type Workspace = (
+    String,
+    Option<zcosmic_workspace_handle_v1::State>,
+    ObjectId,
+    Vec<Window>,
+);
+pub type WorkspaceList = Vec<Workspace>;
 
 pub fn spawn_workspaces(tx: mpsc::Sender<WorkspaceList>) -> SyncSender<WorkspaceEvent> {
     let (workspaces_tx, workspaces_rx) = calloop::channel::sync_channel(100);
@@ -64,6 +105,14 @@ pub fn spawn_workspaces(tx: mpsc::Sender<WorkspaceList>) -> SyncSender<Workspace
                 output_state: OutputState::new(&globals, &qhandle),
                 configured_output,
                 workspace_state: WorkspaceState::new(&registry_state, &qhandle),
+                toplevel_info_state: ToplevelInfoState::new(&registry_state, &qhandle),
+                toplevel_manager_state: ToplevelManagerState::new(&registry_state, &qhandle),
+                seat_state: SeatState::new(&globals, &qhandle),
+                screencopy_state: ScreencopyState::new(&globals, &qhandle),
+                shm: Shm::bind(&globals, &qhandle).unwrap(),
+                captures: Captures::default(),
+                thumbnails: HashMap::new(),
+                queue_handle: qhandle.clone(),
                 registry_state,
                 expected_output: None,
                 tx,
@@ -89,7 +138,78 @@ pub fn spawn_workspaces(tx: mpsc::Sender<WorkspaceList>) -> SyncSender<Workspace
                                 .commit();
                         }
                     }
-                    Event::Msg(WorkspaceEvent::Scroll(v)) => {
+                    Event::Msg(WorkspaceEvent::ActivateWindow(id)) => {
+                        let toplevel = state
+                            .toplevel_info_state
+                            .toplevels()
+                            .find(|(t, _)| t.id() == id)
+                            .map(|(t, _)| t.clone());
+                        if let (Some(toplevel), Some(seat)) =
+                            (toplevel, state.seat_state.seats().next())
+                        {
+                            state
+                                .toplevel_manager_state
+                                .manager
+                                .activate(&toplevel, &seat);
+                        }
+                    }
+                    Event::Msg(WorkspaceEvent::Create) => {
+                        if let Some(g) = state.workspace_state.workspace_groups().iter().find(|g| {
+                            g.outputs
+                                .iter()
+                                .any(|o| Some(o) == state.expected_output.as_ref())
+                        }) {
+                            g.handle
+                                .create_workspace((g.workspaces.len() + 1).to_string());
+                            state
+                                .workspace_state
+                                .workspace_manager()
+                                .get()
+                                .unwrap()
+                                .commit();
+                        }
+                    }
+                    Event::Msg(WorkspaceEvent::Remove(id)) => {
+                        if let Some(w) = state
+                            .workspace_state
+                            .workspace_groups()
+                            .iter()
+                            .find_map(|g| g.workspaces.iter().find(|w| w.handle.id() == id))
+                        {
+                            w.handle.remove();
+                            state
+                                .workspace_state
+                                .workspace_manager()
+                                .get()
+                                .unwrap()
+                                .commit();
+                        }
+                    }
+                    Event::Msg(WorkspaceEvent::Capture) => {
+                        let toplevels: Vec<_> = state
+                            .toplevel_info_state
+                            .toplevels()
+                            .filter_map(|(t, info)| {
+                                let on_output = info?.workspace.iter().any(|w| {
+                                    state.workspace_state.workspace_groups().iter().any(|g| {
+                                        g.outputs
+                                            .iter()
+                                            .any(|o| Some(o) == state.expected_output.as_ref())
+                                            && g.workspaces.iter().any(|gw| &gw.handle == w)
+                                    })
+                                });
+                                on_output.then(|| t.clone())
+                            })
+                            .collect();
+                        for toplevel in toplevels {
+                            state.captures.capture(
+                                &state.screencopy_state,
+                                &state.queue_handle,
+                                toplevel,
+                            );
+                        }
+                    }
+                    Event::Msg(WorkspaceEvent::Scroll(v, wrap)) => {
                         if let Some((w_g, w_i)) = state
                             .workspace_state
                             .workspace_groups()
@@ -114,17 +234,21 @@ pub fn spawn_workspaces(tx: mpsc::Sender<WorkspaceList>) -> SyncSender<Workspace
                         {
                             let max_w = w_g.workspaces.len().wrapping_sub(1);
                             let d_i = if v > 0.0 {
-                                if w_i == max_w {
+                                if w_i < max_w {
+                                    w_i + 1
+                                } else if wrap {
                                     0
                                 } else {
-                                    w_i.wrapping_add(1)
+                                    w_i
                                 }
-                            } else if w_i == 0 {
+                            } else if w_i > 0 {
+                                w_i - 1
+                            } else if wrap {
                                 max_w
                             } else {
-                                w_i.wrapping_sub(1)
+                                w_i
                             };
-                            if let Some(w) = w_g.workspaces.get(d_i) {
+                            if let Some(w) = w_g.workspaces.get(d_i).filter(|_| d_i != w_i) {
                                 w.handle.activate();
                                 state
                                     .workspace_state
@@ -161,7 +285,6 @@ pub fn spawn_workspaces(tx: mpsc::Sender<WorkspaceList>) -> SyncSender<Workspace
     workspaces_tx
 }
 
-#[derive(Debug)]
 pub struct State {
     running: bool,
     tx: mpsc::Sender<WorkspaceList>,
@@ -170,13 +293,40 @@ pub struct State {
     output_state: OutputState,
     registry_state: RegistryState,
     workspace_state: WorkspaceState,
+    toplevel_info_state: ToplevelInfoState,
+    toplevel_manager_state: ToplevelManagerState,
+    seat_state: SeatState,
+    screencopy_state: ScreencopyState,
+    shm: Shm,
+    captures: Captures,
+    thumbnails: HashMap<ObjectId, image::Handle>,
+    queue_handle: QueueHandle<Self>,
     have_workspaces: bool,
 }
 
 impl State {
-    pub fn workspace_list(
+    /// Windows of the workspace, leaving out a toplevel that is being closed
+    fn windows(
         &self,
-    ) -> Vec<(String, Option<zcosmic_workspace_handle_v1::State>, ObjectId)> {
+        workspace: &zcosmic_workspace_handle_v1::ZcosmicWorkspaceHandleV1,
+        closed: Option<&ZcosmicToplevelHandleV1>,
+    ) -> Vec<Window> {
+        self.toplevel_info_state
+            .toplevels()
+            .filter(|(t, _)| Some(*t) != closed)
+            .filter_map(|(t, info)| {
+                let info = info?;
+                info.workspace.contains(workspace).then(|| Window {
+                    id: t.id(),
+                    title: info.title.clone(),
+                    app_id: info.app_id.clone(),
+                    thumbnail: self.thumbnails.get(&t.id()).cloned(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn workspace_list(&self, closed: Option<&ZcosmicToplevelHandleV1>) -> WorkspaceList {
         self.workspace_state
             .workspace_groups()
             .iter()
@@ -210,6 +360,7 @@ impl State {
                                 _ => None,
                             },
                             w.handle.id(),
+                            self.windows(&w.handle, closed),
                         )
                     }))
                 } else {
@@ -225,7 +376,7 @@ impl ProvidesRegistryState for State {
     fn registry(&mut self) -> &mut RegistryState {
         &mut self.registry_state
     }
-    sctk::registry_handlers![OutputState,];
+    sctk::registry_handlers![OutputState, SeatState,];
 }
 
 impl OutputHandler for State {
@@ -243,7 +394,7 @@ impl OutputHandler for State {
         if info.name.as_deref() == Some(&self.configured_output) {
             self.expected_output = Some(output);
             if self.have_workspaces {
-                let _ = block_on(self.tx.send(self.workspace_list()));
+                let _ = block_on(self.tx.send(self.workspace_list(None)));
             }
         }
     }
@@ -272,10 +423,150 @@ impl WorkspaceHandler for State {
 
     fn done(&mut self) {
         self.have_workspaces = true;
-        let _ = block_on(self.tx.send(self.workspace_list()));
+        let _ = block_on(self.tx.send(self.workspace_list(None)));
+    }
+}
+
+impl ToplevelInfoHandler for State {
+    fn toplevel_info_state(&mut self) -> &mut ToplevelInfoState {
+        &mut self.toplevel_info_state
+    }
+
+    fn new_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        if self.have_workspaces {
+            let _ = block_on(self.tx.send(self.workspace_list(None)));
+        }
+    }
+
+    fn update_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        if self.have_workspaces {
+            let _ = block_on(self.tx.send(self.workspace_list(None)));
+        }
+    }
+
+    fn toplevel_closed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        toplevel: &ZcosmicToplevelHandleV1,
+    ) {
+        self.thumbnails.remove(&toplevel.id());
+        // the toplevel is only dropped from the state after this returns
+        if self.have_workspaces {
+            let _ = block_on(self.tx.send(self.workspace_list(Some(toplevel))));
+        }
+    }
+}
+
+impl ToplevelManagerHandler for State {
+    fn toplevel_manager_state(&mut self) -> &mut ToplevelManagerState {
+        &mut self.toplevel_manager_state
+    }
+
+    fn capabilities(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: Vec<WEnum<zcosmic_toplevel_manager_v1::ZcosmicToplelevelManagementCapabilitiesV1>>,
+    ) {
+    }
+}
+
+impl SeatHandler for State {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+
+    fn new_capability(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat, _: Capability) {}
+
+    fn remove_capability(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: WlSeat,
+        _: Capability,
+    ) {
+    }
+
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+}
+
+impl ShmHandler for State {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_buffer::WlBuffer,
+        _: wl_buffer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl ScreencopyHandler for State {
+    fn screencopy_state(&mut self) -> &mut ScreencopyState {
+        &mut self.screencopy_state
+    }
+
+    fn init_done(
+        &mut self,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+        session: &zcosmic_screencopy_session_v1::ZcosmicScreencopySessionV1,
+        buffer_infos: &[BufferInfo],
+    ) {
+        self.captures
+            .init_done(&self.shm, qh, session, buffer_infos);
+    }
+
+    fn ready(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        session: &zcosmic_screencopy_session_v1::ZcosmicScreencopySessionV1,
+    ) {
+        if let Some((toplevel, image)) = self.captures.ready(session) {
+            self.thumbnails.insert(toplevel.id(), image);
+            if self.have_workspaces {
+                let _ = block_on(self.tx.send(self.workspace_list(None)));
+            }
+        }
+    }
+
+    fn failed(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        session: &zcosmic_screencopy_session_v1::ZcosmicScreencopySessionV1,
+        _: WEnum<zcosmic_screencopy_session_v1::FailureReason>,
+    ) {
+        self.captures.failed(session);
     }
 }
 
 cctk::delegate_workspace!(State);
+cctk::delegate_toplevel_info!(State);
+cctk::delegate_toplevel_manager!(State);
 sctk::delegate_output!(State);
+sctk::delegate_seat!(State);
+sctk::delegate_shm!(State);
+cctk::delegate_screencopy!(State);
 sctk::delegate_registry!(State);